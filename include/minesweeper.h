@@ -0,0 +1,146 @@
+#ifndef MINESWEEPER_H
+#define MINESWEEPER_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ What the player sees in a cell.
+ */
+typedef enum MsCellKind {
+  /*
+   A covered cell without a mark.
+   */
+  MS_CELL_KIND_COVERED,
+  /*
+   A covered cell with a flag.
+   */
+  MS_CELL_KIND_FLAGGED,
+  /*
+   A covered cell with a question mark.
+   */
+  MS_CELL_KIND_QUESTIONED,
+  /*
+   An uncovered cell, `count` holds the number of mined neighbors.
+   */
+  MS_CELL_KIND_OPEN,
+  /*
+   An uncovered mine.
+   */
+  MS_CELL_KIND_MINE,
+} MsCellKind;
+
+/*
+ State of a game as seen from C.
+ */
+typedef enum MsGameState {
+  /*
+   No cell has been uncovered yet.
+   */
+  MS_GAME_STATE_INITIAL,
+  /*
+   The game is in progress.
+   */
+  MS_GAME_STATE_PLAYING,
+  /*
+   Every safe cell has been uncovered.
+   */
+  MS_GAME_STATE_WON,
+  /*
+   A mine was uncovered.
+   */
+  MS_GAME_STATE_LOST,
+} MsGameState;
+
+typedef struct MsGame MsGame;
+
+/*
+ A cell as seen from C.
+ */
+typedef struct MsCell {
+  /*
+   What the player sees in the cell.
+   */
+  enum MsCellKind kind;
+  /*
+   Number of mined neighbors of an open cell, otherwise 0.
+   */
+  uint8_t count;
+} MsCell;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Creates a game with randomly placed mines, returning null if the board is
+ empty, has more than 32767 cells or has no room for that many mines.
+ Release it with [`ms_game_free`].
+ */
+struct MsGame *ms_game_new(int16_t width, int16_t height, uint16_t mines);
+
+/*
+ Releases a game created with [`ms_game_new`].
+ */
+void ms_game_free(struct MsGame *game);
+
+/*
+ Lays out the mines again from a seed so the same board can be replayed.
+ */
+void ms_game_reset(struct MsGame *game, uint64_t seed);
+
+/*
+ Width of the board in cells.
+ */
+int16_t ms_game_width(const struct MsGame *game);
+
+/*
+ Height of the board in cells.
+ */
+int16_t ms_game_height(const struct MsGame *game);
+
+/*
+ Current state of the game.
+ */
+enum MsGameState ms_game_state(const struct MsGame *game);
+
+/*
+ Number of mines less the number of flags placed.
+ */
+uint16_t ms_game_mines_left(const struct MsGame *game);
+
+/*
+ The cell at `x`, `y` as the player sees it, covered when off the board.
+ */
+struct MsCell ms_game_cell(const struct MsGame *game, int16_t x, int16_t y);
+
+/*
+ Uncovers a cell and returns the resulting game state.
+ */
+enum MsGameState ms_game_uncover(struct MsGame *game, int16_t x, int16_t y);
+
+/*
+ Uncovers the neighbors of a counted cell whose flags match its count and
+ returns the resulting game state.
+ */
+enum MsGameState ms_game_chord(struct MsGame *game, int16_t x, int16_t y);
+
+/*
+ Toggles a flag on a covered cell.
+ */
+void ms_game_flag(struct MsGame *game, int16_t x, int16_t y);
+
+/*
+ Clears a flag or question mark.
+ */
+void ms_game_clear_mark(struct MsGame *game, int16_t x, int16_t y);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MINESWEEPER_H */
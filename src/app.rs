@@ -1,35 +1,134 @@
+mod audio;
+mod backdrop;
+mod browser;
+mod chart;
+mod clipboard;
+mod composition;
+mod config_watch;
+#[cfg(feature = "dev-tools")]
+mod debug_console;
 mod direct2d;
-mod game;
 mod gameboard;
+mod settings;
+mod statistics;
 
-use gameboard::{BoardLevel, GameBoard};
-use std::{error::Error, sync::Once};
+#[cfg(feature = "logging")]
+use minesweeper_d2d::logging;
+use minesweeper_d2d::{
+    assets, bindings, checkpoint, config, crash, csv, cues, drill, error, game, ghost, inspect,
+    music, patterns, print, render, replay, save, score, share, skin, snapshot, solver, splits,
+    stats, theme, versus,
+};
+#[cfg(feature = "dev-tools")]
+use minesweeper_d2d::{console, perf};
+
+use audio::{Audio, Music};
+use browser::{BrowserWindow, WM_LOAD_GAME};
+use config::{BoardSettings, Config};
+use config_watch::WM_CONFIG_CHANGED;
+#[cfg(feature = "dev-tools")]
+use debug_console::{DebugConsole, WM_CONSOLE};
+use game::{Difficulty, Game, GameState};
+use gameboard::{Assists, BoardLevel, GameBoard, Theme};
+use music::Stem;
+use settings::SettingsWindow;
+use skin::Skin;
+use statistics::StatisticsWindow;
+use std::{
+    cell::RefCell,
+    error::Error,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Once,
+    time::Duration,
+};
+use theme::ThemeFile;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, VK_CONTROL, VK_F3};
+#[cfg(feature = "dev-tools")]
+use windows::Win32::UI::Input::KeyboardAndMouse::{VK_F7, VK_F8, VK_F9};
 use windows::{
     core::Result,
-    core::{w, HSTRING},
+    core::{w, HSTRING, PCWSTR, PWSTR},
     Win32::{
         Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
         Graphics::{
             Direct2D::ID2D1Factory1,
-            Gdi::{COLOR_WINDOW, HBRUSH},
+            Gdi::{
+                FillRect, GetStockObject, InvalidateRect, BLACK_BRUSH, COLOR_WINDOW, HBRUSH, HDC,
+            },
         },
         System::{
             Com::{CoInitializeEx, COINIT_MULTITHREADED},
             LibraryLoader::GetModuleHandleW,
+            SystemServices::MK_SHIFT,
         },
-        UI::WindowsAndMessaging::{
-            AdjustWindowRect, CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW,
-            GetWindowLongPtrA, GetWindowRect, LoadCursorW, PostQuitMessage, RegisterClassW,
-            SetWindowLongPtrA, SetWindowPos, ShowWindow, CREATESTRUCTA, CS_HREDRAW, CS_VREDRAW,
-            CW_USEDEFAULT, GWLP_USERDATA, HMENU, IDC_ARROW, MSG, SWP_NOMOVE, SW_SHOW,
-            WINDOW_EX_STYLE, WM_CREATE, WM_DESTROY, WNDCLASSW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+        UI::{
+            Controls::Dialogs::{
+                GetSaveFileNameW, OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST, OPENFILENAMEW,
+            },
+            Shell::ShellExecuteW,
+            WindowsAndMessaging::{
+                AdjustWindowRect, AppendMenuW, CreateWindowExW, DefWindowProcW, DestroyWindow,
+                DispatchMessageW, GetClientRect, GetMessageW, GetSystemMenu, GetWindowLongPtrA,
+                GetWindowRect, IsWindow, KillTimer, LoadCursorW, MessageBoxW, PostQuitMessage,
+                RegisterClassW, SetForegroundWindow, SetTimer, SetWindowLongPtrA, SetWindowPos,
+                SetWindowTextW, ShowWindow, CREATESTRUCTA, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT,
+                GWLP_USERDATA, HMENU, IDC_ARROW, IDYES, MB_ICONERROR, MB_ICONINFORMATION,
+                MB_ICONQUESTION, MB_OK, MB_YESNO, MF_SEPARATOR, MF_STRING, MSG, SWP_NOMOVE,
+                SW_SHOW, SW_SHOWNORMAL, WHEEL_DELTA, WINDOW_EX_STYLE, WM_CREATE, WM_DESTROY,
+                WM_ERASEBKGND, WM_KEYDOWN, WM_MOUSEHWHEEL, WM_MOUSEWHEEL, WM_SYSCOMMAND, WM_TIMER,
+                WNDCLASSW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+            },
         },
     },
 };
 
 static REGISTER_WINDOW_CLASS: Once = Once::new();
 
+// system menu command opening the sound settings, below the 0xF000 system commands
+const IDM_SETTINGS: usize = 0x0010;
+// system menu command opening the win streaks and session history
+const IDM_STATISTICS: usize = 0x0020;
+// system menu command taking a hint on the board
+const IDM_HINT: usize = 0x0030;
+// system menu commands keeping a practice checkpoint and going back to it
+const IDM_CHECKPOINT: usize = 0x0040;
+const IDM_REVERT: usize = 0x0050;
+// system menu command swapping the left and right mouse buttons
+const IDM_SWAP_BUTTONS: usize = 0x0060;
+// system menu command showing the version and how the board is drawn
+const IDM_ABOUT: usize = 0x0070;
+// system menu command printing the board as a PDF puzzle
+const IDM_PRINT: usize = 0x0080;
+// system menu command, also F3, playing the same board again from the start
+const IDM_RESTART: usize = 0x0090;
+// system menu command listing the saved games and authored boards to load
+const IDM_LOAD: usize = 0x00A0;
+// system menu command saving the finished game as an animated image
+const IDM_EXPORT: usize = 0x00B0;
+// system menu command copying a spoiler-free summary of the finished game
+const IDM_SHARE: usize = 0x00C0;
+// keeps the music buffers topped up
+const MUSIC_TIMER: usize = 1;
+const MUSIC_PERIOD_MS: u32 = 100;
+// keeps the clock in the title running between moves
+const TITLE_TIMER: usize = 2;
+const TITLE_PERIOD_MS: u32 = 1000;
+// cells a board larger than the window scrolls per notch of the mouse wheel
+const SCROLL_CELLS: f32 = 3.0;
+
 fn main() -> windows::core::Result<()> {
+    crash::install(report_crash);
+    // the window has nowhere to report a log file that cannot be started
+    #[cfg(feature = "logging")]
+    let _log = match Config::load().ok().and_then(|config| config.log) {
+        Some(level) => logging::init(level).ok().flatten(),
+        // keep the latest traces for a crash report all the same
+        None => {
+            let _ = logging::init_recent(tracing::Level::INFO);
+            None
+        }
+    };
     unsafe {
         let result = CoInitializeEx(None, COINIT_MULTITHREADED);
         if result.is_err() {
@@ -47,9 +146,80 @@ fn main() -> windows::core::Result<()> {
     Ok(())
 }
 
+/// Shows the level, the mines left to flag and the time of a game in the
+/// title of the window, where the taskbar preview shows it too, such as
+/// "MineSweeper — Expert — 34 mines — 01:12".
+fn show_game_title(window: HWND, game: &Game) {
+    let level = match Difficulty::from_config(game.config()) {
+        Some(Difficulty::Beginner) => "Beginner".to_string(),
+        Some(Difficulty::Intermediate) => "Intermediate".to_string(),
+        Some(Difficulty::Expert) => "Expert".to_string(),
+        None => format!("{}x{}", game.width(), game.height()),
+    };
+    let seconds = game.elapsed().as_secs();
+    let title = format!(
+        "MineSweeper \u{2014} {} \u{2014} {} mines \u{2014} {:02}:{:02}",
+        level,
+        game.est_remaining(),
+        seconds / 60,
+        seconds % 60
+    );
+    let _ = unsafe { SetWindowTextW(window, &HSTRING::from(title)) };
+}
+
+/// Whether a control key is held down.
+fn control_down() -> bool {
+    // the high bit, which makes the state negative, is set while it is down
+    let state = unsafe { GetKeyState(VK_CONTROL.0 as i32) };
+    state < 0
+}
+
+/// Tells the player where the crash report was written and offers to open
+/// its folder.
+fn report_crash(path: &Path) {
+    let text = format!(
+        "MineSweeper stopped after an error. A crash report was written to\n{}\n\n\
+         Open its folder?",
+        path.display()
+    );
+    unsafe {
+        let answer = MessageBoxW(
+            HWND(0),
+            &HSTRING::from(text),
+            w!("MineSweeper"),
+            MB_YESNO | MB_ICONERROR,
+        );
+        if let (IDYES, Some(folder)) = (answer, path.parent()) {
+            let _ = ShellExecuteW(
+                HWND(0),
+                w!("open"),
+                &HSTRING::from(folder),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                SW_SHOWNORMAL,
+            );
+        }
+    }
+}
+
 pub(crate) struct AppWindow<'a> {
     handle: HWND,
     game_board: Option<Box<GameBoard<'a>>>,
+    settings_window: Option<Box<SettingsWindow<'a>>>,
+    statistics_window: Option<Box<StatisticsWindow<'a>>>,
+    browser_window: Option<Box<BrowserWindow<'a>>>,
+    // no sound when there is no audio device
+    audio: Option<Rc<Audio>>,
+    // shared with the board observer that fades between the stems
+    music: Option<Rc<RefCell<Music>>>,
+    // whether a system backdrop shows through the board
+    backdrop: bool,
+    // whether the title shows the level, mines left and time of the game,
+    // off while it shows the splits or the score of a match
+    game_title: bool,
+    // the debug console while it is open
+    #[cfg(feature = "dev-tools")]
+    console: Option<DebugConsole>,
     factory: &'a ID2D1Factory1,
 }
 
@@ -73,6 +243,15 @@ impl<'a> AppWindow<'a> {
         let mut app_window = Box::new(AppWindow {
             handle: HWND(0),
             game_board: None,
+            settings_window: None,
+            statistics_window: None,
+            browser_window: None,
+            audio: None,
+            music: None,
+            backdrop: false,
+            game_title: false,
+            #[cfg(feature = "dev-tools")]
+            console: None,
             factory,
         });
         // create the window using Self reference
@@ -103,62 +282,690 @@ impl<'a> AppWindow<'a> {
         wparam: WPARAM,
         lparam: LPARAM,
     ) -> LRESULT {
+        tracing::trace!(message, "app window message");
         match message {
             WM_CREATE => {
-                match GameBoard::new(self.handle, BoardLevel::Medium, self.factory) {
+                // a broken config file falls back to the defaults
+                let config = Config::load().unwrap_or_default();
+                self.start_audio(&config);
+                self.start_music(&config);
+                unsafe {
+                    let menu = GetSystemMenu(self.handle, false);
+                    let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_SETTINGS, w!("Options..."));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_STATISTICS, w!("Statistics..."));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_HINT, w!("Hint"));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_RESTART, w!("Restart this board\tF3"));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_CHECKPOINT, w!("Keep checkpoint"));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_REVERT, w!("Back to checkpoint"));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_LOAD, w!("Load game..."));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_PRINT, w!("Print puzzle..."));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_EXPORT, w!("Export replay..."));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_SHARE, w!("Copy result"));
+                    let _ =
+                        AppendMenuW(menu, MF_STRING, IDM_SWAP_BUTTONS, w!("Swap mouse buttons"));
+                    let _ = AppendMenuW(menu, MF_STRING, IDM_ABOUT, w!("About..."));
+                }
+                let settings = config.board;
+                self.backdrop = backdrop::apply(self.handle, settings.backdrop);
+                let mut builder = GameBoard::builder(self.handle, self.factory)
+                    .level(BoardLevel::Medium)
+                    .assists(assists(&settings))
+                    .versus(settings.versus)
+                    .splits(settings.splits)
+                    .ghost(settings.ghost)
+                    .counters(settings.counters)
+                    .scoring(settings.scoring)
+                    .no_flags(settings.no_flags)
+                    .flag_penalty(settings.flag_penalty)
+                    .neighborhood(settings.neighborhood)
+                    .blind(settings.blind.map(u64::from).map(Duration::from_secs))
+                    .inspect(settings.inspect)
+                    .symmetry(settings.symmetry)
+                    .zones(settings.zones.clone())
+                    .handicap(settings.handicap)
+                    .hint_budget(settings.hint_budget)
+                    .bindings(config.input)
+                    .render(config.render)
+                    .timing(config.timing)
+                    .translucent(self.backdrop)
+                    .observer(|game: &Game, mv| crash::set_game(game, Some(mv)));
+                // a shared board says nothing about either player's record and
+                // practice games do not count
+                if !settings.versus && !settings.practice {
+                    builder = builder.observer(|game: &Game, _| {
+                        if matches!(game.state(), GameState::Won | GameState::Lost) {
+                            // statistics are best effort and never interrupt play
+                            let _ = stats::record(game, None);
+                        }
+                    });
+                }
+                self.game_title = !settings.versus && !settings.splits;
+                if self.game_title {
+                    let handle = self.handle;
+                    builder = builder.observer(move |game: &Game, _| show_game_title(handle, game));
+                }
+                if let Some(music) = &self.music {
+                    let music = music.clone();
+                    builder = builder.observer(move |game: &Game, _| {
+                        let stem = match game.state() {
+                            GameState::Won | GameState::Lost => Stem::GameOver,
+                            _ => Stem::Playing,
+                        };
+                        music.borrow_mut().fade_to(stem);
+                    });
+                }
+                if let Some(difficulty) = settings.difficulty {
+                    builder = builder.config(difficulty.config());
+                }
+                if let Some(size) = settings.cell_size {
+                    builder = builder.cell_size(size);
+                }
+                let file = theme_file(config.theme.as_deref());
+                let (theme, skin) = look(&settings, file.as_ref());
+                builder = builder.theme(theme);
+                if let Some(skin) = skin {
+                    builder = builder.skin(skin);
+                }
+                if let (true, Some(audio)) = (settings.practice, &self.audio) {
+                    builder = builder.cues(audio.clone());
+                }
+                if let (true, Some(audio)) = (settings.sonify, &self.audio) {
+                    builder = builder.sonify(audio.clone());
+                }
+                match builder.build() {
                     Ok(board) => {
+                        if self.game_title {
+                            show_game_title(self.handle, board.game());
+                            unsafe { SetTimer(self.handle, TITLE_TIMER, TITLE_PERIOD_MS, None) };
+                        }
                         self.game_board = Some(board);
-                        let mut rect = RECT::default();
-                        let mut child_rect = RECT::default();
-                        unsafe {
-                            if GetWindowRect(self.handle, &mut rect).is_err() {
-                                return LRESULT(-1);
-                            }
-                            if GetWindowRect(
-                                self.game_board.as_ref().unwrap().hwnd(),
-                                &mut child_rect,
-                            )
-                            .is_err()
-                            {
-                                return LRESULT(-1);
-                            }
-                            if AdjustWindowRect(
-                                &mut child_rect,
-                                WS_VISIBLE | WS_OVERLAPPEDWINDOW,
-                                false,
-                            )
-                            .is_err()
-                            {
-                                return LRESULT(-1);
-                            }
-                            if SetWindowPos(
-                                self.handle,
-                                None,
-                                rect.left,
-                                rect.top,
-                                child_rect.right - child_rect.left,
-                                child_rect.bottom - child_rect.top,
-                                SWP_NOMOVE,
-                            )
-                            .is_err()
-                            {
-                                return LRESULT(-1);
-                            }
+                        if self.fit_board().is_err() {
+                            return LRESULT(-1);
                         }
                     }
-                    Err(_e) => {
+                    Err(error) => {
+                        tracing::error!(%error, "unable to create the board");
                         return LRESULT(-1);
                     }
                 }
+                // without a watch the config applies the next time the game starts
+                if let Some(path) = Config::path() {
+                    if let Err(error) = config_watch::watch(self.handle, &path) {
+                        tracing::warn!(%error, "unable to watch the config file");
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_CONFIG_CHANGED => {
+                self.reload_config();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_SETTINGS => {
+                self.open_settings();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_STATISTICS => {
+                self.open_statistics();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_HINT => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.hint();
+                }
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_RESTART => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.restart();
+                }
+                LRESULT(0)
+            }
+            WM_KEYDOWN if wparam.0 == VK_F3.0 as usize => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.restart();
+                }
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_CHECKPOINT => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.checkpoint();
+                }
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_REVERT => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.revert();
+                }
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_LOAD => {
+                self.open_browser();
+                LRESULT(0)
+            }
+            WM_LOAD_GAME => {
+                self.load_chosen();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_PRINT => {
+                self.print_puzzle();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_EXPORT => {
+                self.export_replay();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_SHARE => {
+                self.copy_result();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_ABOUT => {
+                self.show_about();
+                LRESULT(0)
+            }
+            WM_SYSCOMMAND if wparam.0 & 0xfff0 == IDM_SWAP_BUTTONS => {
+                if let Some(board) = self.game_board.as_mut() {
+                    let entries = board.swap_buttons().entries();
+                    if let Err(error) = Config::store(&entries) {
+                        tracing::error!(%error, "unable to store the mouse buttons");
+                    }
+                }
+                LRESULT(0)
+            }
+            #[cfg(feature = "dev-tools")]
+            WM_KEYDOWN if wparam.0 == VK_F7.0 as usize => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.toggle_perf();
+                }
+                LRESULT(0)
+            }
+            #[cfg(feature = "dev-tools")]
+            WM_KEYDOWN if wparam.0 == VK_F8.0 as usize => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.toggle_debug();
+                }
+                LRESULT(0)
+            }
+            #[cfg(feature = "dev-tools")]
+            WM_KEYDOWN if wparam.0 == VK_F9.0 as usize => {
+                self.toggle_console();
+                LRESULT(0)
+            }
+            #[cfg(feature = "dev-tools")]
+            WM_CONSOLE => {
+                let lines = self.console.as_ref().map(DebugConsole::lines);
+                if let Some(board) = self.game_board.as_mut() {
+                    for line in lines.unwrap_or_default() {
+                        println!("{}", board.console(&line));
+                    }
+                }
+                LRESULT(0)
+            }
+            // the board as text, to paste a position for advice
+            WM_KEYDOWN if wparam.0 == b'C' as usize && control_down() => {
+                self.copy_board();
+                LRESULT(0)
+            }
+            WM_KEYDOWN if wparam.0 == b'V' as usize && control_down() => {
+                self.paste_board();
+                LRESULT(0)
+            }
+            WM_KEYDOWN => {
+                if let Some(board) = self.game_board.as_mut() {
+                    board.key_down(wparam.0, control_down());
+                }
+                LRESULT(0)
+            }
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                if let Some(board) = self.game_board.as_mut() {
+                    // the high word is the signed distance the wheel turned
+                    let notches = (wparam.0 >> 16) as u16 as i16 as f32 / WHEEL_DELTA as f32;
+                    let cells = notches * SCROLL_CELLS;
+                    if message == WM_MOUSEHWHEEL {
+                        board.scroll(cells, 0.0);
+                    } else if wparam.0 as u32 & MK_SHIFT.0 != 0 {
+                        board.scroll(-cells, 0.0);
+                    } else {
+                        board.scroll(0.0, -cells);
+                    }
+                }
+                LRESULT(0)
+            }
+            // black is see through where the frame reaches over the client
+            // area, letting the backdrop show around the board
+            WM_ERASEBKGND if self.backdrop => {
+                let mut rect = RECT::default();
+                unsafe {
+                    let _ = GetClientRect(self.handle, &mut rect);
+                    FillRect(
+                        HDC(wparam.0 as isize),
+                        &rect,
+                        HBRUSH(GetStockObject(BLACK_BRUSH).0),
+                    );
+                }
+                LRESULT(1)
+            }
+            WM_TIMER if wparam.0 == MUSIC_TIMER => {
+                if let Some(music) = &self.music {
+                    if let Err(error) = music.borrow_mut().pump() {
+                        tracing::warn!(%error, "music stopped");
+                        self.music = None;
+                        let _ = unsafe { KillTimer(self.handle, MUSIC_TIMER) };
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == TITLE_TIMER => {
+                if let Some(board) = &self.game_board {
+                    show_game_title(self.handle, board.game());
+                }
                 LRESULT(0)
             }
             WM_DESTROY => {
+                if self.music.is_some() {
+                    let _ = unsafe { KillTimer(self.handle, MUSIC_TIMER) };
+                }
+                if self.game_title {
+                    let _ = unsafe { KillTimer(self.handle, TITLE_TIMER) };
+                }
                 unsafe { PostQuitMessage(0) };
                 LRESULT(0)
             }
             _ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
         }
     }
+
+    /// Applies the look, assists, mouse buttons and volumes of the config
+    /// file to the running game after it changed. Settings that need a new
+    /// board, such as the difficulty, apply the next time the game starts.
+    fn reload_config(&mut self) {
+        let config = match Config::load() {
+            Ok(config) => config,
+            Err(error) => {
+                // editors may write the file in steps, the next change can fix it
+                tracing::warn!(%error, "unable to reload the config");
+                return;
+            }
+        };
+        tracing::info!("config reloaded");
+        if let Some(audio) = &self.audio {
+            if let Err(error) = audio.apply(&config.audio) {
+                tracing::warn!(%error, "unable to set the volume");
+            }
+        }
+        let backdrop = backdrop::apply(self.handle, config.board.backdrop);
+        if backdrop != self.backdrop {
+            self.backdrop = backdrop;
+            unsafe { InvalidateRect(self.handle, None, true) };
+        }
+        if let Some(board) = self.game_board.as_mut() {
+            let file = theme_file(config.theme.as_deref());
+            let (theme, skin) = look(&config.board, file.as_ref());
+            board.set_look(theme, skin);
+            board.set_translucent(backdrop);
+            board.set_assists(assists(&config.board));
+            board.set_bindings(config.input);
+            board.set_render(config.render);
+            board.set_timing(config.timing);
+        }
+    }
+
+    /// Starts the audio engine at the configured volumes, the game plays
+    /// silently when there is no audio device.
+    fn start_audio(&mut self, config: &Config) {
+        let audio = Audio::new().and_then(|audio| {
+            audio.apply(&config.audio)?;
+            Ok(audio)
+        });
+        match audio {
+            Ok(audio) => self.audio = Some(Rc::new(audio)),
+            Err(error) => tracing::warn!(%error, "unable to start audio"),
+        }
+    }
+
+    /// Starts the configured background music, the game plays without music
+    /// when there is no track or it cannot be read.
+    fn start_music(&mut self, config: &Config) {
+        let (Some(audio), Some(playing)) = (&self.audio, &config.audio.music_playing) else {
+            return;
+        };
+        match Music::new(audio, playing, config.audio.music_game_over.as_deref()) {
+            Ok(music) => {
+                self.music = Some(Rc::new(RefCell::new(music)));
+                unsafe { SetTimer(self.handle, MUSIC_TIMER, MUSIC_PERIOD_MS, None) };
+            }
+            Err(error) => tracing::warn!(%error, "unable to start the music"),
+        }
+    }
+
+    /// Shows the sound settings, bringing the window to the front if it is
+    /// already open.
+    fn open_settings(&mut self) {
+        if let Some(window) = &self.settings_window {
+            if unsafe { IsWindow(window.hwnd()) }.as_bool() {
+                let _ = unsafe { SetForegroundWindow(window.hwnd()) };
+                return;
+            }
+        }
+        // the window writes its settings when closed, read them back each time
+        let config = Config::load().unwrap_or_default();
+        let file = theme_file(config.theme.as_deref());
+        let (theme, skin) = look(&config.board, file.as_ref());
+        // the look on the board is exported under the name of the theme or
+        // skin it comes from
+        let name = match (&file, &skin) {
+            (Some(file), _) => file.name.clone(),
+            (None, Some(skin)) => skin.name.clone(),
+            (None, None) if config.board.dark => "Dark".to_string(),
+            (None, None) => "Classic".to_string(),
+        };
+        let theme = match &skin {
+            Some(skin) => theme.with_skin(skin),
+            None => theme,
+        };
+        let mut look = theme.to_file(&name);
+        look.author = file.as_ref().and_then(|file| file.author.clone());
+        if let Some(file) = file {
+            look.glyphs = file.glyphs;
+        }
+        look.glyphs.overlay(&config.glyphs);
+        let audio = self.audio.clone();
+        let window = SettingsWindow::new(
+            self.handle,
+            config.audio,
+            config.board.skin,
+            config.theme,
+            look,
+            config.timing,
+            move |settings| {
+                if let Some(audio) = &audio {
+                    if let Err(error) = audio.apply(settings) {
+                        tracing::warn!(%error, "unable to set the volume");
+                    }
+                }
+            },
+        );
+        match window {
+            Ok(window) => self.settings_window = Some(window),
+            Err(error) => tracing::error!(%error, "unable to open the settings"),
+        }
+    }
+
+    /// Opens the debug console, or closes it if it is open.
+    #[cfg(feature = "dev-tools")]
+    fn toggle_console(&mut self) {
+        if self.console.take().is_some() {
+            return;
+        }
+        match DebugConsole::open(self.handle) {
+            Ok(console) => self.console = Some(console),
+            Err(error) => tracing::error!(%error, "unable to open the debug console"),
+        }
+    }
+
+    /// Shows the version and whether the board is drawn on the graphics card
+    /// or in software.
+    fn show_about(&self) {
+        let rendering = match &self.game_board {
+            Some(board) => board.rendering().to_string(),
+            None => "not started".to_string(),
+        };
+        let text = format!(
+            "MineSweeper {}\nDirect2D rendering: {}",
+            env!("CARGO_PKG_VERSION"),
+            rendering
+        );
+        unsafe {
+            MessageBoxW(
+                self.handle,
+                &HSTRING::from(text),
+                w!("About MineSweeper"),
+                MB_OK | MB_ICONINFORMATION,
+            );
+        }
+    }
+
+    /// Puts the player's view of the board on the clipboard in the glyphs
+    /// of the game's text form, a row per line.
+    fn copy_board(&self) {
+        let Some(board) = self.game_board.as_ref() else {
+            return;
+        };
+        let text = board
+            .game()
+            .to_string()
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        if let Err(error) = clipboard::copy_text(self.handle, &text) {
+            tracing::warn!(%error, "unable to copy the board");
+        }
+    }
+
+    /// Copies the summary of the finished game to share, see
+    /// [`share::summary`], telling the player when the game is not over.
+    fn copy_result(&self) {
+        let Some(board) = self.game_board.as_ref() else {
+            return;
+        };
+        let Some(summary) = share::summary(board.game(), None) else {
+            unsafe {
+                MessageBoxW(
+                    self.handle,
+                    w!("The result can be copied once the game is over."),
+                    w!("Copy result"),
+                    MB_OK | MB_ICONINFORMATION,
+                );
+            }
+            return;
+        };
+        let text = summary.lines().collect::<Vec<_>>().join("\r\n");
+        if let Err(error) = clipboard::copy_text(self.handle, &text) {
+            tracing::warn!(%error, "unable to copy the result");
+        }
+    }
+
+    /// Plays the board layout on the clipboard, see
+    /// [`save::parse_layout`], telling the player when it is not one.
+    fn paste_board(&mut self) {
+        let text = match clipboard::paste_text(self.handle) {
+            Ok(Some(text)) => text,
+            Ok(None) => return,
+            Err(error) => {
+                tracing::warn!(%error, "unable to paste a board");
+                return;
+            }
+        };
+        let game = match save::parse_layout(&text) {
+            Ok(game) => game,
+            Err(error) => {
+                let text = format!("The clipboard does not hold a board layout:\n{}", error);
+                unsafe {
+                    MessageBoxW(
+                        self.handle,
+                        &HSTRING::from(text),
+                        w!("Paste board"),
+                        MB_OK | MB_ICONINFORMATION,
+                    );
+                }
+                return;
+            }
+        };
+        let Some(board) = self.game_board.as_mut() else {
+            return;
+        };
+        if board.load(game) {
+            if let Err(error) = self.fit_board() {
+                tracing::warn!(%error, "unable to fit the window to the board");
+            }
+        }
+    }
+
+    /// Sizes the window around the board, keeping its position.
+    fn fit_board(&self) -> Result<()> {
+        let Some(board) = self.game_board.as_ref() else {
+            return Ok(());
+        };
+        let mut rect = RECT::default();
+        let mut child_rect = RECT::default();
+        unsafe {
+            GetWindowRect(self.handle, &mut rect)?;
+            GetWindowRect(board.hwnd(), &mut child_rect)?;
+            AdjustWindowRect(&mut child_rect, WS_VISIBLE | WS_OVERLAPPEDWINDOW, false)?;
+            SetWindowPos(
+                self.handle,
+                None,
+                rect.left,
+                rect.top,
+                child_rect.right - child_rect.left,
+                child_rect.bottom - child_rect.top,
+                SWP_NOMOVE,
+            )
+        }
+    }
+
+    /// Asks where to save the board as a PDF puzzle and whether to add a
+    /// page with its solution.
+    fn print_puzzle(&self) {
+        let Some(board) = self.game_board.as_ref() else {
+            return;
+        };
+        let mut file = [0u16; 260];
+        for (to, from) in file.iter_mut().zip("puzzle.pdf".encode_utf16()) {
+            *to = from;
+        }
+        let mut dialog = OPENFILENAMEW {
+            lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+            hwndOwner: self.handle,
+            lpstrFilter: w!("PDF documents (*.pdf)\0*.pdf\0"),
+            lpstrFile: PWSTR(file.as_mut_ptr()),
+            nMaxFile: file.len() as u32,
+            lpstrDefExt: w!("pdf"),
+            Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+            ..Default::default()
+        };
+        // cancelled
+        if !unsafe { GetSaveFileNameW(&mut dialog) }.as_bool() {
+            return;
+        }
+        let length = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+        let path = PathBuf::from(String::from_utf16_lossy(&file[..length]));
+        let answer = unsafe {
+            MessageBoxW(
+                self.handle,
+                w!("Add a page with the solution?"),
+                w!("Print puzzle"),
+                MB_YESNO | MB_ICONQUESTION,
+            )
+        };
+        if let Err(error) = print::save(board.game(), answer == IDYES, &path) {
+            tracing::warn!(%error, path = %path.display(), "unable to print the puzzle");
+        }
+    }
+
+    /// Asks where to save the moves of the finished game as an animated GIF,
+    /// or an animated PNG when the file is named so, a frame per move.
+    fn export_replay(&self) {
+        let Some(board) = self.game_board.as_ref() else {
+            return;
+        };
+        let over = matches!(board.game().state(), GameState::Won | GameState::Lost);
+        let Some(replay) = board.recording().filter(|_| over) else {
+            unsafe {
+                MessageBoxW(
+                    self.handle,
+                    w!("The replay can be exported once the game is over."),
+                    w!("Export replay"),
+                    MB_OK | MB_ICONINFORMATION,
+                );
+            }
+            return;
+        };
+        let mut file = [0u16; 260];
+        for (to, from) in file.iter_mut().zip("replay.gif".encode_utf16()) {
+            *to = from;
+        }
+        let mut dialog = OPENFILENAMEW {
+            lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+            hwndOwner: self.handle,
+            lpstrFilter: w!("Animated GIF (*.gif)\0*.gif\0Animated PNG (*.png)\0*.png\0"),
+            lpstrFile: PWSTR(file.as_mut_ptr()),
+            nMaxFile: file.len() as u32,
+            lpstrDefExt: w!("gif"),
+            Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+            ..Default::default()
+        };
+        // cancelled
+        if !unsafe { GetSaveFileNameW(&mut dialog) }.as_bool() {
+            return;
+        }
+        let length = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+        let path = PathBuf::from(String::from_utf16_lossy(&file[..length]));
+        if path.extension().is_some_and(|extension| extension == "png") {
+            if let Err(error) = std::fs::write(&path, snapshot::apng(replay)) {
+                tracing::warn!(%error, path = %path.display(), "unable to export the replay");
+            }
+            return;
+        }
+        // every position is drawn off screen in the board's theme and skin
+        // and encoded by WIC
+        let saved = board
+            .draw_replay(replay)
+            .and_then(|frames| direct2d::save_gif(&frames, &path));
+        if let Err(error) = saved {
+            tracing::warn!(%error, path = %path.display(), "unable to export the replay");
+        }
+    }
+
+    /// Shows the win streaks, this session's games and where games were
+    /// lost. An open window is
+    /// closed and opened again so it lists the games played since.
+    fn open_statistics(&mut self) {
+        if let Some(window) = self.statistics_window.take() {
+            if unsafe { IsWindow(window.hwnd()) }.as_bool() {
+                let _ = unsafe { DestroyWindow(window.hwnd()) };
+            }
+        }
+        match StatisticsWindow::new(self.handle, self.factory) {
+            Ok(window) => self.statistics_window = Some(window),
+            Err(error) => tracing::error!(%error, "unable to open the statistics"),
+        }
+    }
+
+    /// Lists the saved games and authored boards to load. An open window is
+    /// closed and opened again so it lists the files saved since.
+    fn open_browser(&mut self) {
+        if let Some(window) = self.browser_window.take() {
+            if unsafe { IsWindow(window.hwnd()) }.as_bool() {
+                let _ = unsafe { DestroyWindow(window.hwnd()) };
+            }
+        }
+        match BrowserWindow::new(self.handle, self.factory) {
+            Ok(window) => self.browser_window = Some(window),
+            Err(error) => tracing::error!(%error, "unable to open the saved games"),
+        }
+    }
+
+    /// Plays the board chosen in the saved game window like a pasted one and
+    /// closes the window.
+    fn load_chosen(&mut self) {
+        let Some(window) = self.browser_window.take() else {
+            return;
+        };
+        let game = window.chosen();
+        if unsafe { IsWindow(window.hwnd()) }.as_bool() {
+            let _ = unsafe { DestroyWindow(window.hwnd()) };
+        }
+        let (Some(game), Some(board)) = (game, self.game_board.as_mut()) else {
+            return;
+        };
+        if board.load(game) {
+            if let Err(error) = self.fit_board() {
+                tracing::warn!(%error, "unable to fit the window to the board");
+            }
+        }
+    }
     unsafe extern "system" fn wnd_proc(
         window: HWND,
         message: u32,
@@ -179,3 +986,53 @@ impl<'a> AppWindow<'a> {
         DefWindowProcW(window, message, wparam, lparam)
     }
 }
+
+/// The help the config file offers the player.
+fn assists(settings: &BoardSettings) -> Assists {
+    Assists {
+        chording: settings.chording,
+        hints: settings.hints,
+        contradictions: settings.contradictions,
+        safe_chords: settings.safe_chords,
+        auto_open: settings.auto_open,
+        auto_flag: settings.auto_flag,
+    }
+}
+
+/// The theme file the config file names, if it can be loaded.
+fn theme_file(name: Option<&str>) -> Option<ThemeFile> {
+    let name = name?;
+    match ThemeFile::find(name) {
+        Ok(file) => Some(file),
+        Err(error) => {
+            tracing::warn!(%error, theme = name, "unable to load the theme");
+            None
+        }
+    }
+}
+
+/// The theme, with the colors and font of the theme file, and skin the
+/// config file draws the board with. A missing or broken skin leaves the
+/// theme alone.
+fn look(settings: &BoardSettings, file: Option<&ThemeFile>) -> (Theme, Option<Skin>) {
+    let theme = if settings.dark {
+        Theme::DARK
+    } else {
+        Theme::CLASSIC
+    };
+    let theme = match file {
+        Some(file) => theme.with_file(file),
+        None => theme,
+    };
+    let skin = settings
+        .skin
+        .as_ref()
+        .and_then(|name| match Skin::find(name) {
+            Ok(skin) => Some(skin),
+            Err(error) => {
+                tracing::warn!(%error, skin = name, "unable to load the skin");
+                None
+            }
+        });
+    (theme, skin)
+}
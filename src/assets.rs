@@ -0,0 +1,75 @@
+//! Finds the images and other files the GUI ships with.
+//!
+//! An asset is looked for next to the executable, then in the `assets`
+//! folder of the data directory where a user can drop replacements, and
+//! last falls back to a copy built into the program, so starting the game
+//! from another working directory still finds its sprites.
+
+use std::{env, path::PathBuf};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    stats::data_dir,
+};
+
+const ASSETS_DIR: &str = "assets";
+
+/// Where an asset was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A file on disk.
+    File(PathBuf),
+    /// The copy built into the program.
+    Embedded(&'static [u8]),
+}
+
+/// The folders searched for assets, in order.
+pub fn search_dirs() -> Vec<PathBuf> {
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from));
+    let user_dir = data_dir().map(|dir| dir.join(ASSETS_DIR));
+    exe_dir.into_iter().chain(user_dir).collect()
+}
+
+/// Finds the asset `name` in the [`search_dirs`], or gives its `embedded`
+/// copy. Without one the error lists every path searched.
+pub fn resolve(name: &str, embedded: Option<&'static [u8]>) -> Result<Source> {
+    let searched: Vec<PathBuf> = search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .collect();
+    if let Some(path) = searched.iter().find(|path| path.is_file()) {
+        tracing::debug!(path = %path.display(), "asset found");
+        return Ok(Source::File(path.clone()));
+    }
+    match embedded {
+        Some(bytes) => {
+            tracing::debug!(name, "using the embedded asset");
+            Ok(Source::Embedded(bytes))
+        }
+        None => Err(MinesweeperError::MissingAsset {
+            name: name.to_string(),
+            searched,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_resolve() {
+        let name = "no-such-asset.png";
+        assert_eq!(
+            Source::Embedded(b"PNG"),
+            resolve(name, Some(b"PNG")).unwrap()
+        );
+        let error = resolve(name, None).unwrap_err().to_string();
+        assert!(error.starts_with("no-such-asset.png not found, searched "));
+        for dir in search_dirs() {
+            assert!(error.contains(&dir.join(name).display().to_string()));
+        }
+    }
+}
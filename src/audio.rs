@@ -0,0 +1,347 @@
+use std::{fs::File, io::BufReader, mem::ManuallyDrop, path::Path, rc::Rc, time::Instant};
+
+use windows::{
+    core::{Result, PCWSTR},
+    Win32::Media::Audio::{
+        AudioCategory_GameEffects,
+        XAudio2::{
+            IXAudio2, IXAudio2MasteringVoice, IXAudio2SourceVoice, IXAudio2SubmixVoice,
+            IXAudio2Voice, IXAudio2VoiceCallback, XAudio2CreateWithVersionInfo, XAUDIO2_BUFFER,
+            XAUDIO2_COMMIT_NOW, XAUDIO2_DEFAULT_CHANNELS, XAUDIO2_DEFAULT_FREQ_RATIO,
+            XAUDIO2_DEFAULT_PROCESSOR, XAUDIO2_DEFAULT_SAMPLERATE, XAUDIO2_END_OF_STREAM,
+            XAUDIO2_SEND_DESCRIPTOR, XAUDIO2_VOICE_NOSAMPLESPLAYED, XAUDIO2_VOICE_SENDS,
+            XAUDIO2_VOICE_STATE,
+        },
+        WAVEFORMATEX, WAVE_FORMAT_PCM,
+    },
+};
+
+use crate::{
+    config::{AudioSettings, Volume},
+    error,
+    music::{Fader, Stem, WavStream},
+};
+
+// XAudio2 2.9 as shipped with Windows 10
+const NTDDI_WIN10: u32 = 0x0A00_0000;
+
+/// A group of sounds sharing a volume setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Category {
+    /// Short sounds for moves, wins and losses.
+    Effects,
+    /// Background music.
+    Music,
+}
+
+impl Category {
+    /// Every category in the order the settings window lists them.
+    pub(crate) const ALL: [Category; 2] = [Category::Effects, Category::Music];
+
+    /// Label shown in the settings window.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Category::Effects => "Effects",
+            Category::Music => "Music",
+        }
+    }
+
+    /// The volume setting of the category.
+    pub(crate) fn volume(self, settings: &mut AudioSettings) -> &mut Volume {
+        match self {
+            Category::Effects => &mut settings.effects,
+            Category::Music => &mut settings.music,
+        }
+    }
+}
+
+/// The XAudio2 engine with a submix voice per category, sounds are played
+/// through the voice of their category so its volume applies to all of them.
+pub(crate) struct Audio {
+    engine: IXAudio2,
+    master: IXAudio2MasteringVoice,
+    effects: IXAudio2SubmixVoice,
+    music: IXAudio2SubmixVoice,
+}
+
+impl Audio {
+    /// Starts the engine on the default audio device.
+    pub(crate) fn new() -> Result<Self> {
+        let mut engine = None;
+        unsafe {
+            XAudio2CreateWithVersionInfo(&mut engine, 0, XAUDIO2_DEFAULT_PROCESSOR, NTDDI_WIN10)?
+        };
+        let engine: IXAudio2 = engine.unwrap();
+        let mut master = None;
+        unsafe {
+            engine.CreateMasteringVoice(
+                &mut master,
+                XAUDIO2_DEFAULT_CHANNELS,
+                XAUDIO2_DEFAULT_SAMPLERATE,
+                0,
+                PCWSTR::null(),
+                None,
+                AudioCategory_GameEffects,
+            )?
+        };
+        let master = master.unwrap();
+        let submix = || -> Result<IXAudio2SubmixVoice> {
+            let mut voice = None;
+            unsafe {
+                engine.CreateSubmixVoice(
+                    &mut voice,
+                    XAUDIO2_DEFAULT_CHANNELS,
+                    XAUDIO2_DEFAULT_SAMPLERATE,
+                    0,
+                    0,
+                    None,
+                    None,
+                )?
+            };
+            Ok(voice.unwrap())
+        };
+        let effects = submix()?;
+        let music = submix()?;
+        tracing::info!("audio started");
+        Ok(Audio {
+            engine,
+            master,
+            effects,
+            music,
+        })
+    }
+
+    /// The voice sounds of a category are sent to.
+    pub(crate) fn voice(&self, category: Category) -> &IXAudio2SubmixVoice {
+        match category {
+            Category::Effects => &self.effects,
+            Category::Music => &self.music,
+        }
+    }
+
+    /// Sets the volume of every category, taking effect on sounds already
+    /// playing.
+    pub(crate) fn apply(&self, settings: &AudioSettings) -> Result<()> {
+        let mut settings = settings.clone();
+        for category in Category::ALL {
+            let gain = category.volume(&mut settings).gain();
+            unsafe { self.voice(category).SetVolume(gain, XAUDIO2_COMMIT_NOW)? };
+        }
+        Ok(())
+    }
+
+    /// Creates a voice playing 16 bit samples into the voice of `category`.
+    fn source_voice(
+        &self,
+        category: Category,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<IXAudio2SourceVoice> {
+        let block = channels as u32 * 2;
+        let format = WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_PCM as u16,
+            nChannels: channels,
+            nSamplesPerSec: sample_rate,
+            nAvgBytesPerSec: sample_rate * block,
+            nBlockAlign: block as u16,
+            wBitsPerSample: 16,
+            cbSize: 0,
+        };
+        let output: &IXAudio2Voice = self.voice(category).into();
+        let mut send = XAUDIO2_SEND_DESCRIPTOR {
+            Flags: 0,
+            pOutputVoice: ManuallyDrop::new(Some(output.clone())),
+        };
+        let sends = XAUDIO2_VOICE_SENDS {
+            SendCount: 1,
+            pSends: &mut send,
+        };
+        let mut voice = None;
+        unsafe {
+            self.engine.CreateSourceVoice(
+                &mut voice,
+                &format,
+                0,
+                XAUDIO2_DEFAULT_FREQ_RATIO,
+                None::<&IXAudio2VoiceCallback>,
+                Some(&sends),
+                None,
+            )?
+        };
+        Ok(voice.unwrap())
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        // voices are not reference counted and go before the engine
+        unsafe {
+            self.effects.DestroyVoice();
+            self.music.DestroyVoice();
+            self.master.DestroyVoice();
+            self.engine.StopEngine();
+        }
+    }
+}
+
+/// A 16 bit mono sound held in memory and played through the voice of its
+/// category. Playing it again before it ends starts it over.
+pub(crate) struct Sound {
+    voice: IXAudio2SourceVoice,
+    samples: Vec<i16>,
+    // keeps the engine alive until the voice is destroyed
+    _audio: Rc<Audio>,
+}
+
+impl Sound {
+    /// Creates a voice for `samples` played at `sample_rate` samples per
+    /// second.
+    pub(crate) fn new(
+        audio: &Rc<Audio>,
+        category: Category,
+        samples: Vec<i16>,
+        sample_rate: u32,
+    ) -> Result<Self> {
+        Ok(Sound {
+            voice: audio.source_voice(category, 1, sample_rate)?,
+            samples,
+            _audio: audio.clone(),
+        })
+    }
+
+    /// Starts the sound from the beginning.
+    pub(crate) fn play(&self) -> Result<()> {
+        let buffer = XAUDIO2_BUFFER {
+            Flags: XAUDIO2_END_OF_STREAM,
+            AudioBytes: (self.samples.len() * 2) as u32,
+            pAudioData: self.samples.as_ptr() as *const u8,
+            ..Default::default()
+        };
+        unsafe {
+            self.voice.Stop(0, XAUDIO2_COMMIT_NOW)?;
+            self.voice.FlushSourceBuffers()?;
+            self.voice.SubmitSourceBuffer(&buffer, None)?;
+            self.voice.Start(0, XAUDIO2_COMMIT_NOW)
+        }
+    }
+}
+
+impl Drop for Sound {
+    fn drop(&mut self) {
+        // the voice reads the samples until it is destroyed
+        unsafe { self.voice.DestroyVoice() };
+    }
+}
+
+// buffers queued per stem, each holding a quarter of a second
+const STREAM_BUFFERS: usize = 3;
+const BUFFERS_PER_SECOND: u32 = 4;
+
+/// A WAV file streamed from disk into a voice a few buffers at a time.
+struct Stream {
+    voice: IXAudio2SourceVoice,
+    wav: WavStream<BufReader<File>>,
+    buffers: [Vec<i16>; STREAM_BUFFERS],
+    next: usize,
+}
+
+impl Stream {
+    fn open(audio: &Audio, path: &Path) -> error::Result<Self> {
+        let wav = WavStream::open(path)?;
+        let voice = audio.source_voice(Category::Music, wav.channels(), wav.sample_rate())?;
+        let length = (wav.sample_rate() / BUFFERS_PER_SECOND) as usize * wav.channels() as usize;
+        Ok(Stream {
+            voice,
+            wav,
+            buffers: std::array::from_fn(|_| vec![0; length]),
+            next: 0,
+        })
+    }
+
+    /// Refills and queues every buffer the voice has finished playing.
+    fn pump(&mut self) -> error::Result<()> {
+        let mut state = XAUDIO2_VOICE_STATE::default();
+        unsafe {
+            self.voice
+                .GetState(&mut state, XAUDIO2_VOICE_NOSAMPLESPLAYED)
+        };
+        // buffers play in order, so the next one is free while any is
+        for _ in state.BuffersQueued as usize..STREAM_BUFFERS {
+            let samples = &mut self.buffers[self.next];
+            self.wav.fill(samples)?;
+            let buffer = XAUDIO2_BUFFER {
+                AudioBytes: (samples.len() * 2) as u32,
+                pAudioData: samples.as_ptr() as *const u8,
+                ..Default::default()
+            };
+            unsafe { self.voice.SubmitSourceBuffer(&buffer, None)? };
+            self.next = (self.next + 1) % STREAM_BUFFERS;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // the voice reads the buffers until it is destroyed
+        unsafe { self.voice.DestroyVoice() };
+    }
+}
+
+/// Background music looping through the music voice: a stem for a game
+/// being played and an optional one crossfaded to once the game is over.
+/// Without a game over stem the music fades out when a game ends.
+pub(crate) struct Music {
+    playing: Stream,
+    game_over: Option<Stream>,
+    fader: Fader,
+    pumped: Instant,
+    // keeps the engine alive until the voices are destroyed
+    _audio: Rc<Audio>,
+}
+
+impl Music {
+    /// Opens the stems and starts them together, [`Music::pump`] must then
+    /// be called several times a second to keep them playing.
+    pub(crate) fn new(
+        audio: &Rc<Audio>,
+        playing: &Path,
+        game_over: Option<&Path>,
+    ) -> error::Result<Self> {
+        let mut music = Music {
+            playing: Stream::open(audio, playing)?,
+            game_over: game_over
+                .map(|path| Stream::open(audio, path))
+                .transpose()?,
+            fader: Fader::default(),
+            pumped: Instant::now(),
+            _audio: audio.clone(),
+        };
+        music.pump()?;
+        for stream in music.streams() {
+            unsafe { stream.voice.Start(0, XAUDIO2_COMMIT_NOW)? };
+        }
+        Ok(music)
+    }
+
+    fn streams(&mut self) -> impl Iterator<Item = &mut Stream> {
+        std::iter::once(&mut self.playing).chain(self.game_over.as_mut())
+    }
+
+    /// Crossfades to `stem`.
+    pub(crate) fn fade_to(&mut self, stem: Stem) {
+        self.fader.fade_to(stem);
+    }
+
+    /// Tops up the queued buffers and moves the crossfade on.
+    pub(crate) fn pump(&mut self) -> error::Result<()> {
+        let now = Instant::now();
+        let gains = self.fader.step(now - self.pumped);
+        self.pumped = now;
+        for (stream, gain) in self.streams().zip(gains) {
+            stream.pump()?;
+            unsafe { stream.voice.SetVolume(gain, XAUDIO2_COMMIT_NOW)? };
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! The translucent system backdrops Windows 11 draws behind a window.
+
+use std::{ffi::c_void, mem::size_of};
+
+use windows::Win32::{
+    Foundation::HWND,
+    Graphics::Dwm::{
+        DwmExtendFrameIntoClientArea, DwmSetWindowAttribute, DWMSBT_MAINWINDOW, DWMSBT_NONE,
+        DWMSBT_TRANSIENTWINDOW, DWMWA_SYSTEMBACKDROP_TYPE, DWM_SYSTEMBACKDROP_TYPE,
+    },
+    UI::Controls::MARGINS,
+};
+
+use crate::config::Backdrop;
+
+/// Puts `backdrop` behind the whole of `window`, returning whether it is
+/// shown. Windows before Windows 11 have none and the board keeps its
+/// solid color.
+pub(crate) fn apply(window: HWND, backdrop: Backdrop) -> bool {
+    let kind = match backdrop {
+        Backdrop::Solid => DWMSBT_NONE,
+        Backdrop::Mica => DWMSBT_MAINWINDOW,
+        Backdrop::Acrylic => DWMSBT_TRANSIENTWINDOW,
+    };
+    // the frame reaching over the client area lets the backdrop show
+    // through wherever nothing opaque is drawn
+    let inset = if backdrop == Backdrop::Solid { 0 } else { -1 };
+    let margins = MARGINS {
+        cxLeftWidth: inset,
+        cxRightWidth: inset,
+        cyTopHeight: inset,
+        cyBottomHeight: inset,
+    };
+    let result = unsafe {
+        DwmSetWindowAttribute(
+            window,
+            DWMWA_SYSTEMBACKDROP_TYPE,
+            &kind as *const DWM_SYSTEMBACKDROP_TYPE as *const c_void,
+            size_of::<DWM_SYSTEMBACKDROP_TYPE>() as u32,
+        )
+        .and_then(|_| DwmExtendFrameIntoClientArea(window, &margins))
+    };
+    match result {
+        Ok(()) => backdrop != Backdrop::Solid,
+        Err(error) => {
+            if backdrop != Backdrop::Solid {
+                tracing::info!(%error, "no system backdrop, the board stays solid");
+            }
+            false
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    game::{CellState, Game, GameState},
+    solver,
+};
+
+const BENCH_WIDTH: i16 = 128;
+const BENCH_HEIGHT: i16 = 128;
+// roughly the expert board density
+const BENCH_MINES: u16 = 3300;
+
+/// Timings for one benchmark phase across all boards.
+struct Timings {
+    name: &'static str,
+    samples: Vec<Duration>,
+}
+
+impl Timings {
+    fn new(name: &'static str) -> Self {
+        Timings {
+            name,
+            samples: Vec::new(),
+        }
+    }
+
+    fn report(&self) {
+        let total: Duration = self.samples.iter().sum();
+        let min = self.samples.iter().min().copied().unwrap_or_default();
+        let max = self.samples.iter().max().copied().unwrap_or_default();
+        let mean = total / self.samples.len().max(1) as u32;
+        println!(
+            "{:<8} min {:>10.3} ms  mean {:>10.3} ms  max {:>10.3} ms  total {:>10.3} ms",
+            self.name,
+            as_ms(min),
+            as_ms(mean),
+            as_ms(max),
+            as_ms(total)
+        );
+    }
+}
+
+/// Runs the flood fill and solver over `boards` large boards and prints timing
+/// statistics for each.
+pub(crate) fn run(boards: usize) {
+    println!(
+        "Benchmark: {} boards of {}x{} with {} mines",
+        boards, BENCH_WIDTH, BENCH_HEIGHT, BENCH_MINES
+    );
+    let mut cascade = Timings::new("cascade");
+    let mut solve = Timings::new("solver");
+    let mut revealed = 0;
+    let mut solved = 0;
+    for _ in 0..boards {
+        // worst case cascade: an empty board opened from a corner
+        let mut game = Game::with_mines(BENCH_WIDTH, BENCH_HEIGHT, 0);
+        let start = Instant::now();
+        game.uncover(0, 0);
+        cascade.samples.push(start.elapsed());
+
+        let mut game = Game::with_mines(BENCH_WIDTH, BENCH_HEIGHT, BENCH_MINES);
+        let Some((x, y)) = solver::opening(&game) else {
+            continue;
+        };
+        let start = Instant::now();
+        game.uncover(x, y);
+        let state = solver::play_out(&mut game);
+        solve.samples.push(start.elapsed());
+        revealed += uncovered(&game);
+        if state == GameState::Won {
+            solved += 1;
+        }
+    }
+    cascade.report();
+    solve.report();
+    println!(
+        "solver revealed {} cells per board on average, {} of {} boards solved without guessing",
+        revealed / solve.samples.len().max(1),
+        solved,
+        solve.samples.len()
+    );
+}
+
+fn uncovered(game: &Game) -> usize {
+    let mut count = 0;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if matches!(
+                game.cell_state(x, y),
+                CellState::Known(false) | CellState::Counted(_)
+            ) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
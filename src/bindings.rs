@@ -0,0 +1,279 @@
+//! Which mouse button or key does what on the board.
+//!
+//! [`Bindings`] map each [`Input`] to an [`Action`] on the cell under the
+//! pointer. By default the left button uncovers, the right button flags,
+//! the middle button chords and the space bar is the smart action, flagging
+//! or chording depending on the cell; the config file can reassign them, e.g.
+//!
+//! ```text
+//! input.left = flag
+//! input.right = uncover
+//! input.key_space = chord
+//! input.key_d = smart
+//! input.middle = none
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    game::{CellState, Game},
+    solver,
+};
+
+/// A mouse button or a key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// The left mouse button.
+    Left,
+    /// The right mouse button.
+    Right,
+    /// The middle mouse button or wheel click.
+    Middle,
+    /// A letter, digit or the space bar, in lower case.
+    Key(char),
+}
+
+/// What an input does to the cell under the pointer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Uncovers the cell, or chords a number when chording is on.
+    Uncover,
+    /// Cycles the cell through flagged, questioned and unmarked.
+    Flag,
+    /// Uncovers the neighbors of a number once its flags are placed.
+    Chord,
+    /// Marks the cell as a possible mine, or clears the mark.
+    Question,
+    /// Flags a covered cell and chords a number with as many flags around
+    /// it as its count, one key for both as in most modern clones.
+    Smart,
+}
+
+impl Action {
+    /// The action to do on the cell at `x`, `y`: what a smart action does
+    /// there, `None` when it does nothing, or the action itself.
+    pub fn resolve(self, game: &Game, x: i16, y: i16) -> Option<Action> {
+        if self != Action::Smart {
+            return Some(self);
+        }
+        match game.cell_state(x, y) {
+            cell if cell.is_covered() => Some(Action::Flag),
+            CellState::Counted(count) => {
+                let flags = solver::neighbors(game, x, y)
+                    .filter(|&(nx, ny)| matches!(game.cell_state(nx, ny), CellState::Flagged(_)))
+                    .count();
+                (flags == count as usize).then_some(Action::Chord)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The action bound to every input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bindings {
+    bindings: Vec<(Input, Action)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            bindings: vec![
+                (Input::Left, Action::Uncover),
+                (Input::Right, Action::Flag),
+                (Input::Middle, Action::Chord),
+                (Input::Key(' '), Action::Smart),
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    /// The default bindings with the left and right buttons swapped.
+    pub fn left_handed() -> Self {
+        let mut bindings = Bindings::default();
+        bindings.swap(Input::Left, Input::Right);
+        bindings
+    }
+
+    /// Swaps the actions of two inputs.
+    pub fn swap(&mut self, first: Input, second: Input) {
+        let (a, b) = (self.action(first), self.action(second));
+        self.set(first, b);
+        self.set(second, a);
+    }
+
+    /// The action bound to an input, if any.
+    pub fn action(&self, input: Input) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == input)
+            .map(|&(_, action)| action)
+    }
+
+    /// Binds an input to an action, or unbinds it.
+    pub fn set(&mut self, input: Input, action: Option<Action>) {
+        self.bindings.retain(|(bound, _)| *bound != input);
+        if let Some(action) = action {
+            self.bindings.push((input, action));
+        }
+    }
+
+    /// Reads a config file setting such as `left = flag`.
+    pub(crate) fn set_str(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let input = key.parse()?;
+        let action = match value {
+            "none" => None,
+            value => Some(value.parse()?),
+        };
+        self.set(input, action);
+        Ok(())
+    }
+
+    /// The bindings as config file keys and values, see
+    /// [`Config::store`](crate::config::Config::store). The mouse buttons
+    /// are always written so an unbound one stays unbound.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut inputs = vec![Input::Left, Input::Right, Input::Middle];
+        for &(input, _) in &self.bindings {
+            if !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+        inputs
+            .into_iter()
+            .map(|input| {
+                let action = match self.action(input) {
+                    Some(action) => action.to_string(),
+                    None => "none".to_string(),
+                };
+                (format!("input.{}", input), action)
+            })
+            .collect()
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Left => write!(f, "left"),
+            Input::Right => write!(f, "right"),
+            Input::Middle => write!(f, "middle"),
+            Input::Key(' ') => write!(f, "key_space"),
+            Input::Key(key) => write!(f, "key_{}", key),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "unknown input {}, expected left, right, middle or a key such as key_f",
+                s
+            )
+        };
+        match s {
+            "left" => Ok(Input::Left),
+            "right" => Ok(Input::Right),
+            "middle" => Ok(Input::Middle),
+            "key_space" => Ok(Input::Key(' ')),
+            _ => {
+                let mut key = s.strip_prefix("key_").ok_or_else(invalid)?.chars();
+                match (key.next(), key.next()) {
+                    (Some(key), None) if key.is_ascii_alphanumeric() => {
+                        Ok(Input::Key(key.to_ascii_lowercase()))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Action::Uncover => "uncover",
+            Action::Flag => "flag",
+            Action::Chord => "chord",
+            Action::Question => "question",
+            Action::Smart => "smart",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uncover" => Ok(Action::Uncover),
+            "flag" => Ok(Action::Flag),
+            "chord" => Ok(Action::Chord),
+            "question" => Ok(Action::Question),
+            "smart" => Ok(Action::Smart),
+            _ => Err(format!(
+                "unknown action {}, expected uncover, flag, chord, question, smart or none",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_bindings() {
+        let mut bindings = Bindings::default();
+        assert_eq!(Some(Action::Uncover), bindings.action(Input::Left));
+        assert_eq!(None, bindings.action(Input::Key('f')));
+
+        let swapped = Bindings::left_handed();
+        assert_eq!(Some(Action::Flag), swapped.action(Input::Left));
+        assert_eq!(Some(Action::Uncover), swapped.action(Input::Right));
+
+        bindings.set_str("key_F", "question").unwrap();
+        bindings.set_str("key_space", "chord").unwrap();
+        bindings.set_str("middle", "none").unwrap();
+        assert_eq!(Some(Action::Question), bindings.action(Input::Key('f')));
+        assert_eq!(Some(Action::Chord), bindings.action(Input::Key(' ')));
+        assert_eq!(None, bindings.action(Input::Middle));
+        assert!(bindings.set_str("key_", "flag").is_err());
+        assert!(bindings.set_str("left", "dig").is_err());
+
+        let entries = bindings.entries();
+        assert_eq!(("input.middle".to_string(), "none".to_string()), entries[2]);
+        let mut read = Bindings::default();
+        for (key, value) in &entries {
+            read.set_str(key.strip_prefix("input.").unwrap(), value)
+                .unwrap();
+        }
+        for input in [Input::Left, Input::Middle, Input::Key('f'), Input::Key(' ')] {
+            assert_eq!(bindings.action(input), read.action(input));
+        }
+    }
+
+    #[test]
+    pub fn test_smart() {
+        let mut game = Game::from_layout(3, 1, &[true, false, false]);
+        assert_eq!(
+            Some(Action::Smart),
+            Bindings::default().action(Input::Key(' '))
+        );
+        assert_eq!(Some(Action::Flag), Action::Smart.resolve(&game, 0, 0));
+        game.uncover(1, 0);
+        // a number waits for its flags
+        assert_eq!(None, Action::Smart.resolve(&game, 1, 0));
+        game.flag(0, 0);
+        assert_eq!(Some(Action::Chord), Action::Smart.resolve(&game, 1, 0));
+        assert_eq!(Some(Action::Flag), Action::Smart.resolve(&game, 0, 0));
+        assert_eq!(Some(Action::Uncover), Action::Uncover.resolve(&game, 1, 0));
+        assert_eq!(Ok(Action::Smart), "smart".parse());
+    }
+}
@@ -0,0 +1,470 @@
+use std::{sync::Once, time::SystemTime};
+
+use windows::{
+    core::{w, Result, HSTRING, PCWSTR},
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_COLOR_F, D2D_RECT_F, D2D_SIZE_U},
+                ID2D1Bitmap, ID2D1Factory1, ID2D1HwndRenderTarget,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, D2D1_HWND_RENDER_TARGET_PROPERTIES,
+                D2D1_PRESENT_OPTIONS,
+            },
+            Gdi::{BeginPaint, EndPaint, InvalidateRect, COLOR_WINDOW, HBRUSH, PAINTSTRUCT},
+            Imaging::IWICImagingFactory,
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::{
+            AdjustWindowRect, CreateWindowExW, DefWindowProcW, EnableWindow, GetClientRect,
+            GetWindowLongPtrA, LoadCursorW, PostMessageW, RegisterClassW, SendMessageW,
+            SetWindowLongPtrA, BN_CLICKED, CREATESTRUCTA, CW_USEDEFAULT, GWLP_USERDATA, HMENU,
+            IDC_ARROW, LBN_DBLCLK, LBN_SELCHANGE, LBS_NOINTEGRALHEIGHT, LBS_NOTIFY, LB_ADDSTRING,
+            LB_GETCURSEL, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_COMMAND, WM_CREATE, WM_PAINT,
+            WNDCLASSW, WS_BORDER, WS_CAPTION, WS_CHILD, WS_CHILDWINDOW, WS_OVERLAPPED, WS_SYSMENU,
+            WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+        },
+    },
+};
+
+use crate::{
+    direct2d::{create_hwnd_target, create_image_factory, load_bitmap_from_memory, Rendering},
+    game::Game,
+    save::{self, SavedBoard},
+    snapshot,
+};
+
+/// Posted to the owner of a [`BrowserWindow`] when a board is chosen, the
+/// owner takes it with [`BrowserWindow::chosen`].
+pub(crate) const WM_LOAD_GAME: u32 = WM_APP + 3;
+
+static REGISTER_BROWSER_WINDOW_CLASS: Once = Once::new();
+static REGISTER_PREVIEW_WINDOW_CLASS: Once = Once::new();
+
+const STYLE: WINDOW_STYLE = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0);
+// client area and control layout in pixels
+const LIST_WIDTH: i32 = 360;
+const MARGIN: i32 = 15;
+const LINE_HEIGHT: i32 = 24;
+const LIST_HEIGHT: i32 = 240;
+const BUTTON_WIDTH: i32 = 90;
+const PREVIEW_SIZE: i32 = 240;
+const LIST_ID: usize = 100;
+const LOAD_ID: usize = 101;
+
+const BACKGROUND: D2D1_COLOR_F = D2D1_COLOR_F {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+/// A window listing the saved games and authored boards in the saves folder
+/// of the data directory, the latest first, with a preview of the selected
+/// board. Double clicking a board or pressing Load hands it to the owner.
+pub(crate) struct BrowserWindow<'a> {
+    handle: HWND,
+    owner: HWND,
+    boards: Vec<SavedBoard>,
+    factory: &'a ID2D1Factory1,
+    list: HWND,
+    load: HWND,
+    preview: Option<Box<BoardPreview<'a>>>,
+    selected: Option<usize>,
+}
+
+impl<'a> BrowserWindow<'a> {
+    /// Opens the window over its owner with the boards saved as they are now.
+    pub(crate) fn new(owner: HWND, factory: &'a ID2D1Factory1) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_BROWSER_WINDOW_CLASS.call_once(|| {
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hbrBackground: HBRUSH(COLOR_WINDOW.0 as isize),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-browser"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        let boards = save::saves_dir()
+            .map(|dir| save::browse(&dir))
+            .unwrap_or_default();
+        let mut window = Box::new(BrowserWindow {
+            handle: HWND(0),
+            owner,
+            boards,
+            factory,
+            list: HWND(0),
+            load: HWND(0),
+            preview: None,
+            selected: None,
+        });
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: LIST_WIDTH + PREVIEW_SIZE + MARGIN * 3,
+            bottom: MARGIN * 4 + LINE_HEIGHT * 2 + LIST_HEIGHT,
+        };
+        unsafe { AdjustWindowRect(&mut rect, STYLE, false)? };
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-browser"),
+                w!("Load game"),
+                STYLE | WS_VISIBLE,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                owner,
+                HMENU(0),
+                instance,
+                Some(window.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(window)
+    }
+
+    pub(crate) fn hwnd(&self) -> HWND {
+        self.handle
+    }
+
+    /// The board chosen to play, once [`WM_LOAD_GAME`] was posted.
+    pub(crate) fn chosen(&self) -> Option<Game> {
+        self.selected.map(|index| self.boards[index].game.clone())
+    }
+
+    /// Creates the folder label, the list of boards, the preview and the load
+    /// button, which is enabled once a board is selected.
+    fn create_controls(&mut self) -> Result<()> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        let control = |class: PCWSTR, text: &str, style: WINDOW_STYLE, rect: RECT, id: usize| unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class,
+                &HSTRING::from(text),
+                WS_CHILD | WS_VISIBLE | style,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                self.handle,
+                HMENU(id as isize),
+                instance,
+                None,
+            )
+        };
+        let folder = match save::saves_dir() {
+            Some(dir) => format!("Games and boards in {}", dir.display()),
+            None => "No data directory to keep games in".to_string(),
+        };
+        let right = LIST_WIDTH + PREVIEW_SIZE + MARGIN * 2;
+        let mut top = MARGIN;
+        control(
+            w!("STATIC"),
+            &folder,
+            WINDOW_STYLE(0),
+            RECT {
+                left: MARGIN,
+                top,
+                right,
+                bottom: top + LINE_HEIGHT,
+            },
+            0,
+        );
+        top += LINE_HEIGHT + MARGIN;
+        self.list = control(
+            w!("LISTBOX"),
+            "",
+            WS_BORDER
+                | WS_VSCROLL
+                | WS_TABSTOP
+                | WINDOW_STYLE((LBS_NOINTEGRALHEIGHT | LBS_NOTIFY) as u32),
+            RECT {
+                left: MARGIN,
+                top,
+                right: MARGIN + LIST_WIDTH,
+                bottom: top + LIST_HEIGHT,
+            },
+            LIST_ID,
+        );
+        let now = SystemTime::now();
+        let lines: Vec<String> = match self.boards.is_empty() {
+            true => vec!["No saved games or boards yet".to_string()],
+            false => self.boards.iter().map(|board| board.summary(now)).collect(),
+        };
+        for line in lines {
+            let line = HSTRING::from(line);
+            unsafe {
+                SendMessageW(
+                    self.list,
+                    LB_ADDSTRING,
+                    WPARAM(0),
+                    LPARAM(line.as_ptr() as isize),
+                )
+            };
+        }
+        let left = MARGIN * 2 + LIST_WIDTH;
+        self.preview = Some(BoardPreview::new(
+            self.handle,
+            self.factory,
+            RECT {
+                left,
+                top,
+                right: left + PREVIEW_SIZE,
+                bottom: top + PREVIEW_SIZE,
+            },
+        )?);
+        top += LIST_HEIGHT + MARGIN;
+        self.load = control(
+            w!("BUTTON"),
+            "Load",
+            WS_TABSTOP,
+            RECT {
+                left: right - BUTTON_WIDTH,
+                top,
+                right,
+                bottom: top + LINE_HEIGHT,
+            },
+            LOAD_ID,
+        );
+        unsafe { EnableWindow(self.load, false) };
+        Ok(())
+    }
+
+    /// Shows the board selected in the list, if any.
+    fn select(&mut self) {
+        let index = unsafe { SendMessageW(self.list, LB_GETCURSEL, WPARAM(0), LPARAM(0)) };
+        // the placeholder line of an empty list is not a board
+        self.selected = usize::try_from(index.0)
+            .ok()
+            .filter(|&index| index < self.boards.len());
+        unsafe { EnableWindow(self.load, self.selected.is_some()) };
+        if let Some(preview) = self.preview.as_mut() {
+            preview.show(self.selected.map(|index| &self.boards[index].game));
+        }
+    }
+
+    /// Hands the selected board to the owner.
+    fn choose(&self) {
+        if self.selected.is_some() {
+            let _ = unsafe { PostMessageW(self.owner, WM_LOAD_GAME, WPARAM(0), LPARAM(0)) };
+        }
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_CREATE => {
+                if let Err(error) = self.create_controls() {
+                    tracing::error!(%error, "unable to create the saved game controls");
+                    return LRESULT(-1);
+                }
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                let id = wparam.0 & 0xffff;
+                let notification = ((wparam.0 >> 16) & 0xffff) as u32;
+                match (id, notification) {
+                    (LIST_ID, LBN_SELCHANGE) => self.select(),
+                    (LIST_ID, LBN_DBLCLK) => {
+                        self.select();
+                        self.choose();
+                    }
+                    (LOAD_ID, BN_CLICKED) => self.choose(),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+/// A child window drawing a thumbnail of a board as the player left it. The
+/// board is rendered to a PNG, see [`snapshot::png`], and scaled to fit the
+/// window keeping its shape.
+struct BoardPreview<'a> {
+    handle: HWND,
+    factory: &'a ID2D1Factory1,
+    image_factory: IWICImagingFactory,
+    // the encoded thumbnail and its size in pixels
+    image: Option<(Vec<u8>, (u32, u32))>,
+    target: Option<ID2D1HwndRenderTarget>,
+    bitmap: Option<ID2D1Bitmap>,
+}
+
+impl<'a> BoardPreview<'a> {
+    /// Creates the preview in `rect` of its parent's client area.
+    fn new(parent: HWND, factory: &'a ID2D1Factory1, rect: RECT) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_PREVIEW_WINDOW_CLASS.call_once(|| {
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-preview"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        let mut preview = Box::new(BoardPreview {
+            handle: HWND(0),
+            factory,
+            image_factory: create_image_factory()?,
+            image: None,
+            target: None,
+            bitmap: None,
+        });
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-preview"),
+                w!(""),
+                WS_VISIBLE | WS_CHILDWINDOW,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                parent,
+                HMENU(0),
+                instance,
+                Some(preview.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(preview)
+    }
+
+    /// Draws `game`, or nothing when no board is selected.
+    fn show(&mut self, game: Option<&Game>) {
+        self.image = game.map(|game| {
+            // whole pixels per cell so the grid stays even
+            let cell = (PREVIEW_SIZE / game.width().max(game.height()) as i32).max(1) as u32;
+            let size = (game.width() as u32 * cell, game.height() as u32 * cell);
+            (snapshot::png(game, &[]), size)
+        });
+        self.bitmap = None;
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    fn create_device_resources(&mut self) -> Result<()> {
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(self.handle, &mut rect)? };
+        let hwnd_props = D2D1_HWND_RENDER_TARGET_PROPERTIES {
+            hwnd: self.handle,
+            pixelSize: D2D_SIZE_U {
+                width: (rect.right - rect.left) as u32,
+                height: (rect.bottom - rect.top) as u32,
+            },
+            presentOptions: D2D1_PRESENT_OPTIONS::default(),
+        };
+        let (target, _) = create_hwnd_target(self.factory, &hwnd_props, Rendering::Hardware)?;
+        self.target = Some(target);
+        Ok(())
+    }
+
+    fn release_device_resources(&mut self) {
+        self.bitmap = None;
+        self.target = None;
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.target.is_none() {
+            self.create_device_resources()?;
+        }
+        let target = self.target.as_ref().unwrap();
+        if let (None, Some((png, size))) = (&self.bitmap, &self.image) {
+            self.bitmap = Some(load_bitmap_from_memory(
+                png,
+                target,
+                &self.image_factory,
+                Some(*size),
+            )?);
+        }
+        unsafe {
+            target.BeginDraw();
+            target.Clear(Some(&BACKGROUND));
+            if let (Some(bitmap), Some((_, (width, height)))) = (&self.bitmap, &self.image) {
+                // centered, the thumbnail is never larger than the window
+                let area = target.GetSize();
+                let (width, height) = (*width as f32, *height as f32);
+                let rect = D2D_RECT_F {
+                    left: (area.width - width) / 2.0,
+                    top: (area.height - height) / 2.0,
+                    right: (area.width + width) / 2.0,
+                    bottom: (area.height + height) / 2.0,
+                };
+                target.DrawBitmap(
+                    bitmap,
+                    Some(&rect),
+                    1.0,
+                    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                    None,
+                );
+            }
+            target.EndDraw(None, None)
+        }
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                unsafe {
+                    BeginPaint(self.handle, &mut ps);
+                    if let Err(error) = self.render() {
+                        // the resources are recreated on the next paint
+                        tracing::error!(%error, "unable to render the board preview");
+                        self.release_device_resources();
+                    }
+                    EndPaint(self.handle, &ps);
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
@@ -0,0 +1,393 @@
+use std::sync::Once;
+
+use windows::{
+    core::{w, Result},
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_COLOR_F, D2D_POINT_2F, D2D_RECT_F, D2D_SIZE_U},
+                ID2D1Factory1, ID2D1HwndRenderTarget, ID2D1SolidColorBrush,
+                D2D1_HWND_RENDER_TARGET_PROPERTIES, D2D1_PRESENT_OPTIONS,
+            },
+            Gdi::{BeginPaint, EndPaint, PAINTSTRUCT},
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, GetClientRect, GetWindowLongPtrA, LoadCursorW,
+            RegisterClassW, SetWindowLongPtrA, CREATESTRUCTA, GWLP_USERDATA, HMENU, IDC_ARROW,
+            WINDOW_EX_STYLE, WM_CREATE, WM_PAINT, WNDCLASSW, WS_CHILDWINDOW, WS_VISIBLE,
+        },
+    },
+};
+
+use crate::{
+    direct2d::{create_brush, create_hwnd_target, Rendering},
+    stats::{Heatmap, Histogram},
+};
+
+static REGISTER_CHART_WINDOW_CLASS: Once = Once::new();
+static REGISTER_PLOT_WINDOW_CLASS: Once = Once::new();
+
+const BACKGROUND: D2D1_COLOR_F = D2D1_COLOR_F {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+// areas are filled with this color, more opaque the more games were lost there
+const HOT: (f32, f32, f32) = (0.85, 0.1, 0.05);
+const GRID: (f32, f32, f32) = (0.75, 0.75, 0.75);
+// trend lines and histogram bars
+const PLOT: (f32, f32, f32) = (0.1, 0.35, 0.75);
+// horizontal grid lines dividing a plot's range
+const PLOT_DIVISIONS: usize = 4;
+// space between the plot and the edges of its window in DIPs
+const PLOT_MARGIN: f32 = 4.0;
+
+/// A child window drawing where games were lost over a board, the areas with
+/// more fatal clicks in stronger colors.
+pub(crate) struct HeatmapChart<'a> {
+    handle: HWND,
+    heatmap: Heatmap,
+    factory: &'a ID2D1Factory1,
+    target: Option<ID2D1HwndRenderTarget>,
+    hot_brush: Option<ID2D1SolidColorBrush>,
+    grid_brush: Option<ID2D1SolidColorBrush>,
+}
+
+impl<'a> HeatmapChart<'a> {
+    /// Creates the chart in `rect` of its parent's client area.
+    pub(crate) fn new(
+        parent: HWND,
+        factory: &'a ID2D1Factory1,
+        heatmap: Heatmap,
+        rect: RECT,
+    ) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_CHART_WINDOW_CLASS.call_once(|| {
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-chart"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        let mut chart = Box::new(HeatmapChart {
+            handle: HWND(0),
+            heatmap,
+            factory,
+            target: None,
+            hot_brush: None,
+            grid_brush: None,
+        });
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-chart"),
+                w!(""),
+                WS_VISIBLE | WS_CHILDWINDOW,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                parent,
+                HMENU(0),
+                instance,
+                Some(chart.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(chart)
+    }
+
+    fn create_device_resources(&mut self) -> Result<()> {
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(self.handle, &mut rect)? };
+        let hwnd_props = D2D1_HWND_RENDER_TARGET_PROPERTIES {
+            hwnd: self.handle,
+            pixelSize: D2D_SIZE_U {
+                width: (rect.right - rect.left) as u32,
+                height: (rect.bottom - rect.top) as u32,
+            },
+            presentOptions: D2D1_PRESENT_OPTIONS::default(),
+        };
+        let (target, _) = create_hwnd_target(self.factory, &hwnd_props, Rendering::Hardware)?;
+        self.hot_brush = Some(create_brush(&target, HOT.0, HOT.1, HOT.2, 1.0)?);
+        self.grid_brush = Some(create_brush(&target, GRID.0, GRID.1, GRID.2, 1.0)?);
+        self.target = Some(target);
+        Ok(())
+    }
+
+    fn release_device_resources(&mut self) {
+        self.hot_brush = None;
+        self.grid_brush = None;
+        self.target = None;
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.target.is_none() {
+            self.create_device_resources()?;
+        }
+        let target = self.target.as_ref().unwrap();
+        let hot = self.hot_brush.as_ref().unwrap();
+        let grid = self.grid_brush.as_ref().unwrap();
+        let areas = self.heatmap.size();
+        unsafe {
+            // sizes are in DIPs, whatever the display scaling
+            let size = target.GetSize();
+            let (width, height) = (size.width / areas as f32, size.height / areas as f32);
+            target.BeginDraw();
+            target.Clear(Some(&BACKGROUND));
+            for row in 0..areas {
+                for column in 0..areas {
+                    let rect = D2D_RECT_F {
+                        left: column as f32 * width,
+                        top: row as f32 * height,
+                        right: (column + 1) as f32 * width,
+                        bottom: (row + 1) as f32 * height,
+                    };
+                    let intensity = self.heatmap.intensity(column, row);
+                    if intensity > 0.0 {
+                        hot.SetOpacity(intensity);
+                        target.FillRectangle(&rect, hot);
+                    }
+                    target.DrawRectangle(&rect, grid, 1.0, None);
+                }
+            }
+            target.EndDraw(None, None)
+        }
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                unsafe {
+                    BeginPaint(self.handle, &mut ps);
+                    if let Err(error) = self.render() {
+                        // the resources are recreated on the next paint
+                        tracing::error!(%error, "unable to render the heatmap");
+                        self.release_device_resources();
+                    }
+                    EndPaint(self.handle, &ps);
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+/// What a [`PlotChart`] draws.
+#[derive(Debug, Clone)]
+pub(crate) enum Plot {
+    /// Values from left to right as a line, from 0 at the bottom to `max` at
+    /// the top.
+    Line { values: Vec<f64>, max: f64 },
+    /// The bins of a histogram as bars, the fullest reaching the top.
+    Bars(Histogram),
+}
+
+/// A child window drawing a trend line or a histogram over a grid.
+pub(crate) struct PlotChart<'a> {
+    handle: HWND,
+    plot: Plot,
+    factory: &'a ID2D1Factory1,
+    target: Option<ID2D1HwndRenderTarget>,
+    plot_brush: Option<ID2D1SolidColorBrush>,
+    grid_brush: Option<ID2D1SolidColorBrush>,
+}
+
+impl<'a> PlotChart<'a> {
+    /// Creates the chart in `rect` of its parent's client area.
+    pub(crate) fn new(
+        parent: HWND,
+        factory: &'a ID2D1Factory1,
+        plot: Plot,
+        rect: RECT,
+    ) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_PLOT_WINDOW_CLASS.call_once(|| {
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-plot"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        let mut chart = Box::new(PlotChart {
+            handle: HWND(0),
+            plot,
+            factory,
+            target: None,
+            plot_brush: None,
+            grid_brush: None,
+        });
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-plot"),
+                w!(""),
+                WS_VISIBLE | WS_CHILDWINDOW,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                parent,
+                HMENU(0),
+                instance,
+                Some(chart.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(chart)
+    }
+
+    fn create_device_resources(&mut self) -> Result<()> {
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(self.handle, &mut rect)? };
+        let hwnd_props = D2D1_HWND_RENDER_TARGET_PROPERTIES {
+            hwnd: self.handle,
+            pixelSize: D2D_SIZE_U {
+                width: (rect.right - rect.left) as u32,
+                height: (rect.bottom - rect.top) as u32,
+            },
+            presentOptions: D2D1_PRESENT_OPTIONS::default(),
+        };
+        let (target, _) = create_hwnd_target(self.factory, &hwnd_props, Rendering::Hardware)?;
+        self.plot_brush = Some(create_brush(&target, PLOT.0, PLOT.1, PLOT.2, 1.0)?);
+        self.grid_brush = Some(create_brush(&target, GRID.0, GRID.1, GRID.2, 1.0)?);
+        self.target = Some(target);
+        Ok(())
+    }
+
+    fn release_device_resources(&mut self) {
+        self.plot_brush = None;
+        self.grid_brush = None;
+        self.target = None;
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.target.is_none() {
+            self.create_device_resources()?;
+        }
+        let target = self.target.as_ref().unwrap();
+        let plot = self.plot_brush.as_ref().unwrap();
+        let grid = self.grid_brush.as_ref().unwrap();
+        unsafe {
+            // sizes are in DIPs, whatever the display scaling
+            let size = target.GetSize();
+            let area = D2D_RECT_F {
+                left: PLOT_MARGIN,
+                top: PLOT_MARGIN,
+                right: size.width - PLOT_MARGIN,
+                bottom: size.height - PLOT_MARGIN,
+            };
+            let (width, height) = (area.right - area.left, area.bottom - area.top);
+            target.BeginDraw();
+            target.Clear(Some(&BACKGROUND));
+            for division in 0..=PLOT_DIVISIONS {
+                let y = area.top + height * division as f32 / PLOT_DIVISIONS as f32;
+                target.DrawLine(
+                    D2D_POINT_2F { x: area.left, y },
+                    D2D_POINT_2F { x: area.right, y },
+                    grid,
+                    1.0,
+                    None,
+                );
+            }
+            match &self.plot {
+                Plot::Line { values, max } if values.len() > 1 && *max > 0.0 => {
+                    let step = width / (values.len() - 1) as f32;
+                    let point = |index: usize| D2D_POINT_2F {
+                        x: area.left + step * index as f32,
+                        y: area.bottom - height * (values[index] / max).clamp(0.0, 1.0) as f32,
+                    };
+                    for index in 1..values.len() {
+                        target.DrawLine(point(index - 1), point(index), plot, 2.0, None);
+                    }
+                }
+                Plot::Bars(histogram) if histogram.max() > 0 => {
+                    let bins = histogram.counts.len() as f32;
+                    let bar = width / bins;
+                    for (bin, &count) in histogram.counts.iter().enumerate() {
+                        let rect = D2D_RECT_F {
+                            left: area.left + bar * bin as f32 + 1.0,
+                            top: area.bottom - height * count as f32 / histogram.max() as f32,
+                            right: area.left + bar * (bin + 1) as f32 - 1.0,
+                            bottom: area.bottom,
+                        };
+                        target.FillRectangle(&rect, plot);
+                    }
+                }
+                // too little to plot yet, the grid alone is drawn
+                _ => {}
+            }
+            target.EndDraw(None, None)
+        }
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                unsafe {
+                    BeginPaint(self.handle, &mut ps);
+                    if let Err(error) = self.render() {
+                        // the resources are recreated on the next paint
+                        tracing::error!(%error, "unable to render the plot");
+                        self.release_device_resources();
+                    }
+                    EndPaint(self.handle, &ps);
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
@@ -0,0 +1,164 @@
+//! Practice checkpoints the player can set and later go back to.
+//!
+//! [`Checkpoints`] keeps up to [`SLOTS`] [`Snapshot`]s of the game it is used
+//! with. Going back to one restores the board as it was and marks the game
+//! as [practice](Game::practice), so it is left out of the statistics. The
+//! checkpoints belong to one board and are cleared when a new one starts.
+
+use std::time::Duration;
+
+use crate::game::{Game, GameState, Snapshot};
+
+/// Number of checkpoint slots.
+pub const SLOTS: usize = 3;
+
+/// A game as it was when a checkpoint was set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The board when the checkpoint was set.
+    pub snapshot: Snapshot,
+    /// Time played when the checkpoint was set.
+    pub elapsed: Duration,
+}
+
+/// The checkpoint slots of a game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoints {
+    slots: [Option<Checkpoint>; SLOTS],
+    latest: Option<usize>,
+}
+
+impl Checkpoints {
+    /// No checkpoints set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a checkpoint in `slot`, counting from 0, replacing the one there.
+    /// Returns false, setting nothing, for a slot past [`SLOTS`] or a game
+    /// that is over.
+    pub fn set(&mut self, slot: usize, game: &Game) -> bool {
+        if slot >= SLOTS || matches!(game.state(), GameState::Won | GameState::Lost) {
+            return false;
+        }
+        self.slots[slot] = Some(Checkpoint {
+            snapshot: game.snapshot(),
+            elapsed: game.elapsed(),
+        });
+        self.latest = Some(slot);
+        tracing::debug!(slot, "checkpoint set");
+        true
+    }
+
+    /// Sets a checkpoint in the first free slot, or the slot after the
+    /// latest once they are all used, and returns the slot.
+    pub fn push(&mut self, game: &Game) -> Option<usize> {
+        let slot = match self.slots.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => self.latest.map_or(0, |latest| (latest + 1) % SLOTS),
+        };
+        self.set(slot, game).then_some(slot)
+    }
+
+    /// Puts the game back to the checkpoint in `slot` and marks it as
+    /// practice. Returns false if the slot is empty.
+    pub fn revert(&self, slot: usize, game: &mut Game) -> bool {
+        let Some(checkpoint) = self.get(slot) else {
+            return false;
+        };
+        game.restore(&checkpoint.snapshot);
+        game.set_practice(true);
+        tracing::debug!(slot, "reverted to checkpoint");
+        true
+    }
+
+    /// The checkpoint in `slot`, if one is set.
+    pub fn get(&self, slot: usize) -> Option<&Checkpoint> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    /// The slot of the checkpoint set last.
+    pub fn latest(&self) -> Option<usize> {
+        self.latest
+    }
+
+    /// Whether no checkpoint is set.
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Forgets every checkpoint, for a new board.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The slots as one line for a panel, numbered from 1 with the time each
+    /// was set and the latest starred, e.g. `1 0:42*  2 -  3 -`.
+    pub fn panel(&self) -> String {
+        let slots: Vec<String> = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(slot, checkpoint)| match checkpoint {
+                Some(checkpoint) => {
+                    let seconds = checkpoint.elapsed.as_secs();
+                    let latest = if self.latest == Some(slot) { "*" } else { "" };
+                    format!(
+                        "{} {}:{:02}{}",
+                        slot + 1,
+                        seconds / 60,
+                        seconds % 60,
+                        latest
+                    )
+                }
+                None => format!("{} -", slot + 1),
+            })
+            .collect();
+        slots.join("  ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::CellState;
+
+    #[test]
+    pub fn test_checkpoints() {
+        //   * . .
+        //   . . .
+        let mut layout = vec![false; 6];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 2, &layout);
+        let mut checkpoints = Checkpoints::new();
+        assert!(checkpoints.is_empty());
+        assert_eq!("1 -  2 -  3 -", checkpoints.panel());
+
+        game.uncover(2, 1);
+        assert_eq!(Some(0), checkpoints.push(&game));
+        assert!(!checkpoints.set(SLOTS, &game));
+        assert_eq!("1 0:00*  2 -  3 -", checkpoints.panel());
+
+        // a lost game goes back to the checkpoint as practice
+        game.uncover(0, 0);
+        assert_eq!(GameState::Lost, game.state());
+        assert_eq!(None, checkpoints.push(&game));
+        assert!(!checkpoints.revert(1, &mut game));
+        assert!(checkpoints.revert(0, &mut game));
+        assert_eq!(GameState::Playing, game.state());
+        assert_eq!(CellState::Unknown(true), game.cell_state(0, 0));
+        assert!(game.practice());
+
+        // full slots are reused after the latest
+        assert_eq!(Some(1), checkpoints.push(&game));
+        assert_eq!(Some(2), checkpoints.push(&game));
+        assert_eq!(Some(0), checkpoints.push(&game));
+        assert_eq!(Some(0), checkpoints.latest());
+        checkpoints.clear();
+        assert!(checkpoints.is_empty() && checkpoints.get(0).is_none());
+
+        // a new board is played for real again
+        game.reset_with_seed(1);
+        assert!(!game.practice());
+    }
+}
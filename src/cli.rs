@@ -1,14 +1,50 @@
-mod game;
+mod bench;
+mod explain;
+mod generate;
+mod tui;
 
-use crate::game::Game;
-use std::io::{self, Result};
+#[cfg(feature = "logging")]
+use minesweeper_d2d::logging;
+use minesweeper_d2d::{
+    checkpoint, config, crash, csv, drill, game, infinite, inspect, json, multi, patterns, print,
+    race, render, replay, save, score, serve, share, snapshot, solver, splits, stats, text, theme,
+    versus,
+};
+
+use crate::{
+    checkpoint::Checkpoints,
+    config::Config,
+    drill::{Drill, DrillStats, Pattern},
+    game::{Density, Difficulty, Game, GameState, Handicap, Move, Neighborhood, Symmetry, Zone},
+    infinite::{InfiniteBoard, Viewport},
+    multi::MultiBoard,
+    patterns::PatternHint,
+    race::Race,
+    render::Overlay,
+    replay::{Action, Replay},
+    solver::Deduction,
+    splits::Timer,
+    text::{Glyphs, Style, TextRenderer},
+    theme::ThemeFile,
+    tui::{Button, Event},
+    versus::Match,
+};
+use std::{
+    env,
+    io::{self, IsTerminal, Result},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 const BOARD_WIDTH: i16 = 10;
 const BOARD_HEIGHT: i16 = 5;
-
-fn main() -> Result<()> {
-    println!(
-        r#"
+// the part of an endless board shown at once
+const VIEW_WIDTH: i16 = 30;
+const VIEW_HEIGHT: i16 = 16;
+const BENCH_BOARDS: usize = 10;
+const GENERATE_BOARDS: usize = 10;
+const HELP: &str = r#"
 Minesweeper CLI
 ----------------------------------------
 The Minesweeper CLI application is a simple testbed
@@ -17,48 +53,1733 @@ for the game logic.
 Commands:
 ----------------------------------------
 x       Exit
-r       Restart 
+r       Restart
+a       Again: restart this board with the same mines, the
+        game is then practice and not recorded
 u[x,y]  Uncover a tile at the coordinates
 f[x,y]  Flag a mine at the coordinates
 ?[x,y]  Mark as unknown at the coordinates
-    "#
+c[x,y]  Chord: uncover around a number once its mines are flagged
+i[x,y]  Inspect a cell: what it shows and, in a practice game, the
+        chance of it being a mine
+z       Undo the last move, taking back the move that ended
+        the game makes it practice and not recorded
+y       Redo the last undone move
+h       Hint: show a cell the solver can prove safe (or a mine),
+        adding 20 seconds to the time of the game
+k[n]    Keep a practice checkpoint in slot n (1 to 3, the next
+        free one if left out)
+b[n]    Go back to the checkpoint in slot n (the latest if left
+        out), the game is then practice and not recorded
+s file  Save the game to a file
+l file  Load a game from a file
+p       Share: once the game is over, print its level, time and
+        3BV/s with a map of the opening that gives nothing away
+
+Subcommands:
+----------------------------------------
+stats           Print games played, win rate and best times per level,
+                win streaks, the games of the last session and the
+                drills played
+export <file>   Write every game played to a CSV file and the results
+                per level next to it in file-summary.csv, for
+                spreadsheets
+daily           Play today's board, the same for everyone, and keep a
+                streak of daily wins
+generate [--count n] [--width w] [--height h] [--mines m]
+         [--density d] [--seed n] [--out dir] [--no-guess]
+                Write n board layouts as save files to dir (boards by
+                default), with m mines or as many as --density gives
+                the size. No guess boards can be cleared by the solver
+                and are saved with their opening uncovered
+drill <pattern> Drill a classic pattern on small boards turned at random:
+                121, 1221 or 5050 for the edge 50/50, each board is
+                timed until it is cleared and kept apart from the
+                games in the stats, r skips a board and x stops
+infinite [d]    Play an endless board laid out as it is explored with d
+                of the cells mined (0.16 by default), w a s d scroll
+                the view, coordinates are those shown and the score is
+                the safe cells uncovered before a mine is hit
+replay <file>   Step through a recorded game, enter shows the next
+                move, p the previous one and x exits
+print <board> <file> [--solution]
+                Draw a saved board, such as one from generate, as a
+                PDF page for solving on paper, followed by a page with
+                its solution
+verify <file>   Play a replay again and check that it ends with the
+                result, time and board it claims
+animate <replay> <file>
+                Draw a recorded game as an animated PNG, a frame per
+                move, for sharing the run
+race host [port]
+                Wait for an opponent to join and race them on the same
+                board, chosen with --difficulty and --seed, both
+                players' progress is shown with every board
+race join <host[:port]>
+                Join a race hosted on another machine
+
+Options:
+----------------------------------------
+--bench [n]     Time flood fill and solver runs over n large boards
+--json          Print every result as a JSON document
+--style <style> Board glyphs: emoji, unicode (default) or ascii
+--glyphs <list> Override glyphs per state, e.g. flag=F,covered=#
+                (states: covered, blank, mine, flag, question)
+                Both can also be set in config.txt next to the stats,
+                over the glyphs of the config's theme
+--seed <n>      Lay out the first board from a seed
+--record <file> Record the game as a replay, restarting or loading a
+                game starts a new recording
+--explain       After each uncover show the flood fill wave by wave,
+                the frontier and every deduction the solver can make
+--difficulty <level>
+                Play beginner (9x9, 10 mines), intermediate
+                (16x16, 40 mines) or expert (30x16, 99 mines)
+--plain         Print a new board after every command instead of
+                redrawing it in place on the alternate screen, where
+                left click uncovers and right click flags a cell
+--accessible    Describe the board in words row by row and announce
+                the effect of each move, for screen readers
+--versus        Two players take turns on the same board, uncovered
+                cells score a point each, flags are settled at the
+                end and whoever uncovers a mine loses
+--serve [port]  Publish the game on http://localhost:port/ (4808 by
+                default) for stream overlays: the page shows the board
+                and timer, /state and /stats are JSON and /board.png
+                a snapshot of the board
+--splits        Time the first click and clearing 25, 50 and 75% of the
+                board's 3BV and the finish, shown with every board
+                against the best run on the level
+--score         Show the arcade score with every board: points for the
+                cells cleared and chords, times a speed multiplier,
+                less the wrong flags once the game is over
+--boards <n>    Play 2 to 4 boards at once, tiled with the first at the
+                top left, beginner boards unless --difficulty says
+                otherwise, the round is lost if any of them blows up
+--density <d>   Mines on the default board: a count (40), a share of
+                the cells (15%) or a curve over the number of cells
+                (curve a b c for a*cells^2 + b*cells + c), also set
+                with density in config.txt
+--nf            Play without flags or question marks, no flag games
+                are ranked apart from flagged ones in the stats
+--neighborhood <rule>
+                Count the mines of the 8 adjacent cells (adjacent, the
+                default) or of the 24 cells in the 5x5 square around a
+                number (distance2), variant games are not recorded
+--blind <s>     Fade every number s seconds after it is uncovered, to
+                be remembered until the game is over, blind games are
+                not recorded
+--symmetry <s>  Lay out the mines mirrored left to right (horizontal),
+                top to bottom (vertical) or turned half a turn
+                (rotational), with a mine more when the count cannot
+                be mirrored
+--zone <x,y,w,h,weight%>
+                Lay out the mines of a w by h rectangle from column x
+                and row y weight% as densely as the rest, such as
+                15,0,15,16,200% for a hard right half of an expert
+                board, repeat for more zones, zoned games are not
+                recorded
+--handicap <n>  Start every board with n random safe cells uncovered,
+                or with opening for one random opening, handicapped
+                games are not recorded
+--flag-penalty  Refuse a flag on a safe cell and add 10 s to the clock
+                for it, games with the penalty are not recorded
+--safe-chords   Refuse a chord the solver can prove careless, with a
+                flag on a safe cell or a mine among the cells it opens
+--auto-open     After every move uncover the cells a single number
+                proves safe, games are ranked as assisted in the stats
+--auto-flag     After every move flag the cells the solver proves
+                mines, shown in their own color, nothing is uncovered
+                for you, games are ranked as assisted in the stats
+                and do not count as speedruns
+--hints <n>     Allow n hints per game, games with hints are ranked
+                apart in the stats
+--log <level>   Write traces at error, warn, info, debug or trace level
+                to a daily log file in the data directory, also set
+                with log in config.txt
+"#;
+
+const TUI_HEADER: &str =
+    "Minesweeper  u[x,y] uncover  f[x,y] flag  ?[x,y] question  c[x,y] chord\n\
+     h hint  z undo  y redo  k/b checkpoint keep/back  s/l file save/load  r restart  a again  x exit\n\
+     left click uncover  right click flag";
+
+enum Command {
+    Exit,
+    Restart,
+    Again,
+    Uncover(i16, i16),
+    Flag(i16, i16),
+    Question(i16, i16),
+    Chord(i16, i16),
+    Inspect(i16, i16),
+    Undo,
+    Redo,
+    Hint,
+    Keep(Option<usize>),
+    Back(Option<usize>),
+    Save(String),
+    Load(String),
+    Share,
+    #[cfg(feature = "dev-tools")]
+    Debug,
+}
+
+impl Command {
+    /// Returns the cell a move command acts on.
+    fn cell(&self) -> Option<(i16, i16)> {
+        match *self {
+            Command::Uncover(x, y)
+            | Command::Flag(x, y)
+            | Command::Question(x, y)
+            | Command::Chord(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    /// Returns the command as a replay action if it changes the board.
+    fn action(&self) -> Option<Action> {
+        let mv = match *self {
+            Command::Uncover(x, y) => Move::Uncover(x, y),
+            Command::Flag(x, y) => Move::Flag(x, y),
+            Command::Question(x, y) => Move::Question(x, y),
+            Command::Chord(x, y) => Move::Chord(x, y),
+            Command::Undo => return Some(Action::Undo),
+            Command::Redo => return Some(Action::Redo),
+            _ => return None,
+        };
+        Some(Action::Play(mv))
+    }
+}
+
+/// Command line options for the CLI.
+#[derive(Default)]
+struct Options {
+    bench: Option<usize>,
+    json: bool,
+    style: Option<Style>,
+    glyphs: Glyphs,
+    seed: Option<u64>,
+    difficulty: Option<Difficulty>,
+    /// File the session is recorded to as a replay.
+    record: Option<String>,
+    /// Number of boards played at once instead of a single game.
+    boards: Option<usize>,
+    /// Share of mined cells of the endless board played instead.
+    infinite: Option<f64>,
+    /// Pattern drilled on small boards.
+    drill: Option<Pattern>,
+    /// Replay file to step through instead of playing.
+    replay: Option<String>,
+    /// Replay file whose claimed result is checked.
+    verify: Option<String>,
+    /// Saved board printed to a PDF file, with its solution page or not.
+    print: Option<(String, String, bool)>,
+    /// Replay drawn as an animated PNG and the file it is written to.
+    animate: Option<(String, String)>,
+    generate: Option<generate::Settings>,
+    /// Day of the daily challenge being played.
+    daily: Option<u64>,
+    stats: bool,
+    /// CSV file the game history is exported to.
+    export: Option<String>,
+    plain: bool,
+    accessible: bool,
+    explain: bool,
+    versus: bool,
+    splits: bool,
+    /// Whether the arcade score is shown with every board.
+    score: bool,
+    /// Mines on the default board, the config file's when not set.
+    density: Option<Density>,
+    /// Whether flags and question marks are refused.
+    no_flags: bool,
+    /// Whether flags on safe cells are refused and cost time.
+    flag_penalty: bool,
+    /// Cells the numbers count the mines of.
+    neighborhood: Neighborhood,
+    /// Seconds numbers stay shown, never fading when not set.
+    blind: Option<u16>,
+    /// Symmetry of the mine layouts.
+    symmetry: Symmetry,
+    /// Rectangles laid out more or less densely, the later over the earlier.
+    zones: Vec<Zone>,
+    /// Cells uncovered for the player on every board.
+    handicap: Option<Handicap>,
+    /// Whether chords the solver can prove careless are refused.
+    safe_chords: bool,
+    /// Whether cells proven safe by a single number open on their own.
+    auto_open: bool,
+    /// Whether mines the solver proves are flagged on their own.
+    auto_flag: bool,
+    /// Hints allowed per game, unlimited when not set.
+    hints: Option<u16>,
+    /// Side of the race being hosted or joined.
+    race: Option<Role>,
+    /// Port the overlay server listens on.
+    serve: Option<u16>,
+    /// Level of the traces written to the log file.
+    #[cfg(feature = "logging")]
+    log: Option<tracing::Level>,
+}
+
+/// An interactive game on stdin and stdout.
+struct Session {
+    options: Options,
+    style: Style,
+    glyphs: Glyphs,
+    // when the config file the style and glyphs came from was written
+    config_modified: Option<SystemTime>,
+    game: Game,
+    hint: Option<Deduction>,
+    // the pattern a hint named, outlined like the hint
+    pattern: Option<PatternHint>,
+    // messages shown with the next board
+    messages: Vec<String>,
+    screen: Option<tui::Screen>,
+    recording: Option<Replay>,
+    // turns and scores when two players share the board
+    versus: Option<Match>,
+    // the opponent when racing on identical boards
+    race: Option<Race>,
+    // the game published by the overlay server
+    published: Option<serve::Shared>,
+    // speedrun splits of the current game
+    splits: Option<Timer>,
+    // practice checkpoints of the current game
+    checkpoints: Checkpoints,
+    // the current game ended once already and was written to the statistics,
+    // undo and redo can end it again
+    recorded: bool,
+    // the hidden mines and the solver's deductions shown over the board
+    #[cfg(feature = "dev-tools")]
+    debug: bool,
+}
+
+/// Which side of a race this player is on.
+enum Role {
+    /// Host the race on a port.
+    Host(u16),
+    /// Join the race at an address.
+    Join(String),
+}
+
+fn main() -> Result<()> {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    crash::install(|path| eprintln!("A crash report was written to {}", path.display()));
+    #[cfg(feature = "logging")]
+    let _log = start_logging(&options);
+    if let Some(boards) = options.bench {
+        bench::run(boards);
+        return Ok(());
+    }
+    if options.stats {
+        return print_stats();
+    }
+    if let Some(path) = &options.export {
+        return export_stats(path);
+    }
+    if let Some(settings) = &options.generate {
+        let attempts = generate::run(settings)?;
+        println!(
+            "Wrote {} boards to {} ({} layouts tried)",
+            settings.count,
+            settings.out.display(),
+            attempts
+        );
+        return Ok(());
+    }
+    if let Some(path) = &options.replay {
+        return watch(&options, path);
+    }
+    if let Some(density) = options.infinite {
+        return play_infinite(&options, density);
+    }
+    if let Some(pattern) = options.drill {
+        return play_drills(&options, pattern);
+    }
+    if let Some(count) = options.boards {
+        return play_multi(&options, count);
+    }
+    if let Some(path) = &options.verify {
+        return verify(path);
+    }
+    if let Some((board, path, solution)) = &options.print {
+        return print_board(board, path, *solution);
+    }
+    if let Some((replay, path)) = &options.animate {
+        return animate(replay, path);
+    }
+    let race = lobby(&options)?;
+    Session::new(options, race)?.run()
+}
+
+impl Session {
+    fn new(options: Options, race: Option<Race>) -> Result<Self> {
+        let mut game = match &race {
+            Some(race) => {
+                let mut game = race.game();
+                game.set_no_flags(options.no_flags);
+                game.set_flag_penalty(options.flag_penalty);
+                game.set_auto_open(options.auto_open);
+                game.set_hint_budget(options.hints);
+                game
+            }
+            None => new_game(&options),
+        };
+        if let (Some(seed), None) = (options.seed, &race) {
+            game.reset_with_seed(seed);
+        }
+        let config_modified = Config::modified();
+        let (style, glyphs) = board_glyphs(&options);
+        let recording = options.record.as_ref().map(|_| Replay::new(&game));
+        let versus = options.versus.then(|| Match::new(&game));
+        let splits = options.splits.then(|| Timer::new(&game));
+        let mut messages = Vec::new();
+        let published = match options.serve {
+            Some(port) => {
+                // only this machine's streaming software needs to see the game
+                let listener = TcpListener::bind(("127.0.0.1", port))?;
+                let shared = serve::Shared::new();
+                serve::serve(listener, shared.clone());
+                messages.push(format!("Overlay at http://localhost:{}/", port));
+                Some(shared)
+            }
+            None => None,
+        };
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+        let screen = if options.json || options.plain || options.accessible || !interactive {
+            None
+        } else {
+            Some(tui::Screen::enter()?)
+        };
+        Ok(Session {
+            options,
+            style,
+            glyphs,
+            config_modified,
+            game,
+            hint: None,
+            pattern: None,
+            messages,
+            screen,
+            recording,
+            versus,
+            race,
+            published,
+            splits,
+            checkpoints: Checkpoints::new(),
+            recorded: false,
+            #[cfg(feature = "dev-tools")]
+            debug: false,
+        })
+    }
+
+    fn run(&mut self) -> Result<()> {
+        if !self.options.json && self.screen.is_none() {
+            println!("{}", HELP);
+        }
+        self.show("")?;
+        crash::set_game(&self.game, None);
+        let mut buf = String::new();
+        loop {
+            let input = match self.screen.as_mut() {
+                Some(screen) => match screen.read_event()? {
+                    Event::Line(line) => line.trim().to_string(),
+                    Event::Click(button, column, row) => {
+                        match click_command(button, screen.cell_at(column, row), &self.game) {
+                            Some(input) => input,
+                            None => continue,
+                        }
+                    }
+                    Event::Eof => break,
+                },
+                None => {
+                    buf.clear();
+                    if io::stdin().read_line(&mut buf)? == 0 {
+                        break;
+                    }
+                    buf.trim().to_string()
+                }
+            };
+            self.reload_config();
+            match parse_command(&input, &self.game) {
+                Ok(Command::Exit) => break,
+                Ok(command) => {
+                    let executed = self.execute(command, &input);
+                    let last = self.game.moves().last().map(|&(mv, _)| mv);
+                    crash::set_game(&self.game, last);
+                    if executed {
+                        self.show(&input)?;
+                        continue;
+                    }
+                }
+                Err(message) => self.error(&input, message),
+            }
+            // a redraw is needed for the error to be seen on the alternate screen
+            if self.screen.is_some() {
+                self.show(&input)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a command, returning false if it failed and there is no new board
+    /// to show.
+    fn execute(&mut self, command: Command, input: &str) -> bool {
+        let previous = self.game.state();
+        let target = command.cell();
+        let action = command.action();
+        let restarted = matches!(
+            command,
+            Command::Restart | Command::Again | Command::Load(_)
+        );
+        let covered = text::covered(&self.game);
+        let before = self.options.explain.then(|| self.game.clone());
+        if self.versus.is_some()
+            && matches!(
+                command,
+                Command::Question(..)
+                    | Command::Undo
+                    | Command::Redo
+                    | Command::Hint
+                    | Command::Keep(_)
+                    | Command::Back(_)
+                    | Command::Load(_)
+            )
+        {
+            self.error(input, "not available in a versus match");
+            return false;
+        }
+        if self.race.is_some()
+            && matches!(
+                command,
+                Command::Restart
+                    | Command::Again
+                    | Command::Undo
+                    | Command::Redo
+                    | Command::Hint
+                    | Command::Keep(_)
+                    | Command::Back(_)
+                    | Command::Load(_)
+            )
+        {
+            self.error(input, "not available in a race");
+            return false;
+        }
+        if self.game.no_flags() && matches!(command, Command::Flag(..) | Command::Question(..)) {
+            self.error(input, "flags are off in a no flag game");
+            return false;
+        }
+        if let Command::Chord(x, y) = command {
+            let risk = self
+                .options
+                .safe_chords
+                .then(|| solver::chord_risk(&self.game, x, y))
+                .flatten();
+            if let Some(risk) = risk {
+                self.error(input, &format!("chord refused, {}", risk));
+                return false;
+            }
+        }
+        match command {
+            Command::Exit => {}
+            Command::Uncover(..) | Command::Flag(..) | Command::Chord(..)
+                if self.versus.is_some() =>
+            {
+                if !self.play_turn(action) {
+                    self.error(input, "that cell cannot be played");
+                    return false;
+                }
+            }
+            Command::Restart => self.game = new_game(&self.options),
+            Command::Again => self.game.restart(),
+            Command::Uncover(x, y) => {
+                self.game.uncover(x, y);
+            }
+            Command::Flag(x, y) => {
+                let wrong = self.game.wrong_flags();
+                self.game.flag(x, y);
+                if self.game.wrong_flags() > wrong {
+                    let penalty = game::FLAG_PENALTY.as_secs();
+                    let message = format!("flag refused, that cell is safe (+{} s)", penalty);
+                    self.error(input, &message);
+                    return false;
+                }
+            }
+            Command::Question(x, y) => self.game.question(x, y),
+            Command::Chord(x, y) => {
+                self.game.chord(x, y);
+            }
+            Command::Inspect(x, y) => {
+                let probabilities = self
+                    .game
+                    .practice()
+                    .then(|| solver::probabilities(&self.game));
+                self.say(inspect::describe(
+                    &self.game,
+                    x,
+                    y,
+                    probabilities.as_deref(),
+                ));
+            }
+            Command::Hint => match self.game.hint() {
+                Some(deduction) => {
+                    if self.options.json {
+                        println!("{}", json::hint_json(input, &deduction));
+                    }
+                    let penalty = game::HINT_PENALTY.as_secs();
+                    let left = match self.game.hints_left() {
+                        Some(left) => format!(", {} left", left),
+                        None => String::new(),
+                    };
+                    match patterns::find(&self.game) {
+                        Some(pattern) if pattern.pattern != Pattern::EdgeFiftyFifty => {
+                            let text = pattern.explanation();
+                            self.say(format!("hint: {} (+{} s{})", text, penalty, left));
+                            self.pattern = Some(pattern);
+                        }
+                        _ => {
+                            self.say(format!("hint: {} (+{} s{})", deduction, penalty, left));
+                            self.hint = Some(deduction);
+                        }
+                    }
+                }
+                None if self.game.hints_left() == Some(0) => {
+                    self.error(input, "no hints left in this game");
+                    return false;
+                }
+                None => match patterns::find(&self.game) {
+                    Some(pattern) => {
+                        self.say(format!("hint: {}", pattern.explanation()));
+                        self.pattern = Some(pattern);
+                    }
+                    None => {
+                        self.error(input, "the solver cannot prove any cell, guess");
+                        return false;
+                    }
+                },
+            },
+            Command::Keep(slot) => {
+                let kept = match slot {
+                    Some(slot) => self.checkpoints.set(slot, &self.game).then_some(slot),
+                    None => self.checkpoints.push(&self.game),
+                };
+                match kept {
+                    Some(slot) => self.say(format!("checkpoint {} kept", slot + 1)),
+                    None => {
+                        self.error(input, "no checkpoint can be kept once the game is over");
+                        return false;
+                    }
+                }
+            }
+            Command::Back(slot) => {
+                let Some(slot) = slot.or(self.checkpoints.latest()) else {
+                    self.error(input, "no checkpoint kept yet");
+                    return false;
+                };
+                if !self.checkpoints.revert(slot, &mut self.game) {
+                    self.error(input, "no checkpoint in that slot");
+                    return false;
+                }
+                self.say(format!("back to checkpoint {}, practice game", slot + 1));
+            }
+            #[cfg(feature = "dev-tools")]
+            Command::Debug => {
+                self.debug = !self.debug;
+                let status = if self.debug {
+                    let deductions = solver::solve(&self.game).len();
+                    format!("debug view on, {} deductions", deductions)
+                } else {
+                    "debug view off".to_string()
+                };
+                self.say(status);
+            }
+            Command::Save(path) => {
+                if let Err(e) = save::save(&self.game, &path) {
+                    self.error(input, &format!("unable to save {}: {}", path, e));
+                    return false;
+                }
+                self.say(format!("saved to {}", path));
+            }
+            Command::Share => match share::summary(&self.game, self.options.daily) {
+                Some(summary) => self.say(summary),
+                None => {
+                    self.error(input, "the game is not over yet");
+                    return false;
+                }
+            },
+            Command::Load(path) => match save::load(&path) {
+                Ok(loaded) => self.game = loaded,
+                Err(e) => {
+                    self.error(input, &format!("unable to load {}: {}", path, e));
+                    return false;
+                }
+            },
+            Command::Undo | Command::Redo => {
+                let (mv, done, empty) = match command {
+                    Command::Undo => (self.game.undo(), "undid", "nothing to undo"),
+                    _ => (self.game.redo(), "redid", "nothing to redo"),
+                };
+                match mv {
+                    Some(mv) => self.say(format!("{} {}", done, mv)),
+                    None => {
+                        self.error(input, empty);
+                        return false;
+                    }
+                }
+            }
+        }
+        self.record(action, restarted);
+        if restarted {
+            self.checkpoints.clear();
+            self.recorded = false;
+        }
+        if let Some(versus) = self.versus.as_mut() {
+            if restarted {
+                *versus = Match::new(&self.game);
+            }
+            let status = versus_status(versus);
+            self.say(status);
+        }
+        if let Some(timer) = self.splits.as_mut() {
+            if restarted {
+                *timer = Timer::new(&self.game);
+            } else {
+                timer.update(&self.game);
+            }
+        }
+        if let Some(race) = self.race.as_mut() {
+            if let Err(e) = race.update(&self.game) {
+                let message = format!("unable to reach {}: {}", race.opponent, e);
+                self.say(message);
+            }
+        }
+        if let (Some(before), Some(Action::Play(Move::Uncover(x, y) | Move::Chord(x, y)))) =
+            (before, action)
+        {
+            for line in explain::explain(&before, &self.game, x, y) {
+                self.say(line);
+            }
+        }
+        if let (true, Some((x, y))) = (self.options.accessible, target) {
+            self.say(text::announce(&self.game, x, y, covered));
+        }
+        let state = self.game.state();
+        if state != previous && matches!(state, GameState::Won | GameState::Lost) {
+            self.finished(state);
+        }
+        true
+    }
+
+    /// Plays a move for the player whose turn it is in a versus match,
+    /// returning false if the move is not allowed.
+    fn play_turn(&mut self, action: Option<Action>) -> bool {
+        match (self.versus.as_mut(), action) {
+            (Some(versus), Some(Action::Play(mv))) => versus.play(&mut self.game, mv),
+            _ => false,
+        }
+    }
+
+    /// Adds an action to the replay being recorded and rewrites the replay
+    /// file, a new game starts a new replay.
+    fn record(&mut self, action: Option<Action>, restarted: bool) {
+        let (Some(path), Some(replay)) = (&self.options.record, self.recording.as_mut()) else {
+            return;
+        };
+        match action {
+            _ if restarted => *replay = Replay::new(&self.game),
+            Some(action) => replay.push(self.game.elapsed(), action),
+            None => return,
+        }
+        if let Err(e) = replay.save(path) {
+            let message = format!("unable to write replay {}: {}", path, e);
+            self.say(message);
+        }
+    }
+
+    /// Records a game that has just ended and reports the result.
+    fn finished(&mut self, state: GameState) {
+        // the match status already names the winner
+        if self.versus.is_some() {
+            return;
+        }
+        if core::mem::replace(&mut self.recorded, true) {
+            self.say(format!("Game {} again, already recorded", state));
+            return;
+        }
+        let elapsed = self.game.elapsed().as_secs_f64();
+        if let Err(e) = stats::record(&self.game, self.options.daily) {
+            eprintln!("unable to record statistics: {}", e);
+        }
+        if let Some(day) = self.options.daily {
+            let streak = stats::Stats::load()
+                .map(|stats| stats.daily_streak(day))
+                .unwrap_or(0);
+            self.say(format!(
+                "Minesweeper daily {}\n{} in {:.1} seconds\nStreak: {} day{}",
+                stats::date(day),
+                if state == GameState::Won {
+                    "Won"
+                } else {
+                    "Lost"
+                },
+                elapsed,
+                streak,
+                if streak == 1 { "" } else { "s" }
+            ));
+            return;
+        }
+        let mut message = format!("Game {} in {:.1} seconds", state, elapsed);
+        if let Some(seed) = self.game.seed() {
+            message.push_str(&format!(", seed {} (replay with --seed {})", seed, seed));
+        }
+        if self.game.practice() {
+            message.push_str(", practice game not recorded");
+        } else if self.game.neighborhood() != Neighborhood::Adjacent {
+            message.push_str(", variant game not recorded");
+        }
+        self.say(message);
+        if let Some(loss) = solver::analyze_loss(&self.game) {
+            self.say(format!("Fatal click: {}", loss));
+        }
+        if let Some(record) = stats::Record::from_game(&self.game) {
+            self.say(efficiency(&record));
+        }
+        // assisted runs are not speedruns
+        if let Some(timer) = self.splits.as_ref().filter(|_| !self.game.assisted()) {
+            let message = match timer.record() {
+                Ok(true) => format!("New best run on {}", timer.level()),
+                Ok(false) => match timer.best() {
+                    Some(best) => format!("Best run: {}", best.summary(None)),
+                    None => return,
+                },
+                Err(e) => format!("unable to record splits: {}", e),
+            };
+            self.say(message);
+        }
+    }
+
+    /// Picks up the board style and glyphs again once the config file was
+    /// written, command line options still take precedence.
+    fn reload_config(&mut self) {
+        let modified = Config::modified();
+        if modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+        match Config::load() {
+            Ok(config) => {
+                (self.style, self.glyphs) = glyphs_from(&self.options, config);
+                self.say("Config reloaded".to_string());
+            }
+            Err(e) => self.say(format!("ignoring config file: {}", e)),
+        }
+    }
+
+    /// Queues a message for the next board, JSON output has no messages.
+    fn say(&mut self, message: String) {
+        if !self.options.json {
+            self.messages.push(message);
+        }
+    }
+
+    fn error(&mut self, input: &str, message: &str) {
+        if self.options.json {
+            println!("{}", json::error_json(input, message));
+        } else if self.screen.is_some() {
+            self.messages.push(message.to_string());
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// Shows the board along with any queued messages.
+    fn show(&mut self, input: &str) -> Result<()> {
+        // the hint is only marked on the board shown right after it
+        let hint = self.hint.take();
+        let pattern = self.pattern.take();
+        let mut overlays = self
+            .versus
+            .as_ref()
+            .map(Match::overlays)
+            .unwrap_or_default();
+        overlays.extend(hint.map(|hint| (hint.x, hint.y, Overlay::Hint(hint.kind))));
+        overlays.extend(pattern.iter().flat_map(PatternHint::overlays));
+        overlays.extend(render::auto_flag_overlays(&self.game));
+        #[cfg(feature = "dev-tools")]
+        if self.debug {
+            overlays.extend(render::debug_overlays(&self.game));
+        }
+        if let Some(published) = &self.published {
+            published.publish(&self.game, &overlays);
+        }
+        if self.options.json {
+            println!("{}", json::game_json(&self.game, input));
+            return Ok(());
+        }
+        if let Some(race) = self.race.as_mut() {
+            let status = race_status(race, &self.game);
+            self.say(status);
+        }
+        if let Some(timer) = &self.splits {
+            if !timer.run().is_empty() {
+                let status = format!("Splits: {}", timer.summary());
+                self.say(status);
+            }
+        }
+        if self.options.score {
+            let status = format!("Score: {}", score::score(&self.game));
+            self.say(status);
+        }
+        if !self.checkpoints.is_empty() {
+            let status = format!("Checkpoints: {}", self.checkpoints.panel());
+            self.say(status);
+        }
+        let board = if self.options.accessible {
+            text::describe(&self.game)
+        } else {
+            text::render_overlays(&self.game, self.style, &self.glyphs, &overlays)
+        };
+        match self.screen.as_mut() {
+            Some(screen) => screen.draw(TUI_HEADER, &board, &self.messages)?,
+            None => {
+                for message in &self.messages {
+                    println!("{}", message);
+                }
+                println!("{}", board);
+            }
+        }
+        self.messages.clear();
+        Ok(())
+    }
+}
+
+/// Puts the progress of both players in a race side by side.
+fn race_status(race: &mut Race, game: &Game) -> String {
+    let progress = race.opponent_progress();
+    let theirs = match (progress.finished, progress.left) {
+        (Some(true), _) => format!("cleared in {:.1} s", progress.elapsed.as_secs_f64()),
+        (Some(false), _) => "hit a mine".to_string(),
+        (None, true) => "left the race".to_string(),
+        (None, false) => format!(
+            "{}% in {:.1} s",
+            progress.percent,
+            progress.elapsed.as_secs_f64()
+        ),
+    };
+    let elapsed = game.elapsed().as_secs_f64();
+    let yours = match game.state() {
+        GameState::Won => format!("cleared in {:.1} s", elapsed),
+        GameState::Lost => "hit a mine".to_string(),
+        _ => format!("{}% in {:.1} s", race::revealed(game), elapsed),
+    };
+    format!("You: {}  |  {}: {}", yours, race.opponent, theirs)
+}
+
+/// Hosts or joins the race chosen on the command line, waiting until both
+/// players are there.
+fn lobby(options: &Options) -> Result<Option<Race>> {
+    let name = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "player".to_string());
+    let race = match &options.race {
+        None => return Ok(None),
+        Some(Role::Host(port)) => {
+            let listener = TcpListener::bind(("0.0.0.0", *port))?;
+            println!("Waiting for an opponent on port {}", port);
+            let config = new_game(options).config();
+            let seed = options.seed.unwrap_or_else(rand::random);
+            Race::host(&listener, &name, config, seed)?
+        }
+        Some(Role::Join(address)) => {
+            println!("Joining the race at {}", address);
+            Race::join(address.as_str(), &name)?
+        }
+    };
+    println!(
+        "Racing {} on a {}x{} board with {} mines, seed {}",
+        race.opponent, race.config.width, race.config.height, race.config.mines, race.seed
+    );
+    Ok(Some(race))
+}
+
+/// Sums up the 3BV, speed and clicks of a finished game.
+fn efficiency(record: &stats::Record) -> String {
+    let (Some(three_bv), Some(clicks)) = (record.three_bv, record.clicks) else {
+        return String::new();
+    };
+    format!(
+        "3BV {}/{} at {:.2}/s, {} clicks ({} wasted), {:.0}% efficiency",
+        three_bv.cleared,
+        three_bv.total,
+        record.three_bv_per_second().unwrap_or(0.0),
+        clicks.total(),
+        clicks.wasted,
+        record.efficiency().unwrap_or(0.0)
+    )
+}
+
+/// Describes whose turn it is in a versus match, or who won it.
+fn versus_status(versus: &Match) -> String {
+    let score = format!("{} : {}", versus.score(0), versus.score(1));
+    match (versus.is_over(), versus.winner()) {
+        (false, _) => format!("Player {} to move, score {}", versus.turn() + 1, score),
+        (true, Some(winner)) => format!("Player {} wins {}", winner + 1, score),
+        (true, None) => format!("Draw {}", score),
+    }
+}
+
+/// Resolves the density of boards sized without a mine count, the command
+/// line's takes precedence over the config file's.
+fn board_density(density: Option<Density>) -> Density {
+    density.unwrap_or_else(|| {
+        Config::load()
+            .map(|config| config.density)
+            .unwrap_or_default()
+    })
+}
+
+/// Resolves the board style and glyph overrides, command line options take
+/// precedence over the config file.
+fn board_glyphs(options: &Options) -> (Style, Glyphs) {
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("ignoring config file: {}", e);
+        Config::default()
+    });
+    glyphs_from(options, config)
+}
+
+/// The config file's glyphs take precedence over its theme's, a theme that
+/// cannot be loaded is left out.
+fn glyphs_from(options: &Options, config: Config) -> (Style, Glyphs) {
+    let style = options.style.or(config.style).unwrap_or_default();
+    let mut glyphs = match config.theme.as_deref().map(ThemeFile::find) {
+        Some(Ok(theme)) => theme.glyphs,
+        Some(Err(error)) => {
+            tracing::warn!(%error, "unable to load the theme");
+            Glyphs::default()
+        }
+        None => Glyphs::default(),
+    };
+    glyphs.overlay(&config.glyphs);
+    glyphs.overlay(&options.glyphs);
+    (style, glyphs)
+}
+
+/// Starts the log file when asked to on the command line or in the config
+/// file, the returned guard flushes it on exit.
+#[cfg(feature = "logging")]
+fn start_logging(options: &Options) -> Option<tracing_appender::non_blocking::WorkerGuard> {
+    let Some(level) = options.log.or_else(|| Config::load().ok()?.log) else {
+        // keep the latest traces for a crash report all the same
+        if let Err(e) = logging::init_recent(tracing::Level::INFO) {
+            eprintln!("unable to start logging: {}", e);
+        }
+        return None;
+    };
+    match logging::init(level) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("unable to start logging: {}", e);
+            None
+        }
+    }
+}
+
+/// Checks the result a replay file claims, exiting with an error when it
+/// does not hold.
+fn verify(path: &str) -> Result<()> {
+    let replay = Replay::load(path)?;
+    let outcome = match replay.verify() {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Verified: game {} at {:.3} seconds after {} moves, board {:016x}",
+        outcome.state,
+        outcome.time.as_secs_f64(),
+        replay.steps().len(),
+        outcome.hash
     );
+    Ok(())
+}
 
-    let mut game = Game::new(BOARD_WIDTH, BOARD_HEIGHT);
+/// Draws a saved board as a printable PDF puzzle.
+fn print_board(board: &str, path: &str, solution: bool) -> Result<()> {
+    let game = save::load(board)?;
+    print::save(&game, solution, path)?;
+    let pages = if solution {
+        "puzzle and solution"
+    } else {
+        "puzzle"
+    };
+    println!("Wrote the {} to {}", pages, path);
+    Ok(())
+}
+
+/// Draws a replay file as an animated PNG.
+fn animate(replay: &str, path: &str) -> Result<()> {
+    let replay = Replay::load(replay)?;
+    std::fs::write(path, snapshot::apng(&replay))?;
+    println!("Wrote {} moves to {}", replay.steps().len(), path);
+    Ok(())
+}
+
+/// Steps through a replay file, enter shows the next move, p the previous one
+/// and x exits.
+fn watch(options: &Options, path: &str) -> Result<()> {
+    let replay = Replay::load(path)?;
+    let (style, glyphs) = board_glyphs(options);
+    let steps = replay.steps();
+    let mut shown = 0;
     let mut buf = String::new();
     loop {
-        println!("{}", game);
-        let stdin = io::stdin();
-        stdin.read_line(&mut buf)?;
-        let input = buf.trim();
-        match input.chars().nth(0).unwrap() {
-            'x' => break,
-            'r' => game = Game::new(BOARD_WIDTH, BOARD_HEIGHT),
-            'u' => {
-                let (x, y) = get_coords(&input[1..input.len()]);
+        match shown {
+            0 => println!("Start of replay, {} moves", steps.len()),
+            _ => {
+                let step = &steps[shown - 1];
+                println!(
+                    "Move {} of {} at {:.1} seconds: {}",
+                    shown,
+                    steps.len(),
+                    step.at.as_secs_f64(),
+                    step.action
+                );
+            }
+        }
+        let game = replay.position(shown);
+        println!("{}", text::render(&game, style, &glyphs));
+        if shown == steps.len() {
+            println!("End of replay, game {}", game.state());
+        }
+        buf.clear();
+        if io::stdin().read_line(&mut buf)? == 0 {
+            break;
+        }
+        match buf.trim() {
+            "x" => break,
+            "p" => shown = shown.saturating_sub(1),
+            _ => shown = (shown + 1).min(steps.len()),
+        }
+    }
+    Ok(())
+}
+
+/// Plays drills of a pattern one after another until stopped, recording
+/// every board played to its end in the drill store.
+fn play_drills(options: &Options, pattern: Pattern) -> Result<()> {
+    let (style, glyphs) = board_glyphs(options);
+    let mut drill = Drill::new(pattern);
+    let mut buf = String::new();
+    loop {
+        println!("Drill {}", drill.pattern());
+        println!("{}", text::render(drill.game(), style, &glyphs));
+        buf.clear();
+        if io::stdin().read_line(&mut buf)? == 0 {
+            break;
+        }
+        let game = drill.game_mut();
+        match parse_command(buf.trim(), game) {
+            Ok(Command::Exit) => break,
+            Ok(Command::Restart) => {
+                drill = Drill::new(pattern);
+                continue;
+            }
+            Ok(Command::Uncover(x, y)) => {
                 game.uncover(x, y);
             }
-            'f' => {
-                let (x, y) = get_coords(&input[1..input.len()]);
-                game.flag(x, y);
+            Ok(Command::Flag(x, y)) => game.flag(x, y),
+            Ok(Command::Question(x, y)) => game.question(x, y),
+            Ok(Command::Chord(x, y)) => {
+                game.chord(x, y);
+            }
+            Ok(_) => {
+                println!("not available in drills");
+                continue;
             }
-            '?' => {
-                let (x, y) = get_coords(&input[1..input.len()]);
-                game.question(x, y);
+            Err(message) => {
+                println!("{}", message);
+                continue;
             }
+        }
+        let Some(attempt) = drill.attempt(drill.elapsed()) else {
+            continue;
+        };
+        println!("{}", text::render(drill.game(), style, &glyphs));
+        let verb = if attempt.solved { "Solved" } else { "Mine hit" };
+        println!("{} in {:.1} seconds", verb, attempt.time.as_secs_f64());
+        // the drill store is best effort and never interrupts the drills
+        if let Err(error) = drill::record(&attempt) {
+            tracing::warn!(%error, "unable to record the drill");
+        }
+        drill = Drill::new(pattern);
+    }
+    print_drill_stats()
+}
+
+/// Prints the drills played per pattern after a blank line, nothing when
+/// none were.
+fn print_drill_stats() -> Result<()> {
+    let summary = DrillStats::load()?.summary();
+    if summary.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "\n{:<16} {:>8} {:>8} {:>11} {:>11}",
+        "Drill", "Played", "Solved", "Best time", "Average"
+    );
+    let seconds = |time: Option<Duration>| match time {
+        Some(time) => format!("{:.1} s", time.as_secs_f64()),
+        None => "-".to_string(),
+    };
+    for pattern in summary {
+        println!(
+            "{:<16} {:>8} {:>8} {:>11} {:>11}",
+            pattern.pattern.to_string(),
+            pattern.played,
+            pattern.solved,
+            seconds(pattern.best),
+            seconds(pattern.average)
+        );
+    }
+    Ok(())
+}
+
+/// Plays an endless board, laying out chunks as the view scrolls over them.
+/// Moves use the coordinates of the view.
+fn play_infinite(options: &Options, density: f64) -> Result<()> {
+    let (style, glyphs) = board_glyphs(options);
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut board = InfiniteBoard::new(seed, density);
+    let mut viewport = Viewport::centered(VIEW_WIDTH, VIEW_HEIGHT);
+    let mut buf = String::new();
+    loop {
+        let view = board.view(&viewport);
+        let (left, top) = viewport.to_board(0, 0);
+        let (right, bottom) = viewport.to_board(VIEW_WIDTH - 1, VIEW_HEIGHT - 1);
+        println!(
+            "[{},{}] to [{},{}], {} cells uncovered",
+            left,
+            top,
+            right,
+            bottom,
+            board.uncovered()
+        );
+        println!("{}", text::render(&view, style, &glyphs));
+        if board.state() == GameState::Lost {
+            println!(
+                "Game over with {} cells uncovered, seed {} (replay with --seed {})",
+                board.uncovered(),
+                seed,
+                seed
+            );
+            break;
+        }
+        buf.clear();
+        if io::stdin().read_line(&mut buf)? == 0 {
+            break;
+        }
+        let (dx, dy) = (VIEW_WIDTH as i64 / 2, VIEW_HEIGHT as i64 / 2);
+        match buf.trim() {
+            "w" => viewport.scroll(0, -dy),
+            "a" => viewport.scroll(-dx, 0),
+            "s" => viewport.scroll(0, dy),
+            "d" => viewport.scroll(dx, 0),
+            input => match parse_command(input, &view) {
+                Ok(Command::Exit) => break,
+                Ok(Command::Uncover(x, y)) => {
+                    let (x, y) = viewport.to_board(x, y);
+                    board.uncover(x, y);
+                }
+                Ok(Command::Flag(x, y)) => {
+                    let (x, y) = viewport.to_board(x, y);
+                    board.flag(x, y);
+                }
+                Ok(Command::Chord(x, y)) => {
+                    let (x, y) = viewport.to_board(x, y);
+                    board.chord(x, y);
+                }
+                Ok(_) => println!("not available on an endless board"),
+                Err(message) => println!("{}", message),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Plays rounds of several boards tiled into one, moves use the coordinates
+/// of the tiled board.
+fn play_multi(options: &Options, count: usize) -> Result<()> {
+    let (style, glyphs) = board_glyphs(options);
+    let config = options.difficulty.unwrap_or(Difficulty::Beginner).config();
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut round = MultiBoard::new(count, config.clone(), seed);
+    let mut renderer = TextRenderer::new(style, glyphs);
+    let mut buf = String::new();
+    loop {
+        round
+            .draw(&mut renderer, &[])
+            .unwrap_or_else(|never| match never {});
+        println!(
+            "{} boards, {} mines left, {:.1} s",
+            round.games().len(),
+            round.est_remaining(),
+            round.elapsed().as_secs_f64()
+        );
+        println!("{}", renderer.frame());
+        match round.state() {
+            GameState::Won => println!("Round won, r for the next one"),
+            GameState::Lost => println!("Round lost, r for the next one"),
             _ => {}
         }
         buf.clear();
+        if io::stdin().read_line(&mut buf)? == 0 {
+            break;
+        }
+        // the tiled board is only used to check the coordinates
+        let tiled = Game::with_mines(round.width(), round.height(), 0);
+        let mv = match parse_command(buf.trim(), &tiled) {
+            Ok(Command::Exit) => break,
+            Ok(Command::Restart) => {
+                round = MultiBoard::new(count, config.clone(), rand::random());
+                continue;
+            }
+            Ok(Command::Uncover(x, y)) => Move::Uncover(x, y),
+            Ok(Command::Flag(x, y)) => Move::Flag(x, y),
+            Ok(Command::Question(x, y)) => Move::Question(x, y),
+            Ok(Command::Chord(x, y)) => Move::Chord(x, y),
+            Ok(_) => {
+                println!("not available with several boards");
+                continue;
+            }
+            Err(message) => {
+                println!("{}", message);
+                continue;
+            }
+        };
+        let (x, y) = mv.cell();
+        if round.locate(x, y).is_none() {
+            println!("coordinates are between the boards");
+            continue;
+        }
+        round.play(mv);
     }
     Ok(())
 }
 
-fn get_coords(s: &str) -> (i16, i16) {
-    let s = &s[1..s.len() - 1];
+/// Creates the daily board, a board of the chosen difficulty, or the small
+/// test board.
+fn new_game(options: &Options) -> Game {
+    let mut game = match (options.daily, options.difficulty) {
+        (Some(day), _) => {
+            // everyone gets the same intermediate board each day
+            let mut game = Game::with_difficulty(Difficulty::Intermediate);
+            game.reset_with_seed(day);
+            game
+        }
+        (None, Some(difficulty)) => Game::with_difficulty(difficulty),
+        (None, None) => {
+            Game::with_density(BOARD_WIDTH, BOARD_HEIGHT, board_density(options.density))
+        }
+    };
+    game.set_no_flags(options.no_flags);
+    game.set_flag_penalty(options.flag_penalty);
+    game.set_neighborhood(options.neighborhood);
+    game.set_blind(options.blind.map(u64::from).map(Duration::from_secs));
+    let custom = options.symmetry != Symmetry::None || !options.zones.is_empty();
+    if custom || options.handicap.is_some() {
+        // the same seed laid out again, so a daily board stays shared
+        game.set_symmetry(options.symmetry);
+        game.set_zones(options.zones.clone());
+        game.set_handicap(options.handicap);
+        game.reset_with_seed(game.seed().unwrap_or_else(rand::random));
+    }
+    game.set_auto_open(options.auto_open);
+    game.set_auto_flag(options.auto_flag);
+    game.set_hint_budget(options.hints);
+    game
+}
+
+fn print_stats() -> Result<()> {
+    let stats = stats::Stats::load()?;
+    let summary = stats.summary();
+    if summary.is_empty() {
+        println!("No games played yet");
+        return print_drill_stats();
+    }
+    println!(
+        "{:<16} {:>8} {:>8} {:>9} {:>11} {:>11}",
+        "Level", "Played", "Won", "Win rate", "Best time", "Best score"
+    );
+    for level in summary {
+        let best = match level.best {
+            Some(best) => format!("{:.1} s", best),
+            None => "-".to_string(),
+        };
+        let best_score = match level.best_score {
+            Some(score) => score.to_string(),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<16} {:>8} {:>8} {:>8.1}% {:>11} {:>11}",
+            level.level,
+            level.played,
+            level.won,
+            level.win_rate(),
+            best,
+            best_score
+        );
+    }
+    let streaks = stats.streaks();
+    println!("\nWin streak: {} (best {})", streaks.current, streaks.best);
+    let streak = stats.daily_streak(stats::today());
+    if streak > 0 {
+        println!("Daily streak: {}", streak);
+    }
+    if let Some(&session) = stats.sessions().last() {
+        println!("\nLast session:");
+        for record in stats.history(session) {
+            println!("{}", record.history_line());
+        }
+    }
+    print_drill_stats()
+}
+
+fn export_stats(path: &str) -> Result<()> {
+    let stats = stats::Stats::load()?;
+    let summary = csv::export(&stats, Path::new(path))?;
+    println!(
+        "Wrote {} games to {} and the summary to {}",
+        stats.records().len(),
+        path,
+        summary.display()
+    );
+    Ok(())
+}
+
+/// Turns a click on a board cell into the matching command, left click
+/// uncovers and right click flags.
+fn click_command(button: Button, cell: Option<(i16, i16)>, game: &Game) -> Option<String> {
+    let (x, y) = cell?;
+    if x >= game.width() || y >= game.height() {
+        return None;
+    }
+    let command = match button {
+        Button::Left => 'u',
+        Button::Right => 'f',
+        Button::Middle => return None,
+    };
+    Some(format!("{}[{},{}]", command, x, y))
+}
+
+fn parse_command(input: &str, game: &Game) -> std::result::Result<Command, &'static str> {
+    let mut chars = input.chars();
+    let command = match chars.next() {
+        Some('x') => Command::Exit,
+        Some('r') => Command::Restart,
+        Some('a') => Command::Again,
+        Some('z') => Command::Undo,
+        Some('y') => Command::Redo,
+        Some('h') => Command::Hint,
+        Some('k') => Command::Keep(get_slot(chars.as_str())?),
+        Some('b') => Command::Back(get_slot(chars.as_str())?),
+        Some('s') => Command::Save(get_path(chars.as_str())?),
+        Some('l') => Command::Load(get_path(chars.as_str())?),
+        Some('p') => Command::Share,
+        #[cfg(feature = "dev-tools")]
+        Some('!') => Command::Debug,
+        Some('u') => {
+            let (x, y) = get_coords(chars.as_str(), game)?;
+            Command::Uncover(x, y)
+        }
+        Some('f') => {
+            let (x, y) = get_coords(chars.as_str(), game)?;
+            Command::Flag(x, y)
+        }
+        Some('?') => {
+            let (x, y) = get_coords(chars.as_str(), game)?;
+            Command::Question(x, y)
+        }
+        Some('c') => {
+            let (x, y) = get_coords(chars.as_str(), game)?;
+            Command::Chord(x, y)
+        }
+        Some('i') => {
+            let (x, y) = get_coords(chars.as_str(), game)?;
+            Command::Inspect(x, y)
+        }
+        _ => return Err("unknown command"),
+    };
+    Ok(command)
+}
+
+fn parse_args() -> std::result::Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bench" => {
+                let boards = args.peek().and_then(|s| s.parse::<usize>().ok());
+                if boards.is_some() {
+                    args.next();
+                }
+                options.bench = Some(boards.unwrap_or(BENCH_BOARDS));
+            }
+            "--json" => options.json = true,
+            "--serve" => {
+                let port = args.peek().and_then(|s| s.parse::<u16>().ok());
+                if port.is_some() {
+                    args.next();
+                }
+                options.serve = Some(port.unwrap_or(serve::DEFAULT_PORT));
+            }
+            "--seed" => {
+                let seed = args.next().ok_or("--seed needs a value")?;
+                let seed = seed
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid seed {}", seed))?;
+                options.seed = Some(seed);
+            }
+            "--style" => {
+                let style = args.next().ok_or("--style needs a value")?;
+                options.style = Some(style.parse()?);
+            }
+            "--glyphs" => {
+                let glyphs = args.next().ok_or("--glyphs needs a value")?;
+                options.glyphs = glyphs.parse()?;
+            }
+            "--difficulty" => {
+                let difficulty = args.next().ok_or("--difficulty needs a value")?;
+                options.difficulty = Some(difficulty.parse()?);
+            }
+            #[cfg(feature = "logging")]
+            "--log" => {
+                let level = args.next().ok_or("--log needs a value")?;
+                let level = level
+                    .parse()
+                    .map_err(|_| format!("invalid log level {}", level))?;
+                options.log = Some(level);
+            }
+            "--plain" => options.plain = true,
+            "--accessible" => options.accessible = true,
+            "--explain" => options.explain = true,
+            "--versus" => options.versus = true,
+            "--splits" => options.splits = true,
+            "--score" => options.score = true,
+            "--boards" => {
+                let boards = args.next().ok_or("--boards needs a value")?;
+                match boards.parse() {
+                    Ok(count) if (multi::MIN_BOARDS..=multi::MAX_BOARDS).contains(&count) => {
+                        options.boards = Some(count)
+                    }
+                    _ => return Err(format!("invalid board count {}", boards)),
+                }
+            }
+            "--density" => {
+                let density = args.next().ok_or("--density needs a value")?;
+                options.density = Some(density.parse()?);
+            }
+            "--nf" => options.no_flags = true,
+            "--flag-penalty" => options.flag_penalty = true,
+            "--neighborhood" => {
+                let rule = args.next().ok_or("--neighborhood needs a value")?;
+                options.neighborhood = rule.parse()?;
+            }
+            "--blind" => {
+                let seconds = args.next().ok_or("--blind needs a value")?;
+                let seconds = seconds
+                    .parse()
+                    .map_err(|_| format!("invalid blind time {}", seconds))?;
+                options.blind = Some(seconds);
+            }
+            "--symmetry" => {
+                let symmetry = args.next().ok_or("--symmetry needs a value")?;
+                options.symmetry = symmetry.parse()?;
+            }
+            "--zone" => {
+                let zone = args.next().ok_or("--zone needs a value")?;
+                options.zones.push(zone.parse()?);
+            }
+            "--handicap" => {
+                let handicap = args.next().ok_or("--handicap needs a value")?;
+                options.handicap = Some(handicap.parse()?);
+            }
+            "--safe-chords" => options.safe_chords = true,
+            "--auto-open" => options.auto_open = true,
+            "--auto-flag" => options.auto_flag = true,
+            "--hints" => {
+                let hints = args.next().ok_or("--hints needs a value")?;
+                let hints = hints
+                    .parse()
+                    .map_err(|_| format!("invalid hint count {}", hints))?;
+                options.hints = Some(hints);
+            }
+            "stats" => options.stats = true,
+            "export" => {
+                let path = args.next().ok_or("export needs a file")?;
+                options.export = Some(path);
+            }
+            "daily" => options.daily = Some(stats::today()),
+            "drill" => {
+                let pattern = args.next().ok_or("drill needs a pattern")?;
+                options.drill = Some(pattern.parse()?);
+            }
+            "generate" => options.generate = Some(parse_generate(&mut args)?),
+            "infinite" => {
+                let density = args.peek().and_then(|s| s.parse::<f64>().ok());
+                if density.is_some() {
+                    args.next();
+                }
+                options.infinite = Some(density.unwrap_or(infinite::DEFAULT_DENSITY));
+            }
+            "replay" => {
+                let path = args.next().ok_or("replay needs a file")?;
+                options.replay = Some(path);
+            }
+            "verify" => {
+                let path = args.next().ok_or("verify needs a file")?;
+                options.verify = Some(path);
+            }
+            "print" => {
+                let board = args.next().ok_or("print needs a board and a file")?;
+                let path = args.next().ok_or("print needs a board and a file")?;
+                let solution = args.peek().is_some_and(|arg| arg == "--solution");
+                if solution {
+                    args.next();
+                }
+                options.print = Some((board, path, solution));
+            }
+            "animate" => {
+                let replay = args.next().ok_or("animate needs a replay and a file")?;
+                let path = args.next().ok_or("animate needs a replay and a file")?;
+                options.animate = Some((replay, path));
+            }
+            "race" => {
+                let role = args.next().ok_or("race needs host or join")?;
+                options.race = Some(match role.as_str() {
+                    "host" => {
+                        let port = args.peek().and_then(|s| s.parse::<u16>().ok());
+                        if port.is_some() {
+                            args.next();
+                        }
+                        Role::Host(port.unwrap_or(race::DEFAULT_PORT))
+                    }
+                    "join" => {
+                        let address = args.next().ok_or("race join needs an address")?;
+                        if address.contains(':') {
+                            Role::Join(address)
+                        } else {
+                            Role::Join(format!("{}:{}", address, race::DEFAULT_PORT))
+                        }
+                    }
+                    _ => return Err(format!("unknown race role {}", role)),
+                });
+            }
+            "--record" => {
+                let path = args.next().ok_or("--record needs a file")?;
+                options.record = Some(path);
+            }
+            _ => eprintln!("ignoring unknown option {}", arg),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the options of the generate subcommand, which take the rest of the
+/// command line.
+fn parse_generate(
+    args: &mut impl Iterator<Item = String>,
+) -> std::result::Result<generate::Settings, String> {
+    let mut settings = generate::Settings {
+        count: GENERATE_BOARDS,
+        width: BOARD_WIDTH,
+        height: BOARD_HEIGHT,
+        mines: 0,
+        out: PathBuf::from("boards"),
+        no_guess: false,
+        seed: rand::random(),
+    };
+    let mut mines = None;
+    let mut density = None;
+    while let Some(arg) = args.next() {
+        if arg == "--no-guess" {
+            settings.no_guess = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", arg))?;
+        let invalid = || format!("invalid value {} for {}", value, arg);
+        match arg.as_str() {
+            "--count" => settings.count = value.parse().map_err(|_| invalid())?,
+            "--width" => settings.width = value.parse().map_err(|_| invalid())?,
+            "--height" => settings.height = value.parse().map_err(|_| invalid())?,
+            "--mines" => mines = Some(value.parse().map_err(|_| invalid())?),
+            "--density" => density = Some(value.parse()?),
+            "--seed" => settings.seed = value.parse().map_err(|_| invalid())?,
+            "--out" => settings.out = PathBuf::from(value),
+            _ => return Err(format!("unknown generate option {}", arg)),
+        }
+    }
+    let cells = settings.width as usize * settings.height as usize;
+    if settings.width <= 0 || settings.height <= 0 || cells > game::MAX_CELLS {
+        return Err("invalid board size".to_string());
+    }
+    settings.mines =
+        mines.unwrap_or_else(|| board_density(density).mines(settings.width, settings.height));
+    if settings.mines as usize >= cells {
+        return Err(format!(
+            "{} mines do not fit on the board, use --mines",
+            settings.mines
+        ));
+    }
+    Ok(settings)
+}
+
+fn get_slot(s: &str) -> std::result::Result<Option<usize>, &'static str> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    match s.parse::<usize>() {
+        Ok(slot) if (1..=checkpoint::SLOTS).contains(&slot) => Ok(Some(slot - 1)),
+        _ => Err("expected a checkpoint slot from 1 to 3"),
+    }
+}
+
+fn get_path(s: &str) -> std::result::Result<String, &'static str> {
+    let path = s.trim();
+    if path.is_empty() {
+        Err("expected a file name")
+    } else {
+        Ok(path.to_string())
+    }
+}
+
+fn get_coords(s: &str, game: &Game) -> std::result::Result<(i16, i16), &'static str> {
+    let s = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or("expected coordinates as [x,y]")?;
     let mut parts = s.split(',');
-    let s: &str = parts.next().unwrap();
-    let x = s.parse::<i16>().unwrap();
-    let s: &str = parts.next().unwrap();
-    let y = s.parse::<i16>().unwrap();
-    (x, y)
+    let x = parts.next().and_then(|s| s.trim().parse::<i16>().ok());
+    let y = parts.next().and_then(|s| s.trim().parse::<i16>().ok());
+    match (x, y) {
+        (Some(x), Some(y)) if x >= 0 && y >= 0 && x < game.width() && y < game.height() => {
+            Ok((x, y))
+        }
+        (Some(_), Some(_)) => Err("coordinates are outside the board"),
+        _ => Err("expected coordinates as [x,y]"),
+    }
 }
@@ -0,0 +1,70 @@
+use windows::{
+    core::Result,
+    Win32::{
+        Foundation::{HANDLE, HGLOBAL, HWND},
+        System::{
+            DataExchange::{
+                CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
+                OpenClipboard, SetClipboardData,
+            },
+            Memory::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+            Ole::CF_UNICODETEXT,
+        },
+    },
+};
+
+/// Puts `text` on the clipboard, `owner` is the window copying it.
+pub(crate) fn copy_text(owner: HWND, text: &str) -> Result<()> {
+    let mut wide: Vec<u16> = text.encode_utf16().collect();
+    wide.push(0);
+    unsafe {
+        OpenClipboard(owner)?;
+        let copied = EmptyClipboard().and_then(|_| set_text(&wide));
+        let _ = CloseClipboard();
+        copied
+    }
+}
+
+// hands the open clipboard a copy of the text, the memory is freed on every
+// failure as the clipboard only owns it once it took it
+unsafe fn set_text(wide: &[u16]) -> Result<()> {
+    let memory = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(wide))?;
+    let target = GlobalLock(memory) as *mut u16;
+    if target.is_null() {
+        let error = windows::core::Error::from_win32();
+        let _ = GlobalFree(memory);
+        return Err(error);
+    }
+    std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+    let _ = GlobalUnlock(memory);
+    if let Err(error) = SetClipboardData(CF_UNICODETEXT.0 as u32, HANDLE(memory.0 as isize)) {
+        let _ = GlobalFree(memory);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// The text on the clipboard, `None` when it holds no text, `owner` is the
+/// window pasting it.
+pub(crate) fn paste_text(owner: HWND) -> Result<Option<String>> {
+    unsafe {
+        if IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32).is_err() {
+            return Ok(None);
+        }
+        OpenClipboard(owner)?;
+        let text = GetClipboardData(CF_UNICODETEXT.0 as u32).map(|data| {
+            // the clipboard keeps the memory, it is only borrowed to read it
+            let memory = HGLOBAL(data.0 as _);
+            let source = GlobalLock(memory) as *const u16;
+            if source.is_null() {
+                return None;
+            }
+            let length = (0..).take_while(|&at| *source.add(at) != 0).count();
+            let text = String::from_utf16_lossy(std::slice::from_raw_parts(source, length));
+            let _ = GlobalUnlock(memory);
+            Some(text)
+        });
+        let _ = CloseClipboard();
+        text
+    }
+}
@@ -0,0 +1,251 @@
+//! Presents the board through DirectComposition.
+//!
+//! The cells are drawn to a swap chain shown by the root visual, and
+//! overlays are drawn to [`Layer`]s, visuals of their own above it, which
+//! fade without the board being drawn again. A window presented this way
+//! has no redirection bitmap for GDI to paint over, so it does not flicker
+//! while it is resized.
+
+use windows::{
+    core::{Interface, Result},
+    Foundation::Numerics::Matrix3x2,
+    Win32::{
+        Foundation::{HMODULE, HWND, POINT},
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT},
+                ID2D1DeviceContext, ID2D1Factory1, D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+                D2D1_BITMAP_OPTIONS_TARGET, D2D1_BITMAP_PROPERTIES1,
+                D2D1_DEVICE_CONTEXT_OPTIONS_NONE,
+            },
+            Direct3D::{D3D_DRIVER_TYPE, D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_WARP},
+            Direct3D11::{
+                D3D11CreateDevice, ID3D11Device, D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                D3D11_SDK_VERSION,
+            },
+            DirectComposition::{
+                DCompositionCreateDevice2, IDCompositionDesktopDevice, IDCompositionEffectGroup,
+                IDCompositionSurface, IDCompositionTarget, IDCompositionVisual2,
+            },
+            Dxgi::{
+                Common::{
+                    DXGI_ALPHA_MODE_PREMULTIPLIED, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_SAMPLE_DESC,
+                },
+                IDXGIDevice, IDXGIFactory2, IDXGISurface, IDXGISwapChain1, DXGI_SWAP_CHAIN_DESC1,
+                DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL, DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            },
+        },
+    },
+};
+
+use crate::direct2d::Rendering;
+
+/// The devices, swap chain and visuals presenting one window.
+pub(crate) struct Composition {
+    context: ID2D1DeviceContext,
+    swap_chain: IDXGISwapChain1,
+    device: IDCompositionDesktopDevice,
+    // kept alive for the visuals to stay on the window
+    _target: IDCompositionTarget,
+    root: IDCompositionVisual2,
+    rendering: Rendering,
+    vsync: bool,
+    dpi: (f32, f32),
+}
+
+impl Composition {
+    /// Sets up the presentation of `window`, `size` pixels large, drawn at
+    /// `dpi` on the graphics card unless `rendering` asks for software, and
+    /// in software with WARP when the graphics card cannot be used. Frames
+    /// wait for the display refresh with `vsync`.
+    pub(crate) fn new(
+        factory: &ID2D1Factory1,
+        window: HWND,
+        size: (u32, u32),
+        dpi: (f32, f32),
+        rendering: Rendering,
+        vsync: bool,
+    ) -> Result<Self> {
+        let (d3d, rendering) = match rendering {
+            Rendering::Hardware => match create_device(D3D_DRIVER_TYPE_HARDWARE) {
+                Ok(device) => (device, Rendering::Hardware),
+                Err(error) => {
+                    tracing::warn!(%error, "no hardware rendering, falling back to WARP");
+                    (create_device(D3D_DRIVER_TYPE_WARP)?, Rendering::Software)
+                }
+            },
+            Rendering::Software => (create_device(D3D_DRIVER_TYPE_WARP)?, Rendering::Software),
+        };
+        unsafe {
+            let dxgi: IDXGIDevice = d3d.cast()?;
+            let d2d = factory.CreateDevice(&dxgi)?;
+            let context = d2d.CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE)?;
+            let dxgi_factory: IDXGIFactory2 = dxgi.GetAdapter()?.GetParent()?;
+            let desc = DXGI_SWAP_CHAIN_DESC1 {
+                Width: size.0.max(1),
+                Height: size.1.max(1),
+                Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+                BufferCount: 2,
+                SwapEffect: DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL,
+                AlphaMode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+                ..Default::default()
+            };
+            let swap_chain = dxgi_factory.CreateSwapChainForComposition(&d3d, &desc, None)?;
+            let surface: IDXGISurface = swap_chain.GetBuffer(0)?;
+            let props = D2D1_BITMAP_PROPERTIES1 {
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                dpiX: dpi.0,
+                dpiY: dpi.1,
+                bitmapOptions: D2D1_BITMAP_OPTIONS_TARGET | D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+                ..Default::default()
+            };
+            let bitmap = context.CreateBitmapFromDxgiSurface(&surface, Some(&props))?;
+            context.SetTarget(&bitmap);
+            context.SetDpi(dpi.0, dpi.1);
+
+            let device: IDCompositionDesktopDevice = DCompositionCreateDevice2(&d2d)?;
+            let target = device.CreateTargetForHwnd(window, true)?;
+            let root = device.CreateVisual()?;
+            root.SetContent(&swap_chain)?;
+            target.SetRoot(&root)?;
+            device.Commit()?;
+            Ok(Composition {
+                context,
+                swap_chain,
+                device,
+                _target: target,
+                root,
+                rendering,
+                vsync,
+                dpi,
+            })
+        }
+    }
+
+    /// The device context drawing to the swap chain, between a `BeginDraw`
+    /// and an `EndDraw` call.
+    pub(crate) fn context(&self) -> &ID2D1DeviceContext {
+        &self.context
+    }
+
+    /// Where the frames are drawn, on the graphics card or in software.
+    pub(crate) fn rendering(&self) -> Rendering {
+        self.rendering
+    }
+
+    /// Shows the frame drawn to the swap chain.
+    pub(crate) fn present(&self) -> Result<()> {
+        let interval = if self.vsync { 1 } else { 0 };
+        unsafe { self.swap_chain.Present(interval, Default::default()).ok() }
+    }
+
+    /// Adds an overlay `size` pixels large above the board and the layers
+    /// added before, hidden until it is drawn.
+    pub(crate) fn layer(&self, size: (u32, u32)) -> Result<Layer> {
+        unsafe {
+            let surface = self.device.CreateSurface(
+                size.0.max(1),
+                size.1.max(1),
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                DXGI_ALPHA_MODE_PREMULTIPLIED,
+            )?;
+            let visual = self.device.CreateVisual()?;
+            let effect = self.device.CreateEffectGroup()?;
+            effect.SetOpacity2(0.0)?;
+            visual.SetEffect(&effect)?;
+            visual.SetContent(&surface)?;
+            self.root.AddVisual(&visual, true, None)?;
+            self.device.Commit()?;
+            Ok(Layer {
+                device: self.device.clone(),
+                surface,
+                visual,
+                effect,
+                dpi: self.dpi,
+            })
+        }
+    }
+}
+
+/// An overlay on a visual of its own above the board, with an opacity of
+/// its own.
+pub(crate) struct Layer {
+    device: IDCompositionDesktopDevice,
+    surface: IDCompositionSurface,
+    visual: IDCompositionVisual2,
+    effect: IDCompositionEffectGroup,
+    dpi: (f32, f32),
+}
+
+impl Layer {
+    /// Redraws the whole layer with `draw`, given a device context cleared
+    /// to transparent that shares the resources of the board's.
+    pub(crate) fn draw(&self, draw: impl FnOnce(&ID2D1DeviceContext)) -> Result<()> {
+        unsafe {
+            let mut offset = POINT::default();
+            let context: ID2D1DeviceContext = self.surface.BeginDraw(None, &mut offset)?;
+            context.SetDpi(self.dpi.0, self.dpi.1);
+            // the surface may be drawn at an offset into a larger atlas
+            context.SetTransform(&Matrix3x2::translation(
+                offset.x as f32 * 96.0 / self.dpi.0,
+                offset.y as f32 * 96.0 / self.dpi.1,
+            ));
+            context.Clear(None);
+            draw(&context);
+            self.surface.EndDraw()
+        }
+    }
+
+    /// Moves the layer to `y` pixels from the top of the window.
+    pub(crate) fn move_to(&self, y: f32) -> Result<()> {
+        unsafe {
+            self.visual.SetOffsetY2(y)?;
+            self.device.Commit()
+        }
+    }
+
+    /// Fades the layer in over `seconds`.
+    pub(crate) fn fade_in(&self, seconds: f64) -> Result<()> {
+        unsafe {
+            let animation = self.device.CreateAnimation()?;
+            animation.AddCubic(0.0, 0.0, (1.0 / seconds) as f32, 0.0, 0.0)?;
+            animation.End(seconds, 1.0)?;
+            self.effect.SetOpacity(&animation)?;
+            self.device.Commit()
+        }
+    }
+
+    /// Hides the layer at once.
+    pub(crate) fn hide(&self) -> Result<()> {
+        unsafe {
+            self.effect.SetOpacity2(0.0)?;
+            self.device.Commit()
+        }
+    }
+}
+
+fn create_device(driver: D3D_DRIVER_TYPE) -> Result<ID3D11Device> {
+    let mut device = None;
+    unsafe {
+        D3D11CreateDevice(
+            None,
+            driver,
+            HMODULE::default(),
+            D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            None,
+        )?;
+    }
+    Ok(device.unwrap())
+}
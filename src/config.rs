@@ -0,0 +1,747 @@
+//! User settings read from `config.txt` in the per user data directory.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    str::FromStr,
+    time::SystemTime,
+};
+
+use tracing::Level;
+
+use crate::{
+    bindings::Bindings,
+    error::{MinesweeperError, Result},
+    game::{Density, Difficulty, Handicap, Neighborhood, Symmetry, Zone},
+    stats::data_dir,
+    text::{Glyphs, Style},
+};
+
+const CONFIG_FILE: &str = "config.txt";
+/// The `board.skin` setting that draws the board with the built in theme.
+pub const CLASSIC_SKIN: &str = "classic";
+/// The `theme` setting that draws both boards without a theme file.
+pub const NO_THEME: &str = "none";
+
+// The config file is plain text with one `key = value` setting per line:
+//
+//   # traces written to the log file
+//   log = debug
+//   # colors, font and glyphs of both boards
+//   theme = sunset.theme
+//   # mines on boards without a set count: 40, 15% or curve a b c
+//   density = 15%
+//   # terminal board
+//   style = ascii
+//   glyphs.flag = F
+//   # window board
+//   board.difficulty = expert
+//   board.chording = true
+//   board.no_flags = true
+//   board.flag_penalty = true
+//   board.auto_flag = true
+//   board.hint_budget = 3
+//   board.ghost = true
+//   board.sonify = true
+//   board.inspect = true
+//   board.neighborhood = distance2
+//   board.blind = 3
+//   board.symmetry = rotational
+//   board.zone = 15,0,15,16,200%
+//   board.handicap = opening
+//   board.skin = midnight.zip
+//   board.backdrop = mica
+//   # window mouse buttons and keys
+//   input.left = flag
+//   input.right = uncover
+//   input.key_space = chord
+//   # window sounds
+//   audio.music = 0.5
+//   audio.effects_muted = true
+//   audio.music_playing = music/playing.wav
+//   audio.music_game_over = music/game_over.wav
+//   # window drawing
+//   render.vsync = false
+//   render.frame_rate = 60
+//   render.text = grayscale
+//   render.antialias = false
+//   # window pointer timing, in milliseconds, and clicks acting as their
+//   # button goes down
+//   timing.chord = 400
+//   timing.long_press = 800
+//   timing.double_click = 0
+//   timing.act_on_press = true
+//
+// Lines starting with # are comments and unknown keys are skipped so older
+// builds can read newer files.
+//
+// Both front-ends pick up edits made while they run: the window applies the
+// look, assists, buttons, timing, volumes and drawing and the terminal the
+// style and glyphs.
+
+/// User settings shared by the GUI and the CLI.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    /// Terminal board style, the CLI default when not set.
+    pub style: Option<Style>,
+    /// Terminal glyph overrides, set over the glyphs of the theme.
+    pub glyphs: Glyphs,
+    /// Theme file both boards are drawn with, a name from
+    /// [`theme::installed`](crate::theme::installed) or a path, the built in
+    /// look when not set.
+    pub theme: Option<String>,
+    /// Mines laid out on boards sized without a count, such as the terminal
+    /// board and generated ones, the built in curve when not set.
+    pub density: Density,
+    /// Window board settings.
+    pub board: BoardSettings,
+    /// Window sound volumes and music.
+    pub audio: AudioSettings,
+    /// What the mouse buttons and keys do on the window board.
+    pub input: Bindings,
+    /// How the window board is drawn and presented.
+    pub render: RenderSettings,
+    /// How quickly presses must follow each other on the window board.
+    pub timing: TimingSettings,
+    /// Level of the traces written to the log file, no log when not set.
+    pub log: Option<Level>,
+}
+
+/// How the GUI sets up its board.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BoardSettings {
+    /// Preset the board starts with, the GUI default when not set.
+    pub difficulty: Option<Difficulty>,
+    /// Width and height of a cell in millimeters.
+    pub cell_size: Option<f32>,
+    /// Whether clicking a number whose flags are all placed uncovers its
+    /// neighbors.
+    pub chording: bool,
+    /// Whether the solver's next deduction is outlined on the board.
+    pub hints: bool,
+    /// Whether numbers with more flags around them than their count are
+    /// drawn in red.
+    pub contradictions: bool,
+    /// Whether chords the solver can prove careless are refused with a
+    /// warning.
+    pub safe_chords: bool,
+    /// Whether cells a single number proves safe are uncovered after every
+    /// move, the games are recorded as assisted.
+    pub auto_open: bool,
+    /// Whether mines the solver proves are flagged after every move, the
+    /// games are recorded as assisted.
+    pub auto_flag: bool,
+    /// Hints allowed per game, no limit when not set.
+    pub hint_budget: Option<u16>,
+    /// Whether the board uses dark colors.
+    pub dark: bool,
+    /// Whether two players take turns on the board.
+    pub versus: bool,
+    /// Whether games are practice, played with sound cues and left out of
+    /// the statistics.
+    pub practice: bool,
+    /// Whether speedrun splits are shown in the window title.
+    pub splits: bool,
+    /// Whether every board races the ghost of the best run on its level,
+    /// laid out the same.
+    pub ghost: bool,
+    /// Whether moving the keyboard focus plays a tone for the focused cell,
+    /// for playing by ear.
+    pub sonify: bool,
+    /// Whether a tooltip tells about the cell under the pointer, with its
+    /// chance of being a mine when practicing.
+    pub inspect: bool,
+    /// Whether the left click, right click and chord counts are shown in a
+    /// corner of the board.
+    pub counters: bool,
+    /// Whether the arcade score is shown in a corner of the board.
+    pub scoring: bool,
+    /// Whether games are played without flags and ranked apart in the
+    /// statistics.
+    pub no_flags: bool,
+    /// Whether flags on safe cells are refused, adding time to the clock.
+    pub flag_penalty: bool,
+    /// Which cells around a number it counts the mines of.
+    pub neighborhood: Neighborhood,
+    /// Seconds a number stays shown after it is uncovered in a blind game,
+    /// the numbers never fade when not set.
+    pub blind: Option<u16>,
+    /// Symmetry the mines are laid out with.
+    pub symmetry: Symmetry,
+    /// Rectangles laid out more or less densely than the rest, one per
+    /// `board.zone` line.
+    pub zones: Vec<Zone>,
+    /// Cells uncovered for the player on every board, a number of cells or
+    /// an opening.
+    pub handicap: Option<Handicap>,
+    /// Skin the board is drawn with, a name from
+    /// [`skin::installed`](crate::skin::installed) or a path, the built in
+    /// theme when not set.
+    pub skin: Option<String>,
+    /// What shows through the board around the cells.
+    pub backdrop: Backdrop,
+}
+
+/// What shows through the window board around its cells.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Backdrop {
+    /// The board color of the theme.
+    #[default]
+    Solid,
+    /// The desktop wallpaper tinted and blurred, Mica, on Windows 11.
+    Mica,
+    /// The windows below blurred, acrylic, on Windows 11.
+    Acrylic,
+}
+
+impl FromStr for Backdrop {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(Backdrop::Solid),
+            "mica" => Ok(Backdrop::Mica),
+            "acrylic" => Ok(Backdrop::Acrylic),
+            _ => Err(format!(
+                "unknown backdrop {}, expected solid, mica or acrylic",
+                s
+            )),
+        }
+    }
+}
+
+impl BoardSettings {
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let flag = || value.parse::<bool>().map_err(|e| e.to_string());
+        match key {
+            "difficulty" => self.difficulty = Some(value.parse()?),
+            "cell_size" => match value.parse::<f32>() {
+                Ok(size) if size > 0.0 => self.cell_size = Some(size),
+                _ => return Err(format!("invalid cell size {}", value)),
+            },
+            "chording" => self.chording = flag()?,
+            "hints" => self.hints = flag()?,
+            "contradictions" => self.contradictions = flag()?,
+            "safe_chords" => self.safe_chords = flag()?,
+            "auto_open" => self.auto_open = flag()?,
+            "auto_flag" => self.auto_flag = flag()?,
+            "hint_budget" => match value.parse() {
+                Ok(budget) => self.hint_budget = Some(budget),
+                Err(_) => return Err(format!("invalid hint budget {}", value)),
+            },
+            "dark" => self.dark = flag()?,
+            "versus" => self.versus = flag()?,
+            "practice" => self.practice = flag()?,
+            "splits" => self.splits = flag()?,
+            "ghost" => self.ghost = flag()?,
+            "sonify" => self.sonify = flag()?,
+            "inspect" => self.inspect = flag()?,
+            "counters" => self.counters = flag()?,
+            "scoring" => self.scoring = flag()?,
+            "no_flags" => self.no_flags = flag()?,
+            "flag_penalty" => self.flag_penalty = flag()?,
+            "neighborhood" => self.neighborhood = value.parse()?,
+            "blind" => match value.parse() {
+                Ok(seconds) => self.blind = Some(seconds),
+                Err(_) => return Err(format!("invalid blind time {}", value)),
+            },
+            "symmetry" => self.symmetry = value.parse()?,
+            "zone" => self.zones.push(value.parse()?),
+            "handicap" => self.handicap = Some(value.parse()?),
+            "skin" => self.skin = (value != CLASSIC_SKIN).then(|| value.to_string()),
+            "backdrop" => self.backdrop = value.parse()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// How the GUI draws and presents its frames, the defaults favor battery
+/// life over latency.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderSettings {
+    /// Whether frames wait for the display refresh, when off they are shown
+    /// as soon as they are drawn, for low latency speedrun setups.
+    pub vsync: bool,
+    /// Most updates per second of the board's timed effects.
+    pub frame_rate: u16,
+    /// How the numbers on the board are smoothed.
+    pub text: TextAntialias,
+    /// Whether the edges of cells, outlines and sprites are smoothed, when
+    /// off they are drawn on whole pixels.
+    pub antialias: bool,
+}
+
+/// How the window board smooths the edges of its text.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TextAntialias {
+    /// As the system is set.
+    #[default]
+    System,
+    /// ClearType, grayscale where the board is see through.
+    ClearType,
+    /// Shades of gray, the same on every monitor.
+    Grayscale,
+    /// Whole pixels, sharp but jagged.
+    Aliased,
+}
+
+impl FromStr for TextAntialias {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "system" => Ok(TextAntialias::System),
+            "cleartype" => Ok(TextAntialias::ClearType),
+            "grayscale" => Ok(TextAntialias::Grayscale),
+            "aliased" => Ok(TextAntialias::Aliased),
+            _ => Err(format!(
+                "unknown text antialiasing {}, expected system, cleartype, grayscale or aliased",
+                s
+            )),
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            vsync: true,
+            frame_rate: 20,
+            text: TextAntialias::System,
+            antialias: true,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Milliseconds between the updates of the board's timed effects.
+    pub fn timer_period(&self) -> u32 {
+        1000 / self.frame_rate.max(1) as u32
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        match key {
+            "vsync" => self.vsync = value.parse::<bool>().map_err(|e| e.to_string())?,
+            "frame_rate" => match value.parse::<u16>() {
+                Ok(rate) if (1..=1000).contains(&rate) => self.frame_rate = rate,
+                _ => return Err(format!("invalid frame rate {}, expected 1 to 1000", value)),
+            },
+            "text" => self.text = value.parse()?,
+            "antialias" => self.antialias = value.parse::<bool>().map_err(|e| e.to_string())?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// How the GUI tells chords, long presses and double clicks apart from
+/// single clicks, and when a click acts. Longer times suit players who
+/// cannot press quickly or steadily.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimingSettings {
+    /// Most milliseconds between pressing the left and right buttons for
+    /// the pair to chord, 0 never chords with both buttons.
+    pub chord: u32,
+    /// Milliseconds a finger stays down on a cell before it acts as the
+    /// right button.
+    pub long_press: u32,
+    /// Most milliseconds between two clicks on a number for them to chord,
+    /// 0 never chords on a double click.
+    pub double_click: u32,
+    /// Whether a click acts when its button goes down rather than up, a
+    /// chord still acts when the buttons go up.
+    pub act_on_press: bool,
+}
+
+impl Default for TimingSettings {
+    fn default() -> Self {
+        TimingSettings {
+            chord: 250,
+            long_press: 500,
+            double_click: 0,
+            act_on_press: false,
+        }
+    }
+}
+
+impl TimingSettings {
+    /// Longest time any of the settings takes, ten seconds.
+    pub const MAX: u32 = 10_000;
+    /// Shortest long press, quicker ones would flag cells meant to be
+    /// uncovered.
+    pub const MIN_LONG_PRESS: u32 = 100;
+
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let millis = |least: u32| match value.parse::<u32>() {
+            Ok(millis) if (least..=Self::MAX).contains(&millis) => Ok(millis),
+            _ => Err(format!(
+                "invalid time {}, expected {} to {} milliseconds",
+                value,
+                least,
+                Self::MAX
+            )),
+        };
+        match key {
+            "chord" => self.chord = millis(0)?,
+            "long_press" => self.long_press = millis(Self::MIN_LONG_PRESS)?,
+            "double_click" => self.double_click = millis(0)?,
+            "act_on_press" => {
+                self.act_on_press = value.parse::<bool>().map_err(|e| e.to_string())?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The times as config file keys and values, see [`Config::store`].
+    pub fn entries(&self) -> Vec<(String, String)> {
+        vec![
+            ("timing.chord".to_string(), self.chord.to_string()),
+            ("timing.long_press".to_string(), self.long_press.to_string()),
+            (
+                "timing.double_click".to_string(),
+                self.double_click.to_string(),
+            ),
+            (
+                "timing.act_on_press".to_string(),
+                self.act_on_press.to_string(),
+            ),
+        ]
+    }
+}
+
+/// Loudness of one category of sounds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Volume {
+    /// Level from 0, silent, to 1, full volume.
+    pub level: f32,
+    /// Whether the category is silent whatever its level.
+    pub muted: bool,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume {
+            level: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Volume {
+    /// The level sounds are played at, 0 when muted.
+    pub fn gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.level
+        }
+    }
+}
+
+/// How loud the GUI plays each category of sounds and the music it plays.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AudioSettings {
+    /// Short sounds for moves, wins and losses.
+    pub effects: Volume,
+    /// Background music.
+    pub music: Volume,
+    /// WAV file looped while a game is played, no music when not set.
+    pub music_playing: Option<PathBuf>,
+    /// WAV file crossfaded to once a game is over, looped in step with
+    /// [`AudioSettings::music_playing`].
+    pub music_game_over: Option<PathBuf>,
+}
+
+impl AudioSettings {
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let level = || match value.parse::<f32>() {
+            Ok(level) if (0.0..=1.0).contains(&level) => Ok(level),
+            _ => Err(format!("invalid volume {}, expected 0 to 1", value)),
+        };
+        let flag = || value.parse::<bool>().map_err(|e| e.to_string());
+        match key {
+            "effects" => self.effects.level = level()?,
+            "effects_muted" => self.effects.muted = flag()?,
+            "music" => self.music.level = level()?,
+            "music_muted" => self.music.muted = flag()?,
+            "music_playing" => self.music_playing = Some(value.into()),
+            "music_game_over" => self.music_game_over = Some(value.into()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The volumes as config file keys and values, see [`Config::store`].
+    /// The music files are only ever set by hand and left as they are.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        vec![
+            ("audio.effects".to_string(), self.effects.level.to_string()),
+            (
+                "audio.effects_muted".to_string(),
+                self.effects.muted.to_string(),
+            ),
+            ("audio.music".to_string(), self.music.level.to_string()),
+            (
+                "audio.music_muted".to_string(),
+                self.music.muted.to_string(),
+            ),
+        ]
+    }
+}
+
+impl Config {
+    /// Loads the user's config file. A missing file gives the defaults.
+    pub fn load() -> Result<Config> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        match File::open(path) {
+            Ok(file) => Config::read(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Where the user's config file is, `None` without a data directory.
+    pub fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    /// When the user's config file was last written, `None` when there is
+    /// no file.
+    pub fn modified() -> Option<SystemTime> {
+        fs::metadata(Config::path()?).ok()?.modified().ok()
+    }
+
+    /// Reads settings, failing on malformed lines or invalid values.
+    pub fn read<R: BufRead>(reader: R) -> Result<Config> {
+        let mut config = Config::default();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| {
+                MinesweeperError::Parse(format!("line {}: {}", number + 1, message))
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            config.set(key.trim(), value.trim()).map_err(error)?;
+        }
+        Ok(config)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        match key.split_once('.') {
+            Some(("glyphs", glyph)) => self.glyphs.set(glyph, value),
+            Some(("board", setting)) => self.board.set(setting, value),
+            Some(("audio", setting)) => self.audio.set(setting, value),
+            Some(("render", setting)) => self.render.set(setting, value),
+            Some(("timing", setting)) => self.timing.set(setting, value),
+            Some(("input", input)) => self.input.set_str(input, value),
+            _ => {
+                match key {
+                    "style" => self.style = Some(value.parse()?),
+                    "theme" => self.theme = (value != NO_THEME).then(|| value.to_string()),
+                    "density" => self.density = value.parse()?,
+                    "log" => self.log = Some(value.parse().map_err(|_| invalid_level(value))?),
+                    // unknown keys are skipped
+                    _ => {}
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes settings to the user's config file, replacing the lines that
+    /// set the same keys. Comments, unknown keys and other settings are kept.
+    pub fn store(entries: &[(String, String)]) -> Result<()> {
+        let Some(path) = Config::path() else {
+            return Ok(());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, rewrite(&text, entries))?;
+        Ok(())
+    }
+}
+
+/// Replaces the value of every key in `entries` set in `text` and appends the
+/// ones it does not set.
+fn rewrite(text: &str, entries: &[(String, String)]) -> String {
+    let mut written = vec![false; entries.len()];
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        let key = line.split_once('=').map(|(key, _)| key.trim());
+        match entries
+            .iter()
+            .position(|(entry, _)| Some(entry.as_str()) == key)
+        {
+            Some(index) if !line.trim_start().starts_with('#') => {
+                if !written[index] {
+                    out.push_str(&format!("{} = {}\n", entries[index].0, entries[index].1));
+                    written[index] = true;
+                }
+            }
+            _ => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    for ((key, value), written) in entries.iter().zip(written) {
+        if !written {
+            out.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+    out
+}
+
+fn invalid_level(value: &str) -> String {
+    format!(
+        "invalid log level {}, expected error, warn, info, debug or trace",
+        value
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bindings::{Action, Input};
+
+    #[test]
+    pub fn test_read() {
+        let text = "# settings\nstyle = ascii\n\nglyphs.flag = P\nfuture = 1\nlog = debug\n\
+            board.difficulty = expert\nboard.cell_size = 8\nboard.chording = true\n\
+            board.hint_budget = 3\nboard.ghost = true\nboard.sonify = true\nboard.inspect = true\nboard.flag_penalty = true\nboard.neighborhood = distance2\nboard.blind = 3\nboard.symmetry = vertical\nboard.zone = 0,0,4,4,0%\nboard.zone = 4,0,5,9,150\nboard.handicap = 5\nboard.skin = midnight.zip\nboard.backdrop = mica\n\
+            input.left = flag\ntheme = sunset.theme\ndensity = 15%\n";
+        let config = Config::read(text.as_bytes()).unwrap();
+        assert_eq!(Some(Style::Ascii), config.style);
+        assert_eq!(Some(Level::DEBUG), config.log);
+        assert_eq!(Density::Percent(15.0), config.density);
+        assert_eq!(Some("P".to_string()), config.glyphs.flag);
+        assert_eq!(Some(Difficulty::Expert), config.board.difficulty);
+        assert_eq!(Some(8.0), config.board.cell_size);
+        assert!(config.board.chording && !config.board.hints);
+        assert_eq!(Some(3), config.board.hint_budget);
+        assert!(config.board.ghost && config.board.sonify && config.board.inspect);
+        assert!(config.board.flag_penalty);
+        assert_eq!(Neighborhood::Distance2, config.board.neighborhood);
+        assert_eq!(Some(3), config.board.blind);
+        assert_eq!(Symmetry::Vertical, config.board.symmetry);
+        assert_eq!(2, config.board.zones.len());
+        assert_eq!(150, config.board.zones[1].weight);
+        assert_eq!(Some(Handicap::Cells(5)), config.board.handicap);
+        assert_eq!(Some("midnight.zip".to_string()), config.board.skin);
+        assert_eq!(Backdrop::Mica, config.board.backdrop);
+        let classic = Config::read("board.skin = classic\n".as_bytes()).unwrap();
+        assert_eq!(None, classic.board.skin);
+        assert_eq!(Some("sunset.theme".to_string()), config.theme);
+        let none = Config::read("theme = none\n".as_bytes()).unwrap();
+        assert_eq!(None, none.theme);
+        assert_eq!(Some(Action::Flag), config.input.action(Input::Left));
+        assert_eq!(AudioSettings::default(), config.audio);
+        assert_eq!(RenderSettings::default(), config.render);
+        assert_eq!(TimingSettings::default(), config.timing);
+    }
+
+    #[test]
+    pub fn test_timing_round_trip() {
+        let text = "timing.chord = 600\ntiming.long_press = 1500\n";
+        let timing = Config::read(text.as_bytes()).unwrap().timing;
+        assert_eq!(600, timing.chord);
+        assert_eq!(1500, timing.long_press);
+        assert_eq!(TimingSettings::default().double_click, timing.double_click);
+        let timing = TimingSettings {
+            double_click: 700,
+            act_on_press: true,
+            ..timing
+        };
+        let text = rewrite(text, &timing.entries());
+        assert!(text.starts_with("timing.chord = 600\ntiming.long_press = 1500\n"));
+        assert_eq!(timing, Config::read(text.as_bytes()).unwrap().timing);
+    }
+
+    #[test]
+    pub fn test_read_render() {
+        let text = "render.vsync = false
+render.frame_rate = 60
+render.text = aliased
+render.antialias = false
+";
+        let render = Config::read(text.as_bytes()).unwrap().render;
+        assert!(!render.vsync && !render.antialias);
+        assert_eq!(TextAntialias::Aliased, render.text);
+        assert_eq!(16, render.timer_period());
+        assert_eq!(50, RenderSettings::default().timer_period());
+    }
+
+    #[test]
+    pub fn test_read_music() {
+        let text = "audio.music_playing = music/playing.wav\naudio.music_game_over = over.wav\n";
+        let config = Config::read(text.as_bytes()).unwrap();
+        assert_eq!(
+            Some(PathBuf::from("music/playing.wav")),
+            config.audio.music_playing
+        );
+        assert_eq!(
+            Some(PathBuf::from("over.wav")),
+            config.audio.music_game_over
+        );
+        // storing the volumes keeps the tracks
+        let text = rewrite(text, &config.audio.entries());
+        assert_eq!(config.audio, Config::read(text.as_bytes()).unwrap().audio);
+    }
+
+    #[test]
+    pub fn test_audio_round_trip() {
+        let mut audio = AudioSettings::default();
+        audio.effects.muted = true;
+        audio.music.level = 0.25;
+        let text = "# sounds\naudio.music = 1\nstyle = ascii\n";
+        let text = rewrite(text, &audio.entries());
+        assert!(text.starts_with("# sounds\naudio.music = 0.25\nstyle = ascii\n"));
+        let config = Config::read(text.as_bytes()).unwrap();
+        assert_eq!(audio, config.audio);
+        assert_eq!(Some(Style::Ascii), config.style);
+        assert_eq!(0.0, config.audio.effects.gain());
+    }
+
+    #[test]
+    pub fn test_read_invalid() {
+        assert!(Config::read("style = fancy\n".as_bytes()).is_err());
+        assert!(Config::read("glyphs.tile = x\n".as_bytes()).is_err());
+        assert!(Config::read("style\n".as_bytes()).is_err());
+        assert!(Config::read("log = loud\n".as_bytes()).is_err());
+        assert!(Config::read("density = 120%\n".as_bytes()).is_err());
+        assert!(Config::read("board.cell_size = -1\n".as_bytes()).is_err());
+        assert!(Config::read("board.hints = maybe\n".as_bytes()).is_err());
+        assert!(Config::read("board.hint_budget = -1\n".as_bytes()).is_err());
+        assert!(Config::read("board.backdrop = glass\n".as_bytes()).is_err());
+        assert!(Config::read("board.neighborhood = knight\n".as_bytes()).is_err());
+        assert!(Config::read("board.blind = soon\n".as_bytes()).is_err());
+        assert!(Config::read("board.symmetry = spiral\n".as_bytes()).is_err());
+        assert!(Config::read("board.zone = 0,0,0,4,50%\n".as_bytes()).is_err());
+        assert!(Config::read("board.handicap = -1\n".as_bytes()).is_err());
+        assert!(Config::read("audio.music = 2\n".as_bytes()).is_err());
+        assert!(Config::read("render.frame_rate = 0\n".as_bytes()).is_err());
+        assert!(Config::read("render.vsync = maybe\n".as_bytes()).is_err());
+        assert!(Config::read("render.text = smooth\n".as_bytes()).is_err());
+        assert!(Config::read("timing.chord = -1\n".as_bytes()).is_err());
+        assert!(Config::read("timing.long_press = 50\n".as_bytes()).is_err());
+        assert!(Config::read("timing.double_click = 20000\n".as_bytes()).is_err());
+        assert!(Config::read("timing.act_on_press = 1\n".as_bytes()).is_err());
+        assert!(Config::read("input.thumb = flag\n".as_bytes()).is_err());
+    }
+}
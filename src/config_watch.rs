@@ -0,0 +1,96 @@
+//! Watches the config file so the app can apply edits to it while running.
+
+use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::Path, thread};
+
+use windows::{
+    core::{Error, Result, HSTRING},
+    Win32::{
+        Foundation::{CloseHandle, E_INVALIDARG, HANDLE, HWND, LPARAM, WPARAM},
+        Storage::FileSystem::{
+            CreateFileW, ReadDirectoryChangesW, FILE_FLAG_BACKUP_SEMANTICS, FILE_LIST_DIRECTORY,
+            FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_INFORMATION,
+            FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        },
+        UI::WindowsAndMessaging::{PostMessageW, WM_APP},
+    },
+};
+
+/// Posted to the app window when the config file was written.
+pub(crate) const WM_CONFIG_CHANGED: u32 = WM_APP + 2;
+
+// room for the changes made between two reads, in 4 byte words as the
+// notifications must be aligned
+const BUFFER_WORDS: usize = 1024;
+
+/// Watches the folder of the config file at `path` on a thread, posting
+/// [`WM_CONFIG_CHANGED`] to `window` whenever the file is written or renamed
+/// into place, as editors saving a copy do. The thread ends once the window
+/// is gone.
+pub(crate) fn watch(window: HWND, path: &Path) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(Error::new(E_INVALIDARG, "the config file has no folder"));
+    };
+    // the folder is only made when the settings are first stored
+    let _ = std::fs::create_dir_all(dir);
+    let directory = unsafe {
+        CreateFileW(
+            &HSTRING::from(dir),
+            FILE_LIST_DIRECTORY.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            HANDLE(0),
+        )?
+    };
+    let name = name.to_os_string();
+    thread::spawn(move || {
+        let mut buffer = [0u32; BUFFER_WORDS];
+        loop {
+            let mut written = 0;
+            let read = unsafe {
+                ReadDirectoryChangesW(
+                    directory,
+                    buffer.as_mut_ptr().cast(),
+                    std::mem::size_of_val(&buffer) as u32,
+                    false,
+                    FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_FILE_NAME,
+                    Some(&mut written),
+                    None,
+                    None,
+                )
+            };
+            if let Err(error) = read {
+                tracing::warn!(%error, "stopped watching the config file");
+                break;
+            }
+            // nothing written means the changes overflowed the buffer
+            if written == 0 || names(&buffer).any(|changed| changed.eq_ignore_ascii_case(&name)) {
+                tracing::debug!("config file changed");
+                let posted =
+                    unsafe { PostMessageW(window, WM_CONFIG_CHANGED, WPARAM(0), LPARAM(0)) };
+                if posted.is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = unsafe { CloseHandle(directory) };
+    });
+    Ok(())
+}
+
+/// The file names in a buffer of change notifications.
+fn names(buffer: &[u32]) -> impl Iterator<Item = OsString> + '_ {
+    let mut offset = Some(0);
+    std::iter::from_fn(move || {
+        let at = offset?;
+        // the notifications are laid out one after the other from the start
+        let info =
+            unsafe { &*(buffer.as_ptr().cast::<u8>().add(at) as *const FILE_NOTIFY_INFORMATION) };
+        let name = unsafe {
+            std::slice::from_raw_parts(info.FileName.as_ptr(), info.FileNameLength as usize / 2)
+        };
+        offset = (info.NextEntryOffset != 0).then(|| at + info.NextEntryOffset as usize);
+        Some(OsString::from_wide(name))
+    })
+}
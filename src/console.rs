@@ -0,0 +1,175 @@
+//! Commands typed at the debug console of the GUI, run against the live game.
+//!
+//! The console takes the board commands of the CLI, `u[x,y]`, `f[x,y]`,
+//! `?[x,y]` and `c[x,y]` to uncover, flag, question and chord, `z` and `y` to
+//! undo and redo and `h` for a hint, and a few more for reproducing bugs:
+//!
+//! ```text
+//! mine [x,y]   lay a mine under a covered cell
+//! safe [x,y]   take the mine away from under a covered cell
+//! dump         the board as text with the hidden mines shown
+//! solve        every deduction the solver can make
+//! check        the game's invariants
+//! ```
+//!
+//! Laying or taking a mine recreates the game from its cells, so the undo
+//! history starts over.
+
+use crate::{
+    game::{CellState, Game, Move},
+    render, solver,
+    text::{self, Glyphs, Style},
+};
+
+/// Runs one console command on `game` and returns what to print, or why the
+/// command was refused.
+pub fn run(game: &mut Game, line: &str) -> Result<String, String> {
+    let line = line.trim();
+    let (name, rest) = match line.find(|c: char| c == '[' || c.is_whitespace()) {
+        Some(at) => line.split_at(at),
+        None => (line, ""),
+    };
+    let output = match name {
+        "u" | "f" | "?" | "c" => {
+            let (x, y) = coords(rest, game)?;
+            let mv = match name {
+                "u" => Move::Uncover(x, y),
+                "f" => Move::Flag(x, y),
+                "?" => Move::Question(x, y),
+                _ => Move::Chord(x, y),
+            };
+            let state = game.apply(mv);
+            format!("{}, game {}", mv, state)
+        }
+        "z" => match game.undo() {
+            Some(mv) => format!("undid {}", mv),
+            None => "nothing to undo".to_string(),
+        },
+        "y" => match game.redo() {
+            Some(mv) => format!("redid {}", mv),
+            None => "nothing to redo".to_string(),
+        },
+        "h" => match game.hint() {
+            Some(deduction) => format!("hint: {}", deduction),
+            None => "no hint".to_string(),
+        },
+        "mine" | "safe" => {
+            let (x, y) = coords(rest, game)?;
+            set_mine(game, x, y, name == "mine")?;
+            format!("{} [{},{}]", name, x, y)
+        }
+        "dump" => text::render_overlays(
+            game,
+            Style::Ascii,
+            &Glyphs::default(),
+            &render::debug_overlays(game),
+        ),
+        "solve" => {
+            let deductions = solver::solve(game);
+            if deductions.is_empty() {
+                "the solver cannot prove any cell".to_string()
+            } else {
+                let lines: Vec<String> = deductions.iter().map(ToString::to_string).collect();
+                lines.join("\n")
+            }
+        }
+        "check" => match game.check_invariants() {
+            Ok(()) => "invariants hold".to_string(),
+            Err(e) => return Err(e),
+        },
+        _ => return Err(format!("unknown command {}", line)),
+    };
+    tracing::debug!(command = line, "console");
+    Ok(output)
+}
+
+fn coords(s: &str, game: &Game) -> Result<(i16, i16), String> {
+    let invalid = || format!("expected coordinates as [x,y], not {}", s.trim());
+    let (x, y) = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .and_then(|s| s.split_once(','))
+        .ok_or_else(invalid)?;
+    let x: i16 = x.trim().parse().map_err(|_| invalid())?;
+    let y: i16 = y.trim().parse().map_err(|_| invalid())?;
+    if x < 0 || y < 0 || x >= game.width() || y >= game.height() {
+        return Err(format!("[{},{}] is off the board", x, y));
+    }
+    Ok((x, y))
+}
+
+/// Lays or takes away the mine under a covered cell, keeping the counts of
+/// the uncovered cells around it right.
+fn set_mine(game: &mut Game, x: i16, y: i16, mined: bool) -> Result<(), String> {
+    let width = game.width();
+    let index = |x: i16, y: i16| y as usize * width as usize + x as usize;
+    let mut cells: Vec<CellState> = (0..game.height())
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| game.cell_state(x, y))
+        .collect();
+    let cell = &mut cells[index(x, y)];
+    let was_mined = cell.is_mined();
+    *cell = match *cell {
+        CellState::Unknown(_) => CellState::Unknown(mined),
+        CellState::Flagged(_) => CellState::Flagged(mined),
+        CellState::Questioned(_) => CellState::Questioned(mined),
+        _ => return Err(format!("[{},{}] is not covered", x, y)),
+    };
+    if was_mined != mined {
+        for (nx, ny) in solver::neighbors(game, x, y) {
+            let neighbor = &mut cells[index(nx, ny)];
+            *neighbor = match (*neighbor, mined) {
+                (CellState::Known(false), true) => CellState::Counted(1),
+                (CellState::Counted(1), false) => CellState::Known(false),
+                (CellState::Counted(count), true) => CellState::Counted(count + 1),
+                (CellState::Counted(count), false) => CellState::Counted(count - 1),
+                (state, _) => state,
+            };
+        }
+    }
+    *game = Game::from_cells(width, game.height(), cells, game.state(), game.elapsed());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    pub fn test_console() {
+        //   * . .
+        //   . . .
+        let mut layout = vec![false; 6];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 2, &layout);
+        assert_eq!(
+            "uncover [2,0], game playing",
+            run(&mut game, "u[2,0]").unwrap()
+        );
+        assert_eq!("* 1 . \n# 1 . \n", run(&mut game, "dump").unwrap());
+        // both counts see the same two covered cells
+        let solved = run(&mut game, "solve").unwrap();
+        assert_eq!("the solver cannot prove any cell", solved);
+
+        // a mine laid next to the opening bumps its counts
+        assert!(run(&mut game, "mine [2,0]").is_err());
+        assert!(run(&mut game, "mine [9,9]").is_err());
+        assert!(run(&mut game, "safe [0,0]").is_ok());
+        assert_eq!("invariants hold", run(&mut game, "check").unwrap());
+        assert_eq!(0, game.total());
+        assert_eq!(CellState::Known(false), game.cell_state(1, 0));
+        run(&mut game, "mine [0,0]").unwrap();
+        assert_eq!(CellState::Counted(1), game.cell_state(1, 1));
+        assert_eq!("invariants hold", run(&mut game, "check").unwrap());
+
+        assert_eq!(
+            "uncover [0,0], game lost",
+            run(&mut game, "u [0,0]").unwrap()
+        );
+        assert_eq!(GameState::Lost, game.state());
+        assert_eq!("undid uncover [0,0]", run(&mut game, "z").unwrap());
+        assert!(run(&mut game, "x").is_err());
+    }
+}
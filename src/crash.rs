@@ -0,0 +1,188 @@
+//! Crash reports written when the program panics, so a reported crash comes
+//! with something to go on.
+//!
+//! [`install`] sets a panic hook that writes the panic message, a backtrace,
+//! the latest traces and the game being played to a file in the `crashes`
+//! folder of the data directory. The front-ends describe their game with
+//! [`set_game`] as it is played, and the `logging` feature keeps the latest
+//! traces in memory through [`RecentEvents`] even when no log file is
+//! written.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs, io, panic,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    game::{Game, Move},
+    stats::{data_dir, level_name},
+};
+
+/// Number of trace lines kept for a crash report.
+pub const RECENT_EVENTS: usize = 100;
+const CRASH_DIR: &str = "crashes";
+
+static EVENTS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static GAME: Mutex<Option<String>> = Mutex::new(None);
+
+/// Writes formatted trace lines to memory, keeping the latest
+/// [`RECENT_EVENTS`] of them for a crash report.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RecentEvents;
+
+impl io::Write for RecentEvents {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        // a poisoned lock still holds the lines written before the panic
+        let mut events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if events.len() == RECENT_EVENTS {
+                events.pop_front();
+            }
+            events.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The trace lines kept for a crash report, oldest first.
+pub fn recent_events() -> Vec<String> {
+    let events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    events.iter().cloned().collect()
+}
+
+/// Notes the game being played and the move last made on it for a crash
+/// report.
+pub fn set_game(game: &Game, last: Option<Move>) {
+    let config = game.config();
+    let clicks = game.clicks();
+    let mut text = format!(
+        "{}, seed {}, {} after {} uncovers, {} flags and {} chords in {:.1} s",
+        level_name(config.width, config.height, config.mines),
+        game.seed()
+            .map_or_else(|| "none".to_string(), |seed| seed.to_string()),
+        game.state(),
+        clicks.uncovers,
+        clicks.flags,
+        clicks.chords,
+        game.elapsed().as_secs_f32()
+    );
+    if let Some(mv) = last {
+        text.push_str(&format!(", last {}", mv));
+    }
+    *GAME.lock().unwrap_or_else(|e| e.into_inner()) = Some(text);
+}
+
+/// Where crash reports are written.
+pub fn crash_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(CRASH_DIR))
+}
+
+/// Writes a crash report whenever a thread panics, then runs the panic hook
+/// set before, and calls `on_report` with the file written. Traces logged by
+/// a hook set afterwards, such as the one of `logging::init`, make it into
+/// the report.
+pub fn install(on_report: impl Fn(&Path) + Send + Sync + 'static) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let game = GAME.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let text = report(
+            &info.to_string(),
+            thread::current().name(),
+            game.as_deref(),
+            &recent_events(),
+            &Backtrace::force_capture(),
+        );
+        previous(info);
+        match write_report(&text) {
+            Ok(path) => on_report(&path),
+            Err(error) => eprintln!("unable to write a crash report: {}", error),
+        }
+    }));
+}
+
+/// Formats a crash report.
+pub fn report(
+    panic: &str,
+    thread: Option<&str>,
+    game: Option<&str>,
+    events: &[String],
+    backtrace: &dyn std::fmt::Display,
+) -> String {
+    let mut text = format!(
+        "Minesweeper {} crashed on the {} thread\n{}\n\nGame: {}\n\nRecent events:\n",
+        env!("CARGO_PKG_VERSION"),
+        thread.unwrap_or("unnamed"),
+        panic,
+        game.unwrap_or("none started")
+    );
+    if events.is_empty() {
+        text.push_str("none kept, turn logging on for more\n");
+    }
+    for event in events {
+        text.push_str(event);
+        text.push('\n');
+    }
+    text.push_str(&format!("\nBacktrace:\n{}\n", backtrace));
+    text
+}
+
+fn write_report(text: &str) -> io::Result<PathBuf> {
+    let dir = crash_dir().ok_or_else(|| io::Error::other("no data directory"))?;
+    fs::create_dir_all(&dir)?;
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = dir.join(format!("crash-{}.txt", seconds));
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    pub fn test_report() {
+        let mut game = Game::with_mines(9, 9, 10);
+        game.reset_with_seed(42);
+        game.apply(Move::Flag(0, 0));
+        set_game(&game, Some(Move::Flag(0, 0)));
+        let described = GAME.lock().unwrap().clone().unwrap();
+        assert!(described.starts_with("beginner, seed 42, "));
+        assert!(described.ends_with(", last flag [0,0]"));
+
+        let mut events = RecentEvents;
+        write!(events, "first\n\nsecond\n").unwrap();
+        let recent = recent_events();
+        assert!(recent.ends_with(&["first".to_string(), "second".to_string()]));
+        for i in 0..RECENT_EVENTS {
+            writeln!(events, "event {}", i).unwrap();
+        }
+        assert_eq!(RECENT_EVENTS, recent_events().len());
+
+        let text = report(
+            "panicked at src/game.rs:1:1:\noops",
+            Some("main"),
+            Some(&described),
+            &["an event".to_string()],
+            &"frame 0",
+        );
+        assert!(text.contains("crashed on the main thread\npanicked at src/game.rs:1:1:\noops"));
+        assert!(text.contains(&format!("Game: {}\n", described)));
+        assert!(text.contains("Recent events:\nan event\n"));
+        assert!(text.ends_with("Backtrace:\nframe 0\n"));
+        let empty = report("oops", None, None, &[], &"");
+        assert!(empty.contains("none kept, turn logging on for more"));
+    }
+}
@@ -0,0 +1,150 @@
+//! CSV export of the statistics store for spreadsheets: every game played
+//! and the results per level.
+//!
+//! Fields an older build did not keep are left empty. Times are UTC.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::Result,
+    stats::{self, Record, Stats},
+};
+
+/// Writes every game in the order played, a header row first.
+pub fn write_history<W: Write>(stats: &Stats, mut writer: W) -> Result<()> {
+    writeln!(
+        writer,
+        "date,level,result,seconds,3bv,3bv_cleared,3bv_per_second,efficiency,\
+         uncovers,chords,flags,wasted,daily,session,mode,assist,hints,score"
+    )?;
+    for record in stats.records() {
+        writeln!(writer, "{}", history_row(record))?;
+    }
+    Ok(())
+}
+
+/// Writes the games played, won, win rate, best time and best score of every
+/// level and of all levels together, a header row first.
+pub fn write_summary<W: Write>(stats: &Stats, mut writer: W) -> Result<()> {
+    writeln!(writer, "level,played,won,win_rate,best_seconds,best_score")?;
+    let summary = stats.summary();
+    for level in &summary {
+        writeln!(
+            writer,
+            "{},{},{},{:.1},{},{}",
+            level.level,
+            level.played,
+            level.won,
+            level.win_rate(),
+            optional(level.best.map(|best| format!("{:.3}", best))),
+            optional(level.best_score)
+        )?;
+    }
+    let played: usize = summary.iter().map(|level| level.played).sum();
+    let won: usize = summary.iter().map(|level| level.won).sum();
+    let rate = if played == 0 {
+        0.0
+    } else {
+        won as f64 / played as f64 * 100.0
+    };
+    // best times and scores of different levels cannot be compared
+    writeln!(writer, "all,{},{},{:.1},,", played, won, rate)?;
+    Ok(())
+}
+
+/// Writes the history to `path` and the summary next to it, `history.csv`
+/// gives `history-summary.csv`. Returns the path of the summary.
+pub fn export(stats: &Stats, path: &Path) -> Result<PathBuf> {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push("-summary.csv");
+    let summary = path.with_file_name(name);
+    let mut history = BufWriter::new(File::create(path)?);
+    write_history(stats, &mut history)?;
+    history.flush()?;
+    let mut writer = BufWriter::new(File::create(&summary)?);
+    write_summary(stats, &mut writer)?;
+    writer.flush()?;
+    Ok(summary)
+}
+
+fn history_row(record: &Record) -> String {
+    let three_bv = record.three_bv;
+    let clicks = record.clicks;
+    [
+        stats::date_time(record.timestamp),
+        record.level.clone(),
+        if record.won { "won" } else { "lost" }.to_string(),
+        format!("{:.3}", record.elapsed),
+        optional(three_bv.map(|bv| bv.total)),
+        optional(three_bv.map(|bv| bv.cleared)),
+        optional(
+            record
+                .three_bv_per_second()
+                .map(|bvs| format!("{:.3}", bvs)),
+        ),
+        optional(
+            record
+                .efficiency()
+                .map(|efficiency| format!("{:.1}", efficiency)),
+        ),
+        optional(clicks.map(|clicks| clicks.uncovers)),
+        optional(clicks.map(|clicks| clicks.chords)),
+        optional(clicks.map(|clicks| clicks.flags)),
+        optional(clicks.map(|clicks| clicks.wasted)),
+        optional(record.daily.map(stats::date)),
+        optional(record.session),
+        if record.no_flags { "nf" } else { "" }.to_string(),
+        if record.assisted { "auto" } else { "" }.to_string(),
+        record.hints.to_string(),
+        optional(record.score),
+    ]
+    .join(",")
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_write_csv() {
+        let text = "time=1792118682 level=expert result=won elapsed=100.0 3bv=150/150 \
+                    uncovers=120 chords=15 flags=40 wasted=5 session=7 score=2250\n\
+                    time=1792118700 level=beginner result=lost elapsed=3.5 daily=2026-10-16 \
+                    mode=nf\n";
+        let stats = Stats::read(text.as_bytes()).unwrap();
+        let mut history = Vec::new();
+        write_history(&stats, &mut history).unwrap();
+        let history = String::from_utf8(history).unwrap();
+        let rows: Vec<&str> = history.lines().collect();
+        assert_eq!(3, rows.len());
+        assert_eq!(
+            "2026-10-16 02:44:42,expert,won,100.000,150,150,1.500,85.7,120,15,40,5,,7,,,0,2250",
+            rows[1]
+        );
+        assert_eq!(
+            "2026-10-16 02:45:00,beginner,lost,3.500,,,,,,,,,2026-10-16,,nf,,0,",
+            rows[2]
+        );
+        // every row has as many fields as the header
+        let fields = rows[0].split(',').count();
+        assert!(rows.iter().all(|row| row.split(',').count() == fields));
+
+        let mut summary = Vec::new();
+        write_summary(&stats, &mut summary).unwrap();
+        assert_eq!(
+            "level,played,won,win_rate,best_seconds,best_score\n\
+             expert,1,1,100.0,100.000,2250\n\
+             beginner NF,1,0,0.0,,\n\
+             all,2,1,50.0,,\n",
+            String::from_utf8(summary).unwrap()
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Sound cues that react to play: a clock ticking faster as the player
+//! closes in on their best time and a heartbeat while the pointer rests on a
+//! cell likely to hold a mine.
+//!
+//! [`Cues`] decides when each sound is due from the game clock, the samples
+//! are synthesized by [`tick`] and [`heartbeat`] so no sound files are needed
+//! and a front-end only has to play them.
+//!
+//! [`cell_tone`] sonifies a cell for playing by ear, a number sounding
+//! higher the more mines it counts.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use crate::game::CellState;
+
+/// Samples per second of the synthesized sounds, 16 bit mono.
+pub const SAMPLE_RATE: u32 = 22_050;
+/// Time between ticks while the best time is still far off.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(1000);
+/// Time between ticks once the best time is reached.
+pub const FASTEST_TICK: Duration = Duration::from_millis(250);
+/// Share of the best time after which the clock starts to speed up.
+pub const TENSE_FROM: f64 = 0.5;
+/// Mine probability from which a hovered cell gives a heartbeat.
+pub const HEARTBEAT_PROBABILITY: f32 = 0.5;
+/// Time between heartbeats while the pointer stays on a likely mine.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(900);
+/// Pitch of an uncovered cell without mined neighbors, middle C, in hertz.
+pub const EMPTY_PITCH: f32 = 261.63;
+/// Semitones each mined neighbor raises the pitch of a number, a whole tone.
+pub const COUNT_STEP: f32 = 2.0;
+
+/// Sounds to start now.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Due {
+    /// A tick of the clock.
+    pub tick: bool,
+    /// A heartbeat.
+    pub heartbeat: bool,
+}
+
+/// When the clock last ticked and the heart last beat in a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cues {
+    best: Option<Duration>,
+    next_tick: Duration,
+    next_beat: Duration,
+}
+
+impl Cues {
+    /// Starts the cues for a new game, `best` is the player's fastest win on
+    /// the board's level if there is one.
+    pub fn new(best: Option<Duration>) -> Self {
+        Cues {
+            best,
+            next_tick: Duration::ZERO,
+            next_beat: Duration::ZERO,
+        }
+    }
+
+    /// Returns the sounds due `elapsed` into the game, `hovered` is the mine
+    /// probability of the cell under the pointer. Called regularly while the
+    /// game is played.
+    pub fn update(&mut self, elapsed: Duration, hovered: Option<f32>) -> Due {
+        let mut due = Due::default();
+        if elapsed >= self.next_tick {
+            due.tick = true;
+            self.next_tick = elapsed + tick_interval(elapsed, self.best);
+        }
+        match hovered {
+            Some(probability) if probability >= HEARTBEAT_PROBABILITY => {
+                if elapsed >= self.next_beat {
+                    due.heartbeat = true;
+                    self.next_beat = elapsed + HEARTBEAT_INTERVAL;
+                }
+            }
+            // the next likely mine beats as soon as it is hovered
+            _ => self.next_beat = elapsed,
+        }
+        due
+    }
+}
+
+/// Time until the next tick `elapsed` into a game: [`TICK_INTERVAL`] until
+/// [`TENSE_FROM`] of the best time has passed, then shortening steadily to
+/// [`FASTEST_TICK`] at the best time.
+pub fn tick_interval(elapsed: Duration, best: Option<Duration>) -> Duration {
+    let Some(best) = best else {
+        return TICK_INTERVAL;
+    };
+    let progress = elapsed.as_secs_f64() / best.as_secs_f64().max(f64::EPSILON);
+    let tension = ((progress - TENSE_FROM) / (1.0 - TENSE_FROM)).clamp(0.0, 1.0);
+    TICK_INTERVAL.mul_f64(1.0 - tension) + FASTEST_TICK.mul_f64(tension)
+}
+
+/// A short, high click.
+pub fn tick() -> Vec<i16> {
+    tone(2_000.0, Duration::from_millis(20), 0.3, 300.0)
+}
+
+/// Two soft low thumps.
+pub fn heartbeat() -> Vec<i16> {
+    let mut samples = tone(55.0, Duration::from_millis(120), 0.5, 25.0);
+    samples.extend(vec![0; SAMPLE_RATE as usize / 10]);
+    samples.extend(tone(50.0, Duration::from_millis(150), 0.35, 20.0));
+    samples
+}
+
+/// The pitch a number counting `count` mined neighbors is played at,
+/// [`COUNT_STEP`] semitones above [`EMPTY_PITCH`] per mine.
+pub fn count_pitch(count: u8) -> f32 {
+    EMPTY_PITCH * 2f32.powf(count as f32 * COUNT_STEP / 12.0)
+}
+
+/// The tone of a cell as the player sees it: a dull tap for a covered cell,
+/// two high blips for a flag, a rising pair for a question mark, a buzz for
+/// an uncovered mine and a note at [`count_pitch`] for an uncovered cell.
+/// Covered and marked cells sound the same whatever they hide.
+pub fn cell_tone(cell: CellState) -> Vec<i16> {
+    let note = Duration::from_millis(150);
+    let blip = Duration::from_millis(50);
+    let gap = vec![0; SAMPLE_RATE as usize / 40];
+    match cell {
+        CellState::Unknown(_) => tone(110.0, Duration::from_millis(60), 0.4, 40.0),
+        CellState::Flagged(_) => {
+            let mut samples = tone(1_320.0, blip, 0.3, 20.0);
+            samples.extend(&gap);
+            samples.extend(tone(1_320.0, blip, 0.3, 20.0));
+            samples
+        }
+        CellState::Questioned(_) => {
+            let mut samples = tone(660.0, blip, 0.3, 20.0);
+            samples.extend(&gap);
+            samples.extend(tone(880.0, blip, 0.3, 20.0));
+            samples
+        }
+        CellState::Known(true) => {
+            // odd harmonics give a square-ish buzz
+            let length = Duration::from_millis(300);
+            let harmonics = [1.0, 3.0, 5.0].map(|n| tone(80.0 * n, length, 0.5 / n, 8.0));
+            (0..harmonics[0].len())
+                .map(|i| {
+                    harmonics
+                        .iter()
+                        .map(|h| h[i])
+                        .fold(0i16, i16::saturating_add)
+                })
+                .collect()
+        }
+        CellState::Known(false) => tone(count_pitch(0), note, 0.4, 6.0),
+        CellState::Counted(count) => tone(count_pitch(count), note, 0.4, 6.0),
+    }
+}
+
+/// A sine wave fading out exponentially, `decay` per second.
+fn tone(frequency: f32, length: Duration, volume: f32, decay: f32) -> Vec<i16> {
+    let count = (length.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+    (0..count)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let sample = (TAU * frequency * t).sin() * (-decay * t).exp() * volume;
+            (sample * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_tick_interval() {
+        let best = Some(Duration::from_secs(40));
+        assert_eq!(TICK_INTERVAL, tick_interval(Duration::from_secs(30), None));
+        assert_eq!(TICK_INTERVAL, tick_interval(Duration::from_secs(10), best));
+        assert_eq!(
+            Duration::from_millis(625),
+            tick_interval(Duration::from_secs(30), best)
+        );
+        assert_eq!(FASTEST_TICK, tick_interval(Duration::from_secs(60), best));
+    }
+
+    #[test]
+    pub fn test_cues_due() {
+        let mut cues = Cues::new(None);
+        let at = Duration::from_millis;
+        assert!(cues.update(at(0), None).tick);
+        assert_eq!(Due::default(), cues.update(at(500), Some(0.2)));
+        assert_eq!(
+            Due {
+                tick: true,
+                heartbeat: true
+            },
+            cues.update(at(1000), Some(0.8))
+        );
+        assert!(!cues.update(at(1500), Some(0.8)).heartbeat);
+        assert!(cues.update(at(1900), Some(0.8)).heartbeat);
+        assert!(!tick().is_empty() && heartbeat().len() > tick().len());
+    }
+
+    #[test]
+    pub fn test_cell_tone() {
+        assert_eq!(EMPTY_PITCH, count_pitch(0));
+        assert!((1..=8).all(|count| count_pitch(count) > count_pitch(count - 1)));
+        assert!((count_pitch(6) - 2.0 * EMPTY_PITCH).abs() < 0.01);
+        // the tones do not give away the mines under covered cells
+        assert_eq!(
+            cell_tone(CellState::Unknown(true)),
+            cell_tone(CellState::Unknown(false))
+        );
+        assert_eq!(
+            cell_tone(CellState::Flagged(true)),
+            cell_tone(CellState::Flagged(false))
+        );
+        assert_ne!(
+            cell_tone(CellState::Counted(1)),
+            cell_tone(CellState::Counted(2))
+        );
+        assert!(!cell_tone(CellState::Known(true)).is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+//! The debug console of the app: a console window whose lines are run
+//! against the live game with the commands in `minesweeper_d2d::console`.
+
+use std::{
+    io,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use windows::{
+    core::Result,
+    Win32::{
+        Foundation::{HWND, LPARAM, WPARAM},
+        System::Console::{AllocConsole, FreeConsole},
+        UI::WindowsAndMessaging::{PostMessageW, WM_APP},
+    },
+};
+
+/// Posted to the app window when a line was typed at the console.
+pub(crate) const WM_CONSOLE: u32 = WM_APP + 1;
+
+/// An open console window, closed when dropped.
+pub(crate) struct DebugConsole {
+    lines: Receiver<String>,
+}
+
+impl DebugConsole {
+    /// Opens a console window and reads its lines on a thread, posting
+    /// [`WM_CONSOLE`] to `window` as each one arrives.
+    pub(crate) fn open(window: HWND) -> Result<DebugConsole> {
+        unsafe { AllocConsole()? };
+        let (sender, lines) = mpsc::channel();
+        // ends once the console is freed or the receiver dropped
+        thread::spawn(move || {
+            for line in io::stdin().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+                let _ = unsafe { PostMessageW(window, WM_CONSOLE, WPARAM(0), LPARAM(0)) };
+            }
+        });
+        println!("Minesweeper debug console, F9 closes it");
+        println!("u/f/?/c[x,y] z y h, mine/safe [x,y], dump, solve, check");
+        Ok(DebugConsole { lines })
+    }
+
+    /// The lines typed since the last call.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.lines.try_iter().collect()
+    }
+}
+
+impl Drop for DebugConsole {
+    fn drop(&mut self) {
+        let _ = unsafe { FreeConsole() };
+    }
+}
@@ -1,17 +1,23 @@
-use std::ptr::null;
+use std::{
+    path::Path,
+    ptr::{null, null_mut},
+    time::Duration,
+};
 
 use windows::{
     core::*,
     Foundation::Numerics::Matrix3x2,
     Win32::Graphics::Direct2D::Common::*,
     Win32::{
-        Foundation::GENERIC_READ,
+        Foundation::{E_FAIL, GENERIC_READ, GENERIC_WRITE},
         Graphics::{
             Direct2D::*,
             Imaging::{
-                CLSID_WICImagingFactory, GUID_WICPixelFormat32bppPBGRA, IWICImagingFactory,
-                WICBitmapDitherTypeNone, WICBitmapPaletteTypeMedianCut,
-                WICDecodeMetadataCacheOnLoad,
+                CLSID_WICImagingFactory, GUID_ContainerFormatGif, GUID_WICPixelFormat32bppPBGRA,
+                GUID_WICPixelFormat8bppIndexed, IWICBitmap, IWICBitmapDecoder, IWICBitmapSource,
+                IWICImagingFactory, WICBitmapDitherTypeNone, WICBitmapEncoderNoCache,
+                WICBitmapInterpolationModeHighQualityCubic, WICBitmapPaletteTypeCustom,
+                WICBitmapPaletteTypeMedianCut, WICDecodeMetadataCacheOnLoad,
             },
         },
         System::Com::{CoCreateInstance, CLSCTX_ALL},
@@ -29,6 +35,51 @@ pub fn create_factory() -> Result<ID2D1Factory1> {
     unsafe { D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, Some(&options)) }
 }
 
+/// Where a render target rasterizes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rendering {
+    /// On the graphics card.
+    Hardware,
+    /// On the processor with WARP, when the graphics card cannot be used,
+    /// such as over remote desktop or with a broken driver.
+    Software,
+}
+
+impl std::fmt::Display for Rendering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rendering::Hardware => write!(f, "hardware"),
+            Rendering::Software => write!(f, "software (WARP)"),
+        }
+    }
+}
+
+/// Creates a render target for a window, rasterizing as `rendering` asks
+/// and falling back to software when the graphics card cannot be used.
+/// Returns the target and where it rasterizes.
+pub fn create_hwnd_target(
+    factory: &ID2D1Factory1,
+    hwnd_props: &D2D1_HWND_RENDER_TARGET_PROPERTIES,
+    rendering: Rendering,
+) -> Result<(ID2D1HwndRenderTarget, Rendering)> {
+    let props = |type_| D2D1_RENDER_TARGET_PROPERTIES {
+        r#type: type_,
+        ..Default::default()
+    };
+    if rendering == Rendering::Hardware {
+        match unsafe {
+            factory.CreateHwndRenderTarget(&props(D2D1_RENDER_TARGET_TYPE_HARDWARE), hwnd_props)
+        } {
+            Ok(target) => return Ok((target, Rendering::Hardware)),
+            Err(error) => tracing::warn!(%error, "no hardware rendering, falling back to WARP"),
+        }
+    }
+    let target = unsafe {
+        factory.CreateHwndRenderTarget(&props(D2D1_RENDER_TARGET_TYPE_SOFTWARE), hwnd_props)?
+    };
+    Ok((target, Rendering::Software))
+}
+
 pub fn create_image_factory() -> Result<IWICImagingFactory> {
     unsafe { CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_ALL) }
 }
@@ -47,7 +98,7 @@ pub fn create_style(factory: &ID2D1Factory1, dashes: Option<&[f32]>) -> Result<I
 }
 
 pub fn create_brush(
-    target: &ID2D1HwndRenderTarget,
+    target: &ID2D1RenderTarget,
     r: f32,
     g: f32,
     b: f32,
@@ -61,22 +112,65 @@ pub fn create_brush(
     unsafe { target.CreateSolidColorBrush(&color, Some(&properties)) }
 }
 
+/// Loads a bitmap from an image file, scaled to `size` pixels when set so
+/// it is drawn at its own size rather than stretched.
 pub fn load_bitmap(
     filename: &HSTRING,
-    target: &ID2D1HwndRenderTarget,
+    target: &ID2D1RenderTarget,
     factory: &IWICImagingFactory,
+    size: Option<(u32, u32)>,
 ) -> Result<ID2D1Bitmap> {
-    unsafe {
-        let decoder = factory.CreateDecoderFromFilename(
+    let decoder = unsafe {
+        factory.CreateDecoderFromFilename(
             filename,
             Some(null()),
             GENERIC_READ,
             WICDecodeMetadataCacheOnLoad,
-        )?;
+        )?
+    };
+    convert_bitmap(&decoder, target, factory, size)
+}
+
+/// Loads a bitmap from an encoded image held in memory, such as a PNG file
+/// read from a skin, scaled to `size` pixels when set.
+pub fn load_bitmap_from_memory(
+    image: &[u8],
+    target: &ID2D1RenderTarget,
+    factory: &IWICImagingFactory,
+    size: Option<(u32, u32)>,
+) -> Result<ID2D1Bitmap> {
+    let decoder = unsafe {
+        let stream = factory.CreateStream()?;
+        stream.InitializeFromMemory(image)?;
+        factory.CreateDecoderFromStream(&stream, Some(null()), WICDecodeMetadataCacheOnLoad)?
+    };
+    convert_bitmap(&decoder, target, factory, size)
+}
+
+fn convert_bitmap(
+    decoder: &IWICBitmapDecoder,
+    target: &ID2D1RenderTarget,
+    factory: &IWICImagingFactory,
+    size: Option<(u32, u32)>,
+) -> Result<ID2D1Bitmap> {
+    unsafe {
         let frame = decoder.GetFrame(0)?;
+        let source: IWICBitmapSource = match size {
+            Some((width, height)) if width > 0 && height > 0 => {
+                let scaler = factory.CreateBitmapScaler()?;
+                scaler.Initialize(
+                    &frame,
+                    width,
+                    height,
+                    WICBitmapInterpolationModeHighQualityCubic,
+                )?;
+                scaler.cast()?
+            }
+            _ => frame.cast()?,
+        };
         let converter = factory.CreateFormatConverter()?;
         converter.Initialize(
-            &frame,
+            &source,
             &GUID_WICPixelFormat32bppPBGRA,
             WICBitmapDitherTypeNone,
             None,
@@ -86,3 +180,42 @@ pub fn load_bitmap(
         target.CreateBitmapFromWicBitmap(&converter, None)
     }
 }
+
+/// Writes an animated GIF that plays once, a frame per bitmap shown for the
+/// time given with it.
+pub fn save_gif(frames: &[(IWICBitmap, Duration)], path: &Path) -> Result<()> {
+    let factory = create_image_factory()?;
+    unsafe {
+        let stream = factory.CreateStream()?;
+        stream.InitializeFromFilename(&HSTRING::from(path), GENERIC_WRITE.0)?;
+        let encoder = factory.CreateEncoder(&GUID_ContainerFormatGif, None)?;
+        encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
+        for (bitmap, delay) in frames {
+            // boards are drawn in few colors, a palette of them keeps them exact
+            let palette = factory.CreatePalette()?;
+            palette.InitializeFromBitmap(bitmap, 256, false)?;
+            let converter = factory.CreateFormatConverter()?;
+            converter.Initialize(
+                bitmap,
+                &GUID_WICPixelFormat8bppIndexed,
+                WICBitmapDitherTypeNone,
+                &palette,
+                0.0,
+                WICBitmapPaletteTypeCustom,
+            )?;
+            let mut frame = None;
+            encoder.CreateNewFrame(&mut frame, null_mut())?;
+            let frame = frame.ok_or_else(|| Error::from(E_FAIL))?;
+            frame.Initialize(None)?;
+            frame.SetPalette(&palette)?;
+            // in hundredths of a second
+            let delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+            frame
+                .GetMetadataQueryWriter()?
+                .SetMetadataByName(w!("/grctlext/Delay"), &PROPVARIANT::from(delay))?;
+            frame.WriteSource(&converter, None)?;
+            frame.Commit()?;
+        }
+        encoder.Commit()
+    }
+}
@@ -0,0 +1,354 @@
+//! Pattern drills: small boards laid out around one classic pattern, timed
+//! from when the board is shown until the pattern is resolved.
+//!
+//! Every drill is a pattern against a wall of uncovered cells, turned and
+//! mirrored at random so it is recognized rather than remembered. Drills
+//! are kept apart from the games in `drills.txt` in the data directory, one
+//! attempt per line as space separated `key=value` pairs like the
+//! statistics store.
+
+use std::{
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    error::Result,
+    game::{Game, GameState},
+    save,
+    stats::data_dir,
+};
+
+const DRILLS_FILE: &str = "drills.txt";
+
+/// A classic pattern a drill is laid out around.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// 1-2-1 along a wall: the mines are under the ones, the cell under the
+    /// two is safe.
+    OneTwoOne,
+    /// 1-2-2-1 along a wall: the mines are under the twos, the cells under
+    /// the ones are safe.
+    OneTwoTwoOne,
+    /// Two cells in a corner sharing the same ones, a guess that cannot be
+    /// solved and is best made at once.
+    EdgeFiftyFifty,
+}
+
+impl Pattern {
+    /// Every pattern, in the order they are usually learned.
+    pub const ALL: [Pattern; 3] = [
+        Pattern::OneTwoOne,
+        Pattern::OneTwoTwoOne,
+        Pattern::EdgeFiftyFifty,
+    ];
+
+    // the pattern with the wall along the bottom, in the characters of a
+    // save file
+    fn layout(self) -> &'static [&'static str] {
+        match self {
+            Pattern::OneTwoOne => &[".*.*.", "11211", "00000"],
+            Pattern::OneTwoTwoOne => &[".**.", "1221", "0000"],
+            Pattern::EdgeFiftyFifty => &["*.", "11", "00"],
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Pattern::OneTwoOne => "121",
+            Pattern::OneTwoTwoOne => "1221",
+            Pattern::EdgeFiftyFifty => "5050",
+        })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "121" => Ok(Pattern::OneTwoOne),
+            "1221" => Ok(Pattern::OneTwoTwoOne),
+            "5050" => Ok(Pattern::EdgeFiftyFifty),
+            _ => Err(format!("unknown pattern {}, expected 121, 1221 or 5050", s)),
+        }
+    }
+}
+
+/// Lays out a drill of a pattern from a seed, turned a quarter turn at a
+/// time and mirrored. The same seed always gives the same board.
+pub fn board(pattern: Pattern, seed: u64) -> Game {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut rows: Vec<Vec<char>> = pattern
+        .layout()
+        .iter()
+        .map(|row| row.chars().collect())
+        .collect();
+    if rng.gen() {
+        rows.iter_mut().for_each(|row| row.reverse());
+    }
+    for _ in 0..rng.gen_range(0..4) {
+        // a quarter turn clockwise
+        rows = (0..rows[0].len())
+            .map(|x| rows.iter().rev().map(|row| row[x]).collect())
+            .collect();
+    }
+    let text: Vec<String> = rows.into_iter().map(String::from_iter).collect();
+    save::parse_layout(&text.join("\n")).expect("drill layouts are valid")
+}
+
+/// One drill played to its end.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attempt {
+    /// Pattern of the drill.
+    pub pattern: Pattern,
+    /// Whether the board was cleared without uncovering a mine.
+    pub solved: bool,
+    /// Time from when the board was shown until it was cleared or lost.
+    pub time: Duration,
+}
+
+impl Attempt {
+    fn parse(line: &str) -> Option<Attempt> {
+        let (mut pattern, mut solved, mut time) = (None, None, None);
+        for pair in line.split_whitespace() {
+            match pair.split_once('=')? {
+                ("pattern", value) => pattern = value.parse().ok(),
+                ("solved", value) => solved = value.parse().ok(),
+                ("time", value) => {
+                    time = Duration::try_from_secs_f64(value.parse().ok()?).ok();
+                }
+                // newer keys are skipped
+                _ => {}
+            }
+        }
+        Some(Attempt {
+            pattern: pattern?,
+            solved: solved?,
+            time: time?,
+        })
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "pattern={} solved={} time={:.3}",
+            self.pattern,
+            self.solved,
+            self.time.as_secs_f64()
+        )
+    }
+}
+
+/// A drill being played, timed from when it was laid out.
+pub struct Drill {
+    pattern: Pattern,
+    game: Game,
+    started: Instant,
+}
+
+impl Drill {
+    /// Lays out a drill of a pattern with a random seed and starts its
+    /// time.
+    pub fn new(pattern: Pattern) -> Self {
+        Drill {
+            pattern,
+            game: board(pattern, rand::random()),
+            started: Instant::now(),
+        }
+    }
+
+    /// Pattern of the drill.
+    pub fn pattern(&self) -> Pattern {
+        self.pattern
+    }
+
+    /// The board the drill is played on.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The board to play moves on.
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Time since the drill was laid out.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The attempt once the board is cleared or lost, `elapsed` after it
+    /// was laid out.
+    pub fn attempt(&self, elapsed: Duration) -> Option<Attempt> {
+        let solved = match self.game.state() {
+            GameState::Won => true,
+            GameState::Lost => false,
+            _ => return None,
+        };
+        Some(Attempt {
+            pattern: self.pattern,
+            solved,
+            time: elapsed,
+        })
+    }
+}
+
+/// Aggregated attempts at one pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// Pattern drilled.
+    pub pattern: Pattern,
+    /// Drills played.
+    pub played: usize,
+    /// Drills solved.
+    pub solved: usize,
+    /// Fastest solve.
+    pub best: Option<Duration>,
+    /// Mean time of the solves.
+    pub average: Option<Duration>,
+}
+
+/// Every drill played.
+#[derive(Debug, Default)]
+pub struct DrillStats {
+    attempts: Vec<Attempt>,
+}
+
+impl DrillStats {
+    /// Loads the drill store. A missing store is empty.
+    pub fn load() -> Result<DrillStats> {
+        let Some(path) = store_path() else {
+            return Ok(DrillStats::default());
+        };
+        match File::open(path) {
+            Ok(file) => DrillStats::read(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DrillStats::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads attempts, skipping lines that cannot be parsed.
+    pub fn read<R: BufRead>(reader: R) -> Result<DrillStats> {
+        let mut attempts = Vec::new();
+        for line in reader.lines() {
+            if let Some(attempt) = Attempt::parse(&line?) {
+                attempts.push(attempt);
+            }
+        }
+        Ok(DrillStats { attempts })
+    }
+
+    /// The attempts in the order they were played.
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+
+    /// Summarizes the attempts per pattern, leaving out patterns never
+    /// drilled.
+    pub fn summary(&self) -> Vec<Summary> {
+        Pattern::ALL
+            .into_iter()
+            .filter_map(|pattern| {
+                let attempts: Vec<&Attempt> = self
+                    .attempts
+                    .iter()
+                    .filter(|attempt| attempt.pattern == pattern)
+                    .collect();
+                if attempts.is_empty() {
+                    return None;
+                }
+                let solves: Vec<Duration> = attempts
+                    .iter()
+                    .filter(|attempt| attempt.solved)
+                    .map(|attempt| attempt.time)
+                    .collect();
+                Some(Summary {
+                    pattern,
+                    played: attempts.len(),
+                    solved: solves.len(),
+                    best: solves.iter().min().copied(),
+                    average: (!solves.is_empty())
+                        .then(|| solves.iter().sum::<Duration>() / solves.len() as u32),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Appends an attempt to the drill store.
+pub fn record(attempt: &Attempt) -> Result<()> {
+    let Some(path) = store_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", attempt.line())?;
+    Ok(())
+}
+
+fn store_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(DRILLS_FILE))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{game::CellState, solver};
+
+    #[test]
+    pub fn test_board() {
+        for pattern in Pattern::ALL {
+            for seed in 0..8 {
+                let game = board(pattern, seed);
+                assert_eq!(GameState::Playing, game.state());
+                let covered = (0..game.height())
+                    .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+                    .filter(|&(x, y)| matches!(game.cell_state(x, y), CellState::Unknown(_)))
+                    .count();
+                assert_eq!(pattern.layout()[0].len(), covered);
+                // the patterns are solved by the solver, the 50/50 is not
+                let mut played = game;
+                let state = solver::play_out(&mut played);
+                match pattern {
+                    Pattern::EdgeFiftyFifty => assert_eq!(GameState::Playing, state),
+                    _ => assert_eq!(GameState::Won, state),
+                }
+            }
+        }
+        let (a, b) = (board(Pattern::OneTwoOne, 3), board(Pattern::OneTwoOne, 3));
+        assert_eq!(a.config(), b.config());
+    }
+
+    #[test]
+    pub fn test_summary() {
+        let text = "pattern=121 solved=true time=2.000\n\
+            pattern=121 solved=false time=1.000\n\
+            pattern=121 solved=true time=4.000\n\
+            pattern=5050 solved=true time=0.500\n\
+            pattern=knight solved=true time=1.000\n";
+        let stats = DrillStats::read(text.as_bytes()).unwrap();
+        assert_eq!(4, stats.attempts().len());
+        let summary = stats.summary();
+        assert_eq!(2, summary.len());
+        assert_eq!(
+            (Pattern::OneTwoOne, 3, 2),
+            (summary[0].pattern, summary[0].played, summary[0].solved)
+        );
+        assert_eq!(Some(Duration::from_secs(2)), summary[0].best);
+        assert_eq!(Some(Duration::from_secs(3)), summary[0].average);
+        assert_eq!(Pattern::EdgeFiftyFifty, summary[1].pattern);
+        let attempt = stats.attempts()[1];
+        assert_eq!(Some(attempt), Attempt::parse(&attempt.line()));
+        assert_eq!(Ok(Pattern::OneTwoTwoOne), "1221".parse());
+    }
+}
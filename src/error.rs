@@ -0,0 +1,62 @@
+//! The error type returned by the library API.
+
+use std::{io, path::PathBuf};
+
+use crate::game::Move;
+
+/// Everything that can go wrong loading, saving or playing back games.
+#[derive(Debug, thiserror::Error)]
+pub enum MinesweeperError {
+    /// A Direct2D or WIC call failed.
+    #[cfg(windows)]
+    #[error("graphics error: {0}")]
+    Graphics(#[from] windows::core::Error),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A file or setting is malformed.
+    #[error("{0}")]
+    Parse(String),
+    /// A move names a cell that is not on the board.
+    #[error("{mv} is off the {width}x{height} board")]
+    InvalidMove {
+        /// The offending move.
+        mv: Move,
+        /// Width of the board in cells.
+        width: i16,
+        /// Height of the board in cells.
+        height: i16,
+    },
+    /// A replay does not end the way it claims.
+    #[error("replay does not verify: {0}")]
+    Unverified(String),
+    /// An asset is in none of the places it is looked for.
+    #[error("{name} not found, searched {}", paths(.searched))]
+    MissingAsset {
+        /// File name of the asset.
+        name: String,
+        /// Every path looked at, in order.
+        searched: Vec<PathBuf>,
+    },
+}
+
+fn paths(paths: &[PathBuf]) -> String {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    paths.join(", ")
+}
+
+/// Result of the library API.
+pub type Result<T> = std::result::Result<T, MinesweeperError>;
+
+// front-ends that only deal in io::Result can still use `?` on the library
+impl From<MinesweeperError> for io::Error {
+    fn from(error: MinesweeperError) -> Self {
+        match error {
+            MinesweeperError::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
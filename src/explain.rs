@@ -0,0 +1,110 @@
+use crate::{
+    game::{CellState, Game},
+    solver,
+};
+
+/// Walks through what an uncover did, for teaching the algorithms: the flood
+/// fill wave by wave, the frontier of counted cells next to covered ones and
+/// then each deduction the solver can make from it.
+///
+/// `before` is the game as it was before the move at `x`, `y`.
+pub(crate) fn explain(before: &Game, after: &Game, x: i16, y: i16) -> Vec<String> {
+    let mut lines = Vec::new();
+    let opened = |(x, y): (i16, i16)| {
+        solver::is_covered(before.cell_state(x, y)) && !solver::is_covered(after.cell_state(x, y))
+    };
+    // a chord opens the neighbors rather than the cell itself
+    let mut wave: Vec<(i16, i16)> = if opened((x, y)) {
+        vec![(x, y)]
+    } else {
+        solver::neighbors(after, x, y)
+            .filter(|&c| opened(c))
+            .collect()
+    };
+    let mut seen = wave.clone();
+    let mut number = 1;
+    while !wave.is_empty() {
+        lines.push(format!("Flood fill wave {}: {}", number, cells(&wave)));
+        // only blank cells spread the fill to their neighbors
+        let mut next = Vec::new();
+        for &(x, y) in &wave {
+            if after.cell_state(x, y) != CellState::Known(false) {
+                continue;
+            }
+            for cell in solver::neighbors(after, x, y) {
+                if opened(cell) && !seen.contains(&cell) {
+                    seen.push(cell);
+                    next.push(cell);
+                }
+            }
+        }
+        wave = next;
+        number += 1;
+    }
+    if seen.len() > 1 {
+        lines.push(format!("The flood fill opened {} cells", seen.len()));
+    }
+
+    let mut frontier = Vec::new();
+    for y in 0..after.height() {
+        for x in 0..after.width() {
+            if let CellState::Counted(count) = after.cell_state(x, y) {
+                if solver::neighbors(after, x, y).any(|(nx, ny)| {
+                    matches!(
+                        after.cell_state(nx, ny),
+                        CellState::Unknown(_) | CellState::Questioned(_)
+                    )
+                }) {
+                    frontier.push(format!("[{},{}]={}", x, y, count));
+                }
+            }
+        }
+    }
+    if frontier.is_empty() {
+        lines.push("Frontier: none".to_string());
+    } else {
+        lines.push(format!("Frontier: {}", frontier.join(" ")));
+    }
+
+    let deductions = solver::solve(after);
+    if deductions.is_empty() {
+        lines.push("The solver cannot prove any cell from here".to_string());
+    }
+    for (step, deduction) in deductions.iter().enumerate() {
+        lines.push(format!("Step {}: {}", step + 1, deduction));
+    }
+    lines
+}
+
+fn cells(cells: &[(i16, i16)]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|(x, y)| format!("[{},{}]", x, y))
+        .collect();
+    cells.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_explain() {
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        let before = game.clone();
+        game.uncover(2, 2);
+        assert_eq!(
+            vec![
+                "Flood fill wave 1: [2,2]",
+                "Flood fill wave 2: [1,1] [2,1] [1,2]",
+                "Flood fill wave 3: [1,0] [2,0] [0,1] [0,2]",
+                "The flood fill opened 8 cells",
+                "Frontier: [1,0]=1 [0,1]=1 [1,1]=1",
+                "Step 1: [0,0] is a mine: the 1 at [1,0] has no other covered cells left for its mines",
+            ],
+            explain(&before, &game, 2, 2)
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! C interface to the game engine for front-ends written in other languages.
+//!
+//! A game is created with [`ms_game_new`], played through the other `ms_game_`
+//! functions and released with [`ms_game_free`]. Every function accepts a null
+//! game and treats it as a no-op, and coordinates off the board are ignored.
+//! Cells are reported as the player sees them so a front-end cannot peek at
+//! covered mines. The matching C header is `include/minesweeper.h`, generated
+//! with `cbindgen --config cbindgen.toml --output include/minesweeper.h`, and
+//! a library to link against is built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib` (or
+//! `staticlib`).
+
+use crate::game::{CellState, Game, GameState, MAX_CELLS};
+
+/// State of a game as seen from C.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MsGameState {
+    /// No cell has been uncovered yet.
+    Initial,
+    /// The game is in progress.
+    Playing,
+    /// Every safe cell has been uncovered.
+    Won,
+    /// A mine was uncovered.
+    Lost,
+}
+
+/// What the player sees in a cell.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MsCellKind {
+    /// A covered cell without a mark.
+    Covered,
+    /// A covered cell with a flag.
+    Flagged,
+    /// A covered cell with a question mark.
+    Questioned,
+    /// An uncovered cell, `count` holds the number of mined neighbors.
+    Open,
+    /// An uncovered mine.
+    Mine,
+}
+
+/// A cell as seen from C.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MsCell {
+    /// What the player sees in the cell.
+    pub kind: MsCellKind,
+    /// Number of mined neighbors of an open cell, otherwise 0.
+    pub count: u8,
+}
+
+impl From<GameState> for MsGameState {
+    fn from(state: GameState) -> Self {
+        match state {
+            GameState::Initial => MsGameState::Initial,
+            GameState::Playing => MsGameState::Playing,
+            GameState::Won => MsGameState::Won,
+            GameState::Lost => MsGameState::Lost,
+        }
+    }
+}
+
+impl From<CellState> for MsCell {
+    fn from(state: CellState) -> Self {
+        let (kind, count) = match state {
+            CellState::Unknown(_) => (MsCellKind::Covered, 0),
+            CellState::Flagged(_) => (MsCellKind::Flagged, 0),
+            CellState::Questioned(_) => (MsCellKind::Questioned, 0),
+            CellState::Known(false) => (MsCellKind::Open, 0),
+            CellState::Known(true) => (MsCellKind::Mine, 0),
+            CellState::Counted(count) => (MsCellKind::Open, count),
+        };
+        MsCell { kind, count }
+    }
+}
+
+fn on_board(game: &Game, x: i16, y: i16) -> bool {
+    x >= 0 && y >= 0 && x < game.width() && y < game.height()
+}
+
+/// Creates a game with randomly placed mines, returning null if the board is
+/// empty, has more than 32767 cells or has no room for that many mines.
+/// Release it with [`ms_game_free`].
+#[no_mangle]
+pub extern "C" fn ms_game_new(width: i16, height: i16, mines: u16) -> Option<Box<Game>> {
+    let size = width as usize * height as usize;
+    if width <= 0 || height <= 0 || size > MAX_CELLS || mines as usize >= size {
+        return None;
+    }
+    Some(Box::new(Game::with_mines(width, height, mines)))
+}
+
+/// Releases a game created with [`ms_game_new`].
+#[no_mangle]
+pub extern "C" fn ms_game_free(game: Option<Box<Game>>) {
+    drop(game);
+}
+
+/// Lays out the mines again from a seed so the same board can be replayed.
+#[no_mangle]
+pub extern "C" fn ms_game_reset(game: Option<&mut Game>, seed: u64) {
+    if let Some(game) = game {
+        game.reset_with_seed(seed);
+    }
+}
+
+/// Width of the board in cells.
+#[no_mangle]
+pub extern "C" fn ms_game_width(game: Option<&Game>) -> i16 {
+    game.map_or(0, Game::width)
+}
+
+/// Height of the board in cells.
+#[no_mangle]
+pub extern "C" fn ms_game_height(game: Option<&Game>) -> i16 {
+    game.map_or(0, Game::height)
+}
+
+/// Current state of the game.
+#[no_mangle]
+pub extern "C" fn ms_game_state(game: Option<&Game>) -> MsGameState {
+    game.map_or(MsGameState::Initial, |game| game.state().into())
+}
+
+/// Number of mines less the number of flags placed.
+#[no_mangle]
+pub extern "C" fn ms_game_mines_left(game: Option<&Game>) -> u16 {
+    game.map_or(0, Game::est_remaining)
+}
+
+/// The cell at `x`, `y` as the player sees it, covered when off the board.
+#[no_mangle]
+pub extern "C" fn ms_game_cell(game: Option<&Game>, x: i16, y: i16) -> MsCell {
+    match game {
+        Some(game) if on_board(game, x, y) => game.cell_state(x, y).into(),
+        _ => CellState::Unknown(false).into(),
+    }
+}
+
+/// Uncovers a cell and returns the resulting game state.
+#[no_mangle]
+pub extern "C" fn ms_game_uncover(game: Option<&mut Game>, x: i16, y: i16) -> MsGameState {
+    match game {
+        Some(game) if on_board(game, x, y) => game.uncover(x, y).into(),
+        game => ms_game_state(game.map(|game| &*game)),
+    }
+}
+
+/// Uncovers the neighbors of a counted cell whose flags match its count and
+/// returns the resulting game state.
+#[no_mangle]
+pub extern "C" fn ms_game_chord(game: Option<&mut Game>, x: i16, y: i16) -> MsGameState {
+    match game {
+        Some(game) if on_board(game, x, y) => game.chord(x, y).into(),
+        game => ms_game_state(game.map(|game| &*game)),
+    }
+}
+
+/// Toggles a flag on a covered cell.
+#[no_mangle]
+pub extern "C" fn ms_game_flag(game: Option<&mut Game>, x: i16, y: i16) {
+    if let Some(game) = game.filter(|game| on_board(game, x, y)) {
+        game.flag(x, y);
+    }
+}
+
+/// Clears a flag or question mark.
+#[no_mangle]
+pub extern "C" fn ms_game_clear_mark(game: Option<&mut Game>, x: i16, y: i16) {
+    if let Some(game) = game.filter(|game| on_board(game, x, y)) {
+        game.set_unknown(x, y);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_ffi_game() {
+        assert!(ms_game_new(0, 9, 1).is_none());
+        assert!(ms_game_new(3, 3, 9).is_none());
+        let mut game = ms_game_new(9, 9, 10);
+        ms_game_reset(game.as_deref_mut(), 42);
+        assert_eq!(9, ms_game_width(game.as_deref()));
+        assert_eq!(
+            MsCellKind::Covered,
+            ms_game_cell(game.as_deref(), 20, 0).kind
+        );
+        ms_game_flag(game.as_deref_mut(), 0, 0);
+        assert_eq!(
+            MsCellKind::Flagged,
+            ms_game_cell(game.as_deref(), 0, 0).kind
+        );
+        assert_eq!(9, ms_game_mines_left(game.as_deref()));
+        ms_game_clear_mark(game.as_deref_mut(), 0, 0);
+        assert_eq!(
+            MsCellKind::Covered,
+            ms_game_cell(game.as_deref(), 0, 0).kind
+        );
+        assert_ne!(
+            MsGameState::Initial,
+            ms_game_uncover(game.as_deref_mut(), 4, 4)
+        );
+        ms_game_free(game);
+        assert_eq!(MsGameState::Initial, ms_game_uncover(None, 0, 0));
+    }
+}
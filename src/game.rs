@@ -1,28 +1,403 @@
+//! The board, its mine layout and the rules for playing moves on it.
+
+use alloc::{format, string::String, vec::Vec};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::{fmt::Display, str::FromStr, time::Instant};
+
+#[cfg(not(feature = "std"))]
+use clock::Instant;
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use std::fmt::Display;
 
+/// State of a game from creation to its end.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum GameState {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    /// Mines are laid out but nothing has been uncovered.
     Initial,
+    /// The first cell has been uncovered and the timer is running.
     Playing,
+    /// Every safe cell has been uncovered.
     Won,
+    /// A mine was uncovered.
     Lost,
 }
 
+/// State of a single cell, the flag is whether the cell holds a mine.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum CellState {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellState {
+    /// Not yet uncovered or marked.
     Unknown(bool),
+    /// Uncovered with no mined neighbors, or an uncovered mine.
     Known(bool),
+    /// Marked by the player as a mine.
     Flagged(bool),
+    /// Uncovered with the given number of mined neighbors.
     Counted(u8),
+    /// Marked by the player as a possible mine.
     Questioned(bool),
 }
 
+impl CellState {
+    /// Whether the cell holds a mine, whatever the player can see of it.
+    pub fn is_mined(self) -> bool {
+        matches!(
+            self,
+            CellState::Unknown(true)
+                | CellState::Known(true)
+                | CellState::Flagged(true)
+                | CellState::Questioned(true)
+        )
+    }
+
+    /// Whether the player has not uncovered the cell, marked or not.
+    pub fn is_covered(self) -> bool {
+        matches!(
+            self,
+            CellState::Unknown(_) | CellState::Flagged(_) | CellState::Questioned(_)
+        )
+    }
+}
+
+/// The cells a number counts the mines of, the rule the whole board is
+/// played by.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighborhood {
+    /// The up to eight cells touching it, the classic rule.
+    #[default]
+    Adjacent,
+    /// The up to 24 cells at most two columns and rows away, a 5x5 square
+    /// around it.
+    Distance2,
+}
+
+impl Neighborhood {
+    /// Every rule, the classic one first.
+    pub const ALL: [Neighborhood; 2] = [Neighborhood::Adjacent, Neighborhood::Distance2];
+
+    /// How many columns and rows away the counted cells reach.
+    pub fn radius(self) -> i16 {
+        match self {
+            Neighborhood::Adjacent => 1,
+            Neighborhood::Distance2 => 2,
+        }
+    }
+
+    /// The highest count a number can show, when all the cells it counts
+    /// are mined.
+    pub fn max_count(self) -> u8 {
+        let side = 2 * self.radius() as u8 + 1;
+        side * side - 1
+    }
+
+    /// Iterates over the cells the number at `x`, `y` counts on a board of
+    /// `width` by `height` cells, in row order.
+    pub fn cells(
+        self,
+        width: i16,
+        height: i16,
+        x: i16,
+        y: i16,
+    ) -> impl Iterator<Item = (i16, i16)> {
+        let radius = self.radius();
+        let columns = (x - radius).max(0)..=(x + radius).min(width - 1);
+        ((y - radius).max(0)..=(y + radius).min(height - 1))
+            .flat_map(move |ny| columns.clone().map(move |nx| (nx, ny)))
+            .filter(move |&cell| cell != (x, y))
+    }
+}
+
+/// The classic board presets shared by the GUI and the CLI.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Difficulty {
+    /// 9x9 with 10 mines.
+    Beginner,
+    /// 16x16 with 40 mines.
+    Intermediate,
+    /// 30x16 with 99 mines.
+    Expert,
+}
+
+/// Size and number of mines of a board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    /// Width of the board in cells.
+    pub width: i16,
+    /// Height of the board in cells.
+    pub height: i16,
+    /// Number of mines laid out on the board.
+    pub mines: u16,
+    /// Parts of a custom board laid out more or less densely than the rest,
+    /// empty for mines anywhere alike.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub zones: Vec<Zone>,
+}
+
+/// A rectangle of a custom board whose cells are more or less likely to be
+/// mined than the cells outside every zone, such as a hard right half. A
+/// later zone wins over an earlier one where they overlap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    /// Column of the left edge.
+    pub x: i16,
+    /// Row of the top edge.
+    pub y: i16,
+    /// Width in cells.
+    pub width: i16,
+    /// Height in cells.
+    pub height: i16,
+    /// How likely a cell is to be mined in percent of a cell outside every
+    /// zone: 200 is twice as likely, 0 never.
+    pub weight: u16,
+}
+
+impl Zone {
+    /// Whether the cell at `x`, `y` is in the zone.
+    pub fn contains(&self, x: i16, y: i16) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+/// How many mines a board of a given size is laid out with when its count
+/// is not set, such as a custom board.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Density {
+    /// The same number of mines whatever the size.
+    Fixed(u16),
+    /// A share of the cells, in percent.
+    Percent(f32),
+    /// `a * cells² + b * cells + c` mines for a board of `cells` cells.
+    Curve {
+        /// Factor of the squared number of cells.
+        a: f32,
+        /// Factor of the number of cells.
+        b: f32,
+        /// Mines added whatever the size.
+        c: f32,
+    },
+}
+
+impl Default for Density {
+    /// The built in curve, close to the presets at their sizes.
+    fn default() -> Self {
+        Density::Curve {
+            a: DENSITY_FACTOR_A,
+            b: DENSITY_FACTOR_B,
+            c: DENSITY_FACTOR_C,
+        }
+    }
+}
+
+impl Density {
+    /// Number of mines for a board of `width` by `height` cells, this can
+    /// exceed the number of cells.
+    pub fn mines(self, width: i16, height: i16) -> u16 {
+        let cells = width as f32 * height as f32;
+        match self {
+            Density::Fixed(mines) => mines,
+            Density::Percent(percent) => (cells * percent / 100.0) as u16,
+            Density::Curve { a, b, c } => (cells * cells * a + cells * b + c) as u16,
+        }
+    }
+}
+
+/// Cells uncovered for the player as soon as a board is laid out, a head
+/// start for beginners or quick rounds. The timer still starts with the
+/// first move.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Handicap {
+    /// This many random safe cells, a cell without mines around it opening
+    /// its area like a click does. A board is never won by its handicap.
+    Cells(u16),
+    /// One random cell without mines around it and its area, none when the
+    /// board has no such cell.
+    Opening,
+}
+
+/// A symmetry the mines of a board are laid out with, for boards that look
+/// designed. The mine count is rounded up to one the symmetry allows, so a
+/// symmetric board never has fewer mines than asked for while there is room
+/// for them and a cell without a mine.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Mines anywhere, the classic layout.
+    #[default]
+    None,
+    /// The left half mirrors the right half.
+    Horizontal,
+    /// The top half mirrors the bottom half.
+    Vertical,
+    /// The board looks the same turned half a turn.
+    Rotational,
+}
+
+impl Symmetry {
+    /// Every symmetry, no symmetry first.
+    pub const ALL: [Symmetry; 4] = [
+        Symmetry::None,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Rotational,
+    ];
+
+    /// The cell at the other end of `x`, `y` on a board of `width` by
+    /// `height` cells, the cell itself when it is on the axis or center.
+    pub fn image(self, width: i16, height: i16, x: i16, y: i16) -> (i16, i16) {
+        match self {
+            Symmetry::None => (x, y),
+            Symmetry::Horizontal => (width - 1 - x, y),
+            Symmetry::Vertical => (x, height - 1 - y),
+            Symmetry::Rotational => (width - 1 - x, height - 1 - y),
+        }
+    }
+}
+
+// draws cells until one is not skipped, each kept in proportion to its
+// weight when there are weights
+fn pick(rng: &mut StdRng, weights: &[u16], size: usize, skip: impl Fn(usize) -> bool) -> usize {
+    let most = weights.iter().copied().max().unwrap_or_default();
+    loop {
+        let cell = rng.gen_range(0..size);
+        if skip(cell) {
+            continue;
+        }
+        match weights.get(cell) {
+            Some(&weight) if rng.gen_range(0..most) >= weight => continue,
+            _ => return cell,
+        }
+    }
+}
+
+/// A player action that can be undone.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    /// Uncover the cell at `x`, `y`.
+    Uncover(i16, i16),
+    /// Toggle the flag on the cell at `x`, `y`.
+    Flag(i16, i16),
+    /// Mark the cell at `x`, `y` with a question mark.
+    Question(i16, i16),
+    /// Clear any mark from the cell at `x`, `y`.
+    SetUnknown(i16, i16),
+    /// Uncover the neighbors of a counted cell whose mines are all flagged.
+    Chord(i16, i16),
+}
+
+impl Move {
+    /// The cell the move is made on.
+    pub fn cell(self) -> (i16, i16) {
+        match self {
+            Move::Uncover(x, y)
+            | Move::Flag(x, y)
+            | Move::Question(x, y)
+            | Move::SetUnknown(x, y)
+            | Move::Chord(x, y) => (x, y),
+        }
+    }
+
+    /// The same move made on another cell.
+    pub fn at(self, x: i16, y: i16) -> Move {
+        match self {
+            Move::Uncover(..) => Move::Uncover(x, y),
+            Move::Flag(..) => Move::Flag(x, y),
+            Move::Question(..) => Move::Question(x, y),
+            Move::SetUnknown(..) => Move::SetUnknown(x, y),
+            Move::Chord(..) => Move::Chord(x, y),
+        }
+    }
+}
+
+/// The clicks a player made in a game, counted whether or not they changed
+/// the board.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Clicks {
+    /// Cells uncovered, left clicks.
+    pub uncovers: u32,
+    /// Chords on counted cells.
+    pub chords: u32,
+    /// Flags, question marks and cleared marks, right clicks.
+    pub flags: u32,
+    /// Clicks of any kind that changed nothing.
+    pub wasted: u32,
+}
+
+impl Clicks {
+    /// Every click made.
+    pub fn total(&self) -> u32 {
+        self.uncovers + self.chords + self.flags
+    }
+
+    fn count(&mut self, mv: Move) {
+        match mv {
+            Move::Uncover(..) => self.uncovers += 1,
+            Move::Chord(..) => self.chords += 1,
+            Move::Flag(..) | Move::Question(..) | Move::SetUnknown(..) => self.flags += 1,
+        }
+    }
+}
+
+/// The player visible state of a game at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    field_state: Vec<CellState>,
+    state: GameState,
+    est_remaining: u16,
+    act_remaining: u16,
+    unknown: usize,
+    flags: u16,
+    exploded: Option<(i16, i16)>,
+    auto_flags: Vec<bool>,
+}
+
+// one side of a move kept for undo and redo: the cells the move changed, as
+// they are on that side with their co-pilot flags, and the counters
+#[derive(Clone)]
+struct Step {
+    cells: Vec<(usize, CellState, bool)>,
+    state: GameState,
+    est_remaining: u16,
+    act_remaining: u16,
+    unknown: usize,
+    flags: u16,
+    exploded: Option<(i16, i16)>,
+}
+
 const DENSITY_FACTOR_A: f32 = 0.0002;
 const DENSITY_FACTOR_B: f32 = 0.0938;
 const DENSITY_FACTOR_C: f32 = 0.8937;
 
-pub(crate) struct Game {
+/// Time added to the clock for every hint taken, see [`Game::hint`].
+pub const HINT_PENALTY: Duration = Duration::from_secs(20);
+
+/// Time added to the clock for every flag refused on a safe cell, see
+/// [`Game::set_flag_penalty`].
+pub const FLAG_PENALTY: Duration = Duration::from_secs(10);
+
+/// Number of candidate marks a covered cell can be annotated with, see
+/// [`Game::annotate`].
+pub const ANNOTATIONS: u8 = 4;
+
+/// Most cells a board can have, the cells are indexed from their
+/// coordinates in `i16` arithmetic.
+pub const MAX_CELLS: usize = i16::MAX as usize;
+
+/// A minesweeper board, its hidden mine layout and the player's progress.
+///
+/// With the `serde` feature a game serializes to the same fields as the save
+/// file; the undo history is not kept.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "GameData", try_from = "GameData")
+)]
+pub struct Game {
     width: i16,
     height: i16,
     state: GameState,
@@ -31,251 +406,1845 @@ pub(crate) struct Game {
     est_remaining: u16,
     act_remaining: u16,
     unknown: usize,
+    flags: u16,
+    // clicks while the game was not over, kept through undo
+    clicks: Clicks,
+    // the mine that lost the game
+    exploded: Option<(i16, i16)>,
+    // flags and question marks are refused
+    no_flags: bool,
+    // flags on safe cells are refused and cost time, and how many were in
+    // this game, kept through undo
+    flag_penalty: bool,
+    wrong_flags: u16,
+    // cells a single number proves safe are uncovered after every move
+    auto_open: bool,
+    // mines the solver proves are flagged after every move, and the flags
+    // placed so, left out of saved games
+    auto_flag: bool,
+    auto_flags: Vec<bool>,
+    // hints taken in this game and how many may be taken, kept through undo
+    hints: u16,
+    hint_budget: Option<u16>,
+    // time the hints and refused flags added to the clock, kept through undo
+    penalty: Duration,
+    // went back to a checkpoint or undid the end of the game, left out of
+    // the statistics
+    practice: bool,
+    // the cells the numbers count
+    neighborhood: Neighborhood,
+    // the symmetry the next layout is made with, and the zones weighing
+    // its cells
+    symmetry: Symmetry,
+    zones: Vec<Zone>,
+    // cells uncovered once a board is laid out
+    handicap: Option<Handicap>,
+    // numbers fade this long after they are uncovered, and when every cell
+    // was uncovered while they do, left out of saved games
+    blind: Option<Duration>,
+    revealed: Vec<Option<Instant>>,
+    // cells that look different since the move before, and the numbers with
+    // more flags around them than their count
+    changed: Vec<(i16, i16)>,
+    contradictions: Vec<(i16, i16)>,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+    undo: Vec<(Move, Step)>,
+    seed: Option<u64>,
+    redo: Vec<(Move, Step)>,
+    // cells still to expand in a cascade, and the cells the move being
+    // recorded changed with what they were, kept between moves so large
+    // openings do not allocate
+    cascade: Vec<(i16, i16)>,
+    journal: Vec<(usize, CellState, bool)>,
+    // the candidate mark of every cell counting from 1, 0 for none, kept
+    // through undo and left out of saved games
+    annotations: Vec<u8>,
 }
 
 impl Game {
-    pub(crate) fn new(width: i16, height: i16) -> Self {
+    /// Creates a game with a number of mines from the density curve.
+    pub fn new(width: i16, height: i16) -> Self {
+        Game::with_mines(width, height, Game::default_mines(width, height))
+    }
+
+    /// Number of mines the density curve gives a board, this can exceed the
+    /// number of cells on very large boards.
+    pub fn default_mines(width: i16, height: i16) -> u16 {
+        Density::default().mines(width, height)
+    }
+
+    /// Creates a game with the number of mines a density gives its size, at
+    /// most a mine in every cell but one.
+    pub fn with_density(width: i16, height: i16, density: Density) -> Self {
+        let most = (width as usize * height as usize).saturating_sub(1);
+        let mines = density.mines(width, height).min(most as u16);
+        Game::with_mines(width, height, mines)
+    }
+
+    /// Creates a game with one of the classic presets.
+    pub fn with_difficulty(difficulty: Difficulty) -> Self {
+        Game::with_config(difficulty.config())
+    }
+
+    /// Creates a game with the size and number of mines of a config.
+    pub fn with_config(config: GameConfig) -> Self {
+        let mut game = Game::with_mines(config.width, config.height, config.mines);
+        if !config.zones.is_empty() {
+            game.set_zones(config.zones);
+            game.reset_with_seed(game.seed.unwrap_or_default());
+        }
+        game
+    }
+
+    /// Creates a game with a fixed number of mines rather than the density curve.
+    ///
+    /// Without the `std` feature there is no entropy source, so the mines are
+    /// laid out from seed 0 until [`Game::reset_with_seed`] is called.
+    ///
+    /// Panics if the board has more than [`MAX_CELLS`] cells or no room for
+    /// the mines.
+    pub fn with_mines(width: i16, height: i16, mines: u16) -> Self {
         let size = width as usize * height as usize;
+        assert!(size <= MAX_CELLS, "too many cells for the board");
+        assert!((mines as usize) < size, "too many mines for the board");
         let minefield = Vec::<CellState>::with_capacity(size);
         let mut game = Game {
             width,
             height,
             state: GameState::Initial,
             field_state: minefield,
-            total: 0,
+            total: mines,
             est_remaining: 0,
             act_remaining: 0,
             unknown: size,
+            flags: 0,
+            clicks: Clicks::default(),
+            exploded: None,
+            no_flags: false,
+            auto_open: false,
+            auto_flag: false,
+            auto_flags: Vec::new(),
+            hints: 0,
+            hint_budget: None,
+            penalty: Duration::ZERO,
+            flag_penalty: false,
+            wrong_flags: 0,
+            practice: false,
+            neighborhood: Neighborhood::Adjacent,
+            symmetry: Symmetry::None,
+            zones: Vec::new(),
+            handicap: None,
+            blind: None,
+            revealed: Vec::new(),
+            changed: Vec::new(),
+            contradictions: Vec::new(),
+            started: None,
+            finished: None,
+            undo: Vec::new(),
+            seed: None,
+            redo: Vec::new(),
+            cascade: Vec::new(),
+            journal: Vec::new(),
+            annotations: Vec::new(),
         };
+        #[cfg(feature = "std")]
         game.reset();
+        #[cfg(not(feature = "std"))]
+        game.reset_with_seed(0);
+        game
+    }
+
+    /// Creates a game from an explicit layout of mined cells in row order.
+    pub fn from_layout(width: i16, height: i16, mined: &[bool]) -> Self {
+        let cells = mined.iter().map(|&mine| CellState::Unknown(mine)).collect();
+        Game::from_cells(width, height, cells, GameState::Initial, Duration::ZERO)
+    }
+
+    /// Recreates a game in progress from its cells in row order. The mine
+    /// counters are derived from the cells and the timer resumes from `elapsed`.
+    pub fn from_cells(
+        width: i16,
+        height: i16,
+        cells: Vec<CellState>,
+        state: GameState,
+        elapsed: Duration,
+    ) -> Self {
+        let size = width as usize * height as usize;
+        assert_eq!(size, cells.len(), "cells do not match the board size");
+        let mut game = Game::with_mines(width, height, 0);
+        let mut total = 0;
+        let mut flagged = 0;
+        let mut flagged_mines = 0;
+        let mut revealed = 0;
+        for cell in &cells {
+            match cell {
+                CellState::Unknown(true) | CellState::Questioned(true) | CellState::Known(true) => {
+                    total += 1
+                }
+                CellState::Flagged(mined) => {
+                    flagged += 1;
+                    if *mined {
+                        total += 1;
+                        flagged_mines += 1;
+                    }
+                }
+                CellState::Known(false) | CellState::Counted(_) => revealed += 1,
+                _ => {}
+            }
+        }
+        game.field_state = cells;
+        game.seed = None;
+        game.total = total;
+        game.flags = flagged;
+        game.est_remaining = total.saturating_sub(flagged);
+        game.act_remaining = total - flagged_mines;
+        game.unknown = size - revealed;
+        game.state = state;
+        game.contradictions = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| game.contradicted(x, y))
+            .collect();
+        if state != GameState::Initial {
+            let now = Instant::now();
+            game.started = Some(now.checked_sub(elapsed).unwrap_or(now));
+            if matches!(state, GameState::Won | GameState::Lost) {
+                game.finished = Some(now);
+            }
+        }
         game
     }
 
-    pub(crate) fn width(&self) -> i16 {
+    /// Size and number of mines of the board.
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            width: self.width,
+            height: self.height,
+            mines: self.total,
+            zones: self.zones.clone(),
+        }
+    }
+
+    /// The zones weighing the cells of the next layout.
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    /// Lays out the mines of the next reset, and the ones after it, more or
+    /// less densely in zones. The board on the table is left as it is.
+    pub fn set_zones(&mut self, zones: Vec<Zone>) {
+        self.zones = zones;
+    }
+
+    /// The cells uncovered for the player once a board is laid out.
+    pub fn handicap(&self) -> Option<Handicap> {
+        self.handicap
+    }
+
+    /// Uncovers cells for the player on the next reset, and the ones after
+    /// it. The board on the table is left as it is.
+    pub fn set_handicap(&mut self, handicap: Option<Handicap>) {
+        self.handicap = handicap;
+    }
+
+    /// Width of the board in cells.
+    pub fn width(&self) -> i16 {
         self.width
     }
 
-    pub(crate) fn height(&self) -> i16 {
-        self.height
+    /// Height of the board in cells.
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    /// Current state of the game.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// State of the cell at `x`, `y`, which must be on the board.
+    pub fn cell_state(&self, x: i16, y: i16) -> CellState {
+        let index = (y * self.width + x) as usize;
+        self.field_state[index]
+    }
+
+    /// Lays out the mines again with a random seed.
+    #[cfg(feature = "std")]
+    pub fn reset(&mut self) {
+        self.reset_with_seed(StdRng::from_entropy().gen());
+    }
+
+    /// Lays out a new board from a seed. The same seed and board size always
+    /// produce the same layout.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut density = self.total;
+        let size = self.width as usize * self.height as usize;
+        self.clear();
+        let weights = self.weights();
+        if self.symmetry == Symmetry::None {
+            // no more mines than cells a zone lets be mined
+            if !weights.is_empty() {
+                density = density.min(weights.iter().filter(|&&w| w > 0).count() as u16);
+            }
+            for _ in 0..density {
+                let field = &self.field_state;
+                let cell = pick(&mut rng, &weights, size, |cell| field[cell].is_mined());
+                self.field_state[cell] = CellState::Unknown(true);
+            }
+        } else {
+            density = self.lay_out_symmetric(&mut rng, &weights, density);
+        }
+        self.act_remaining = density;
+        self.est_remaining = density;
+        self.total = density;
+        self.unknown = size;
+        self.flags = 0;
+        self.state = GameState::Initial;
+        self.started = None;
+        self.finished = None;
+        self.seed = Some(seed);
+        self.uncover_handicap(&mut rng);
+        tracing::debug!(
+            seed,
+            width = self.width,
+            height = self.height,
+            mines = density,
+            "new board"
+        );
+        self.debug_check();
+    }
+
+    // uncovers the handicap's cells and leaves the game to be started by the
+    // first move, stopping short of a cell that would win the board
+    fn uncover_handicap(&mut self, rng: &mut StdRng) {
+        let (count, opening) = match self.handicap {
+            None => return,
+            Some(Handicap::Cells(count)) => (count, false),
+            Some(Handicap::Opening) => (1, true),
+        };
+        let width = self.width;
+        let (mut uncovered, mut winning) = (0, Vec::new());
+        while uncovered < count {
+            let covered: Vec<(i16, i16)> = (0..self.height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&(x, y)| self.cell_state(x, y) == CellState::Unknown(false))
+                .filter(|&(x, y)| !opening || self.neighbor_count(x, y) == 0)
+                .filter(|cell| !winning.contains(cell))
+                .collect();
+            if covered.is_empty() {
+                break;
+            }
+            let (x, y) = covered[rng.gen_range(0..covered.len())];
+            let before = self.snapshot();
+            if self.uncover_cell(x, y) == GameState::Won {
+                self.restore(&before);
+                winning.push((x, y));
+                continue;
+            }
+            uncovered += 1;
+        }
+        self.changed.clear();
+        self.state = GameState::Initial;
+        self.started = None;
+    }
+
+    // the weight of every cell in percent, empty when there are no zones
+    fn weights(&self) -> Vec<u16> {
+        if self.zones.is_empty() {
+            return Vec::new();
+        }
+        let width = self.width;
+        (0..self.height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let zone = self.zones.iter().rev().find(|zone| zone.contains(x, y));
+                zone.map_or(100, |zone| zone.weight)
+            })
+            .collect()
+    }
+
+    // lays out mines a cell and its image at a time, taking a cell that is
+    // its own image while an odd count is left and rounding that count up to
+    // a pair when there is none; returns the mines laid
+    fn lay_out_symmetric(&mut self, rng: &mut StdRng, weights: &[u16], mines: u16) -> u16 {
+        let size = self.field_state.len();
+        let weighed = |cell: usize| weights.get(cell).is_none_or(|&w| w > 0);
+        // unmined cells a mine may be drawn on
+        let mut open = (0..size).filter(|&cell| weighed(cell)).count();
+        let (width, height) = (self.width, self.height);
+        let image = |cell: usize| {
+            let (x, y) = (cell as i16 % width, cell as i16 / width);
+            let (ix, iy) = self.symmetry.image(width, height, x, y);
+            (iy * width + ix) as usize
+        };
+        let centers: Vec<usize> = (0..size)
+            .filter(|&cell| image(cell) == cell && weighed(cell))
+            .collect();
+        let mut placed = 0u16;
+        while placed < mines && (placed as usize) < size - 1 {
+            let free: Vec<usize> = centers
+                .iter()
+                .copied()
+                .filter(|&cell| !self.field_state[cell].is_mined())
+                .collect();
+            // a pair is left to draw and still leaves a cell without a mine
+            let pairs = open > free.len() && (placed as usize) + 2 < size;
+            let odd = (mines - placed) % 2 == 1;
+            let cell = if !free.is_empty() && (odd || !pairs) {
+                free[rng.gen_range(0..free.len())]
+            } else if pairs {
+                let field = &self.field_state;
+                pick(rng, weights, size, |cell| {
+                    field[cell].is_mined() || image(cell) == cell
+                })
+            } else {
+                break;
+            };
+            for cell in [cell, image(cell)] {
+                if !self.field_state[cell].is_mined() {
+                    self.field_state[cell] = CellState::Unknown(true);
+                    placed += 1;
+                    open -= weighed(cell) as usize;
+                }
+            }
+        }
+        placed
+    }
+
+    /// The seed the layout was generated from, if it was generated.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Sets the seed reported for a game restored from its cells.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Covers every cell and removes all mines. The cell buffer is reused, so
+    /// clearing a board of the same size never allocates.
+    pub fn clear(&mut self) {
+        let size = self.width as usize * self.height as usize;
+        self.field_state.clear();
+        self.field_state.resize(size, CellState::Unknown(false));
+        self.cover();
+    }
+
+    /// Covers every cell again keeping the mines where they are, to play the
+    /// same board from the start. The layout was seen, so the game is
+    /// practice.
+    pub fn restart(&mut self) {
+        for cell in &mut self.field_state {
+            *cell = CellState::Unknown(cell.is_mined());
+        }
+        self.cover();
+        self.est_remaining = self.total;
+        self.act_remaining = self.total;
+        self.practice = true;
+        tracing::debug!(seed = self.seed, "board restarted");
+        self.debug_check();
+    }
+
+    // forgets what was played on the layout: the player's view, the counters
+    // and the timer
+    fn cover(&mut self) {
+        self.unknown = self.field_state.len();
+        self.flags = 0;
+        self.clicks = Clicks::default();
+        self.hints = 0;
+        self.penalty = Duration::ZERO;
+        self.wrong_flags = 0;
+        self.practice = false;
+        self.exploded = None;
+        self.changed.clear();
+        self.contradictions.clear();
+        self.state = GameState::Initial;
+        self.started = None;
+        self.finished = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.journal.clear();
+        self.annotations.clear();
+        self.revealed.clear();
+        self.auto_flags.clear();
+    }
+
+    /// Time spent on the current game with the penalties added, stopped once
+    /// it is won or lost.
+    pub fn elapsed(&self) -> Duration {
+        let played = match (self.started, self.finished) {
+            (Some(started), Some(finished)) => finished - started,
+            (Some(started), None) => started.elapsed(),
+            _ => Duration::ZERO,
+        };
+        played + self.penalty
+    }
+
+    /// Mines left to find according to the player's flags.
+    pub fn est_remaining(&self) -> u16 {
+        self.est_remaining
+    }
+
+    /// Mines that have not been flagged correctly.
+    pub fn act_remaining(&self) -> u16 {
+        self.act_remaining
+    }
+
+    /// Total number of mines on the board.
+    pub fn total(&self) -> u16 {
+        self.total
+    }
+
+    /// Safe cells still covered, the game is won once none are left. A mine
+    /// that was uncovered is not counted as a covered cell either way.
+    pub fn safe_cells_remaining(&self) -> usize {
+        self.unknown - self.total as usize
+    }
+
+    /// Clicks made on the board until the game was won or lost.
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// The mine uncovered when the game was lost.
+    pub fn exploded(&self) -> Option<(i16, i16)> {
+        self.exploded
+    }
+
+    /// Whether the game is played without flags, a category of its own. Flag
+    /// and question mark moves are refused and not counted as clicks.
+    pub fn no_flags(&self) -> bool {
+        self.no_flags
+    }
+
+    /// The cells the last move, undo or redo changed, in row order. Numbers
+    /// that became or stopped being [contradicted](Game::contradicted) are
+    /// included though their state did not change.
+    pub fn changed(&self) -> &[(i16, i16)] {
+        &self.changed
+    }
+
+    /// Whether the cell is a number with more flags around it than its count,
+    /// a mistake the player can prove without knowing the mines.
+    pub fn contradicted(&self, x: i16, y: i16) -> bool {
+        let CellState::Counted(count) = self.cell_state(x, y) else {
+            return false;
+        };
+        let flagged = self
+            .neighbors(x, y)
+            .filter(|&(nx, ny)| matches!(self.cell_state(nx, ny), CellState::Flagged(_)))
+            .count();
+        flagged > count as usize
+    }
+
+    /// The [contradicted](Game::contradicted) numbers, checked after every
+    /// move around the cells it changed.
+    pub fn contradictions(&self) -> &[(i16, i16)] {
+        &self.contradictions
+    }
+
+    /// The candidate mark on the cell at `x`, `y`, counting from 0, while the
+    /// cell is covered.
+    pub fn annotation(&self, x: i16, y: i16) -> Option<u8> {
+        let index = (y * self.width + x) as usize;
+        match self.annotations.get(index) {
+            Some(&mark) if mark > 0 && self.field_state[index].is_covered() => Some(mark - 1),
+            _ => None,
+        }
+    }
+
+    /// Moves the covered cell at `x`, `y` to its next candidate mark, from
+    /// none through the [`ANNOTATIONS`] marks and back to none, and returns
+    /// the new mark. The marks are notes for reasoning about the board, such
+    /// as the two sides of a 50/50, and are not moves: they change no cell
+    /// and are not undone. Uncovered cells and finished games keep none.
+    pub fn annotate(&mut self, x: i16, y: i16) -> Option<u8> {
+        let over = matches!(self.state, GameState::Won | GameState::Lost);
+        if over || !self.cell_state(x, y).is_covered() {
+            return None;
+        }
+        if self.annotations.len() != self.field_state.len() {
+            self.annotations.resize(self.field_state.len(), 0);
+        }
+        let index = (y * self.width + x) as usize;
+        let mark = &mut self.annotations[index];
+        *mark = (*mark + 1) % (ANNOTATIONS + 1);
+        self.changed.clear();
+        self.changed.push((x, y));
+        self.annotation(x, y)
+    }
+
+    /// The covered cells with a candidate mark and their marks.
+    pub fn annotations(&self) -> Vec<(i16, i16, u8)> {
+        let mut annotations = Vec::new();
+        for (index, &mark) in self.annotations.iter().enumerate() {
+            let width = self.width as usize;
+            let (x, y) = ((index % width) as i16, (index / width) as i16);
+            if mark > 0 && self.cell_state(x, y).is_covered() {
+                annotations.push((x, y, mark - 1));
+            }
+        }
+        annotations
+    }
+
+    /// Whether cells proven safe by a single number are uncovered after every
+    /// move, see [`solver::obvious_safe`](crate::solver::obvious_safe). Such
+    /// games are assisted play.
+    pub fn auto_open(&self) -> bool {
+        self.auto_open
+    }
+
+    /// Plays the game, and the games after a reset, with or without the
+    /// cells proven safe opening on their own.
+    pub fn set_auto_open(&mut self, auto_open: bool) {
+        self.auto_open = auto_open;
+    }
+
+    /// Whether the mines the solver proves are flagged after every move, see
+    /// [`solver::solve`](crate::solver::solve). Nothing is ever uncovered
+    /// for the player and such games are assisted play.
+    pub fn auto_flag(&self) -> bool {
+        self.auto_flag
+    }
+
+    /// Plays the game, and the games after a reset, with or without the
+    /// proven mines flagged on their own. Games without flags get none.
+    pub fn set_auto_flag(&mut self, auto_flag: bool) {
+        self.auto_flag = auto_flag;
+    }
+
+    /// Whether the flag on the cell was placed by [`Game::auto_flag`] rather
+    /// than the player.
+    pub fn auto_flagged(&self, x: i16, y: i16) -> bool {
+        let index = (y * self.width + x) as usize;
+        self.auto_flags.get(index).copied().unwrap_or(false)
+            && matches!(self.field_state[index], CellState::Flagged(_))
+    }
+
+    /// Whether cells opened or mines were flagged on their own, see
+    /// [`Game::auto_open`] and [`Game::auto_flag`].
+    pub fn assisted(&self) -> bool {
+        self.auto_open || self.auto_flag
+    }
+
+    /// Hints taken in this game, such games are hint assisted.
+    pub fn hints(&self) -> u16 {
+        self.hints
+    }
+
+    /// Hints that may still be taken, `None` when they are not limited.
+    pub fn hints_left(&self) -> Option<u16> {
+        self.hint_budget.map(|budget| budget.saturating_sub(self.hints))
+    }
+
+    /// Sets the hints taken for a game restored from its cells.
+    pub fn set_hints(&mut self, hints: u16) {
+        self.hints = hints;
+    }
+
+    /// Limits the hints of this game and the games after a reset, `None`
+    /// for no limit.
+    pub fn set_hint_budget(&mut self, budget: Option<u16>) {
+        self.hint_budget = budget;
+    }
+
+    /// Whether the game went back to a checkpoint, see
+    /// [`checkpoint`](crate::checkpoint), or took back the move that ended
+    /// it. Practice games are not recorded in the statistics.
+    pub fn practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Marks the game as practice until the next reset.
+    pub fn set_practice(&mut self, practice: bool) {
+        self.practice = practice;
+    }
+
+    /// Takes a hint while the game is being played: the first cell the
+    /// solver proves safe, or failing that a mine, see
+    /// [`solver::hint`](crate::solver::hint). Every hint moves the clock
+    /// forward by [`HINT_PENALTY`]. Returns `None`, using nothing, when no
+    /// hints are left or the solver cannot prove any cell.
+    pub fn hint(&mut self) -> Option<crate::solver::Deduction> {
+        if self.state != GameState::Playing || self.hints_left() == Some(0) {
+            return None;
+        }
+        let deduction = crate::solver::hint(self)?;
+        self.hints += 1;
+        self.penalty += HINT_PENALTY;
+        tracing::debug!(hints = self.hints, "hint taken");
+        Some(deduction)
+    }
+
+    /// The cells the numbers on the board count.
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Plays the game, and the games after a reset, by another counting
+    /// rule. Set it before the first move, or on a game restored from cells
+    /// counted by the rule.
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+        self.contradictions = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.contradicted(x, y))
+            .collect();
+    }
+
+    /// The symmetry mines are laid out with.
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Lays out the mines of the next reset, and the ones after it, with a
+    /// symmetry. The board on the table is left as it is.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    /// How long numbers stay shown after they are uncovered in a blind game,
+    /// `None` when the game is not blind.
+    pub fn blind(&self) -> Option<Duration> {
+        self.blind
+    }
+
+    /// Plays the game, and the games after a reset, blind: every number
+    /// fades `fade` after it is uncovered and the player has to remember it.
+    /// Numbers already on the board fade `fade` from now.
+    pub fn set_blind(&mut self, fade: Option<Duration>) {
+        self.blind = fade;
+        self.revealed.clear();
+        if fade.is_some() {
+            let now = Instant::now();
+            self.revealed = self
+                .field_state
+                .iter()
+                .map(|cell| (!cell.is_covered()).then_some(now))
+                .collect();
+        }
+    }
+
+    /// Whether the cell is a number of a blind game that has faded. Every
+    /// number shows again once the game is over.
+    pub fn faded(&self, x: i16, y: i16) -> bool {
+        let (Some(fade), GameState::Playing) = (self.blind, self.state) else {
+            return false;
+        };
+        let index = (y * self.width + x) as usize;
+        matches!(self.field_state[index], CellState::Counted(_))
+            && self
+                .revealed
+                .get(index)
+                .copied()
+                .flatten()
+                .is_some_and(|revealed| revealed.elapsed() >= fade)
+    }
+
+    /// Number of faded numbers on the board, see [`Game::faded`].
+    pub fn faded_count(&self) -> usize {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.faded(x, y))
+            .count()
+    }
+
+    /// Plays the game, and the games after a reset, with or without flags.
+    /// Set it before the first move, marks already placed are kept.
+    pub fn set_no_flags(&mut self, no_flags: bool) {
+        self.no_flags = no_flags;
+    }
+
+    /// Whether flags placed on safe cells are refused, each one moving the
+    /// clock forward by [`FLAG_PENALTY`].
+    pub fn flag_penalty(&self) -> bool {
+        self.flag_penalty
+    }
+
+    /// Plays the game, and the games after a reset, with flags checked
+    /// against the layout, see [`Game::flag_penalty`].
+    pub fn set_flag_penalty(&mut self, flag_penalty: bool) {
+        self.flag_penalty = flag_penalty;
+    }
+
+    /// Flags refused in this game for being on safe cells.
+    pub fn wrong_flags(&self) -> u16 {
+        self.wrong_flags
+    }
+
+    /// Sets the flags refused for a game restored from its cells.
+    pub fn set_wrong_flags(&mut self, wrong_flags: u16) {
+        self.wrong_flags = wrong_flags;
+    }
+
+
+    /// Toggles a flag on a covered cell. With the [`Game::flag_penalty`]
+    /// on, a flag on a safe cell is refused and counted in
+    /// [`Game::wrong_flags`].
+    pub fn flag(&mut self, x: i16, y: i16) {
+        self.record(Move::Flag(x, y), |game| game.flag_cell(x, y));
+    }
+
+    /// Sets a question mark on a covered cell, replacing its flag if it has
+    /// one. A question mark is cleared with [`Game::set_unknown`].
+    pub fn question(&mut self, x: i16, y: i16) {
+        self.record(Move::Question(x, y), |game| game.question_cell(x, y));
+    }
+
+    /// Clears a flag or question mark.
+    pub fn set_unknown(&mut self, x: i16, y: i16) {
+        self.record(Move::SetUnknown(x, y), |game| game.unknown_cell(x, y));
+    }
+
+    /// Uncovers a cell, flood filling from cells without mined neighbors, and
+    /// returns the resulting game state.
+    pub fn uncover(&mut self, x: i16, y: i16) -> GameState {
+        self.record(Move::Uncover(x, y), |game| {
+            game.uncover_cell(x, y);
+        });
+        self.state
+    }
+
+    /// Uncovers the covered neighbors of a counted cell once the number of
+    /// flags around it matches its count. Flagged neighbors are left alone.
+    pub fn chord(&mut self, x: i16, y: i16) -> GameState {
+        self.record(Move::Chord(x, y), |game| {
+            game.chord_cell(x, y);
+        });
+        self.state
+    }
+
+    /// Plays a move as if the player had made it. Once the game is won or
+    /// lost moves change nothing.
+    pub fn apply(&mut self, mv: Move) -> GameState {
+        match mv {
+            Move::Uncover(x, y) => return self.uncover(x, y),
+            Move::Flag(x, y) => self.flag(x, y),
+            Move::Question(x, y) => self.question(x, y),
+            Move::SetUnknown(x, y) => self.set_unknown(x, y),
+            Move::Chord(x, y) => return self.chord(x, y),
+        }
+        self.state
+    }
+
+    /// The moves that changed the board since it was laid out, oldest first,
+    /// with the number of safe cells each one uncovered. Undone moves are
+    /// left out and a game restored from its cells starts with none.
+    pub fn moves(&self) -> Vec<(Move, usize)> {
+        let mut moves = Vec::with_capacity(self.undo.len());
+        for (i, (mv, before)) in self.undo.iter().enumerate() {
+            let after = match self.undo.get(i + 1) {
+                Some((_, next)) => next.unknown,
+                None => self.unknown,
+            };
+            moves.push((*mv, before.unknown.saturating_sub(after)));
+        }
+        moves
+    }
+
+    /// Reverts the last move that changed the board and returns it. Only the
+    /// cells the move changed are put back. Taking back the move that won or
+    /// lost the game makes it [practice](Game::practice).
+    pub fn undo(&mut self) -> Option<Move> {
+        let (mv, mut step) = self.undo.pop()?;
+        tracing::trace!(?mv, "undo");
+        if matches!(self.state, GameState::Won | GameState::Lost) {
+            self.practice = true;
+        }
+        self.exchange(&mut step);
+        self.redo.push((mv, step));
+        Some(mv)
+    }
+
+    /// Reapplies the last undone move and returns it.
+    pub fn redo(&mut self) -> Option<Move> {
+        let (mv, mut step) = self.redo.pop()?;
+        tracing::trace!(?mv, "redo");
+        self.exchange(&mut step);
+        self.undo.push((mv, step));
+        Some(mv)
+    }
+
+    /// Captures the player visible state of the whole board, to go back to
+    /// with [`Game::restore`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            field_state: self.field_state.clone(),
+            state: self.state,
+            est_remaining: self.est_remaining,
+            act_remaining: self.act_remaining,
+            unknown: self.unknown,
+            flags: self.flags,
+            exploded: self.exploded,
+            auto_flags: self.auto_flags.clone(),
+        }
+    }
+
+    /// Puts the board back to a snapshot taken from this game. The timer keeps
+    /// running unless the snapshot was taken after the game ended. The moves
+    /// played before no longer lead to the board, so there is nothing left
+    /// to undo or redo.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let before = core::mem::replace(&mut self.field_state, snapshot.field_state.clone());
+        let changed: Vec<usize> = (0..before.len().min(self.field_state.len()))
+            .filter(|&index| before[index] != self.field_state[index])
+            .collect();
+        self.check_changes(changed.into_iter());
+        self.state = snapshot.state;
+        self.est_remaining = snapshot.est_remaining;
+        self.act_remaining = snapshot.act_remaining;
+        self.unknown = snapshot.unknown;
+        self.flags = snapshot.flags;
+        self.exploded = snapshot.exploded;
+        self.auto_flags.clone_from(&snapshot.auto_flags);
+        self.undo.clear();
+        self.redo.clear();
+        self.debug_check();
+        self.settle_clock();
+    }
+
+    // the counters of the board as a step that has no cells yet
+    fn step(&self) -> Step {
+        Step {
+            cells: Vec::new(),
+            state: self.state,
+            est_remaining: self.est_remaining,
+            act_remaining: self.act_remaining,
+            unknown: self.unknown,
+            flags: self.flags,
+            exploded: self.exploded,
+        }
+    }
+
+    // swaps the cells and counters of the board with the other side of a
+    // move, leaving the step with the side it replaced
+    fn exchange(&mut self, step: &mut Step) {
+        for (index, cell, auto_flag) in &mut step.cells {
+            core::mem::swap(&mut self.field_state[*index], cell);
+            let placed = self.auto_flags.get(*index).copied().unwrap_or(false);
+            if placed != *auto_flag {
+                self.auto_flags.resize(self.field_state.len(), false);
+                self.auto_flags[*index] = *auto_flag;
+                *auto_flag = placed;
+            }
+        }
+        core::mem::swap(&mut self.state, &mut step.state);
+        core::mem::swap(&mut self.est_remaining, &mut step.est_remaining);
+        core::mem::swap(&mut self.act_remaining, &mut step.act_remaining);
+        core::mem::swap(&mut self.unknown, &mut step.unknown);
+        core::mem::swap(&mut self.flags, &mut step.flags);
+        core::mem::swap(&mut self.exploded, &mut step.exploded);
+        self.check_changes(step.cells.iter().map(|&(index, ..)| index));
+        self.debug_check();
+        self.settle_clock();
+    }
+
+    // stops the timer of a game that is over and runs it again for one that
+    // is played on
+    fn settle_clock(&mut self) {
+        match self.state {
+            GameState::Won | GameState::Lost => {
+                if self.finished.is_none() {
+                    self.finished = Some(Instant::now());
+                }
+            }
+            _ => self.finished = None,
+        }
+    }
+
+    /// Applies a move and keeps the cells it changed for undo if there are
+    /// any. The work done for undo is in proportion to those cells rather
+    /// than the size of the board. A game that is won or lost takes no more
+    /// moves.
+    fn record<F: FnOnce(&mut Self)>(&mut self, mv: Move, apply: F) {
+        if matches!(self.state, GameState::Won | GameState::Lost) {
+            return;
+        }
+        if self.no_flags && matches!(mv, Move::Flag(..) | Move::Question(..)) {
+            return;
+        }
+        if let (true, Move::Flag(x, y)) = (self.flag_penalty, mv) {
+            if self.refuse_flag(x, y) {
+                return;
+            }
+        }
+        let mut before = self.step();
+        self.journal.clear();
+        apply(self);
+        if self.auto_open && matches!(mv, Move::Uncover(..) | Move::Chord(..)) {
+            self.open_obvious();
+        }
+        self.settle_journal();
+        self.claim_marks();
+        if self.auto_flag && !self.no_flags {
+            self.flag_proven();
+            self.settle_journal();
+        }
+        tracing::trace!(?mv, state = ?self.state, "move");
+        let changed = !self.journal.is_empty();
+        let journal = core::mem::take(&mut self.journal);
+        self.check_changes(journal.iter().map(|&(index, ..)| index));
+        if changed {
+            before.cells.extend_from_slice(&journal);
+            self.undo.push((mv, before));
+            self.redo.clear();
+        }
+        self.journal = journal;
+        self.clicks.count(mv);
+        self.clicks.wasted += !changed as u32;
+        self.debug_check();
+    }
+
+    /// Checks the board against the counters kept alongside it: the number of
+    /// mines, the flags and remaining mines, the number of covered cells and
+    /// the count on every uncovered cell. Returns the first mismatch found.
+    /// Debug builds check after every change to the board.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let size = self.width as usize * self.height as usize;
+        if self.field_state.len() != size {
+            return Err(format!(
+                "{} cells on a {}x{} board",
+                self.field_state.len(),
+                self.width,
+                self.height
+            ));
+        }
+        let mut mines = 0;
+        let mut flags = 0;
+        let mut flagged_mines = 0;
+        let mut revealed = 0;
+        for (index, &cell) in self.field_state.iter().enumerate() {
+            if cell.is_mined() {
+                mines += 1;
+            }
+            let shown = match cell {
+                CellState::Flagged(mined) => {
+                    flags += 1;
+                    if mined {
+                        flagged_mines += 1;
+                    }
+                    continue;
+                }
+                CellState::Known(false) => 0,
+                CellState::Counted(count) => count,
+                _ => continue,
+            };
+            revealed += 1;
+            let x = (index % self.width as usize) as i16;
+            let y = (index / self.width as usize) as i16;
+            let count = self.neighbor_count(x, y);
+            if shown != count {
+                return Err(format!(
+                    "[{},{}] shows {} mined neighbors but has {}",
+                    x, y, shown, count
+                ));
+            }
+        }
+        if mines != self.total {
+            return Err(format!("{} mines laid out of {}", mines, self.total));
+        }
+        if flags != self.flags {
+            return Err(format!("{} flags placed but {} counted", flags, self.flags));
+        }
+        if self.est_remaining != self.total.saturating_sub(flags) {
+            return Err(format!(
+                "{} mines shown as remaining with {} of {} flagged",
+                self.est_remaining, flags, self.total
+            ));
+        }
+        if self.act_remaining != self.total - flagged_mines {
+            return Err(format!(
+                "{} mines remaining but {} of {} are flagged",
+                self.act_remaining, flagged_mines, self.total
+            ));
+        }
+        if self.unknown != size - revealed {
+            return Err(format!(
+                "{} cells counted as covered but {} are",
+                self.unknown,
+                size - revealed
+            ));
+        }
+        Ok(())
+    }
+
+    /// Panics if the board and its counters disagree, in debug builds only.
+    fn debug_check(&self) {
+        #[cfg(debug_assertions)]
+        if let Err(message) = self.check_invariants() {
+            panic!("game invariant broken: {}", message);
+        }
+    }
+
+    fn flag_cell(&mut self, x: i16, y: i16) {
+        let index = (y * self.width + x) as usize;
+        match self.field_state[index] {
+            CellState::Unknown(mined) | CellState::Questioned(mined) => {
+                self.set_cell(index, CellState::Flagged(mined));
+                self.add_flags(1);
+                if mined {
+                    self.act_remaining -= 1;
+                }
+            }
+            _ => {}
+        }
+        self.play();
+    }
+
+    fn question_cell(&mut self, x: i16, y: i16) {
+        let index = (y * self.width + x) as usize;
+        match self.field_state[index] {
+            CellState::Unknown(mined) => self.set_cell(index, CellState::Questioned(mined)),
+            CellState::Flagged(mined) => {
+                self.set_cell(index, CellState::Questioned(mined));
+                self.add_flags(-1);
+                if mined {
+                    self.act_remaining += 1;
+                }
+            }
+            _ => {}
+        }
+        self.play();
+    }
+
+    fn unknown_cell(&mut self, x: i16, y: i16) {
+        let index = (y * self.width + x) as usize;
+        match self.field_state[index] {
+            CellState::Flagged(mined) => {
+                self.set_cell(index, CellState::Unknown(mined));
+                self.add_flags(-1);
+                if mined {
+                    self.act_remaining += 1;
+                }
+            }
+            CellState::Known(true) | CellState::Questioned(_) => {
+                let mined = self.field_state[index].is_mined();
+                self.set_cell(index, CellState::Unknown(mined));
+            }
+            CellState::Known(false) | CellState::Counted(_) => {
+                self.set_cell(index, CellState::Unknown(false));
+                self.unknown += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Uncovers every mine, used to show the layout once a game is lost. The
+    /// mines are covered again with the move that lost it when that is
+    /// undone.
+    pub fn show_mined(&mut self) {
+        for i in 0..self.field_state.len() {
+            if self.field_state[i] == CellState::Unknown(true) {
+                self.field_state[i] = CellState::Known(true);
+                if let Some((_, step)) = self.undo.last_mut() {
+                    step.cells.push((i, CellState::Unknown(true), false));
+                }
+            }
+        }
+        self.debug_check();
+    }
+
+    fn uncover_cell(&mut self, x: i16, y: i16) -> GameState {
+        if matches!(self.state, GameState::Won | GameState::Lost) {
+            return self.state;
+        }
+        self.play();
+        let index = (y * self.width + x) as usize;
+        // a marked cell is uncovered as if it was never marked
+        match self.field_state[index] {
+            CellState::Flagged(mined) => {
+                self.set_cell(index, CellState::Unknown(mined));
+                self.add_flags(-1);
+                if mined {
+                    self.act_remaining += 1;
+                }
+            }
+            CellState::Questioned(mined) => self.set_cell(index, CellState::Unknown(mined)),
+            _ => {}
+        }
+        match self.field_state[index] {
+            CellState::Unknown(true) | CellState::Flagged(true) | CellState::Questioned(true) => {
+                self.set_cell(index, CellState::Known(true));
+                self.exploded = Some((x, y));
+                self.finish(GameState::Lost);
+            }
+            CellState::Questioned(false)
+            | CellState::Flagged(false)
+            | CellState::Unknown(false) => {
+                let count = self.neighbor_count(x, y);
+                if count != 0 {
+                    self.set_cell(index, CellState::Counted(count));
+                    self.unknown -= 1;
+                } else {
+                    self.set_cell(index, CellState::Known(false));
+                    self.unknown -= 1;
+                    let mut stack = core::mem::take(&mut self.cascade);
+                    stack.push((x, y));
+                    while let Some((x, y)) = stack.pop() {
+                        let cells = self.neighborhood.cells(self.width, self.height, x, y);
+                        for (x_idx, y_idx) in cells {
+                            let index = (y_idx * self.width + x_idx) as usize;
+                            if self.field_state[index] == CellState::Unknown(false) {
+                                self.unknown -= 1;
+                                match self.neighbor_count(x_idx, y_idx) {
+                                    0 => {
+                                        self.set_cell(index, CellState::Known(false));
+                                        stack.push((x_idx, y_idx));
+                                    }
+                                    count => self.set_cell(index, CellState::Counted(count)),
+                                }
+                            }
+                        }
+                    }
+                    self.cascade = stack;
+                }
+                if self.unknown == self.total as usize {
+                    self.finish(GameState::Won);
+                }
+            }
+            _ => {}
+
+        }
+        self.state
+    }
+
+    fn chord_cell(&mut self, x: i16, y: i16) -> GameState {
+        let CellState::Counted(count) = self.cell_state(x, y) else {
+            return self.state;
+        };
+        let mut flagged = 0;
+        let mut covered = Vec::new();
+        for (x_idx, y_idx) in self.neighbors(x, y) {
+            match self.cell_state(x_idx, y_idx) {
+                CellState::Flagged(_) => flagged += 1,
+                CellState::Unknown(_) | CellState::Questioned(_) => covered.push((x_idx, y_idx)),
+                _ => {}
+            }
+        }
+        if flagged != count {
+            return self.state;
+        }
+        for (x_idx, y_idx) in covered {
+            // an earlier cascade may already have opened this cell
+            if !matches!(
+                self.cell_state(x_idx, y_idx),
+                CellState::Unknown(_) | CellState::Questioned(_)
+            ) {
+                continue;
+            }
+            if self.uncover_cell(x_idx, y_idx) == GameState::Lost {
+                break;
+            }
+        }
+        self.state
+    }
+
+    /// Uncovers the cells a single number proves safe until there are none,
+    /// as part of the move that revealed them.
+    fn open_obvious(&mut self) {
+        while self.state == GameState::Playing {
+            let safe = crate::solver::obvious_safe(self);
+            if safe.is_empty() {
+                break;
+            }
+            tracing::trace!(cells = safe.len(), "auto open");
+            for (x, y) in safe {
+                self.uncover_cell(x, y);
+            }
+        }
+    }
+
+    // a cell the player's move changed holds no flag of the co-pilot
+    fn claim_marks(&mut self) {
+        for &(index, ..) in &self.journal {
+            if let Some(placed) = self.auto_flags.get_mut(index) {
+                *placed = false;
+            }
+        }
+    }
+
+    // changes a cell, noting what it was for the move being recorded
+    fn set_cell(&mut self, index: usize, cell: CellState) {
+        let placed = self.auto_flags.get(index).copied().unwrap_or(false);
+        self.journal.push((index, self.field_state[index], placed));
+        self.field_state[index] = cell;
+    }
+
+    // keeps what each cell was before its first change in the move, in row
+    // order, leaving out the cells that ended the move as they started it
+    fn settle_journal(&mut self) {
+        self.journal.sort_by_key(|&(index, ..)| index);
+        self.journal.dedup_by_key(|&mut (index, ..)| index);
+        let field_state = &self.field_state;
+        self.journal
+            .retain(|&(index, cell, _)| field_state[index] != cell);
+    }
+
+    /// Flags the covered cells the solver proves mines, as part of the move
+    /// before them. Question marks are left to the player.
+    fn flag_proven(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        self.auto_flags.resize(self.field_state.len(), false);
+        let mines: Vec<(i16, i16)> = crate::solver::solve(self)
+            .into_iter()
+            .filter(|deduction| deduction.kind == crate::solver::DeductionKind::Mine)
+            .map(|deduction| (deduction.x, deduction.y))
+            .collect();
+        tracing::trace!(cells = mines.len(), "auto flag");
+        for (x, y) in mines {
+            let index = (y * self.width + x) as usize;
+            if let CellState::Unknown(_) = self.field_state[index] {
+                self.flag_cell(x, y);
+                self.auto_flags[index] = true;
+            }
+        }
+    }
+
+    /// Lists the cells that changed and their numbered neighbors in row
+    /// order, then checks those numbers for contradictions.
+    fn check_changes(&mut self, cells: impl Iterator<Item = usize>) {
+        let mut changed = core::mem::take(&mut self.changed);
+        changed.clear();
+        for index in cells {
+            let x = (index % self.width as usize) as i16;
+            let y = (index / self.width as usize) as i16;
+            changed.push((x, y));
+            changed.extend(
+                self.neighbors(x, y)
+                    .filter(|&(x, y)| matches!(self.cell_state(x, y), CellState::Counted(_))),
+            );
+        }
+        changed.sort_unstable_by_key(|&(x, y)| (y, x));
+        changed.dedup();
+        self.changed = changed;
+        if self.blind.is_some() {
+            self.stamp_reveals();
+        }
+        let mut contradictions = core::mem::take(&mut self.contradictions);
+        contradictions.retain(|&(x, y)| self.contradicted(x, y));
+        for &(x, y) in &self.changed {
+            if self.contradicted(x, y) && !contradictions.contains(&(x, y)) {
+                contradictions.push((x, y));
+            }
+        }
+        self.contradictions = contradictions;
+    }
+
+    // times the cells uncovered since the last move and forgets the ones
+    // covered again by undo, going over the whole board only the first time
+    // after it was laid out
+    fn stamp_reveals(&mut self) {
+        let now = Instant::now();
+        let stamp = |cell: CellState, revealed: &mut Option<Instant>| {
+            if cell.is_covered() {
+                *revealed = None;
+            } else if revealed.is_none() {
+                *revealed = Some(now);
+            }
+        };
+        if self.revealed.len() != self.field_state.len() {
+            self.revealed.clear();
+            self.revealed.resize(self.field_state.len(), None);
+            for (&cell, revealed) in self.field_state.iter().zip(&mut self.revealed) {
+                stamp(cell, revealed);
+            }
+            return;
+        }
+        for &(x, y) in &self.changed {
+            let index = (y * self.width + x) as usize;
+            stamp(self.field_state[index], &mut self.revealed[index]);
+        }
+    }
+
+    // refuses a flag on a covered safe cell, a wasted click that starts the
+    // game and adds the penalty to its clock
+    fn refuse_flag(&mut self, x: i16, y: i16) -> bool {
+        let index = (y * self.width + x) as usize;
+        let safe = matches!(
+            self.field_state[index],
+            CellState::Unknown(false) | CellState::Questioned(false)
+        );
+        if !safe {
+            return false;
+        }
+        self.play();
+        self.wrong_flags += 1;
+        self.penalty += FLAG_PENALTY;
+        self.clicks.count(Move::Flag(x, y));
+        self.clicks.wasted += 1;
+        tracing::debug!(x, y, wrong_flags = self.wrong_flags, "flag refused");
+        true
+    }
+
+    /// Adds to the number of flags and updates the estimated remaining mines.
+    fn add_flags(&mut self, change: i16) {
+        self.flags = self.flags.saturating_add_signed(change);
+        self.est_remaining = self.total.saturating_sub(self.flags);
+    }
+
+    fn play(&mut self) {
+        if self.state == GameState::Initial {
+            tracing::debug!("game started");
+        }
+        self.state = GameState::Playing;
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    fn finish(&mut self, state: GameState) {
+        self.state = state;
+        self.finished = Some(Instant::now());
+        tracing::debug!(?state, "game over");
+    }
+
+    // the cells the number at x, y counts
+    fn neighbors(&self, x: i16, y: i16) -> impl Iterator<Item = (i16, i16)> {
+        self.neighborhood.cells(self.width, self.height, x, y)
+    }
+
+    fn neighbor_count(&self, x: i16, y: i16) -> u8 {
+        self.neighbors(x, y)
+            .filter(|&(nx, ny)| self.field_state[(ny * self.width + nx) as usize].is_mined())
+            .count() as u8
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut field = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = (y * self.width + x) as usize;
+                match self.field_state[idx] {
+                    CellState::Unknown(_) => field.push('\u{25A0}'),
+                    CellState::Known(false) => field.push('\u{25A1}'),
+                    CellState::Known(true) => field.push('*'),
+                    CellState::Counted(count) => field.push(crate::render::count_glyph(count)),
+                    CellState::Flagged(_) => field.push('\u{1F3F3}'),
+                    CellState::Questioned(_) => field.push('?'),
+                }
+                field.push(' ');
+            }
+            field.push('\n');
+        }
+        f.write_str(field.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GameState::Initial => "initial",
+            GameState::Playing => "playing",
+            GameState::Won => "won",
+            GameState::Lost => "lost",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for GameState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "initial" => Ok(GameState::Initial),
+            "playing" => Ok(GameState::Playing),
+            "won" => Ok(GameState::Won),
+            "lost" => Ok(GameState::Lost),
+            _ => Err(format!("unknown game state {}", s)),
+        }
+    }
+}
+
+impl Difficulty {
+    /// Every preset from easiest to hardest.
+    pub const ALL: [Difficulty; 3] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Expert,
+    ];
+
+    /// Returns the size and number of mines of the preset.
+    pub fn config(self) -> GameConfig {
+        let (width, height, mines) = match self {
+            Difficulty::Beginner => (9, 9, 10),
+            Difficulty::Intermediate => (16, 16, 40),
+            Difficulty::Expert => (30, 16, 99),
+        };
+        GameConfig {
+            width,
+            height,
+            mines,
+            zones: Vec::new(),
+        }
+    }
+
+    /// Finds the preset matching a board, if any.
+    pub fn from_config(config: GameConfig) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|d| d.config() == config)
+    }
+}
+
+/// The serialized form of a game.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GameData {
+    width: i16,
+    height: i16,
+    state: GameState,
+    elapsed: Duration,
+    seed: Option<u64>,
+    #[serde(default)]
+    no_flags: bool,
+    #[serde(default)]
+    auto_open: bool,
+    #[serde(default)]
+    auto_flag: bool,
+    #[serde(default)]
+    hints: u16,
+    #[serde(default)]
+    hint_budget: Option<u16>,
+    #[serde(default)]
+    flag_penalty: bool,
+    #[serde(default)]
+    wrong_flags: u16,
+    #[serde(default)]
+    practice: bool,
+    #[serde(default)]
+    neighborhood: Neighborhood,
+    #[serde(default)]
+    zones: Vec<Zone>,
+    cells: Vec<CellState>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Game> for GameData {
+    fn from(game: Game) -> Self {
+        GameData {
+            width: game.width,
+            height: game.height,
+            state: game.state,
+            elapsed: game.elapsed(),
+            seed: game.seed,
+            no_flags: game.no_flags,
+            auto_open: game.auto_open,
+            auto_flag: game.auto_flag,
+            hints: game.hints,
+            hint_budget: game.hint_budget,
+            flag_penalty: game.flag_penalty,
+            wrong_flags: game.wrong_flags,
+            practice: game.practice,
+            neighborhood: game.neighborhood,
+            zones: game.zones,
+            cells: game.field_state,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GameData> for Game {
+    type Error = String;
+
+    fn try_from(data: GameData) -> Result<Self, Self::Error> {
+        let size = data.width as usize * data.height as usize;
+        if data.width <= 0 || data.height <= 0 || size > MAX_CELLS {
+            return Err("invalid board size".into());
+        }
+        if data.cells.len() != size {
+            return Err("cells do not match the board size".into());
+        }
+        let mut game = Game::from_cells(
+            data.width,
+            data.height,
+            data.cells,
+            data.state,
+            data.elapsed,
+        );
+        game.seed = data.seed;
+        game.no_flags = data.no_flags;
+        game.auto_open = data.auto_open;
+        game.auto_flag = data.auto_flag;
+        game.hints = data.hints;
+        game.hint_budget = data.hint_budget;
+        game.flag_penalty = data.flag_penalty;
+        game.wrong_flags = data.wrong_flags;
+        game.practice = data.practice;
+        game.set_neighborhood(data.neighborhood);
+        game.zones = data.zones;
+        Ok(game)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Expert => "expert",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err(format!(
+                "unknown difficulty {}, expected beginner, intermediate or expert",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Density {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Density::Fixed(mines) => write!(f, "{}", mines),
+            Density::Percent(percent) => write!(f, "{}%", percent),
+            Density::Curve { a, b, c } => write!(f, "curve {} {} {}", a, b, c),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Density {
+    type Err = String;
+
+    /// Parses a mine count, `40`, a percentage, `15%`, or a curve, `curve`
+    /// for the built in one or `curve a b c` for `a * cells² + b * cells + c`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid density {}, expected 40, 15% or curve a b c", s);
+        if let Some(percent) = s.strip_suffix('%') {
+            return match percent.trim().parse::<f32>() {
+                Ok(percent) if percent > 0.0 && percent < 100.0 => Ok(Density::Percent(percent)),
+                _ => Err(invalid()),
+            };
+        }
+        let mut words = s.split_whitespace();
+        if words.next() != Some("curve") {
+            return s.parse().map(Density::Fixed).map_err(|_| invalid());
+        }
+        let factors = words
+            .map(|word| word.parse::<f32>().ok().filter(|factor| factor.is_finite()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        match factors[..] {
+            [] => Ok(Density::default()),
+            [a, b, c] => Ok(Density::Curve { a, b, c }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Neighborhood {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Neighborhood::Adjacent => "adjacent",
+            Neighborhood::Distance2 => "distance2",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Neighborhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "adjacent" => Ok(Neighborhood::Adjacent),
+            "distance2" => Ok(Neighborhood::Distance2),
+            _ => Err(format!(
+                "unknown neighborhood {}, expected adjacent or distance2",
+                s
+            )),
+        }
     }
+}
 
-    pub(crate) fn state(&self) -> GameState {
-        self.state
+#[cfg(feature = "std")]
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Symmetry::None => "none",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Rotational => "rotational",
+        })
     }
+}
 
-    pub(crate) fn cell_state(&self, x: i16, y: i16) -> CellState {
-        let index = (y * self.width + x) as usize;
-        self.field_state[index]
-    }
+#[cfg(feature = "std")]
+impl FromStr for Symmetry {
+    type Err = String;
 
-    pub(crate) fn reset(&mut self) {
-        let mut rng = StdRng::from_entropy();
-        let density = ((self.width as f32 * self.height as f32).powi(2) * DENSITY_FACTOR_A
-            + (self.width as f32 * self.height as f32) * DENSITY_FACTOR_B
-            + DENSITY_FACTOR_C) as u16;
-        let size = (self.width * self.height) as usize;
-        self.clear();
-        for _ in 0..density {
-            let mut cell = rng.gen_range(0..size);
-            while let CellState::Unknown(true) = self.field_state[cell] {
-                cell = rng.gen_range(0..size);
-            }
-            self.field_state[cell] = CellState::Unknown(true);
-        }
-        self.act_remaining = density;
-        self.est_remaining = density;
-        self.total = density;
-        self.unknown = size;
-        self.state = GameState::Initial;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Symmetry::ALL
+            .into_iter()
+            .find(|symmetry| symmetry.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown symmetry {}, expected none, horizontal, vertical or rotational",
+                    s
+                )
+            })
     }
+}
 
-    pub(crate) fn clear(&mut self) {
-        // wipe the board and push new values
-        self.field_state.clear();
-        for _i in 0..(self.width as usize * self.height as usize) {
-            self.field_state.push(CellState::Unknown(false));
+#[cfg(feature = "std")]
+impl Display for Handicap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Handicap::Cells(count) => write!(f, "{}", count),
+            Handicap::Opening => f.write_str("opening"),
         }
-        self.state = GameState::Initial;
     }
+}
 
-    pub(crate) fn est_remaining(&self) -> u16 {
-        self.est_remaining
+#[cfg(feature = "std")]
+impl FromStr for Handicap {
+    type Err = String;
+
+    /// Parses a number of cells or `opening`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opening" => Ok(Handicap::Opening),
+            _ => s.parse().map(Handicap::Cells).map_err(|_| {
+                format!(
+                    "invalid handicap {}, expected a number of cells or opening",
+                    s
+                )
+            }),
+        }
     }
+}
 
-    pub(crate) fn act_remaining(&self) -> u16 {
-        self.act_remaining
+#[cfg(feature = "std")]
+impl Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}%",
+            self.x, self.y, self.width, self.height, self.weight
+        )
     }
+}
 
+#[cfg(feature = "std")]
+impl FromStr for Zone {
+    type Err = String;
 
-    pub(crate) fn flag(&mut self, x: i16, y: i16) {
-        let index = (y * self.width + x) as usize;
-        match self.field_state[index] {
-            CellState::Unknown(mined) | CellState::Questioned(mined) => {
-                self.field_state[index] = CellState::Flagged(mined);
-                if self.est_remaining > 0 {
-                    self.est_remaining -= 1;    
-                }
-                if mined {
-                    self.act_remaining -= 1;
-                }
-            }
-            _ => {}
+    /// Parses the left column, top row, width, height and weight of a zone
+    /// separated by commas, such as `15,0,15,16,200%` for a right half of
+    /// an expert board with twice the mines.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid zone {}, expected x,y,width,height,weight%", s);
+        let fields: Vec<&str> = s.split(',').collect();
+        let [x, y, width, height, weight] = fields[..] else {
+            return Err(invalid());
+        };
+        let number = |field: &str| field.parse::<i16>().map_err(|_| invalid());
+        let zone = Zone {
+            x: number(x)?,
+            y: number(y)?,
+            width: number(width)?,
+            height: number(height)?,
+            weight: weight
+                .strip_suffix('%')
+                .unwrap_or(weight)
+                .parse()
+                .map_err(|_| invalid())?,
+        };
+        match zone.x >= 0 && zone.y >= 0 && zone.width > 0 && zone.height > 0 {
+            true => Ok(zone),
+            false => Err(invalid()),
         }
-        self.state = GameState::Playing;
     }
+}
 
-    pub(crate) fn question(&mut self, x: i16, y: i16) {
-        let index = (y * self.width + x) as usize;
-        match self.field_state[index] {
-            CellState::Unknown(mined) => self.field_state[index] = CellState::Questioned(mined),
-            CellState::Flagged(mined) => {
-                self.field_state[index] = CellState::Questioned(mined);
-                if self.est_remaining != self.total {
-                    self.est_remaining += 1;
-                }
-                if mined {
-                    self.act_remaining += 1;
-                }
-            }
-            _ => {}
+#[cfg(feature = "std")]
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Uncover(x, y) => write!(f, "uncover [{},{}]", x, y),
+            Move::Flag(x, y) => write!(f, "flag [{},{}]", x, y),
+            Move::Question(x, y) => write!(f, "question [{},{}]", x, y),
+            Move::SetUnknown(x, y) => write!(f, "clear mark [{},{}]", x, y),
+            Move::Chord(x, y) => write!(f, "chord [{},{}]", x, y),
         }
-        self.state = GameState::Playing;
     }
+}
 
-    pub(crate) fn set_unknown(&mut self, x: i16, y: i16) {
-        let index = (y * self.width + x) as usize;
-        match self.field_state[index] {
-            CellState::Flagged(mined) => {
-                self.field_state[index] = CellState::Unknown(mined);
-                if self.est_remaining != self.total {
-                    self.est_remaining += 1;
-                }
-                if mined {
-                    self.act_remaining += 1;
-                }
-            }
-            CellState::Known(mined) | CellState::Questioned(mined) => {
-                self.field_state[index] = CellState::Unknown(mined)
-            }
+#[cfg(feature = "std")]
+impl FromStr for Move {
+    type Err = String;
 
-            CellState::Counted(_) => self.field_state[index] = CellState::Unknown(false),
-            _ => {}
+    /// Parses a move in the form written by `Display`, e.g. `flag [3,4]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid move {}", s);
+        let (name, coords) = s.rsplit_once(' ').ok_or_else(invalid)?;
+        let (x, y) = coords
+            .strip_prefix('[')
+            .and_then(|c| c.strip_suffix(']'))
+            .and_then(|c| c.split_once(','))
+            .ok_or_else(invalid)?;
+        let x = x.trim().parse().map_err(|_| invalid())?;
+        let y = y.trim().parse().map_err(|_| invalid())?;
+        match name {
+            "uncover" => Ok(Move::Uncover(x, y)),
+            "flag" => Ok(Move::Flag(x, y)),
+            "question" => Ok(Move::Question(x, y)),
+            "clear mark" => Ok(Move::SetUnknown(x, y)),
+            "chord" => Ok(Move::Chord(x, y)),
+            _ => Err(invalid()),
         }
     }
+}
 
-    pub(crate) fn show_mined(&mut self) {
-        for i in 0..self.field_state.len() {
-            if self.field_state[i] == CellState::Unknown(true) {
-                self.field_state[i] = CellState::Known(true);
-            }
-        }
-    }
+/// Stand-in for `std::time::Instant` without the `std` feature. There is no
+/// clock to read, so every game takes no time.
+#[cfg(not(feature = "std"))]
+mod clock {
+    use core::{ops::Sub, time::Duration};
 
-    pub(crate) fn uncover(&mut self, x: i16, y: i16) -> GameState {
-        if self.state == GameState::Lost {
-            return self.state;
+    #[derive(Debug, Copy, Clone)]
+    pub struct Instant;
+
+    impl Instant {
+        pub fn now() -> Self {
+            Instant
         }
-        self.state = GameState::Playing;
-        let index = (y * self.width + x) as usize;
-        match self.field_state[index] {
-            CellState::Unknown(true) | CellState::Flagged(true) | CellState::Questioned(true) => {
-                self.field_state[index] = CellState::Known(true);
-                self.state = GameState::Lost;
-            }
-            CellState::Questioned(false)
-            | CellState::Flagged(false)
-            | CellState::Unknown(false) => {
-                let count = self.neighbor_count(x, y);
-                if count != 0 {
-                    self.field_state[index] = CellState::Counted(count);
-                    self.unknown -= 1;
-                } else {
-                    let mut stack = Vec::<(i16, i16)>::new();
-                    stack.push((x, y));
-                    while stack.len() > 0 {
-                        let (x, y) = stack.pop().unwrap();
-                        for y_idx in y - 1..=y + 1 {
-                            if y_idx < 0 || y_idx == self.height {
-                                continue;
-                            }
-                            let row_idx = (y_idx * self.width) as usize;
-                            for x_idx in x - 1..=x + 1 {
-                                if x_idx < 0 || x_idx == self.width {
-                                    continue;
-                                }
-                                let index = row_idx + x_idx as usize;
-                                if self.field_state[index] == CellState::Unknown(false) {
-                                    self.unknown -= 1;
-                                    match self.neighbor_count(x_idx, y_idx) {
-                                        0 => 
-                                            {
-                                                self.field_state[index] = CellState::Known(false);
-                                                stack.push((x_idx, y_idx));
-                                            }
-                                        count =>  self.field_state[index] = CellState::Counted(count)
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                if self.unknown == self.total as usize {
-                    self.state = GameState::Won;
-                }
-            }
-            _ => {}
 
+        pub fn elapsed(&self) -> Duration {
+            Duration::ZERO
         }
-        self.state
-    }
 
-    fn neighbor_count(&self, x: i16, y: i16) -> u8 {
-        let mut count: u8 = 0;
-        for y_idx in y - 1..=y + 1 {
-            if y_idx < 0 || y_idx == self.height {
-                continue;
-            }
-            let row_idx = (y_idx * self.width) as usize;
-            for x_idx in x - 1..=x + 1 {
-                if x_idx < 0 || x_idx == self.width {
-                    continue;
-                }
-                let index = row_idx + x_idx as usize;
-                // do not check self
-                if index == (y * self.width + x) as usize {
-                    continue;
-                }
-                if self.field_state[index] == CellState::Unknown(true)
-                    || self.field_state[index] == CellState::Questioned(true)
-                    || self.field_state[index] == CellState::Flagged(true)
-                {
-                    count += 1
-                }
-            }
+        pub fn checked_sub(&self, _duration: Duration) -> Option<Self> {
+            Some(Instant)
         }
-        count
     }
-}
 
-impl Display for Game {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut field = String::new();
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let idx = (y * self.width + x) as usize;
-                match self.field_state[idx] {
-                    CellState::Unknown(_) => field.push('\u{25A0}'),
-                    CellState::Known(false) => field.push('\u{25A1}'),
-                    CellState::Known(true) => field.push('*'),
-                    CellState::Counted(count) => field.push_str(count.to_string().as_str()),
-                    CellState::Flagged(_) => field.push('\u{1F3F3}'),
-                    CellState::Questioned(_) => field.push('?'),
-                }
-                field.push(' ');
-            }
-            field.push('\n');
+    impl Sub for Instant {
+        type Output = Duration;
+
+        fn sub(self, _earlier: Instant) -> Duration {
+            Duration::ZERO
         }
-        f.write_str(field.as_str())
     }
 }
 
@@ -329,12 +2298,11 @@ mod test {
         //   1 1 1 1 1
         //   0 0 1 * 1
         //   0 0 1 1 1
-        let mut game = Game::new(5, 5);
-        game.clear();
-        game.field_state[0] = CellState::Unknown(true);
-        game.field_state[4] = CellState::Unknown(true);
-        game.field_state[5] = CellState::Unknown(true);
-        game.field_state[18] = CellState::Unknown(true);
+        let mut layout = vec![false; 25];
+        for index in [0, 4, 5, 18] {
+            layout[index] = true;
+        }
+        let mut game = Game::from_layout(5, 5, &layout);
         assert_eq!(0, game.neighbor_count(2, 0));
         game.uncover(2, 0);
         assert_eq!(CellState::Known(false), game.field_state[7]);
@@ -369,19 +2337,655 @@ mod test {
         assert_eq!(CellState::Counted(1), game.field_state[7]);
     }
 
+    #[test]
+    pub fn test_density() {
+        assert_eq!(38, Game::default_mines(16, 16));
+        let expert = Density::default().mines(30, 16);
+        assert_eq!(Game::default_mines(30, 16), expert);
+        assert_eq!(Ok(Density::default()), "curve".parse());
+        assert_eq!(Ok(Density::Fixed(12)), "12".parse());
+        let percent: Density = "15%".parse().unwrap();
+        assert_eq!(38, percent.mines(16, 16));
+        let curve: Density = "curve 0 0.1 2".parse().unwrap();
+        assert_eq!(27, curve.mines(16, 16));
+        assert_eq!(Ok(curve), curve.to_string().parse());
+        assert_eq!(27, Game::with_density(16, 16, curve).total());
+        assert_eq!(8, Game::with_density(3, 3, Density::Fixed(40)).total());
+        for invalid in ["", "many", "-1", "0%", "100%", "curve 1 2", "curve a b c"] {
+            assert!(invalid.parse::<Density>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    pub fn test_largest_board() {
+        // the last cell of the largest square board can be played
+        let mut layout = vec![false; 181 * 181];
+        layout[0] = true;
+        let mut game = Game::from_layout(181, 181, &layout);
+        assert_eq!(GameState::Won, game.uncover(180, 180));
+    }
+
+    #[test]
+    #[should_panic(expected = "too many cells")]
+    pub fn test_board_too_large() {
+        Game::with_mines(182, 182, 10);
+    }
+
+    #[test]
+    pub fn test_distance2() {
+        // * . . . .
+        // . . . . .
+        // . . 2 . .
+        // . . . . .
+        // . . . . *
+        let mut layout = vec![false; 25];
+        layout[0] = true;
+        layout[24] = true;
+        let mut game = Game::from_layout(5, 5, &layout);
+        game.set_neighborhood(Neighborhood::Distance2);
+        game.uncover(2, 2);
+        assert_eq!(CellState::Counted(2), game.cell_state(2, 2));
+        assert_eq!(CellState::Unknown(false), game.cell_state(3, 2));
+        // the zero at the corner opens the 3x3 block it counts
+        game.uncover(4, 0);
+        assert_eq!(CellState::Known(false), game.cell_state(4, 0));
+        assert_eq!(CellState::Counted(1), game.cell_state(2, 0));
+        assert_eq!(CellState::Counted(1), game.cell_state(3, 2));
+
+        let mut layout = vec![true; 25];
+        layout[12] = false;
+        let mut game = Game::from_layout(5, 5, &layout);
+        game.set_neighborhood(Neighborhood::Distance2);
+        assert_eq!(GameState::Won, game.uncover(2, 2));
+        assert_eq!(CellState::Counted(24), game.cell_state(2, 2));
+        assert_eq!(24, Neighborhood::Distance2.max_count());
+        assert_eq!(8, Neighborhood::Adjacent.max_count());
+    }
+
+    #[test]
+    fn test_chord() {
+        //   * 1 0
+        //   1 1 0
+        //   0 0 0
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(1, 1);
+        // no flag yet so nothing happens
+        assert_eq!(GameState::Playing, game.chord(1, 1));
+        assert_eq!(CellState::Unknown(false), game.field_state[2]);
+        game.flag(0, 0);
+        assert_eq!(GameState::Won, game.chord(1, 1));
+        assert_eq!(CellState::Flagged(true), game.field_state[0]);
+        assert_eq!(CellState::Counted(1), game.field_state[1]);
+        assert_eq!(CellState::Known(false), game.field_state[8]);
+    }
+
+    #[test]
+    fn test_chord_wrong_flag() {
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(1, 1);
+        game.flag(1, 0);
+        assert_eq!(GameState::Lost, game.chord(1, 1));
+        assert_eq!(CellState::Known(true), game.field_state[0]);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.flag(0, 0);
+        // flagging a counted cell does not change the board
+        game.uncover(1, 1);
+        game.flag(1, 1);
+        assert_eq!(Some(Move::Uncover(1, 1)), game.undo());
+        assert_eq!(CellState::Unknown(false), game.field_state[4]);
+        assert_eq!(Some(Move::Flag(0, 0)), game.undo());
+        assert_eq!(1, game.est_remaining());
+        assert_eq!(None, game.undo());
+        assert_eq!(Some(Move::Flag(0, 0)), game.redo());
+        assert_eq!(CellState::Flagged(true), game.field_state[0]);
+        assert_eq!(0, game.est_remaining());
+        // a new move drops the redo history
+        game.uncover(2, 2);
+        assert_eq!(None, game.redo());
+        assert_eq!(GameState::Won, game.state());
+        assert_eq!(Some(Move::Uncover(2, 2)), game.undo());
+        assert_eq!(GameState::Playing, game.state());
+    }
+
+    #[test]
+    fn test_undo_finished() {
+        // . * . .
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        game.uncover(3, 0);
+        game.undo();
+        assert!(!game.practice());
+        game.redo();
+        assert_eq!(GameState::Lost, game.uncover(1, 0));
+        game.undo();
+        assert!(game.practice());
+        assert_eq!(GameState::Won, game.uncover(0, 0));
+        assert!(game.practice());
+        game.reset();
+        assert!(!game.practice());
+    }
+
+    #[test]
+    fn test_reset_with_seed() {
+        let mut game = Game::new(10, 10);
+        let mut other = Game::new(10, 10);
+        game.reset_with_seed(42);
+        other.reset_with_seed(42);
+        assert_eq!(Some(42), game.seed());
+        assert_eq!(game.field_state, other.field_state);
+        other.reset_with_seed(43);
+        assert_ne!(game.field_state, other.field_state);
+    }
+
     #[test]
     fn test_game_state() {
-        let mut game = Game::new(5, 5);
+        let mut layout = vec![false; 25];
+        layout[0] = true;
+        layout[24] = true;
+        let mut game = Game::from_layout(5, 5, &layout);
         assert_eq!(GameState::Initial, game.state);
-        game.clear();
-        game.field_state[24] = CellState::Unknown(true);
+        assert_eq!(23, game.safe_cells_remaining());
         let state = game.uncover(1, 1);
         assert_eq!(GameState::Playing, state);
+        assert_eq!(22, game.safe_cells_remaining());
 
-        game.field_state[0] = CellState::Unknown(true);
         let state = game.uncover(0, 0);
         assert_eq!(GameState::Lost, state);
+        assert_eq!(22, game.safe_cells_remaining());
         game.reset();
         assert_eq!(GameState::Initial, game.state);
+
+        // uncovering the last safe cell wins, flags or not
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        game.flag(1, 0);
+        assert_eq!(GameState::Playing, game.uncover(3, 0));
+        assert_eq!(1, game.safe_cells_remaining());
+        assert_eq!(GameState::Won, game.uncover(0, 0));
+        assert_eq!(0, game.safe_cells_remaining());
+        game.undo();
+        assert_eq!(
+            (GameState::Playing, 1),
+            (game.state, game.safe_cells_remaining())
+        );
+    }
+
+    #[test]
+    fn test_moves_after_end() {
+        // . * . .
+        let layout = [false, true, false, false];
+        let mut game = Game::from_layout(4, 1, &layout);
+        game.uncover(3, 0);
+        assert_eq!(GameState::Won, game.uncover(0, 0));
+        let clicks = game.clicks();
+        // a won game stays won whatever is clicked
+        assert_eq!(GameState::Won, game.uncover(2, 0));
+        game.flag(1, 0);
+        assert_eq!(GameState::Won, game.uncover(1, 0));
+        assert_eq!(CellState::Unknown(true), game.cell_state(1, 0));
+        assert_eq!((clicks, 2), (game.clicks(), game.moves().len()));
+
+        let mut game = Game::from_layout(4, 1, &layout);
+        game.uncover(3, 0);
+        assert_eq!(GameState::Lost, game.uncover(1, 0));
+        // and a lost game stays lost
+        game.flag(0, 0);
+        game.question(0, 0);
+        assert_eq!(GameState::Lost, game.uncover(0, 0));
+        assert_eq!(GameState::Lost, game.chord(2, 0));
+        assert_eq!(CellState::Unknown(false), game.cell_state(0, 0));
+        assert_eq!(2, game.moves().len());
+    }
+
+    #[test]
+    pub fn test_difficulty() {
+        let game = Game::with_difficulty(Difficulty::Expert);
+        assert_eq!((30, 16, 99), (game.width(), game.height(), game.total()));
+        assert_eq!(Ok(Difficulty::Intermediate), "intermediate".parse());
+        let mut config = game.config();
+        config.mines = 98;
+        assert_eq!(
+            Some(Difficulty::Expert),
+            Difficulty::from_config(game.config())
+        );
+        assert_eq!(None, Difficulty::from_config(config));
+    }
+
+    #[test]
+    pub fn test_random_moves_keep_invariants() {
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let width = rng.gen_range(1..12);
+            let height = rng.gen_range(1..12);
+            let mines = rng.gen_range(0..width as u16 * height as u16);
+            let mut game = Game::with_mines(width, height, mines);
+            game.reset_with_seed(seed);
+            for _ in 0..100 {
+                let x = rng.gen_range(0..width);
+                let y = rng.gen_range(0..height);
+                match rng.gen_range(0..8) {
+                    0 | 1 => {
+                        game.uncover(x, y);
+                    }
+                    2 => game.flag(x, y),
+                    3 => game.question(x, y),
+                    4 => game.set_unknown(x, y),
+                    5 => {
+                        game.chord(x, y);
+                    }
+                    6 => {
+                        game.undo();
+                    }
+                    _ => {
+                        game.redo();
+                    }
+                }
+                if let Err(message) = game.check_invariants() {
+                    panic!("seed {}: {}\n{}", seed, message, game);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_reset_reuses_board() {
+        let mut game = Game::with_mines(180, 180, 100);
+        game.reset_with_seed(1);
+        let cells = game.field_state.as_ptr();
+        for seed in 2..5 {
+            game.reset_with_seed(seed);
+            game.uncover(90, 90);
+            game.clear();
+            assert_eq!(cells, game.field_state.as_ptr());
+        }
+        assert_eq!(180 * 180, game.field_state.len());
+    }
+
+    #[test]
+    pub fn test_undo_keeps_changes() {
+        let mut layout = vec![false; 180 * 180];
+        layout[0] = true;
+        layout[1] = true;
+        layout[180] = true;
+        let mut game = Game::from_layout(180, 180, &layout);
+        game.flag(0, 0);
+        assert_eq!(1, game.undo[0].1.cells.len());
+        assert_eq!(&[(0, 0)], game.changed());
+        // the opening around the mines is one move of the cells it uncovered
+        game.uncover(179, 179);
+        assert_eq!(180 * 180 - 3, game.undo[1].1.cells.len());
+        assert_eq!(Some(Move::Uncover(179, 179)), game.undo());
+        assert_eq!(180 * 180 - 3, game.changed().len());
+        assert_eq!((2, CellState::Counted(1), false), game.redo[0].1.cells[0]);
+        // the mines shown after a loss are covered again with the losing move
+        game.uncover(1, 0);
+        game.show_mined();
+        assert_eq!(CellState::Known(true), game.cell_state(0, 1));
+        assert_eq!(Some(Move::Uncover(1, 0)), game.undo());
+        assert_eq!(CellState::Flagged(true), game.cell_state(0, 0));
+        assert_eq!(CellState::Unknown(true), game.cell_state(1, 0));
+        assert_eq!(CellState::Unknown(true), game.cell_state(0, 1));
+        assert_eq!(GameState::Playing, game.state());
+        // a restored snapshot leaves nothing to undo
+        let snapshot = game.snapshot();
+        game.uncover(5, 5);
+        game.restore(&snapshot);
+        assert_eq!(CellState::Unknown(false), game.cell_state(5, 5));
+        assert_eq!((None, None), (game.undo(), game.redo()));
+    }
+
+    #[test]
+    pub fn test_clicks() {
+        // 1 * 1 0 0
+        let mut game = Game::from_layout(5, 1, &[false, true, false, false, false]);
+        game.flag(1, 0);
+        game.uncover(4, 0);
+        // chording and uncovering a count with nothing left to uncover
+        // change nothing
+        game.chord(2, 0);
+        game.uncover(2, 0);
+        game.undo();
+        assert_eq!(
+            Clicks {
+                uncovers: 2,
+                chords: 1,
+                flags: 1,
+                wasted: 2
+            },
+            game.clicks()
+        );
+        assert_eq!(4, game.clicks().total());
+        game.clear();
+        assert_eq!(Clicks::default(), game.clicks());
+    }
+
+    #[test]
+    pub fn test_no_flags() {
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.set_no_flags(true);
+        game.flag(2, 0);
+        game.apply(Move::Question(1, 0));
+        assert_eq!(CellState::Unknown(true), game.cell_state(2, 0));
+        assert_eq!(CellState::Unknown(false), game.cell_state(1, 0));
+        assert_eq!(Clicks::default(), game.clicks());
+        assert_eq!(None, game.undo());
+        game.reset();
+        assert!(game.no_flags());
+    }
+
+    #[test]
+    pub fn test_restart() {
+        let mines = [false, false, true, false, true, false];
+        let mut game = Game::from_layout(3, 2, &mines);
+        game.flag(2, 0);
+        game.uncover(0, 0);
+        game.uncover(1, 1);
+        assert_eq!(GameState::Lost, game.state());
+        game.show_mined();
+        game.restart();
+        assert_eq!(GameState::Initial, game.state());
+        assert_eq!(Duration::ZERO, game.elapsed());
+        assert_eq!(Clicks::default(), game.clicks());
+        assert_eq!((2, 2), (game.est_remaining(), game.act_remaining()));
+        assert!(game.practice() && game.undo().is_none());
+        for (index, &mined) in mines.iter().enumerate() {
+            let (x, y) = (index as i16 % 3, index as i16 / 3);
+            assert_eq!(CellState::Unknown(mined), game.cell_state(x, y));
+        }
+        assert_eq!(Ok(()), game.check_invariants());
+    }
+
+    #[test]
+    pub fn test_symmetry() {
+        for symmetry in Symmetry::ALL {
+            // odd and even sides, and an odd count that needs a center
+            for (width, height, mines) in [(9, 9, 10), (8, 8, 11), (9, 8, 11), (3, 1, 2)] {
+                let mut game = Game::with_mines(width, height, mines);
+                game.set_symmetry(symmetry);
+                game.reset_with_seed(7);
+                let total = game.act_remaining();
+                assert!(total >= mines && total <= mines + 1);
+                assert_eq!(total, game.est_remaining());
+                for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+                    let (ix, iy) = symmetry.image(width, height, x, y);
+                    assert_eq!(game.cell_state(x, y), game.cell_state(ix, iy));
+                }
+                assert_eq!(Ok(()), game.check_invariants());
+            }
+            assert_eq!(Ok(symmetry), symmetry.to_string().parse());
+        }
+        // the center of an odd board is kept for an odd count
+        for seed in 0..20 {
+            for mines in [23, 24] {
+                let mut game = Game::with_mines(5, 5, mines);
+                game.set_symmetry(Symmetry::Rotational);
+                game.reset_with_seed(seed);
+                assert_eq!(mines, game.total());
+                assert_eq!(mines % 2 == 1, game.cell_state(2, 2).is_mined());
+            }
+        }
+        // no symmetry keeps the layout of a seed
+        let mut game = Game::with_mines(9, 9, 10);
+        let mut symmetric = Game::with_mines(9, 9, 10);
+        symmetric.set_symmetry(Symmetry::Rotational);
+        symmetric.set_symmetry(Symmetry::None);
+        game.reset_with_seed(3);
+        symmetric.reset_with_seed(3);
+        for (x, y) in (0..9).flat_map(|y| (0..9).map(move |x| (x, y))) {
+            assert_eq!(game.cell_state(x, y), symmetric.cell_state(x, y));
+        }
+    }
+
+    #[test]
+    pub fn test_zones() {
+        let hard_right = Zone {
+            x: 8,
+            y: 0,
+            width: 8,
+            height: 16,
+            weight: 300,
+        };
+        let mut config = Difficulty::Intermediate.config();
+        config.zones = vec![hard_right];
+        // mines left and right
+        let mut mines = [0; 2];
+        for seed in 0..20 {
+            let mut game = Game::with_config(config.clone());
+            game.reset_with_seed(seed);
+            assert_eq!(40, game.act_remaining());
+            for (x, y) in (0..16).flat_map(|y| (0..16).map(move |x| (x, y))) {
+                if game.cell_state(x, y).is_mined() {
+                    mines[hard_right.contains(x, y) as usize] += 1;
+                }
+            }
+        }
+        assert!(mines[1] > 2 * mines[0]);
+        // a zone no mine may be laid in, over a part of one that may
+        let mut game = Game::with_mines(4, 4, 10);
+        game.set_zones(vec![hard_right, "0,0,4,3,0%".parse().unwrap()]);
+        game.reset_with_seed(1);
+        assert_eq!(4, game.act_remaining());
+        assert!((0..4).all(|x| game.cell_state(x, 3).is_mined()));
+        assert_eq!(Ok(()), game.check_invariants());
+        assert_eq!(config, Game::with_config(config.clone()).config());
+        assert_eq!(Ok(hard_right), hard_right.to_string().parse());
+        assert!("1,2,3".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    pub fn test_handicap() {
+        let covered = |game: &Game| {
+            (0..game.height())
+                .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+                .filter(|&(x, y)| game.cell_state(x, y).is_covered())
+                .count()
+        };
+        let mut game = Game::with_mines(9, 9, 10);
+        game.set_handicap(Some(Handicap::Cells(3)));
+        game.reset_with_seed(5);
+        assert!(covered(&game) <= 81 - 3);
+        assert_eq!(GameState::Initial, game.state());
+        assert_eq!(Duration::ZERO, game.elapsed());
+        assert!(game.undo().is_none());
+        assert_eq!(Ok(()), game.check_invariants());
+        game.set_handicap(Some(Handicap::Opening));
+        game.reset_with_seed(5);
+        assert!((0..9)
+            .flat_map(|y| (0..9).map(move |x| (x, y)))
+            .any(|(x, y)| game.cell_state(x, y) == CellState::Known(false)));
+        // the handicap never clears the board
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.set_handicap(Some(Handicap::Cells(5)));
+        game.reset_with_seed(1);
+        assert_eq!((GameState::Initial, 2), (game.state(), covered(&game)));
+        assert_eq!(Ok(Handicap::Opening), "opening".parse());
+        assert_eq!(Ok(Handicap::Cells(4)), "4".parse());
+        assert!("lots".parse::<Handicap>().is_err());
+    }
+
+    #[test]
+    pub fn test_blind() {
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.set_blind(Some(Duration::ZERO));
+        game.uncover(1, 0);
+        assert!(game.faded(1, 0) && !game.faded(0, 0));
+        assert_eq!(1, game.faded_count());
+        game.undo();
+        assert!(!game.faded(1, 0));
+        game.redo();
+        assert!(game.faded(1, 0));
+        // the numbers show again once the game is over
+        game.uncover(2, 0);
+        assert_eq!(0, game.faded_count());
+        game.reset();
+        assert_eq!(Some(Duration::ZERO), game.blind());
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.set_blind(Some(Duration::from_secs(60)));
+        game.uncover(1, 0);
+        assert!(!game.faded(1, 0));
+    }
+
+    #[test]
+    pub fn test_contradictions() {
+        let mut game = Game::from_layout(3, 2, &[false, true, false, false, false, false]);
+        game.uncover(0, 0);
+        assert_eq!(&[(0, 0)], game.changed());
+        game.flag(1, 0);
+        assert_eq!(&[(0, 0), (1, 0)], game.changed());
+        assert!(game.contradictions().is_empty());
+        game.flag(1, 1);
+        assert!(game.contradicted(0, 0));
+        assert_eq!(&[(0, 0)], game.contradictions());
+        assert_eq!(&[(0, 0), (1, 1)], game.changed());
+        game.undo();
+        assert!(game.contradictions().is_empty());
+        assert_eq!(&[(0, 0), (1, 1)], game.changed());
+        game.redo();
+        let cells = (0..6).map(|i| game.cell_state(i % 3, i / 3)).collect();
+        let restored = Game::from_cells(3, 2, cells, GameState::Playing, Duration::ZERO);
+        assert_eq!(&[(0, 0)], restored.contradictions());
+    }
+
+    #[test]
+    pub fn test_auto_open() {
+        // the 1 on the left proves the mine, the 1 beside it then proves the
+        // cell after it safe
+        let mut game = Game::from_layout(5, 1, &[false, true, false, false, true]);
+        game.set_auto_open(true);
+        game.uncover(0, 0);
+        assert_eq!(CellState::Unknown(false), game.cell_state(3, 0));
+        game.uncover(2, 0);
+        assert_eq!(CellState::Counted(1), game.cell_state(3, 0));
+        assert_eq!(GameState::Won, game.state());
+        assert_eq!(2, game.clicks().uncovers);
+        game.undo();
+        assert_eq!(CellState::Unknown(false), game.cell_state(3, 0));
+        game.reset();
+        assert!(game.auto_open());
+    }
+
+    #[test]
+    pub fn test_auto_flag() {
+        // the 1 on the left proves the mine beside it, nothing proves the
+        // last cell
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        game.set_auto_flag(true);
+        game.uncover(0, 0);
+        assert_eq!(CellState::Flagged(true), game.cell_state(1, 0));
+        assert!(game.auto_flagged(1, 0) && !game.auto_flagged(0, 0));
+        assert_eq!(CellState::Unknown(false), game.cell_state(2, 0));
+        assert_eq!((1, 0), (game.clicks().uncovers, game.clicks().flags));
+        assert!(game.assisted());
+        // the player's own flag on the cell is theirs
+        game.question(1, 0);
+        game.flag(1, 0);
+        assert!(!game.auto_flagged(1, 0));
+        game.undo();
+        game.undo();
+        assert!(game.auto_flagged(1, 0));
+        game.undo();
+        assert_eq!(CellState::Unknown(true), game.cell_state(1, 0));
+        game.set_no_flags(true);
+        game.uncover(0, 0);
+        assert_eq!(CellState::Unknown(true), game.cell_state(1, 0));
+    }
+
+    #[test]
+    pub fn test_hints() {
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.set_hint_budget(Some(1));
+        assert_eq!(None, game.hint());
+        game.uncover(0, 1);
+        game.uncover(1, 1);
+        let before = game.elapsed();
+        assert!(game.hint().is_some());
+        assert!(game.elapsed() >= before + HINT_PENALTY);
+        assert_eq!((1, Some(0)), (game.hints(), game.hints_left()));
+        assert_eq!(None, game.hint());
+        game.undo();
+        assert_eq!(1, game.hints());
+        assert!(game.elapsed() >= HINT_PENALTY);
+        // the penalty stays on the clock once it stops
+        game.redo();
+        game.uncover(3, 1);
+        assert_eq!(GameState::Won, game.state());
+        assert!(game.elapsed() >= HINT_PENALTY);
+        game.reset();
+        assert_eq!((0, Some(1)), (game.hints(), game.hints_left()));
+        assert_eq!(Duration::ZERO, game.elapsed());
+    }
+
+    #[test]
+    pub fn test_flag_penalty() {
+        let mut game = Game::from_layout(3, 1, &[true, false, false]);
+        game.set_flag_penalty(true);
+        game.flag(1, 0);
+        assert_eq!(CellState::Unknown(false), game.cell_state(1, 0));
+        assert_eq!((GameState::Playing, 1), (game.state(), game.wrong_flags()));
+        assert!(game.elapsed() >= FLAG_PENALTY);
+        assert_eq!((1, 1), (game.clicks().flags, game.clicks().wasted));
+        assert!(game.moves().is_empty());
+        game.flag(0, 0);
+        assert_eq!(CellState::Flagged(true), game.cell_state(0, 0));
+        game.undo();
+        assert_eq!(1, game.wrong_flags());
+        assert!(game.elapsed() >= FLAG_PENALTY);
+        game.restart();
+        assert_eq!(0, game.wrong_flags());
+        assert_eq!(Duration::ZERO, game.elapsed());
+        // flags are not checked without the penalty
+        game.set_flag_penalty(false);
+        game.flag(1, 0);
+        assert_eq!(CellState::Flagged(false), game.cell_state(1, 0));
+    }
+
+    #[test]
+    pub fn test_exploded() {
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.uncover(2, 0);
+        assert_eq!(Some((2, 0)), game.exploded());
+        game.undo();
+        assert_eq!(None, game.exploded());
+        game.redo();
+        assert_eq!(Some((2, 0)), game.exploded());
+    }
+
+    #[test]
+    pub fn test_annotations() {
+        let mut game = Game::from_layout(3, 2, &[false, false, false, false, true, true]);
+        assert_eq!(None, game.annotation(0, 1));
+        assert_eq!(Some(0), game.annotate(0, 1));
+        assert_eq!(Some(1), game.annotate(0, 1));
+        assert_eq!(Some(0), game.annotate(1, 1));
+        assert_eq!(&[(1, 1)], game.changed());
+        assert_eq!(vec![(0, 1, 1), (1, 1, 0)], game.annotations());
+        for _ in 1..ANNOTATIONS {
+            game.annotate(0, 1);
+        }
+        assert_eq!(None, game.annotation(0, 1));
+
+        // a flagged cell keeps its mark, an uncovered one loses it
+        game.flag(1, 1);
+        assert_eq!(Some(0), game.annotation(1, 1));
+        game.annotate(0, 0);
+        game.uncover(0, 0);
+        assert_eq!(None, game.annotation(0, 0));
+        assert_eq!(None, game.annotate(0, 0));
+        assert_eq!(vec![(1, 1, 0)], game.annotations());
+        game.undo();
+        assert_eq!(Some(0), game.annotation(0, 0));
+
+        game.reset_with_seed(1);
+        assert!(game.annotations().is_empty());
     }
 }
@@ -1,16 +1,33 @@
-use std::sync::Once;
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    rc::Rc,
+    sync::Once,
+    time::{Duration, Instant},
+};
 
 use windows::{
-    core::{Result, HSTRING},
+    core::{Error, Result, HSTRING, PCWSTR, PWSTR},
     Win32::{
-        Foundation::{COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{
+            COLORREF, D2DERR_RECREATE_TARGET, HANDLE, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT,
+            POINT, RECT, WPARAM,
+        },
         Graphics::{
             Direct2D::{
-                Common::{D2D1_COLOR_F, D2D_POINT_2F, D2D_RECT_F},
-                ID2D1Bitmap, ID2D1Factory1, ID2D1HwndRenderTarget, ID2D1SolidColorBrush,
-                ID2D1StrokeStyle1, D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
-                D2D1_DRAW_TEXT_OPTIONS_NONE, D2D1_HWND_RENDER_TARGET_PROPERTIES,
-                D2D1_PRESENT_OPTIONS, D2D1_RENDER_TARGET_PROPERTIES,
+                Common::{
+                    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COLOR_F, D2D1_PIXEL_FORMAT, D2D_POINT_2F,
+                    D2D_RECT_F, D2D_SIZE_F,
+                },
+                ID2D1Bitmap, ID2D1Factory1, ID2D1RenderTarget, ID2D1SolidColorBrush,
+                ID2D1StrokeStyle1, D2D1_ANTIALIAS_MODE_ALIASED, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                D2D1_BITMAP_INTERPOLATION_MODE_NEAREST_NEIGHBOR, D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+                D2D1_BITMAP_OPTIONS_CPU_READ, D2D1_BITMAP_PROPERTIES1,
+                D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE, D2D1_DRAW_TEXT_OPTIONS_NONE,
+                D2D1_ELLIPSE, D2D1_MAP_OPTIONS_READ, D2D1_TEXT_ANTIALIAS_MODE,
+                D2D1_TEXT_ANTIALIAS_MODE_ALIASED, D2D1_TEXT_ANTIALIAS_MODE_CLEARTYPE,
+                D2D1_TEXT_ANTIALIAS_MODE_DEFAULT, D2D1_TEXT_ANTIALIAS_MODE_GRAYSCALE,
             },
             DirectWrite::{
                 DWriteCreateFactory, IDWriteFactory, IDWriteTextFormat, DWRITE_FACTORY_TYPE_SHARED,
@@ -18,44 +35,434 @@ use windows::{
                 DWRITE_MEASURING_MODE_NATURAL, DWRITE_PARAGRAPH_ALIGNMENT_CENTER,
                 DWRITE_TEXT_ALIGNMENT_CENTER,
             },
-            Gdi::{BeginPaint, CreateSolidBrush, EndPaint, InvalidateRect, PAINTSTRUCT},
-            Imaging::IWICImagingFactory,
+            Dxgi::{
+                Common::DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_ERROR_DEVICE_REMOVED,
+                DXGI_ERROR_DEVICE_RESET,
+            },
+            Gdi::{
+                BeginPaint, ClientToScreen, CreateSolidBrush, EndPaint, InvalidateRect,
+                ScreenToClient, PAINTSTRUCT,
+            },
+            Imaging::{GUID_WICPixelFormat32bppPBGRA, IWICBitmap, IWICImagingFactory},
         },
-        System::LibraryLoader::GetModuleHandleW,
-        UI::WindowsAndMessaging::{
-            CreateWindowExW, DefWindowProcW, GetClientRect, GetWindowLongPtrA, LoadCursorW,
-            RegisterClassW, SetWindowLongPtrA, CREATESTRUCTA, CS_HREDRAW, CS_VREDRAW,
-            CW_USEDEFAULT, GWLP_USERDATA, HMENU, IDC_ARROW, WINDOW_EX_STYLE, WM_CREATE, WM_DESTROY,
-            WM_LBUTTONUP, WM_PAINT, WM_RBUTTONUP, WNDCLASSW, WS_CHILDWINDOW, WS_CLIPSIBLINGS,
-            WS_VISIBLE,
+        System::{LibraryLoader::GetModuleHandleW, SystemServices::MK_CONTROL},
+        UI::{
+            Controls::{
+                TOOLTIPS_CLASSW, TTF_ABSOLUTE, TTF_TRACK, TTM_ADDTOOLW, TTM_TRACKACTIVATE,
+                TTM_TRACKPOSITION, TTM_UPDATETIPTEXTW, TTS_ALWAYSTIP, TTS_NOPREFIX, TTTOOLINFOW,
+            },
+            Input::KeyboardAndMouse::{
+                VK_DOWN, VK_END, VK_HOME, VK_LEFT, VK_NEXT, VK_PRIOR, VK_RIGHT, VK_UP,
+            },
+            WindowsAndMessaging::{
+                CreateWindowExW, DefWindowProcW, GetClientRect, GetCursorPos, GetMessageExtraInfo,
+                GetParent, GetWindowLongPtrA, KillTimer, LoadCursorW, RegisterClassW, RemovePropW,
+                SendMessageW, SetPropW, SetTimer, SetWindowLongPtrA, SetWindowPos, SetWindowTextW,
+                CREATESTRUCTA, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, GWLP_USERDATA, HMENU,
+                IDC_ARROW, SWP_NOMOVE, SWP_NOZORDER, WINDOW_STYLE, WM_CREATE, WM_DESTROY,
+                WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_PAINT,
+                WM_RBUTTONDOWN, WM_RBUTTONUP, WM_TIMER, WNDCLASSW, WS_CHILDWINDOW, WS_CLIPSIBLINGS,
+                WS_EX_NOREDIRECTIONBITMAP, WS_EX_TOPMOST, WS_POPUP, WS_VISIBLE,
+            },
         },
     },
 };
 
 use crate::{
-    direct2d::{create_brush, create_image_factory, create_style, load_bitmap},
-    game::{CellState, Game, GameState},
+    assets::{self, Source},
+    audio::{Audio, Category, Sound},
+    bindings::{Action, Bindings, Input},
+    checkpoint::Checkpoints,
+    composition::{Composition, Layer},
+    config::{RenderSettings, TextAntialias, TimingSettings},
+    cues::{self, Cues},
+    direct2d::{
+        create_brush, create_image_factory, create_style, load_bitmap, load_bitmap_from_memory,
+        Rendering,
+    },
+    drill::Pattern,
+    game::{
+        self, CellState, Clicks, Difficulty, Game, GameConfig, GameState, Handicap, Move,
+        Neighborhood, Symmetry, Zone, ANNOTATIONS,
+    },
+    ghost::GhostRace,
+    inspect,
+    patterns::{self, PatternHint},
+    render::{self, BoardRenderer, Overlay, Region},
+    replay, score,
+    skin::{Skin, COLORS},
+    snapshot,
+    solver::{self, Deduction, DeductionKind, Loss},
+    splits::Timer,
+    stats,
+    theme::ThemeFile,
+    versus::{Match, PLAYERS},
 };
+#[cfg(feature = "dev-tools")]
+use crate::{console, perf::FrameStats};
 
 static REGISTER_GAMEBOARD_WINDOW_CLASS: Once = Once::new();
 
-const CELL_WIDTH: f32 = 6.0 / 25.4;
-const CELL_HEIGHT: f32 = 6.0 / 25.4;
-const BOARD_COLOR: (f32, f32, f32) = (0.4, 0.4, 0.4);
-const CELL_COLOR: (f32, f32, f32) = (0.75, 0.75, 0.75);
-const CELL_HIGHLIGHT: (f32, f32, f32) = (1.0, 1.0, 1.0);
-const DEFAULT_COLOR: (f32, f32, f32) = (0.0, 0.0, 0.0);
-const NUM_BRUSH: [(f32, f32, f32); 7] = [
-    (0.0, 0.0, 0.5),
-    (0.0, 0.5, 0.0),
-    (0.5, 0.0, 0.0),
-    (0.35, 0.0, 0.7),
-    (0.25, 0.0, 0.0),
-    (0.0, 0.65, 1.0),
-    (0.0, 0.0, 0.0),
-];
-const MINE_FILE: &HSTRING = windows::core::h!("mine.png");
-const FLAG_FILE: &HSTRING = windows::core::h!("flag.png");
+// default cell size in millimeters
+const CELL_SIZE: f32 = 6.0;
+const MM_PER_INCH: f32 = 25.4;
+// opacity of the player tint over claimed cells
+const CLAIM_OPACITY: f32 = 0.35;
+// timer checking which practice cues are due, as often as the frame rate of
+// the render settings allows
+const CUE_TIMER: usize = 1;
+// the click counters take this many cells in the top right corner, the
+// score as many in the top left and the checkpoints twice as many in the
+// bottom right
+const HUD_CELLS: f32 = 4.0;
+// boards with more cells than this are drawn from cached chunk bitmaps
+const CACHED_CELLS: usize = 65_536;
+// the window shows at most this many columns and rows, larger boards scroll
+const VIEW_COLUMNS: f32 = 60.0;
+const VIEW_ROWS: f32 = 32.0;
+// timer hiding the warning about a refused chord or a hint
+const WARNING_TIMER: usize = 2;
+const WARNING_MS: u32 = 2000;
+const WARNING_FADE_SECONDS: f64 = 0.15;
+// steps the ghost of the best run along at the frame rate of the timed
+// effects
+const GHOST_TIMER: usize = 3;
+// the outlines of the ghost's reveals are faint so they do not read as hints
+const GHOST_OPACITY: f32 = 0.5;
+// the flags the solver placed are tinted in this color, leaving the flag
+// readable
+const AUTO_FLAG_TINT: (f32, f32, f32, f32) = (0.0, 0.55, 0.78, 0.35);
+// width of the outline around the cell with the keyboard focus
+const FOCUS_STROKE: f32 = 3.0;
+// timer acting on a finger held on a cell for the long press time
+const LONG_PRESS_TIMER: usize = 4;
+// redraws a blind board when a number fades or the pointer moves onto one,
+// at the frame rate of the timed effects
+const BLIND_TIMER: usize = 5;
+// moves the inspection tooltip to the focused cell or the one under the
+// pointer, at the frame rate of the timed effects
+const INSPECT_TIMER: usize = 6;
+// the extra information of mouse messages made from touch and pen input,
+// with the touch bit set for fingers
+const PEN_SIGNATURE_MASK: isize = 0xFFFF_FF00;
+const PEN_SIGNATURE: isize = 0xFF51_5700;
+const TOUCH_BIT: isize = 0x80;
+// the window property turning off the system press and hold, which would
+// otherwise make its own right click before the long press time
+const PEN_SERVICE_PROPERTY: PCWSTR = windows::core::w!("MicrosoftTabletPenServiceProperty");
+const DISABLE_PRESS_AND_HOLD: isize = 0x1;
+// opacity of the hidden mines in the debug view
+#[cfg(feature = "dev-tools")]
+const DEBUG_OPACITY: f32 = 0.4;
+const MINE_FILE: &str = "mine.png";
+const FLAG_FILE: &str = "flag.png";
+// the sprites built in, for when their files are nowhere to be found
+const MINE_IMAGE: &[u8] = include_bytes!("../mine.png");
+const FLAG_IMAGE: &[u8] = include_bytes!("../flag.png");
+// family of the counts unless a theme file names another
+const DEFAULT_FONT: &str = "San Serif";
+
+/// Colors a board is drawn with, as red, green and blue from 0 to 1, and the
+/// font of its counts.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Theme {
+    /// Background showing between the cells.
+    pub(crate) board: (f32, f32, f32),
+    /// Cell fill.
+    pub(crate) cell: (f32, f32, f32),
+    /// Raised edge of covered cells.
+    pub(crate) highlight: (f32, f32, f32),
+    /// Question marks.
+    pub(crate) text: (f32, f32, f32),
+    /// Counts from 1 to 7, 8 uses the color of 7.
+    pub(crate) numbers: [(f32, f32, f32); 7],
+    /// Tint of the cells each player claimed in a versus match.
+    pub(crate) players: [(f32, f32, f32); PLAYERS],
+    /// Numbers with more flags around them than their count.
+    pub(crate) contradiction: (f32, f32, f32),
+    /// Badges of the candidate marks, see [`Game::annotate`].
+    pub(crate) annotations: [(f32, f32, f32); ANNOTATIONS as usize],
+    /// Font family of the counts and question marks, [`DEFAULT_FONT`] when
+    /// not set.
+    pub(crate) font: Option<String>,
+}
+
+impl Theme {
+    /// The original grey board.
+    pub(crate) const CLASSIC: Theme = Theme {
+        board: (0.4, 0.4, 0.4),
+        cell: (0.75, 0.75, 0.75),
+        highlight: (1.0, 1.0, 1.0),
+        text: (0.0, 0.0, 0.0),
+        numbers: [
+            (0.0, 0.0, 0.5),
+            (0.0, 0.5, 0.0),
+            (0.5, 0.0, 0.0),
+            (0.35, 0.0, 0.7),
+            (0.25, 0.0, 0.0),
+            (0.0, 0.65, 1.0),
+            (0.0, 0.0, 0.0),
+        ],
+        players: [(0.9, 0.2, 0.2), (0.2, 0.4, 0.95)],
+        contradiction: (1.0, 0.0, 0.0),
+        annotations: [
+            (0.9, 0.75, 0.0),
+            (0.0, 0.65, 0.8),
+            (0.8, 0.0, 0.8),
+            (0.0, 0.65, 0.25),
+        ],
+        font: None,
+    };
+
+    /// Dark cells with light counts.
+    pub(crate) const DARK: Theme = Theme {
+        board: (0.1, 0.1, 0.1),
+        cell: (0.25, 0.25, 0.27),
+        highlight: (0.45, 0.45, 0.5),
+        text: (0.9, 0.9, 0.9),
+        numbers: [
+            (0.45, 0.65, 1.0),
+            (0.4, 0.85, 0.4),
+            (1.0, 0.45, 0.45),
+            (0.75, 0.55, 1.0),
+            (1.0, 0.7, 0.3),
+            (0.3, 0.85, 0.9),
+            (0.9, 0.9, 0.9),
+        ],
+        players: [(1.0, 0.35, 0.35), (0.35, 0.55, 1.0)],
+        contradiction: (1.0, 0.15, 0.15),
+        annotations: [
+            (1.0, 0.85, 0.2),
+            (0.3, 0.85, 1.0),
+            (1.0, 0.4, 1.0),
+            (0.35, 0.9, 0.45),
+        ],
+        font: None,
+    };
+
+    /// The theme with the colors a skin sets in place of its own.
+    pub(crate) fn with_skin(mut self, skin: &Skin) -> Theme {
+        for (name, color) in self.colors() {
+            if let Some(skinned) = skin.color(name) {
+                *color = skinned;
+            }
+        }
+        self
+    }
+
+    /// The theme with the colors and font a theme file sets in place of its
+    /// own.
+    pub(crate) fn with_file(mut self, file: &ThemeFile) -> Theme {
+        for (name, color) in self.colors() {
+            if let Some(themed) = file.color(name) {
+                *color = themed;
+            }
+        }
+        if file.font.is_some() {
+            self.font.clone_from(&file.font);
+        }
+        self
+    }
+
+    /// A theme file setting every color and the font of the theme, to share
+    /// it with other players.
+    pub(crate) fn to_file(&self, name: &str) -> ThemeFile {
+        let mut theme = self.clone();
+        let mut file = ThemeFile::new(name);
+        for (name, color) in theme.colors() {
+            file.set_color(name, *color);
+        }
+        file.font = theme.font;
+        file
+    }
+
+    /// Every color by the name skins and theme files give it.
+    fn colors(&mut self) -> Vec<(&'static str, &mut (f32, f32, f32))> {
+        let mut colors = vec![
+            &mut self.board,
+            &mut self.cell,
+            &mut self.highlight,
+            &mut self.text,
+        ];
+        colors.extend(self.numbers.iter_mut());
+        colors.extend(self.players.iter_mut());
+        colors.push(&mut self.contradiction);
+        colors.extend(self.annotations.iter_mut());
+        COLORS.into_iter().zip(colors).collect()
+    }
+}
+
+/// Optional help for the player, all off by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Assists {
+    /// Clicking a number whose flags are all placed uncovers its neighbors.
+    pub(crate) chording: bool,
+    /// The solver's next deduction is outlined on the board.
+    pub(crate) hints: bool,
+    /// Numbers with more flags around them than their count are drawn in red.
+    pub(crate) contradictions: bool,
+    /// Chords the solver can prove careless, a flag on a safe cell or a mine
+    /// left to uncover, are refused with a warning.
+    pub(crate) safe_chords: bool,
+    /// Cells a single number proves safe are uncovered after every move, the
+    /// games are recorded as assisted.
+    pub(crate) auto_open: bool,
+    /// Mines the solver proves are flagged after every move and tinted, the
+    /// games are recorded as assisted.
+    pub(crate) auto_flag: bool,
+}
+
+/// The tooltip telling about the focused cell or the one under the pointer,
+/// with its chance of being a mine when practicing.
+struct Inspector {
+    tooltip: HWND,
+    // the board window the tooltip's tool belongs to
+    owner: HWND,
+    // the text shown, none while the tooltip is hidden
+    shown: Option<String>,
+    // mine probabilities of a practice game and the clicks they were
+    // worked out after
+    probabilities: Vec<f32>,
+    clicks: Option<Clicks>,
+}
+
+impl Inspector {
+    /// Creates the tooltip of a board, hidden until a cell is inspected.
+    fn new(owner: HWND, instance: HMODULE) -> Option<Self> {
+        let tooltip = unsafe {
+            CreateWindowExW(
+                WS_EX_TOPMOST,
+                TOOLTIPS_CLASSW,
+                PCWSTR::null(),
+                WS_POPUP | WINDOW_STYLE(TTS_NOPREFIX | TTS_ALWAYSTIP),
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                owner,
+                HMENU(0),
+                instance,
+                None,
+            )
+        };
+        if tooltip.0 == 0 {
+            tracing::warn!("unable to create the inspection tooltip");
+            return None;
+        }
+        let inspector = Inspector {
+            tooltip,
+            owner,
+            shown: None,
+            probabilities: Vec::new(),
+            clicks: None,
+        };
+        inspector.send(TTM_ADDTOOLW, WPARAM(0), &mut Vec::new());
+        Some(inspector)
+    }
+
+    /// Shows `text` with its top left corner at `point` on the screen, or
+    /// hides the tooltip.
+    fn show(&mut self, text: Option<String>, point: POINT) {
+        if text == self.shown {
+            return;
+        }
+        match &text {
+            Some(shown) => {
+                let mut wide: Vec<u16> = shown.encode_utf16().chain([0]).collect();
+                self.send(TTM_UPDATETIPTEXTW, WPARAM(0), &mut wide);
+                // the x coordinate in the low word, the y in the high word
+                let position = (point.y as isize) << 16 | (point.x as isize & 0xFFFF);
+                unsafe {
+                    SendMessageW(self.tooltip, TTM_TRACKPOSITION, WPARAM(0), LPARAM(position))
+                };
+                self.send(TTM_TRACKACTIVATE, WPARAM(1), &mut wide);
+            }
+            None => self.send(TTM_TRACKACTIVATE, WPARAM(0), &mut Vec::new()),
+        }
+        self.shown = text;
+    }
+
+    // sends a message about the tool, with `text` as its text when it is
+    // not empty
+    fn send(&self, message: u32, wparam: WPARAM, text: &mut Vec<u16>) {
+        let info = TTTOOLINFOW {
+            cbSize: std::mem::size_of::<TTTOOLINFOW>() as u32,
+            uFlags: TTF_TRACK | TTF_ABSOLUTE,
+            hwnd: self.owner,
+            lpszText: match text.is_empty() {
+                true => PWSTR::null(),
+                false => PWSTR(text.as_mut_ptr()),
+            },
+            ..Default::default()
+        };
+        let info = LPARAM(&info as *const TTTOOLINFOW as isize);
+        unsafe { SendMessageW(self.tooltip, message, wparam, info) };
+    }
+}
+
+/// Called after every move the player makes on a board, the running click
+/// counts are in [`Game::clicks`].
+pub(crate) type Observer<'a> = Box<dyn FnMut(&Game, Move) + 'a>;
+
+/// The practice cues of a board and the sounds they play.
+struct CuePlayer {
+    cues: Cues,
+    best: Option<Duration>,
+    tick: Sound,
+    heartbeat: Sound,
+    // mine probabilities as of the last move, row by row
+    probabilities: Vec<f32>,
+}
+
+impl CuePlayer {
+    fn new(audio: &Rc<Audio>, game: &Game, skin: Option<&Skin>) -> Result<Self> {
+        let best = best_time(game.config());
+        // a skin may replace the generated sounds
+        let sound = |name, samples| match skin.and_then(|skin| skin.sound(name)) {
+            Some(skinned) => Sound::new(
+                audio,
+                Category::Effects,
+                skinned.samples.clone(),
+                skinned.sample_rate,
+            ),
+            None => Sound::new(audio, Category::Effects, samples, cues::SAMPLE_RATE),
+        };
+        Ok(CuePlayer {
+            cues: Cues::new(best),
+            best,
+            tick: sound("tick", cues::tick())?,
+            heartbeat: sound("heartbeat", cues::heartbeat())?,
+            probabilities: solver::probabilities(game),
+        })
+    }
+
+    /// Starts the cues over for a new game.
+    fn restart(&mut self, game: &Game) {
+        self.cues = Cues::new(self.best);
+        self.probabilities = solver::probabilities(game);
+    }
+}
+
+/// Plays the tone of the cell the keyboard focus moves to, see
+/// [`cues::cell_tone`].
+struct Tones {
+    audio: Rc<Audio>,
+    // the tone playing, cut off by the next one
+    playing: Option<Sound>,
+}
+
+impl Tones {
+    fn play(&mut self, cell: CellState) {
+        // destroying the voice of the last tone stops it
+        self.playing = None;
+        let samples = cues::cell_tone(cell);
+        let sound = Sound::new(&self.audio, Category::Effects, samples, cues::SAMPLE_RATE);
+        match sound.and_then(|sound| sound.play().map(|_| sound)) {
+            Ok(sound) => self.playing = Some(sound),
+            Err(error) => tracing::warn!(%error, "unable to play a cell tone"),
+        }
+    }
+}
 
 pub(crate) enum BoardLevel {
     Easy,
@@ -63,57 +470,372 @@ pub(crate) enum BoardLevel {
     Difficult,
 }
 
+impl BoardLevel {
+    /// The classic preset used for the level.
+    pub(crate) fn difficulty(&self) -> Difficulty {
+        match self {
+            BoardLevel::Easy => Difficulty::Beginner,
+            BoardLevel::Medium => Difficulty::Intermediate,
+            BoardLevel::Difficult => Difficulty::Expert,
+        }
+    }
+}
+
 pub(crate) struct GameBoard<'a> {
     handle: HWND,
     factory: &'a ID2D1Factory1,
     image_factory: IWICImagingFactory,
+    write_factory: IDWriteFactory,
     text_format: IDWriteTextFormat,
-    target: Option<ID2D1HwndRenderTarget>,
+    // presents the board and its overlay layers
+    composition: Option<Composition>,
+    // the warning across the bottom row, on a layer of its own to fade in,
+    // and the text drawn on it
+    warning_layer: Option<Layer>,
+    shown_warning: Option<String>,
     line_style: ID2D1StrokeStyle1,
     default_brush: Option<ID2D1SolidColorBrush>,
     cell_brush: Option<ID2D1SolidColorBrush>,
     cell_highlight: Option<ID2D1SolidColorBrush>,
     num_brush: [Option<ID2D1SolidColorBrush>; 7],
+    player_brush: [Option<ID2D1SolidColorBrush>; PLAYERS],
+    contradiction_brush: Option<ID2D1SolidColorBrush>,
+    annotation_brush: [Option<ID2D1SolidColorBrush>; ANNOTATIONS as usize],
+    ghost_brush: Option<ID2D1SolidColorBrush>,
+    auto_flag_brush: Option<ID2D1SolidColorBrush>,
     flag: Option<ID2D1Bitmap>,
     mine: Option<ID2D1Bitmap>,
+    // the pixel size the sprites were scaled to, a cell's at the time
+    sprite_size: (u32, u32),
     game: Game,
+    versus: Option<Match>,
+    cues: Option<CuePlayer>,
+    // the tones of the focused cell in the audio feedback mode
+    tones: Option<Tones>,
+    // the cell the arrow keys moved to, bound keys act on it instead of the
+    // cell under the pointer until the next click
+    focus: Option<(i16, i16)>,
+    splits: Option<Timer>,
+    // the ghost of the best run raced on every board
+    ghost: Option<GhostRace>,
+    // the moves played on the board since it was laid out or loaded, for
+    // exporting the game once it is over
+    recording: Option<replay::Replay>,
+    // the number of faded numbers and the one looked at when a blind board
+    // was last drawn
+    blind_view: (usize, Option<(i16, i16)>),
+    // the tooltip telling about the inspected cell
+    inspector: Option<Inspector>,
+    counters: bool,
+    scoring: bool,
+    theme: Theme,
+    // the skin the sprites come from, the image files when not set
+    skin: Option<Skin>,
+    assists: Assists,
+    bindings: Bindings,
+    render: RenderSettings,
+    timing: TimingSettings,
+    // the presses in progress, to tell chords, long presses and double
+    // clicks from single clicks
+    pointer: Pointer,
+    // where the render target rasterizes, software once the graphics card
+    // failed
+    rendering: Rendering,
+    // whether the board color is left out for a system backdrop to show
+    translucent: bool,
+    // a message shown for a moment across the bottom row and the cell it is
+    // about, such as the deduction a refused chord went against
+    warning: Option<(String, Option<Deduction>)>,
+    // the hint taken, outlined until the next move
+    hint: Option<Deduction>,
+    // the pattern a hint named, outlined until the next move
+    pattern: Option<PatternHint>,
+    // practice checkpoints of the current board
+    checkpoints: Checkpoints,
+    // the hidden mines and the solver's deductions drawn over the board
+    #[cfg(feature = "dev-tools")]
+    debug: bool,
+    // the statistics of the paints while the performance overlay is shown
+    #[cfg(feature = "dev-tools")]
+    perf: Option<FrameStats>,
+    // primitives drawn by the paint under way
+    primitives: Cell<u32>,
+    observers: Vec<Observer<'a>>,
+    // the chunks of a large board drawn so far, until a move changes them
+    chunks: HashMap<(i16, i16), ID2D1Bitmap>,
+    // how far the view is scrolled into the board and its size, in pixels
+    scroll: (f32, f32),
+    view: (f32, f32),
     cell_width: f32,
     cell_height: f32,
     dpix: f32,
     dpiy: f32,
 }
 
-impl<'a> GameBoard<'a> {
-    pub(crate) fn new(
-        parent: HWND,
-        level: BoardLevel,
-        factory: &'a ID2D1Factory1,
-    ) -> Result<Box<Self>> {
+/// Collects the settings for a [`GameBoard`] before its window is created.
+pub(crate) struct GameBoardBuilder<'a> {
+    parent: HWND,
+    factory: &'a ID2D1Factory1,
+    config: GameConfig,
+    theme: Theme,
+    skin: Option<Skin>,
+    cell_size: f32,
+    assists: Assists,
+    versus: bool,
+    splits: bool,
+    ghost: bool,
+    counters: bool,
+    scoring: bool,
+    no_flags: bool,
+    flag_penalty: bool,
+    neighborhood: Neighborhood,
+    blind: Option<Duration>,
+    inspect: bool,
+    symmetry: Symmetry,
+    zones: Vec<Zone>,
+    handicap: Option<Handicap>,
+    hint_budget: Option<u16>,
+    bindings: Bindings,
+    render: RenderSettings,
+    timing: TimingSettings,
+    translucent: bool,
+    audio: Option<Rc<Audio>>,
+    sonify: Option<Rc<Audio>>,
+    observers: Vec<Observer<'a>>,
+}
+
+impl<'a> GameBoardBuilder<'a> {
+    /// Sets the board to one of the menu levels.
+    pub(crate) fn level(self, level: BoardLevel) -> Self {
+        self.config(level.difficulty().config())
+    }
+
+    /// Sets the board size and number of mines.
+    pub(crate) fn config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the colors and font the board is drawn with.
+    pub(crate) fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draws the board with the sprites and colors of a skin, over the
+    /// theme, and plays its cue sounds.
+    pub(crate) fn skin(mut self, skin: Skin) -> Self {
+        self.skin = Some(skin);
+        self
+    }
+
+    /// Sets the width and height of a cell in millimeters.
+    pub(crate) fn cell_size(mut self, millimeters: f32) -> Self {
+        self.cell_size = millimeters;
+        self
+    }
+
+    /// Sets the help offered to the player.
+    pub(crate) fn assists(mut self, assists: Assists) -> Self {
+        self.assists = assists;
+        self
+    }
+
+    /// Sets whether two players take turns on the board, scoring the cells
+    /// they claim.
+    pub(crate) fn versus(mut self, versus: bool) -> Self {
+        self.versus = versus;
+        self
+    }
+
+    /// Sets whether speedrun splits are timed against the best run on the
+    /// level and shown in the title of the parent window. A versus match
+    /// shows its score there instead.
+    pub(crate) fn splits(mut self, splits: bool) -> Self {
+        self.splits = splits;
+        self
+    }
+
+    /// Sets whether every board is laid out like the best run on its level
+    /// and raced against its ghost, the splits timed against the ghost's.
+    pub(crate) fn ghost(mut self, ghost: bool) -> Self {
+        self.ghost = ghost;
+        self
+    }
+
+    /// Sets whether the left click, right click and chord counts are shown
+    /// in the top right corner of the board as they change.
+    pub(crate) fn counters(mut self, counters: bool) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    /// Sets whether the arcade score is shown in the top left corner of the
+    /// board as it changes.
+    pub(crate) fn scoring(mut self, scoring: bool) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Sets whether flagging is ignored and games recorded as played without
+    /// flags.
+    pub(crate) fn no_flags(mut self, no_flags: bool) -> Self {
+        self.no_flags = no_flags;
+        self
+    }
+
+    /// Sets whether a flag on a safe cell is refused with a warning and time
+    /// added to the clock, see [`Game::flag_penalty`].
+    pub(crate) fn flag_penalty(mut self, flag_penalty: bool) -> Self {
+        self.flag_penalty = flag_penalty;
+        self
+    }
+
+    /// Sets the cells the numbers count the mines of, the classic eight
+    /// around them unless set. The ghost is only raced by the classic rule.
+    pub(crate) fn neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Sets how long numbers stay shown before they fade, `None` to play
+    /// with the numbers always shown.
+    pub(crate) fn blind(mut self, fade: Option<Duration>) -> Self {
+        self.blind = fade;
+        self
+    }
+
+    /// Sets whether a tooltip tells about the cell under the pointer or with
+    /// the keyboard focus, off unless set.
+    pub(crate) fn inspect(mut self, inspect: bool) -> Self {
+        self.inspect = inspect;
+        self
+    }
+
+    /// Sets the symmetry the mines are laid out with, none unless set. The
+    /// ghost only races boards laid out without one.
+    pub(crate) fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Sets the rectangles laid out more or less densely than the rest,
+    /// none unless set. The ghost only races boards without zones.
+    pub(crate) fn zones(mut self, zones: Vec<Zone>) -> Self {
+        self.zones = zones;
+        self
+    }
+
+    /// Sets the cells uncovered for the player on every board, none unless
+    /// set. The ghost only races boards without a handicap.
+    pub(crate) fn handicap(mut self, handicap: Option<Handicap>) -> Self {
+        self.handicap = handicap;
+        self
+    }
+
+    /// Sets the hints allowed per game, `None` for no limit.
+    pub(crate) fn hint_budget(mut self, budget: Option<u16>) -> Self {
+        self.hint_budget = budget;
+        self
+    }
+
+    /// Sets what the mouse buttons and keys do, left to uncover, right to
+    /// flag and middle to chord unless set.
+    pub(crate) fn bindings(mut self, bindings: Bindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+
+    /// Sets whether frames wait for the display refresh and how often the
+    /// timed effects update, see [`RenderSettings`].
+    pub(crate) fn render(mut self, render: RenderSettings) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Sets how quickly presses must follow each other to chord and how long
+    /// a finger is held to flag, see [`TimingSettings`].
+    pub(crate) fn timing(mut self, timing: TimingSettings) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Leaves out the board color around the cells, for the system backdrop
+    /// behind the window to show through.
+    pub(crate) fn translucent(mut self, translucent: bool) -> Self {
+        self.translucent = translucent;
+        self
+    }
+
+    /// Plays the practice cues through `audio`: a clock ticking faster as
+    /// the best time on the level nears and a heartbeat while the pointer
+    /// rests on a likely mine.
+    pub(crate) fn cues(mut self, audio: Rc<Audio>) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// Plays the tone of the cell the keyboard focus moves to through
+    /// `audio`, the pitch of a number rising with its count, for playing by
+    /// ear with the arrow keys.
+    pub(crate) fn sonify(mut self, audio: Rc<Audio>) -> Self {
+        self.sonify = Some(audio);
+        self
+    }
+
+    /// Adds a hook called after every move the player makes.
+    pub(crate) fn observer(mut self, observer: impl FnMut(&Game, Move) + 'a) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Creates the board window as a child of the parent window.
+    pub(crate) fn build(self) -> Result<Box<GameBoard<'a>>> {
+        let GameBoardBuilder {
+            parent,
+            factory,
+            config,
+            theme,
+            skin,
+            cell_size,
+            assists,
+            versus,
+            splits,
+            ghost,
+            counters,
+            scoring,
+            no_flags,
+            flag_penalty,
+            neighborhood,
+            blind,
+            inspect,
+            symmetry,
+            zones,
+            handicap,
+            hint_budget,
+            bindings,
+            render,
+            timing,
+            translucent,
+            audio,
+            sonify,
+            observers,
+        } = self;
         let instance = unsafe { GetModuleHandleW(None)? };
         let write_factory: IDWriteFactory =
             unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)? };
         let image_factory = create_image_factory()?;
         let line_style = create_style(factory, None)?;
-        let text_format = unsafe {
-            write_factory.CreateTextFormat(
-                &HSTRING::from("San Serif"),
-                None,
-                DWRITE_FONT_WEIGHT_BOLD,
-                DWRITE_FONT_STYLE_NORMAL,
-                DWRITE_FONT_STRETCH_NORMAL,
-                14.0,
-                &HSTRING::from("en-US"),
-            )?
+        let theme = match &skin {
+            Some(skin) => theme.with_skin(skin),
+            None => theme,
         };
-        unsafe {
-            text_format.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_CENTER)?;
-            text_format.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER)?;
-        }
+        let text_format = create_text_format(&write_factory, theme.font.as_deref())?;
         REGISTER_GAMEBOARD_WINDOW_CLASS.call_once(|| {
             // use defaults for all other fields
             let class = WNDCLASSW {
                 style: CS_HREDRAW | CS_VREDRAW,
-                lpfnWndProc: Some(Self::wnd_proc),
+                lpfnWndProc: Some(GameBoard::wnd_proc),
                 hInstance: instance.into(),
                 hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
                 hbrBackground: unsafe { CreateSolidBrush(COLORREF(0)) },
@@ -127,69 +849,264 @@ impl<'a> GameBoard<'a> {
         let mut dpiy = 0.0;
         unsafe { factory.GetDesktopDpi(&mut dpix, &mut dpiy) };
 
-        let (width, height, game) = match level {
-            BoardLevel::Easy => (
-                dpix * 8.0 * CELL_WIDTH,
-                dpiy * 10.0 * CELL_HEIGHT,
-                Game::new(8, 10),
-            ),
-            BoardLevel::Medium => (
-                dpix * 12.0 * CELL_WIDTH,
-                dpiy * 16.0 * CELL_HEIGHT,
-                Game::new(12, 16),
-            ),
-            BoardLevel::Difficult => (
-                dpix * 30.0 * CELL_WIDTH,
-                dpiy * 18.0 * CELL_HEIGHT,
-                Game::new(30, 18),
-            ),
-        };
+        let mut game = Game::with_config(config);
+        game.set_no_flags(no_flags);
+        game.set_flag_penalty(flag_penalty);
+        game.set_neighborhood(neighborhood);
+        game.set_blind(blind);
+        let custom = symmetry != Symmetry::None || !zones.is_empty() || handicap.is_some();
+        if custom {
+            game.set_symmetry(symmetry);
+            game.set_zones(zones);
+            game.set_handicap(handicap);
+            game.reset();
+        }
+        game.set_auto_open(assists.auto_open);
+        game.set_auto_flag(assists.auto_flag);
+        game.set_hint_budget(hint_budget);
+        let ghost = neighborhood == Neighborhood::Adjacent && !custom && ghost;
+        let ghost = (ghost && !versus).then(|| GhostRace::new(&mut game));
+        let splits = (splits && !versus).then(|| race_timer(&game, ghost.as_ref()));
+        let versus = versus.then(|| Match::new(&game));
+        // the board is still playable without its cues
+        let cues = audio.and_then(|audio| match CuePlayer::new(&audio, &game, skin.as_ref()) {
+            Ok(player) => Some(player),
+            Err(error) => {
+                tracing::warn!(%error, "unable to load the cue sounds");
+                None
+            }
+        });
+        let cell_width = dpix * cell_size / MM_PER_INCH;
+        let cell_height = dpiy * cell_size / MM_PER_INCH;
+        let view = (
+            (game.width() as f32 * cell_width).min(VIEW_COLUMNS * cell_width),
+            (game.height() as f32 * cell_height).min(VIEW_ROWS * cell_height),
+        );
 
         let mut board = Box::new(GameBoard {
             handle: HWND(0),
             factory,
             image_factory,
+            write_factory,
             text_format,
-            target: None,
+            composition: None,
+            warning_layer: None,
+            shown_warning: None,
             line_style,
             default_brush: None,
             cell_brush: None,
             cell_highlight: None,
             num_brush: [None, None, None, None, None, None, None],
+            player_brush: [None, None],
+            contradiction_brush: None,
+            annotation_brush: Default::default(),
+            ghost_brush: None,
+            auto_flag_brush: None,
             flag: None,
             mine: None,
+            sprite_size: (0, 0),
             game,
-            cell_width: dpix * CELL_WIDTH,
-            cell_height: dpiy * CELL_HEIGHT,
+            versus,
+            cues,
+            tones: sonify.map(|audio| Tones {
+                audio,
+                playing: None,
+            }),
+            focus: None,
+            splits,
+            ghost,
+            recording: None,
+            blind_view: (0, None),
+            inspector: None,
+            counters,
+            scoring,
+            theme,
+            skin,
+            assists,
+            bindings,
+            render,
+            timing,
+            pointer: Pointer::default(),
+            rendering: Rendering::Hardware,
+            translucent,
+            warning: None,
+            hint: None,
+            pattern: None,
+            checkpoints: Checkpoints::new(),
+            #[cfg(feature = "dev-tools")]
+            debug: false,
+            #[cfg(feature = "dev-tools")]
+            perf: None,
+            primitives: Cell::new(0),
+            observers,
+            chunks: HashMap::new(),
+            scroll: (0.0, 0.0),
+            view,
+            cell_width,
+            cell_height,
             dpix,
             dpiy,
         });
 
         let _window = unsafe {
             CreateWindowExW(
-                WINDOW_EX_STYLE::default(),
+                // DirectComposition presents the board, there is nothing for
+                // GDI to draw
+                WS_EX_NOREDIRECTIONBITMAP,
                 windows::core::w!("bytetrail.window.bezier-demo"),
                 &HSTRING::from(""),
                 WS_VISIBLE | WS_CLIPSIBLINGS | WS_CHILDWINDOW,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
-                width as i32,
-                height as i32,
+                view.0 as i32,
+                view.1 as i32,
                 parent,
                 HMENU(0),
                 instance,
                 Some(board.as_mut() as *mut _ as _),
             )
         };
+        // long presses are timed by the board
+        let disable = HANDLE(DISABLE_PRESS_AND_HOLD);
+        if let Err(error) = unsafe { SetPropW(board.handle, PEN_SERVICE_PROPERTY, disable) } {
+            tracing::warn!(%error, "unable to turn off press and hold");
+        }
+        if board.cues.is_some() {
+            unsafe { SetTimer(board.handle, CUE_TIMER, render.timer_period(), None) };
+        }
+        if board.ghost.is_some() {
+            unsafe { SetTimer(board.handle, GHOST_TIMER, render.timer_period(), None) };
+        }
+        if board.game.blind().is_some() {
+            unsafe { SetTimer(board.handle, BLIND_TIMER, render.timer_period(), None) };
+        }
+        if inspect {
+            board.inspector = Inspector::new(board.handle, instance);
+            unsafe { SetTimer(board.handle, INSPECT_TIMER, render.timer_period(), None) };
+        }
+        board.show_score();
         Ok(board)
     }
+}
+
+impl<'a> GameBoard<'a> {
+    /// Starts a board for the parent window, an intermediate board with the
+    /// classic theme and no assists unless the builder changes it.
+    pub(crate) fn builder(parent: HWND, factory: &'a ID2D1Factory1) -> GameBoardBuilder<'a> {
+        GameBoardBuilder {
+            parent,
+            factory,
+            config: Difficulty::Intermediate.config(),
+            theme: Theme::CLASSIC,
+            skin: None,
+            cell_size: CELL_SIZE,
+            assists: Assists::default(),
+            versus: false,
+            splits: false,
+            ghost: false,
+            counters: false,
+            scoring: false,
+            no_flags: false,
+            flag_penalty: false,
+            neighborhood: Neighborhood::Adjacent,
+            blind: None,
+            inspect: false,
+            symmetry: Symmetry::None,
+            zones: Vec::new(),
+            handicap: None,
+            hint_budget: None,
+            bindings: Bindings::default(),
+            render: RenderSettings::default(),
+            timing: TimingSettings::default(),
+            translucent: false,
+            audio: None,
+            sonify: None,
+            observers: Vec::new(),
+        }
+    }
 
     pub(crate) fn hwnd(&self) -> HWND {
         self.handle
     }
 
+    /// The game on the board.
+    pub(crate) fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The moves played since the board was laid out, or since it was
+    /// loaded or reverted to a checkpoint, `None` before the first move.
+    pub(crate) fn recording(&self) -> Option<&replay::Replay> {
+        self.recording.as_ref()
+    }
+
+    /// Draws every position of a replay off screen with the board's theme
+    /// and skin, each with the time it is shown for, as bitmaps to encode.
+    pub(crate) fn draw_replay(
+        &self,
+        replay: &replay::Replay,
+    ) -> Result<Vec<(IWICBitmap, Duration)>> {
+        snapshot::frames(replay)
+            .iter()
+            .map(|(game, delay)| Ok((self.draw_game(game)?, *delay)))
+            .collect()
+    }
+
+    /// Draws a whole game, not necessarily the one played, into a bitmap of
+    /// its size and copies it back from the graphics card.
+    fn draw_game(&self, game: &Game) -> Result<IWICBitmap> {
+        let context = self.composition.as_ref().unwrap().context();
+        let size = D2D_SIZE_F {
+            width: game.width() as f32 * self.cell_width,
+            height: game.height() as f32 * self.cell_height,
+        };
+        let target = unsafe {
+            context.CreateCompatibleRenderTarget(
+                Some(&size),
+                None,
+                None,
+                D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE,
+            )?
+        };
+        let mut painter = self.painter(&target, (0.0, 0.0));
+        // an exported image has nothing to show through it
+        painter.board_opacity = 1.0;
+        render::draw(&mut painter, game, &[])?;
+        unsafe {
+            let drawn = target.GetBitmap()?;
+            let pixels = drawn.GetPixelSize();
+            let properties = D2D1_BITMAP_PROPERTIES1 {
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                bitmapOptions: D2D1_BITMAP_OPTIONS_CPU_READ | D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+                ..Default::default()
+            };
+            let readable = context.CreateBitmap(pixels, None, 0, &properties)?;
+            readable.CopyFromBitmap(None, &drawn, None)?;
+            let mapped = readable.Map(D2D1_MAP_OPTIONS_READ)?;
+            let bytes =
+                std::slice::from_raw_parts(mapped.bits, (mapped.pitch * pixels.height) as usize);
+            let bitmap = self.image_factory.CreateBitmapFromMemory(
+                pixels.width,
+                pixels.height,
+                &GUID_WICPixelFormat32bppPBGRA,
+                mapped.pitch,
+                bytes,
+            );
+            readable.Unmap()?;
+            bitmap
+        }
+    }
+
+    /// Where the board is drawn, on the graphics card or in software.
+    pub(crate) fn rendering(&self) -> Rendering {
+        self.rendering
+    }
+
     fn release_device(&mut self) {
-        self.target = None;
+        self.composition = None;
         self.release_device_resources();
     }
 
@@ -200,235 +1117,1324 @@ impl<'a> GameBoard<'a> {
         for i in 0..7 {
             self.num_brush[i] = None;
         }
+        self.player_brush = [None, None];
+        self.contradiction_brush = None;
+        self.annotation_brush = Default::default();
+        self.ghost_brush = None;
+        self.auto_flag_brush = None;
         self.flag = None;
         self.mine = None;
-        self.target = None;
+        self.sprite_size = (0, 0);
+        self.chunks.clear();
+        self.warning_layer = None;
+        self.shown_warning = None;
+        self.composition = None;
     }
 
     fn render(&mut self) -> Result<()> {
-        if self.target.is_none() {
+        let _span = tracing::debug_span!("render").entered();
+        if self.composition.is_none() {
+            tracing::debug!("creating device resources");
             self.create_render_target()?;
-            let target = self.target.as_ref().unwrap();
-            self.flag = Some(load_bitmap(FLAG_FILE, target, &self.image_factory)?);
-            self.mine = Some(load_bitmap(MINE_FILE, target, &self.image_factory)?);
-            unsafe { target.SetDpi(self.dpix, self.dpiy) };
+            self.load_sprites()?;
+            let target = self.composition.as_ref().unwrap().context();
+            let theme = &self.theme;
             self.default_brush = Some(create_brush(
                 target,
-                DEFAULT_COLOR.0,
-                DEFAULT_COLOR.1,
-                DEFAULT_COLOR.2,
+                theme.text.0,
+                theme.text.1,
+                theme.text.2,
                 1.0,
             )?);
             self.cell_highlight = Some(create_brush(
                 target,
-                CELL_HIGHLIGHT.0,
-                CELL_HIGHLIGHT.1,
-                CELL_HIGHLIGHT.2,
+                theme.highlight.0,
+                theme.highlight.1,
+                theme.highlight.2,
                 1.0,
             )?);
             self.cell_brush = Some(create_brush(
                 target,
-                CELL_COLOR.0,
-                CELL_COLOR.1,
-                CELL_COLOR.2,
+                theme.cell.0,
+                theme.cell.1,
+                theme.cell.2,
                 1.0,
             )?);
-            for (i, brush) in NUM_BRUSH.iter().enumerate() {
+            for (i, brush) in theme.numbers.iter().enumerate() {
                 self.num_brush[i] = Some(create_brush(target, brush.0, brush.1, brush.2, 1.0)?);
             }
+            for (i, tint) in theme.players.iter().enumerate() {
+                self.player_brush[i] =
+                    Some(create_brush(target, tint.0, tint.1, tint.2, CLAIM_OPACITY)?);
+            }
+            let red = theme.contradiction;
+            self.contradiction_brush = Some(create_brush(target, red.0, red.1, red.2, 1.0)?);
+            for (i, badge) in theme.annotations.iter().enumerate() {
+                self.annotation_brush[i] =
+                    Some(create_brush(target, badge.0, badge.1, badge.2, 1.0)?);
+            }
+            let (r, g, b) = theme.text;
+            self.ghost_brush = Some(create_brush(target, r, g, b, GHOST_OPACITY)?);
+            let (r, g, b, a) = AUTO_FLAG_TINT;
+            self.auto_flag_brush = Some(create_brush(target, r, g, b, a)?);
         }
-        unsafe {
-            self.target.as_ref().unwrap().BeginDraw();
-            self.draw_board()?;
-            self.target.as_ref().unwrap().EndDraw(None, None)?;
+        // the cells changed size since the sprites were scaled
+        if self.sprite_size != self.cell_pixels() {
+            self.load_sprites()?;
         }
+        self.draw_board()
+    }
+
+    /// The size of a cell in pixels at the board's DPI.
+    fn cell_pixels(&self) -> (u32, u32) {
+        (
+            (self.cell_width * self.dpix / 96.0).round() as u32,
+            (self.cell_height * self.dpiy / 96.0).round() as u32,
+        )
+    }
+
+    /// Loads the sprites scaled to the size of a cell, so they are drawn
+    /// crisp and without stretching. The cached chunks drawn with sprites
+    /// of another size are dropped.
+    fn load_sprites(&mut self) -> Result<()> {
+        let size = self.cell_pixels();
+        tracing::debug!(?size, "scaling sprites");
+        self.flag = Some(self.sprite("flag", FLAG_FILE, FLAG_IMAGE, size)?);
+        self.mine = Some(self.sprite("mine", MINE_FILE, MINE_IMAGE, size)?);
+        self.sprite_size = size;
+        self.chunks.clear();
         Ok(())
     }
 
-    fn draw_board(&mut self) -> Result<()> {
-        let target = self.target.as_mut().unwrap();
-        unsafe {
-            target.Clear(Some(&D2D1_COLOR_F {
-                r: BOARD_COLOR.0,
-                g: BOARD_COLOR.1,
-                b: BOARD_COLOR.2,
-                a: 1.0,
-            }));
+    /// Loads a sprite from the skin, or when there is no skin or its image
+    /// cannot be read from the asset file, see [`assets::resolve`], or the
+    /// built in image, scaled to `size` pixels.
+    fn sprite(
+        &self,
+        name: &str,
+        file: &str,
+        embedded: &'static [u8],
+        size: (u32, u32),
+    ) -> Result<ID2D1Bitmap> {
+        let target = self.composition.as_ref().unwrap().context();
+        let size = Some(size);
+        if let Some(image) = self.skin.as_ref().and_then(|skin| skin.image(name)) {
+            match load_bitmap_from_memory(image, target, &self.image_factory, size) {
+                Ok(bitmap) => return Ok(bitmap),
+                Err(error) => tracing::warn!(%error, name, "unable to load the skin image"),
+            }
         }
-
-        let default_brush = self.default_brush.as_ref().unwrap();
-        let cell_brush = self.cell_brush.as_ref().unwrap();
-        let cell_highlight = self.cell_highlight.as_ref().unwrap();
-        let mut num_brush: Vec<&ID2D1SolidColorBrush> = Vec::new();
-        for brush_ref in &self.num_brush {
-            num_brush.push(brush_ref.as_ref().unwrap());
+        // there is always the built in image to fall back on
+        let source = assets::resolve(file, Some(embedded)).unwrap_or(Source::Embedded(embedded));
+        if let Source::File(path) = source {
+            let path_name = HSTRING::from(path.as_path());
+            match load_bitmap(&path_name, target, &self.image_factory, size) {
+                Ok(bitmap) => return Ok(bitmap),
+                Err(error) => {
+                    tracing::warn!(%error, path = %path.display(), "unable to load the asset")
+                }
+            }
         }
-        let flag = self.flag.as_ref().unwrap();
-        let mine = self.mine.as_ref().unwrap();
+        load_bitmap_from_memory(embedded, target, &self.image_factory, size)
+    }
 
-        for x in 0..self.game.width() {
-            for y in 0..self.game.height() {
-                let left = x as f32 * self.cell_width + 1.0;
-                let top = y as f32 * self.cell_height + 1.0;
-                let right = left + self.cell_width - 2.0;
-                let bottom = top + self.cell_height - 2.0;
+    fn draw_board(&mut self) -> Result<()> {
+        self.primitives.set(0);
+        let region = self.visible_region();
+        let cached = self.is_cached();
+        if cached {
+            self.cache_chunks(region)?;
+        }
+        let mut painter = self.painter(self.composition.as_ref().unwrap().context(), self.scroll);
+        let (width, height) = self.view;
+        painter.hud = self.counters.then(|| {
+            let clicks = self.game.clicks();
+            let rect = D2D_RECT_F {
+                left: width - self.cell_width * HUD_CELLS,
+                top: 0.0,
+                right: width,
+                bottom: self.cell_height,
+            };
+            let text = format!(
+                "L {}  R {}  C {}",
+                clicks.uncovers, clicks.flags, clicks.chords
+            );
+            (text, rect)
+        });
+        painter.score = self.scoring.then(|| {
+            let rect = D2D_RECT_F {
+                left: 0.0,
+                top: 0.0,
+                right: self.cell_width * HUD_CELLS,
+                bottom: self.cell_height,
+            };
+            (score::score(&self.game).to_string(), rect)
+        });
+        painter.checkpoints = (!self.checkpoints.is_empty()).then(|| {
+            let rect = D2D_RECT_F {
+                left: width - self.cell_width * HUD_CELLS * 2.0,
+                top: height - self.cell_height,
+                right: width,
+                bottom: height,
+            };
+            (self.checkpoints.panel(), rect)
+        });
+        painter.focus = self.focus.map(|(x, y)| painter.cell_rect(x, y));
+        #[cfg(feature = "dev-tools")]
+        {
+            painter.perf = self.perf.as_ref().map(|stats| {
                 let rect = D2D_RECT_F {
-                    left,
-                    top,
-                    right,
-                    bottom,
+                    left: 0.0,
+                    top: self.cell_height,
+                    right: self.cell_width * HUD_CELLS * 2.0,
+                    bottom: self.cell_height * 2.0,
                 };
-                match self.game.cell_state(x, y) {
-                    CellState::Flagged(_) | CellState::Questioned(_) | CellState::Unknown(_) => {
-                        unsafe {
-                            target.FillRectangle(&rect, cell_brush);
-                            target.DrawLine(
-                                D2D_POINT_2F { x: left, y: top },
-                                D2D_POINT_2F { x: left, y: bottom },
-                                cell_highlight,
-                                1.5,
-                                &self.line_style,
-                            );
-                            target.DrawLine(
-                                D2D_POINT_2F { x: left, y: top },
-                                D2D_POINT_2F { x: right, y: top },
-                                cell_highlight,
-                                1.5,
-                                &self.line_style,
-                            );
-                        }
-                        match self.game.cell_state(x, y) {
-                            CellState::Flagged(_) => unsafe {
-                                target.DrawBitmap(
-                                    flag,
-                                    Some(&rect),
-                                    1.0,
-                                    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
-                                    None,
-                                );
-                            },
-                            CellState::Questioned(_) => unsafe {
-                                target.DrawText(
-                                    &("?".encode_utf16().collect::<Vec<u16>>()),
-                                    &self.text_format,
-                                    &rect,
-                                    default_brush,
-                                    D2D1_DRAW_TEXT_OPTIONS_NONE,
-                                    DWRITE_MEASURING_MODE_NATURAL,
-                                );
-                            },
-                            _ => {}
-                        }
-                    }
-                    CellState::Known(mined) => {
-                        unsafe {
-                            target.FillRectangle(&rect, cell_brush);
-                        }
-                        if mined {
-                            unsafe {
-                                target.DrawBitmap(
-                                    mine,
-                                    Some(&rect),
-                                    1.0,
-                                    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
-                                    None,
-                                );
-                            }
-                        }
-                    }
-                    CellState::Counted(count) => unsafe {
-                        let mut mine_count = count;
-                        target.FillRectangle(&rect, cell_brush);
-                        let num: Vec<u16> = count.to_string().encode_utf16().collect();
-                        if count > 7 {
-                            mine_count = 7;
-                        }
-                        target.DrawText(
-                            &num,
-                            &self.text_format,
-                            &rect,
-                            num_brush[(mine_count - 1) as usize],
-                            D2D1_DRAW_TEXT_OPTIONS_NONE,
-                            DWRITE_MEASURING_MODE_NATURAL,
-                        );
-                    },
+                (stats.summary(), rect)
+            });
+        }
+        let overlays = self.overlays();
+        if cached {
+            painter.begin(self.game.width(), self.game.height())?;
+            for chunk in region.chunks() {
+                painter.draw_chunk(&self.chunks[&chunk], Region::chunk(&self.game, chunk));
+            }
+            for &(x, y, overlay) in &overlays {
+                if region.contains(x, y) {
+                    painter.draw_overlay(x, y, overlay)?;
                 }
             }
+            painter.present()?;
+        } else {
+            render::draw_region(&mut painter, &self.game, &overlays, region)?;
         }
-        Ok(())
+        self.composition.as_ref().unwrap().present()?;
+        self.show_warning()
     }
 
-    fn create_render_target(&mut self) -> Result<()> {
-        unsafe {
-            let mut rect: RECT = RECT::default();
-            let _ = GetClientRect(self.handle, &mut rect);
-            let props = D2D1_RENDER_TARGET_PROPERTIES::default();
-            let hwnd_props = D2D1_HWND_RENDER_TARGET_PROPERTIES {
-                hwnd: self.handle,
-                pixelSize: windows::Win32::Graphics::Direct2D::Common::D2D_SIZE_U {
-                    width: (rect.right - rect.left) as u32,
-                    height: (rect.bottom - rect.top) as u32,
-                },
-                presentOptions: D2D1_PRESENT_OPTIONS::default(),
-            };
-            let target = self.factory.CreateHwndRenderTarget(&props, &hwnd_props)?;
-            self.target = Some(target);
+    /// Draws the warning across the bottom row on its layer and fades it in,
+    /// or hides the layer once the warning is gone.
+    fn show_warning(&mut self) -> Result<()> {
+        let text = self.warning.as_ref().map(|(text, _)| text.clone());
+        if text == self.shown_warning {
+            return Ok(());
+        }
+        // the layer is sized in pixels and the board drawn in DIPs
+        let scale = (self.dpix / 96.0, self.dpiy / 96.0);
+        let (width, height) = self.view;
+        if self.warning_layer.is_none() {
+            let size = (
+                (width * scale.0).ceil() as u32,
+                (self.cell_height * scale.1).ceil() as u32,
+            );
+            let layer = self.composition.as_ref().unwrap().layer(size)?;
+            layer.move_to((height - self.cell_height) * scale.1)?;
+            self.warning_layer = Some(layer);
+        }
+        let layer = self.warning_layer.as_ref().unwrap();
+        match &text {
+            Some(text) => {
+                let rect = D2D_RECT_F {
+                    left: 0.0,
+                    top: 0.0,
+                    right: width,
+                    bottom: self.cell_height,
+                };
+                layer.draw(|context| {
+                    let painter = self.painter(context, (0.0, 0.0));
+                    painter.count(1);
+                    unsafe { context.FillRectangle(&rect, painter.cell_highlight) };
+                    painter.draw_text(text, &rect, painter.contradiction_brush);
+                })?;
+                layer.fade_in(WARNING_FADE_SECONDS)?;
+            }
+            None => layer.hide()?,
         }
+        self.shown_warning = text;
         Ok(())
     }
 
-    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-        match message {
-            WM_PAINT => {
-                let mut ps = PAINTSTRUCT::default();
-                unsafe {
-                    BeginPaint(self.handle, &mut ps);
-                    self.render().expect("unable to render");
-                    EndPaint(self.handle, &ps);
-                }
-                LRESULT(0)
-            }
-            WM_RBUTTONUP => {
-                let (x, y) = mouse_position(lparam);
-                let x_cell = (x / self.cell_width) as i16;
-                let y_cell = (y / self.cell_height) as i16;
-
-                match self.game.cell_state(x_cell, y_cell) {
-                    CellState::Unknown(_) => self.game.flag(x_cell, y_cell),
-                    CellState::Flagged(_) => self.game.question(x_cell, y_cell),
-                    CellState::Questioned(_) => self.game.set_unknown(x_cell, y_cell),
-                    _ => {}
+    /// The markers drawn over the cells: the claims of a versus match, the
+    /// reveals of the ghost, the candidate marks, the flags the solver
+    /// placed, the hints, the faded number looked at, the contradicted
+    /// numbers and the debug view.
+    fn overlays(&self) -> Vec<(i16, i16, Overlay)> {
+        let mut overlays = match &self.versus {
+            Some(versus) => versus.overlays(),
+            None => Vec::new(),
+        };
+        if let Some(ghost) = self.ghost.as_ref().and_then(GhostRace::ghost) {
+            // a lost board shows its mines instead
+            if self.game.state() != GameState::Lost {
+                for (x, y) in ghost.reveals(&self.game) {
+                    overlays.push((x, y, Overlay::Ghost));
                 }
-                unsafe { InvalidateRect(self.handle, None, false) };
-
-                LRESULT(0)
             }
-            WM_LBUTTONUP => {
-                if self.game.state() == GameState::Lost {
-                    self.game.reset();
-                } else {
-                    let (x, y) = mouse_position(lparam);
-                    let x_cell = (x / self.cell_width) as i16;
-                    let y_cell = (y / self.cell_height) as i16;
-                    let state = self.game.uncover(x_cell, y_cell);
-                    // todo animate lost sequence
-                    if state == GameState::Lost {
-                        self.game.show_mined();
-                    }
-                    // TODO animate won sequence
-                    if state == GameState::Won {
-                        self.game.reset();
-                    }
-                }
-                // TODO manage the results of uncover to control clip
-                unsafe { InvalidateRect(self.handle, None, false) };
-                LRESULT(0)
+        }
+        for (x, y, mark) in self.game.annotations() {
+            overlays.push((x, y, Overlay::Annotation(mark)));
+        }
+        overlays.extend(render::auto_flag_overlays(&self.game));
+        if self.assists.hints && self.game.state() == GameState::Playing {
+            if let Some(hint) = solver::hint(&self.game) {
+                overlays.push((hint.x, hint.y, Overlay::Hint(hint.kind)));
             }
-            WM_DESTROY => {
-                self.release_device();
-                LRESULT(0)
+        }
+        let marked = self.warning.as_ref().and_then(|(_, cell)| *cell);
+        for cell in marked.iter().chain(&self.hint) {
+            overlays.push((cell.x, cell.y, Overlay::Hint(cell.kind)));
+        }
+        if let Some(pattern) = &self.pattern {
+            overlays.extend(pattern.overlays());
+        }
+        if let Some((x, y)) = self.peeked() {
+            if let CellState::Counted(count) = self.game.cell_state(x, y) {
+                overlays.push((x, y, Overlay::Peek(count)));
             }
-            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+        if self.assists.contradictions {
+            for &(x, y) in self.game.contradictions() {
+                if let CellState::Counted(count) = self.game.cell_state(x, y) {
+                    overlays.push((x, y, Overlay::Contradiction(count)));
+                }
+            }
+        }
+        #[cfg(feature = "dev-tools")]
+        if self.debug {
+            overlays.extend(render::debug_overlays(&self.game));
+        }
+        overlays
+    }
+
+    /// A painter drawing on `target` with the board moved `origin` pixels
+    /// left and up, without the corner panels or a warning.
+    fn painter<'p>(&'p self, target: &'p ID2D1RenderTarget, origin: (f32, f32)) -> CellPainter<'p> {
+        // every target keeps its own modes, the chunks' as well as the window's
+        let antialias = match self.render.antialias {
+            true => D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+            false => D2D1_ANTIALIAS_MODE_ALIASED,
+        };
+        unsafe {
+            target.SetAntialiasMode(antialias);
+            target.SetTextAntialiasMode(text_antialias_mode(self.render.text));
+        }
+        CellPainter {
+            target,
+            text_format: &self.text_format,
+            line_style: &self.line_style,
+            default_brush: self.default_brush.as_ref().unwrap(),
+            cell_brush: self.cell_brush.as_ref().unwrap(),
+            cell_highlight: self.cell_highlight.as_ref().unwrap(),
+            num_brush: self.num_brush.iter().map(|b| b.as_ref().unwrap()).collect(),
+            player_brush: self
+                .player_brush
+                .iter()
+                .map(|b| b.as_ref().unwrap())
+                .collect(),
+            contradiction_brush: self.contradiction_brush.as_ref().unwrap(),
+            annotation_brush: self
+                .annotation_brush
+                .iter()
+                .map(|b| b.as_ref().unwrap())
+                .collect(),
+            ghost_brush: self.ghost_brush.as_ref().unwrap(),
+            auto_flag_brush: self.auto_flag_brush.as_ref().unwrap(),
+            flag: self.flag.as_ref().unwrap(),
+            mine: self.mine.as_ref().unwrap(),
+            board_color: self.theme.board,
+            board_opacity: if self.translucent { 0.0 } else { 1.0 },
+            cell_width: self.cell_width,
+            cell_height: self.cell_height,
+            origin,
+            hud: None,
+            score: None,
+            checkpoints: None,
+            focus: None,
+            #[cfg(feature = "dev-tools")]
+            perf: None,
+            primitives: &self.primitives,
+        }
+    }
+
+    /// Whether the board is large enough to be drawn from cached chunks.
+    /// The numbers of a blind board fade without a move, so it is not.
+    fn is_cached(&self) -> bool {
+        self.game.width() as usize * self.game.height() as usize > CACHED_CELLS
+            && self.game.blind().is_none()
+    }
+
+    /// The cells the window shows at its scroll position.
+    fn visible_region(&self) -> Region {
+        let cell = (self.cell_width, self.cell_height);
+        Region::visible(&self.game, self.scroll, self.view, cell)
+    }
+
+    /// Draws the chunks of the region that are not cached yet.
+    fn cache_chunks(&mut self, region: Region) -> Result<()> {
+        let missing: Vec<_> = region
+            .chunks()
+            .filter(|chunk| !self.chunks.contains_key(chunk))
+            .collect();
+        for chunk in missing {
+            let bitmap = self.draw_chunk(chunk)?;
+            self.chunks.insert(chunk, bitmap);
+        }
+        Ok(())
+    }
+
+    /// Draws the cells of a chunk into a bitmap of its size.
+    fn draw_chunk(&self, chunk: (i16, i16)) -> Result<ID2D1Bitmap> {
+        let region = Region::chunk(&self.game, chunk);
+        let size = D2D_SIZE_F {
+            width: region.width as f32 * self.cell_width,
+            height: region.height as f32 * self.cell_height,
+        };
+        let target = unsafe {
+            self.composition
+                .as_ref()
+                .unwrap()
+                .context()
+                .CreateCompatibleRenderTarget(
+                    Some(&size),
+                    None,
+                    None,
+                    D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE,
+                )?
+        };
+        let origin = (
+            region.x as f32 * self.cell_width,
+            region.y as f32 * self.cell_height,
+        );
+        let mut painter = self.painter(&target, origin);
+        render::draw_region(&mut painter, &self.game, &[], region)?;
+        unsafe { target.GetBitmap() }
+    }
+
+    /// Drops the cached chunks a move changed, or all of them when the whole
+    /// board may have changed.
+    fn invalidate_chunks(&mut self, all: bool) {
+        if all {
+            self.chunks.clear();
+        } else {
+            for chunk in render::changed_chunks(self.game.changed()) {
+                self.chunks.remove(&chunk);
+            }
+        }
+    }
+
+    /// Scrolls a board larger than its window by the given number of columns
+    /// and rows, stopping at its edges.
+    pub(crate) fn scroll(&mut self, columns: f32, rows: f32) {
+        let limit = (
+            (self.game.width() as f32 * self.cell_width - self.view.0).max(0.0),
+            (self.game.height() as f32 * self.cell_height - self.view.1).max(0.0),
+        );
+        let scroll = (
+            (self.scroll.0 + columns * self.cell_width).clamp(0.0, limit.0),
+            (self.scroll.1 + rows * self.cell_height).clamp(0.0, limit.1),
+        );
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            unsafe { InvalidateRect(self.handle, None, false) };
+        }
+    }
+
+    /// The cell under a point of the window, taking the scroll into account.
+    fn cell_at(&self, x: f32, y: f32) -> (i16, i16) {
+        (
+            ((x + self.scroll.0) / self.cell_width).floor() as i16,
+            ((y + self.scroll.1) / self.cell_height).floor() as i16,
+        )
+    }
+
+    /// Plays a move, for the player whose turn it is in a versus match, and
+    /// lets the observers know about it.
+    fn play(&mut self, mv: Move) -> GameState {
+        self.warning = None;
+        self.hint = None;
+        self.pattern = None;
+        let recording = self
+            .recording
+            .get_or_insert_with(|| replay::Replay::new(&self.game));
+        let played = match self.versus.as_mut() {
+            Some(versus) => versus.play(&mut self.game, mv),
+            None => {
+                self.game.apply(mv);
+                true
+            }
+        };
+        if played {
+            recording.push(self.game.elapsed(), replay::Action::Play(mv));
+            self.invalidate_chunks(false);
+            for observer in &mut self.observers {
+                observer(&self.game, mv);
+            }
+            if let Some(player) = self.cues.as_mut() {
+                player.probabilities = solver::probabilities(&self.game);
+            }
+            if let Some(timer) = self.splits.as_mut() {
+                // assisted runs are not speedruns
+                let won = self.game.state() == GameState::Won && !self.game.assisted();
+                if timer.update(&self.game) && won {
+                    // splits are best effort like the statistics
+                    let _ = timer.record();
+                }
+            }
+            if let Some(race) = self.ghost.as_mut() {
+                race.play(replay::Action::Play(mv));
+                if self.game.state() == GameState::Won {
+                    match race.record(&self.game) {
+                        Ok(true) => tracing::info!("the run is the new ghost"),
+                        Ok(false) => {}
+                        Err(error) => tracing::warn!(%error, "unable to keep the ghost"),
+                    }
+                }
+            }
+            self.show_score();
+        }
+        self.game.state()
+    }
+
+    /// Moves the keyboard focus with the arrow keys or does what a key is
+    /// bound to on the focused cell, or the cell under the mouse pointer
+    /// when nothing has the focus, `key` being its virtual key code. The
+    /// paging keys, and the arrow keys with `control` down, scroll the view
+    /// and leave the focus where it is.
+    pub(crate) fn key_down(&mut self, key: usize, control: bool) {
+        if let Some((columns, rows)) = self.scroll_step(key as u16, control) {
+            self.scroll(columns, rows);
+            return;
+        }
+        let step = match key as u16 {
+            code if code == VK_LEFT.0 => Some((-1, 0)),
+            code if code == VK_RIGHT.0 => Some((1, 0)),
+            code if code == VK_UP.0 => Some((0, -1)),
+            code if code == VK_DOWN.0 => Some((0, 1)),
+            _ => None,
+        };
+        if let Some(step) = step {
+            self.move_focus(step);
+            return;
+        }
+        // the codes of letters, digits and space are their upper case ASCII
+        let key = match u8::try_from(key) {
+            Ok(key @ (b' ' | b'0'..=b'9' | b'A'..=b'Z')) => key.to_ascii_lowercase() as char,
+            _ => return,
+        };
+        let Some(action) = self.bindings.action(Input::Key(key)) else {
+            return;
+        };
+        if let Some(cell) = self.focus.or_else(|| self.hovered_cell()) {
+            self.act(action, cell);
+            if self.focus.is_some() {
+                self.sonify(cell);
+            }
+        }
+    }
+
+    /// How many columns and rows a key scrolls the view: a page less a row
+    /// with page up and page down, to the left and right edges with home
+    /// and end, or the corners with `control` down, and a cell with
+    /// `control` and an arrow.
+    fn scroll_step(&self, key: u16, control: bool) -> Option<(f32, f32)> {
+        let page = (self.view.1 / self.cell_height - 1.0).floor().max(1.0);
+        // scrolling stops at the edges, so the whole board reaches them
+        let far = self.game.width().max(self.game.height()) as f32;
+        let corner = if control { far } else { 0.0 };
+        match key {
+            code if code == VK_PRIOR.0 => Some((0.0, -page)),
+            code if code == VK_NEXT.0 => Some((0.0, page)),
+            code if code == VK_HOME.0 => Some((-far, -corner)),
+            code if code == VK_END.0 => Some((far, corner)),
+            code if control && code == VK_LEFT.0 => Some((-1.0, 0.0)),
+            code if control && code == VK_RIGHT.0 => Some((1.0, 0.0)),
+            code if control && code == VK_UP.0 => Some((0.0, -1.0)),
+            code if control && code == VK_DOWN.0 => Some((0.0, 1.0)),
+            _ => None,
+        }
+    }
+
+    /// Moves the keyboard focus a cell, stopping at the edges, scrolls it
+    /// into view and plays its tone. The first move puts the focus on the
+    /// cell under the pointer, or the top left one.
+    fn move_focus(&mut self, (dx, dy): (i16, i16)) {
+        let (x, y) = match self.focus {
+            Some((x, y)) => (
+                (x + dx).clamp(0, self.game.width() - 1),
+                (y + dy).clamp(0, self.game.height() - 1),
+            ),
+            None => self.hovered_cell().unwrap_or((0, 0)),
+        };
+        self.focus = Some((x, y));
+        let columns = scroll_into_view(
+            x as f32 * self.cell_width,
+            self.cell_width,
+            self.scroll.0,
+            self.view.0,
+        );
+        let rows = scroll_into_view(
+            y as f32 * self.cell_height,
+            self.cell_height,
+            self.scroll.1,
+            self.view.1,
+        );
+        self.scroll(columns, rows);
+        self.sonify((x, y));
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Plays the tone of a cell in the audio feedback mode.
+    fn sonify(&mut self, (x, y): (i16, i16)) {
+        let cell = self.game.cell_state(x, y);
+        if let Some(tones) = self.tones.as_mut() {
+            tones.play(cell);
+        }
+    }
+
+    /// Swaps what the left and right mouse buttons do and returns the
+    /// bindings to keep.
+    pub(crate) fn swap_buttons(&mut self) -> &Bindings {
+        self.bindings.swap(Input::Left, Input::Right);
+        &self.bindings
+    }
+
+    /// Does an action on a cell. Once a game is won or lost or a match is
+    /// over an uncover starts a new board and anything else is ignored.
+    fn act(&mut self, action: Action, (x, y): (i16, i16)) {
+        let match_over = self.versus.as_ref().is_some_and(Match::is_over);
+        let over = matches!(self.game.state(), GameState::Won | GameState::Lost);
+        if over || match_over {
+            if action == Action::Uncover {
+                self.new_game();
+                unsafe { InvalidateRect(self.handle, None, false) };
+            }
+            return;
+        }
+        let Some(action) = action.resolve(&self.game, x, y) else {
+            return;
+        };
+        let cell = self.game.cell_state(x, y);
+        let state = match action {
+            Action::Uncover | Action::Chord => {
+                let counted = matches!(cell, CellState::Counted(_));
+                let chord = counted && (action == Action::Chord || self.assists.chording);
+                let risk = (chord && self.assists.safe_chords)
+                    .then(|| solver::chord_risk(&self.game, x, y))
+                    .flatten();
+                if let Some(risk) = risk {
+                    self.warn(format!("Chord refused, {}", describe(&risk)), Some(risk));
+                    self.game.state()
+                } else if chord {
+                    self.play(Move::Chord(x, y))
+                } else if action == Action::Uncover {
+                    self.play(Move::Uncover(x, y))
+                } else {
+                    self.game.state()
+                }
+            }
+            Action::Flag | Action::Question => {
+                let mv = match (cell, action) {
+                    _ if self.game.no_flags() => None,
+                    (CellState::Unknown(_), Action::Flag) => Some(Move::Flag(x, y)),
+                    (CellState::Unknown(_), _) => Some(Move::Question(x, y)),
+                    (CellState::Flagged(_), _) => Some(Move::Question(x, y)),
+                    (CellState::Questioned(_), _) => Some(Move::SetUnknown(x, y)),
+                    _ => None,
+                };
+                let wrong = self.game.wrong_flags();
+                let state = match mv {
+                    Some(mv) => self.play(mv),
+                    None => self.game.state(),
+                };
+                if self.game.wrong_flags() > wrong {
+                    let penalty = game::FLAG_PENALTY.as_secs();
+                    let text = format!("Flag refused, the cell is safe, +{} s", penalty);
+                    self.warn(text, None);
+                }
+                state
+            }
+            // resolved to one of the others above
+            Action::Smart => self.game.state(),
+        };
+        // todo animate lost sequence
+        if state == GameState::Lost {
+            self.show_loss();
+            self.game.show_mined();
+            self.invalidate_chunks(true);
+        }
+        // TODO animate won sequence, a match stays up to show the winner
+        if state == GameState::Won && self.versus.is_none() {
+            self.new_game();
+        }
+        // TODO manage the results of uncover to control clip
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Acts on a single click of `input` on `cell`, chording on a double
+    /// click and cycling the candidate mark on a right click with control
+    /// held down.
+    fn click(&mut self, input: Input, cell: (i16, i16), control: bool) {
+        if input == Input::Right && control {
+            self.annotate(cell);
+            return;
+        }
+        // only clicks on numbers count, so a quick second click on a number
+        // just uncovered does not chord
+        let counted = matches!(self.game.cell_state(cell.0, cell.1), CellState::Counted(_));
+        let double = input == Input::Left
+            && self
+                .pointer
+                .double_click(counted.then_some(cell), self.timing.double_click);
+        if double {
+            self.act(Action::Chord, cell);
+        } else if let Some(action) = self.bindings.action(input) {
+            self.act(action, cell);
+        }
+    }
+
+    /// Moves a covered cell to its next candidate mark, see
+    /// [`Game::annotate`]. The marks are drawn over the cells, so the cached
+    /// chunks stay valid.
+    fn annotate(&mut self, (x, y): (i16, i16)) {
+        let mark = self.game.annotate(x, y);
+        tracing::debug!(x, y, ?mark, "annotate");
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Takes a hint, outlined until the next move, and shows its time
+    /// penalty, or says why there is none.
+    pub(crate) fn hint(&mut self) {
+        // hints would favor one player of a match
+        if self.versus.is_some() {
+            return;
+        }
+        match self.game.hint() {
+            Some(hint) => {
+                let left = match self.game.hints_left() {
+                    Some(left) => format!(", {} left", left),
+                    None => String::new(),
+                };
+                let penalty = game::HINT_PENALTY.as_secs();
+                // a pattern on the board teaches more than the cell it proves
+                let text = match patterns::find(&self.game) {
+                    Some(pattern) if pattern.pattern != Pattern::EdgeFiftyFifty => {
+                        let text = format!("Pattern: {}", pattern.explanation());
+                        self.pattern = Some(pattern);
+                        text
+                    }
+                    _ => {
+                        self.hint = Some(hint);
+                        describe(&hint)
+                    }
+                };
+                self.warn(format!("{}, +{} s{}", text, penalty, left), None);
+            }
+            None if self.game.hints_left() == Some(0) => self.warn("No hints left".into(), None),
+            None => match patterns::find(&self.game) {
+                Some(pattern) => {
+                    self.warn(format!("Pattern: {}", pattern.explanation()), None);
+                    self.pattern = Some(pattern);
+                }
+                None => self.warn("No cell can be proven, guess".into(), None),
+            },
+        }
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Draws the board with a new theme and skin, recreating the brushes and
+    /// sprites when they changed.
+    pub(crate) fn set_look(&mut self, theme: Theme, skin: Option<Skin>) {
+        let theme = match &skin {
+            Some(skin) => theme.with_skin(skin),
+            None => theme,
+        };
+        if theme == self.theme && skin == self.skin {
+            return;
+        }
+        if theme.font != self.theme.font {
+            match create_text_format(&self.write_factory, theme.font.as_deref()) {
+                Ok(format) => self.text_format = format,
+                Err(error) => tracing::warn!(%error, "unable to change the font"),
+            }
+        }
+        self.theme = theme;
+        self.skin = skin;
+        self.release_device_resources();
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Leaves out or brings back the board color around the cells.
+    pub(crate) fn set_translucent(&mut self, translucent: bool) {
+        if translucent != self.translucent {
+            self.translucent = translucent;
+            self.invalidate_chunks(true);
+            unsafe { InvalidateRect(self.handle, None, false) };
+        }
+    }
+
+    /// Changes the help offered to the player during a game. A game opened
+    /// with help stays assisted, so turning auto open or auto flag off waits
+    /// for the next board.
+    pub(crate) fn set_assists(&mut self, assists: Assists) {
+        self.assists = assists;
+        if assists.auto_open {
+            self.game.set_auto_open(true);
+        }
+        if assists.auto_flag {
+            self.game.set_auto_flag(true);
+        }
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Changes what the mouse buttons and keys do.
+    pub(crate) fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    /// Changes how quickly presses must follow each other, from the next
+    /// press on.
+    pub(crate) fn set_timing(&mut self, timing: TimingSettings) {
+        self.timing = timing;
+    }
+
+    /// Changes how frames are presented and smoothed and how often the timed
+    /// effects update, recreating the swap chain when vsync changed.
+    pub(crate) fn set_render(&mut self, render: RenderSettings) {
+        if render == self.render {
+            return;
+        }
+        if render.vsync != self.render.vsync {
+            self.release_device();
+            unsafe { InvalidateRect(self.handle, None, false) };
+        } else if (render.text, render.antialias) != (self.render.text, self.render.antialias) {
+            // the cached chunks and the warning were drawn the old way
+            self.invalidate_chunks(true);
+            self.shown_warning = None;
+            unsafe { InvalidateRect(self.handle, None, false) };
+        }
+        // setting a running timer again changes its period
+        if self.cues.is_some() {
+            unsafe { SetTimer(self.handle, CUE_TIMER, render.timer_period(), None) };
+        }
+        if self.ghost.is_some() {
+            unsafe { SetTimer(self.handle, GHOST_TIMER, render.timer_period(), None) };
+        }
+        if self.game.blind().is_some() {
+            unsafe { SetTimer(self.handle, BLIND_TIMER, render.timer_period(), None) };
+        }
+        if self.inspector.is_some() {
+            unsafe { SetTimer(self.handle, INSPECT_TIMER, render.timer_period(), None) };
+        }
+        self.render = render;
+    }
+
+    /// Shows or hides the hidden mines and the solver's deductions.
+    #[cfg(feature = "dev-tools")]
+    pub(crate) fn toggle_debug(&mut self) {
+        self.debug = !self.debug;
+        if self.debug {
+            let deductions = solver::solve(&self.game).len();
+            self.warn(format!("Debug view, {} deductions", deductions), None);
+        }
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Shows or hides the frame rate, paint time, primitives drawn and dirty
+    /// share of the window of the latest paint.
+    #[cfg(feature = "dev-tools")]
+    pub(crate) fn toggle_perf(&mut self) {
+        self.perf = match self.perf {
+            Some(_) => None,
+            None => Some(FrameStats::new()),
+        };
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Runs a debug console command on the game and returns what to print.
+    #[cfg(feature = "dev-tools")]
+    pub(crate) fn console(&mut self, line: &str) -> String {
+        let output = console::run(&mut self.game, line).unwrap_or_else(|e| e);
+        self.invalidate_chunks(true);
+        self.show_score();
+        unsafe { InvalidateRect(self.handle, None, false) };
+        output
+    }
+
+    /// Keeps a practice checkpoint of the board in the next slot.
+    pub(crate) fn checkpoint(&mut self) {
+        // going back would undo the other player's moves
+        if self.versus.is_some() {
+            return;
+        }
+        match self.checkpoints.push(&self.game) {
+            Some(slot) => self.warn(format!("Checkpoint {} kept", slot + 1), None),
+            None => self.warn("No checkpoint once the game is over".into(), None),
+        }
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Goes back to the latest checkpoint, the game is practice from then on
+    /// and left out of the statistics.
+    pub(crate) fn revert(&mut self) {
+        if self.versus.is_some() {
+            return;
+        }
+        match self.checkpoints.latest() {
+            Some(slot) if self.checkpoints.revert(slot, &mut self.game) => {
+                // the moves since the checkpoint were taken back
+                self.recording = None;
+                self.invalidate_chunks(true);
+                self.hint = None;
+                self.pattern = None;
+                if let Some(player) = self.cues.as_mut() {
+                    player.probabilities = solver::probabilities(&self.game);
+                }
+                self.warn(format!("Back to checkpoint {}, practice", slot + 1), None);
+                self.show_score();
+            }
+            _ => self.warn("No checkpoint kept yet".into(), None),
+        }
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Plays a board laid out elsewhere, such as one pasted in, sizing the
+    /// window to it. The layout was seen, so the game is practice and left
+    /// out of the statistics, and the ghost is not raced on it. Returns
+    /// whether it was loaded, a versus match keeps its board.
+    pub(crate) fn load(&mut self, mut game: Game) -> bool {
+        if self.versus.is_some() {
+            self.warn("No pasting boards during a match".into(), None);
+            unsafe { InvalidateRect(self.handle, None, false) };
+            return false;
+        }
+        game.set_auto_open(self.assists.auto_open);
+        game.set_auto_flag(self.assists.auto_flag);
+        // the rules played by carry over to the pasted board
+        game.set_no_flags(self.game.no_flags());
+        game.set_flag_penalty(self.game.flag_penalty());
+        game.set_blind(self.game.blind());
+        game.set_symmetry(self.game.symmetry());
+        game.set_zones(self.game.zones().to_vec());
+        game.set_handicap(self.game.handicap());
+        let budget = self.game.hints_left().map(|left| left + self.game.hints());
+        game.set_hint_budget(budget);
+        game.set_practice(true);
+        self.game = game;
+        if let Some(race) = self.ghost.as_mut() {
+            race.leave();
+        }
+        self.recording = None;
+        self.warning = None;
+        self.hint = None;
+        self.pattern = None;
+        self.focus = None;
+        self.checkpoints.clear();
+        if let Some(player) = self.cues.as_mut() {
+            player.restart(&self.game);
+        }
+        if let Some(timer) = self.splits.as_mut() {
+            *timer = race_timer(&self.game, None);
+        }
+        self.view = (
+            (self.game.width() as f32 * self.cell_width).min(VIEW_COLUMNS * self.cell_width),
+            (self.game.height() as f32 * self.cell_height).min(VIEW_ROWS * self.cell_height),
+        );
+        self.scroll = (0.0, 0.0);
+        // the swap chain is made again at the new size on the next paint
+        self.release_device();
+        unsafe {
+            let _ = SetWindowPos(
+                self.handle,
+                None,
+                0,
+                0,
+                self.view.0 as i32,
+                self.view.1 as i32,
+                SWP_NOMOVE | SWP_NOZORDER,
+            );
+            InvalidateRect(self.handle, None, false);
+        }
+        self.show_score();
+        true
+    }
+
+    /// Covers the board again with its mines where they were, to practice
+    /// the same layout. The layout was seen, so the game is practice and the
+    /// ghost is not raced on it. A versus match keeps its board.
+    pub(crate) fn restart(&mut self) {
+        if self.versus.is_some() {
+            self.warn("No restarting boards during a match".into(), None);
+            unsafe { InvalidateRect(self.handle, None, false) };
+            return;
+        }
+        self.game.restart();
+        if let Some(race) = self.ghost.as_mut() {
+            race.leave();
+        }
+        self.recording = None;
+        self.invalidate_chunks(true);
+        self.warning = None;
+        self.hint = None;
+        self.pattern = None;
+        self.checkpoints.clear();
+        if let Some(player) = self.cues.as_mut() {
+            player.restart(&self.game);
+        }
+        if let Some(timer) = self.splits.as_mut() {
+            *timer = race_timer(&self.game, None);
+        }
+        self.show_score();
+        unsafe { InvalidateRect(self.handle, None, false) };
+    }
+
+    /// Says whether the click that lost the game was a forced guess, or
+    /// outlines what could have been proven instead, until the next board.
+    fn show_loss(&mut self) {
+        // in a match the other player's moves are in the history too
+        if self.versus.is_some() {
+            return;
+        }
+        let (text, cell) = match solver::analyze_loss(&self.game) {
+            Some(Loss::Forced) => ("Lost on a forced guess".to_string(), None),
+            Some(Loss::Mine(cell) | Loss::Safe(cell)) => {
+                (format!("Avoidable, {}", describe(&cell)), Some(cell))
+            }
+            None => return,
+        };
+        let _ = unsafe { KillTimer(self.handle, WARNING_TIMER) };
+        self.warning = Some((text, cell));
+    }
+
+    /// Shows a message and outlines the cell it is about until the warning
+    /// timer hides it.
+    fn warn(&mut self, text: String, cell: Option<Deduction>) {
+        tracing::debug!(%text, "warning");
+        self.warning = Some((text, cell));
+        unsafe { SetTimer(self.handle, WARNING_TIMER, WARNING_MS, None) };
+    }
+
+    /// Lays out a new board, starting a new match in versus mode and the
+    /// same board as the best run when racing its ghost.
+    fn new_game(&mut self) {
+        self.game.set_auto_open(self.assists.auto_open);
+        self.game.set_auto_flag(self.assists.auto_flag);
+        self.game.reset();
+        if self.ghost.is_some() {
+            self.ghost = Some(GhostRace::new(&mut self.game));
+        }
+        self.recording = None;
+        self.invalidate_chunks(true);
+        self.warning = None;
+        self.hint = None;
+        self.pattern = None;
+        self.checkpoints.clear();
+        if let Some(versus) = self.versus.as_mut() {
+            *versus = Match::new(&self.game);
+        }
+        if let Some(player) = self.cues.as_mut() {
+            player.restart(&self.game);
+        }
+        // the title keeps the last run's splits until the first click
+        if let Some(timer) = self.splits.as_mut() {
+            *timer = race_timer(&self.game, self.ghost.as_ref());
+        }
+        self.show_score();
+    }
+
+    /// Plays the moves of the ghost that are due, called by the ghost timer.
+    fn update_ghost(&mut self) {
+        if self.game.state() == GameState::Lost {
+            return;
+        }
+        if self.ghost.as_mut().is_some_and(GhostRace::update) {
+            unsafe { InvalidateRect(self.handle, None, false) };
+        }
+    }
+
+    /// Redraws a blind board when a number faded or the number looked at
+    /// changed, called by the blind timer.
+    fn update_blind(&mut self) {
+        let view = (self.game.faded_count(), self.peeked());
+        if view != self.blind_view {
+            self.blind_view = view;
+            unsafe { InvalidateRect(self.handle, None, false) };
+        }
+    }
+
+    /// Tells about the focused cell, or the one under the pointer, in the
+    /// inspection tooltip just below it, called by the inspection timer.
+    fn update_inspector(&mut self) {
+        let cell = self.focus.or_else(|| self.hovered_cell());
+        let Some(inspector) = self.inspector.as_mut() else {
+            return;
+        };
+        // with the practice cues on or after a checkpoint or peek
+        let practice = self.cues.is_some() || self.game.practice();
+        if practice && inspector.clicks != Some(self.game.clicks()) {
+            inspector.probabilities = solver::probabilities(&self.game);
+            inspector.clicks = Some(self.game.clicks());
+        }
+        let probabilities = practice.then_some(&inspector.probabilities[..]);
+        let text = cell.map(|(x, y)| inspect::describe(&self.game, x, y, probabilities));
+        let (x, y) = cell.unwrap_or_default();
+        let mut point = POINT {
+            x: (x as f32 * self.cell_width - self.scroll.0) as i32,
+            y: ((y + 1) as f32 * self.cell_height - self.scroll.1) as i32,
+        };
+        unsafe { ClientToScreen(self.handle, &mut point) };
+        inspector.show(text, point);
+    }
+
+    /// The faded number with the keyboard focus or under the pointer, shown
+    /// again while it is looked at.
+    fn peeked(&self) -> Option<(i16, i16)> {
+        self.focus
+            .or_else(|| self.hovered_cell())
+            .filter(|&(x, y)| self.game.faded(x, y))
+    }
+
+    /// The cell under the mouse pointer, if it is over the board.
+    fn hovered_cell(&self) -> Option<(i16, i16)> {
+        let mut point = POINT::default();
+        unsafe {
+            GetCursorPos(&mut point).ok()?;
+            if !ScreenToClient(self.handle, &mut point).as_bool() {
+                return None;
+            }
+        }
+        let (x, y) = self.cell_at(point.x as f32, point.y as f32);
+        (x >= 0 && y >= 0 && x < self.game.width() && y < self.game.height()).then_some((x, y))
+    }
+
+    /// Plays the practice cues that are due, called by the cue timer.
+    fn update_cues(&mut self) {
+        if self.game.state() != GameState::Playing {
+            return;
+        }
+        let hovered = self.hovered_cell();
+        let width = self.game.width();
+        let Some(player) = self.cues.as_mut() else {
+            return;
+        };
+        let probability =
+            hovered.and_then(|(x, y)| player.probabilities.get((y * width + x) as usize).copied());
+        let due = player.cues.update(self.game.elapsed(), probability);
+        let sounds = [
+            due.tick.then_some(&player.tick),
+            due.heartbeat.then_some(&player.heartbeat),
+        ];
+        for sound in sounds.into_iter().flatten() {
+            if let Err(error) = sound.play() {
+                tracing::warn!(%error, "unable to play a cue");
+            }
+        }
+    }
+
+    /// Shows the turn and the scores of a versus match, or the splits of the
+    /// run so far, in the title of the parent window.
+    fn show_score(&self) {
+        let Some(versus) = &self.versus else {
+            if let Some(timer) = self.splits.as_ref().filter(|t| !t.run().is_empty()) {
+                self.set_title(&format!("MineSweeper - {}", timer.summary()));
+            }
+            return;
+        };
+        let (first, second) = (versus.score(0), versus.score(1));
+        let title = match (versus.is_over(), versus.winner()) {
+            (false, _) => format!(
+                "MineSweeper - player {} to move - {} : {}",
+                versus.turn() + 1,
+                first,
+                second
+            ),
+            (true, Some(winner)) => format!(
+                "MineSweeper - player {} wins {} : {}",
+                winner + 1,
+                first,
+                second
+            ),
+            (true, None) => format!("MineSweeper - draw {} : {}", first, second),
+        };
+        self.set_title(&title);
+    }
+
+    fn set_title(&self, title: &str) {
+        unsafe {
+            if let Ok(parent) = GetParent(self.handle) {
+                let _ = SetWindowTextW(parent, &HSTRING::from(title));
+            }
+        }
+    }
+
+    fn create_render_target(&mut self) -> Result<()> {
+        let mut rect = RECT::default();
+        let _ = unsafe { GetClientRect(self.handle, &mut rect) };
+        let size = (
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        );
+        let composition = Composition::new(
+            self.factory,
+            self.handle,
+            size,
+            (self.dpix, self.dpiy),
+            self.rendering,
+            self.render.vsync,
+        )?;
+        self.rendering = composition.rendering();
+        self.composition = Some(composition);
+        Ok(())
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        tracing::trace!(message, "board message");
+        match message {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                #[cfg(feature = "dev-tools")]
+                let started = Instant::now();
+                unsafe {
+                    BeginPaint(self.handle, &mut ps);
+                    if let Err(error) = self.render() {
+                        // the resources are recreated on the next paint, in
+                        // software unless the graphics card was only reset
+                        tracing::error!(%error, "unable to render");
+                        let reset = [
+                            D2DERR_RECREATE_TARGET,
+                            DXGI_ERROR_DEVICE_REMOVED,
+                            DXGI_ERROR_DEVICE_RESET,
+                        ];
+                        if !reset.contains(&error.code()) {
+                            self.rendering = Rendering::Software;
+                        }
+                        self.release_device_resources();
+                    }
+                    EndPaint(self.handle, &ps);
+                }
+                #[cfg(feature = "dev-tools")]
+                if let Some(perf) = &mut self.perf {
+                    let mut client = RECT::default();
+                    let _ = unsafe { GetClientRect(self.handle, &mut client) };
+                    let area = |rect: &RECT| {
+                        ((rect.right - rect.left) as f32) * ((rect.bottom - rect.top) as f32)
+                    };
+                    let coverage = area(&ps.rcPaint) / area(&client).max(1.0);
+                    perf.record(started, started.elapsed(), self.primitives.get(), coverage);
+                }
+                LRESULT(0)
+            }
+            WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN => {
+                let input = match message {
+                    WM_LBUTTONDOWN => Input::Left,
+                    WM_RBUTTONDOWN => Input::Right,
+                    _ => Input::Middle,
+                };
+                let (x, y) = mouse_position(lparam);
+                let cell = self.cell_at(x, y);
+                // the mouse takes over from the keyboard
+                if self.focus.take().is_some() {
+                    unsafe { InvalidateRect(self.handle, None, false) };
+                }
+                if input != Input::Middle {
+                    self.pointer.press(input == Input::Left, self.timing.chord);
+                }
+                if input == Input::Left && is_touch(unsafe { GetMessageExtraInfo() }) {
+                    self.pointer.touch = Some(cell);
+                    unsafe {
+                        SetTimer(self.handle, LONG_PRESS_TIMER, self.timing.long_press, None)
+                    };
+                } else if self.timing.act_on_press && !self.pointer.chord {
+                    // the second button of a chord waits for the release
+                    let control = wparam.0 as u32 & MK_CONTROL.0 != 0;
+                    self.click(input, cell, control);
+                }
+                LRESULT(0)
+            }
+            WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP => {
+                let input = match message {
+                    WM_LBUTTONUP => Input::Left,
+                    WM_RBUTTONUP => Input::Right,
+                    _ => Input::Middle,
+                };
+                let (x, y) = mouse_position(lparam);
+                let cell = self.cell_at(x, y);
+                let touched = input == Input::Left && self.pointer.touch.take().is_some();
+                if touched {
+                    let _ = unsafe { KillTimer(self.handle, LONG_PRESS_TIMER) };
+                }
+                // a finger held long enough already acted as the right
+                // button
+                if input == Input::Left && std::mem::take(&mut self.pointer.held) {
+                    self.pointer.release(true);
+                    return LRESULT(0);
+                }
+                // control and right-click cycle the candidate mark whatever
+                // the right button is bound to
+                let control = wparam.0 as u32 & MK_CONTROL.0 != 0;
+                let chord = match input {
+                    Input::Middle => Release::Single,
+                    _ => self.pointer.release(input == Input::Left),
+                };
+                match chord {
+                    Release::Chord => self.act(Action::Chord, cell),
+                    Release::Ignored => {}
+                    // a finger acts when it lifts, so it can be held
+                    Release::Single if self.timing.act_on_press && !touched => {}
+                    Release::Single => self.click(input, cell, control),
+                }
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == LONG_PRESS_TIMER => {
+                let _ = unsafe { KillTimer(self.handle, LONG_PRESS_TIMER) };
+                if let Some(cell) = self.pointer.touch.take() {
+                    self.pointer.held = true;
+                    if let Some(action) = self.bindings.action(Input::Right) {
+                        self.act(action, cell);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == CUE_TIMER => {
+                self.update_cues();
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == GHOST_TIMER => {
+                self.update_ghost();
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == BLIND_TIMER => {
+                self.update_blind();
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == INSPECT_TIMER => {
+                self.update_inspector();
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == WARNING_TIMER => {
+                let _ = unsafe { KillTimer(self.handle, WARNING_TIMER) };
+                self.warning = None;
+                unsafe { InvalidateRect(self.handle, None, false) };
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                if self.cues.is_some() {
+                    let _ = unsafe { KillTimer(self.handle, CUE_TIMER) };
+                }
+                if self.ghost.is_some() {
+                    let _ = unsafe { KillTimer(self.handle, GHOST_TIMER) };
+                }
+                if self.game.blind().is_some() {
+                    let _ = unsafe { KillTimer(self.handle, BLIND_TIMER) };
+                }
+                if self.inspector.is_some() {
+                    let _ = unsafe { KillTimer(self.handle, INSPECT_TIMER) };
+                }
+                let _ = unsafe { KillTimer(self.handle, WARNING_TIMER) };
+                let _ = unsafe { KillTimer(self.handle, LONG_PRESS_TIMER) };
+                let _ = unsafe { RemovePropW(self.handle, PEN_SERVICE_PROPERTY) };
+                self.release_device();
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
         }
     }
 
@@ -455,9 +2461,450 @@ impl<'a> GameBoard<'a> {
     }
 }
 
+/// Draws cells on the board's render target with its device resources.
+struct CellPainter<'a> {
+    target: &'a ID2D1RenderTarget,
+    text_format: &'a IDWriteTextFormat,
+    line_style: &'a ID2D1StrokeStyle1,
+    default_brush: &'a ID2D1SolidColorBrush,
+    cell_brush: &'a ID2D1SolidColorBrush,
+    cell_highlight: &'a ID2D1SolidColorBrush,
+    num_brush: Vec<&'a ID2D1SolidColorBrush>,
+    player_brush: Vec<&'a ID2D1SolidColorBrush>,
+    contradiction_brush: &'a ID2D1SolidColorBrush,
+    annotation_brush: Vec<&'a ID2D1SolidColorBrush>,
+    ghost_brush: &'a ID2D1SolidColorBrush,
+    auto_flag_brush: &'a ID2D1SolidColorBrush,
+    flag: &'a ID2D1Bitmap,
+    mine: &'a ID2D1Bitmap,
+    board_color: (f32, f32, f32),
+    // 0 when a system backdrop shows through around the cells
+    board_opacity: f32,
+    cell_width: f32,
+    cell_height: f32,
+    // how far the board is moved left and up, the scroll of the window or the
+    // corner of a cached chunk
+    origin: (f32, f32),
+    // drawn over the top right corner once the board is done
+    hud: Option<(String, D2D_RECT_F)>,
+    // the arcade score over the top left corner
+    score: Option<(String, D2D_RECT_F)>,
+    // the checkpoint slots over the bottom right corner
+    checkpoints: Option<(String, D2D_RECT_F)>,
+    // outlined over the cell with the keyboard focus
+    focus: Option<D2D_RECT_F>,
+    // the frame statistics below the score
+    #[cfg(feature = "dev-tools")]
+    perf: Option<(String, D2D_RECT_F)>,
+    // counts the rectangles, lines, bitmaps and texts drawn
+    primitives: &'a Cell<u32>,
+}
+
+impl CellPainter<'_> {
+    fn count(&self, primitives: u32) {
+        self.primitives.set(self.primitives.get() + primitives);
+    }
+
+    fn cell_rect(&self, x: i16, y: i16) -> D2D_RECT_F {
+        let left = x as f32 * self.cell_width - self.origin.0 + 1.0;
+        let top = y as f32 * self.cell_height - self.origin.1 + 1.0;
+        D2D_RECT_F {
+            left,
+            top,
+            right: left + self.cell_width - 2.0,
+            bottom: top + self.cell_height - 2.0,
+        }
+    }
+
+    fn draw_text(&self, text: &str, rect: &D2D_RECT_F, brush: &ID2D1SolidColorBrush) {
+        self.count(1);
+        unsafe {
+            self.target.DrawText(
+                &(text.encode_utf16().collect::<Vec<u16>>()),
+                self.text_format,
+                rect,
+                brush,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+                DWRITE_MEASURING_MODE_NATURAL,
+            );
+        }
+    }
+
+    /// Copies a cached chunk to where its cells lie.
+    fn draw_chunk(&self, bitmap: &ID2D1Bitmap, region: Region) {
+        let left = region.x as f32 * self.cell_width - self.origin.0;
+        let top = region.y as f32 * self.cell_height - self.origin.1;
+        let rect = D2D_RECT_F {
+            left,
+            top,
+            right: left + region.width as f32 * self.cell_width,
+            bottom: top + region.height as f32 * self.cell_height,
+        };
+        self.count(1);
+        unsafe {
+            self.target.DrawBitmap(
+                bitmap,
+                Some(&rect),
+                1.0,
+                D2D1_BITMAP_INTERPOLATION_MODE_NEAREST_NEIGHBOR,
+                None,
+            );
+        }
+    }
+
+    fn draw_bitmap(&self, bitmap: &ID2D1Bitmap, rect: &D2D_RECT_F) {
+        self.count(1);
+        unsafe {
+            self.target.DrawBitmap(
+                bitmap,
+                Some(rect),
+                1.0,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                None,
+            );
+        }
+    }
+}
+
+impl BoardRenderer for CellPainter<'_> {
+    type Error = Error;
+
+    fn begin(&mut self, _width: i16, _height: i16) -> Result<()> {
+        unsafe {
+            self.target.BeginDraw();
+            self.target.Clear(Some(&D2D1_COLOR_F {
+                r: self.board_color.0,
+                g: self.board_color.1,
+                b: self.board_color.2,
+                a: self.board_opacity,
+            }));
+        }
+        Ok(())
+    }
+
+    fn draw_cell(&mut self, x: i16, y: i16, state: CellState) -> Result<()> {
+        let rect = self.cell_rect(x, y);
+        self.count(1);
+        unsafe { self.target.FillRectangle(&rect, self.cell_brush) };
+        match state {
+            CellState::Flagged(_) | CellState::Questioned(_) | CellState::Unknown(_) => {
+                let (left, top) = (rect.left, rect.top);
+                self.count(2);
+                unsafe {
+                    self.target.DrawLine(
+                        D2D_POINT_2F { x: left, y: top },
+                        D2D_POINT_2F {
+                            x: left,
+                            y: rect.bottom,
+                        },
+                        self.cell_highlight,
+                        1.5,
+                        self.line_style,
+                    );
+                    self.target.DrawLine(
+                        D2D_POINT_2F { x: left, y: top },
+                        D2D_POINT_2F {
+                            x: rect.right,
+                            y: top,
+                        },
+                        self.cell_highlight,
+                        1.5,
+                        self.line_style,
+                    );
+                }
+                match state {
+                    CellState::Flagged(_) => self.draw_bitmap(self.flag, &rect),
+                    CellState::Questioned(_) => self.draw_text("?", &rect, self.default_brush),
+                    _ => {}
+                }
+            }
+            CellState::Known(true) => self.draw_bitmap(self.mine, &rect),
+            CellState::Known(false) => {}
+            CellState::Counted(count) => {
+                let brush = self.num_brush[render::count_color(count, self.num_brush.len())];
+                self.draw_text(&count.to_string(), &rect, brush);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_overlay(&mut self, x: i16, y: i16, overlay: Overlay) -> Result<()> {
+        let rect = self.cell_rect(x, y);
+        match overlay {
+            Overlay::Hint(kind) => {
+                // green for a safe cell and dark red for a mine
+                let brush = match kind {
+                    DeductionKind::Safe => self.num_brush[1],
+                    DeductionKind::Mine => self.num_brush[2],
+                };
+                self.count(1);
+                unsafe {
+                    self.target
+                        .DrawRectangle(&rect, brush, 2.0, self.line_style)
+                };
+            }
+            Overlay::Claimed(player) => {
+                let brush = self.player_brush[player as usize % self.player_brush.len()];
+                self.count(1);
+                unsafe { self.target.FillRectangle(&rect, brush) };
+            }
+            Overlay::Contradiction(count) => {
+                // covers the number in its usual color
+                self.count(1);
+                unsafe { self.target.FillRectangle(&rect, self.cell_brush) };
+                self.draw_text(&count.to_string(), &rect, self.contradiction_brush);
+            }
+            Overlay::Annotation(mark) => {
+                // a dot in the top right corner leaves the flag or question
+                // mark readable
+                let brush = self.annotation_brush[mark as usize % self.annotation_brush.len()];
+                let radius = (rect.right - rect.left).min(rect.bottom - rect.top) / 6.0;
+                let badge = D2D1_ELLIPSE {
+                    point: D2D_POINT_2F {
+                        x: rect.right - radius * 1.5,
+                        y: rect.top + radius * 1.5,
+                    },
+                    radiusX: radius,
+                    radiusY: radius,
+                };
+                self.count(1);
+                unsafe { self.target.FillEllipse(&badge, brush) };
+            }
+            Overlay::Ghost => {
+                self.count(1);
+                unsafe {
+                    self.target
+                        .DrawRectangle(&rect, self.ghost_brush, 1.0, self.line_style)
+                };
+            }
+            Overlay::AutoFlag => {
+                self.count(1);
+                unsafe { self.target.FillRectangle(&rect, self.auto_flag_brush) };
+            }
+            Overlay::Peek(count) => {
+                let brush = self.num_brush[render::count_color(count, self.num_brush.len())];
+                self.draw_text(&count.to_string(), &rect, brush);
+            }
+            Overlay::Pattern => {
+                // in the blue of the 1s
+                self.count(1);
+                unsafe {
+                    self.target
+                        .DrawRectangle(&rect, self.num_brush[0], 2.0, self.line_style)
+                };
+            }
+            #[cfg(feature = "dev-tools")]
+            Overlay::Mined => unsafe {
+                // faint so the covered cell still reads as covered
+                self.count(1);
+                self.target.DrawBitmap(
+                    self.mine,
+                    Some(&rect),
+                    DEBUG_OPACITY,
+                    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                    None,
+                );
+            },
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<()> {
+        if let Some(rect) = &self.focus {
+            self.count(1);
+            unsafe {
+                self.target
+                    .DrawRectangle(rect, self.default_brush, FOCUS_STROKE, self.line_style)
+            };
+        }
+        if let Some((text, rect)) = &self.hud {
+            self.count(1);
+            unsafe { self.target.FillRectangle(rect, self.cell_highlight) };
+            self.draw_text(text, rect, self.default_brush);
+        }
+        if let Some((text, rect)) = &self.score {
+            self.count(1);
+            unsafe { self.target.FillRectangle(rect, self.cell_highlight) };
+            self.draw_text(text, rect, self.default_brush);
+        }
+        if let Some((text, rect)) = &self.checkpoints {
+            self.count(1);
+            unsafe { self.target.FillRectangle(rect, self.cell_highlight) };
+            self.draw_text(text, rect, self.default_brush);
+        }
+        #[cfg(feature = "dev-tools")]
+        if let Some((text, rect)) = &self.perf {
+            self.count(1);
+            unsafe { self.target.FillRectangle(rect, self.cell_highlight) };
+            self.draw_text(text, rect, self.default_brush);
+        }
+        unsafe { self.target.EndDraw(None, None) }
+    }
+}
+
+/// How many cells to scroll for the cell at `start` pixels of length `size`
+/// to show in a view of length `view` scrolled to `scroll`.
+fn scroll_into_view(start: f32, size: f32, scroll: f32, view: f32) -> f32 {
+    if start < scroll {
+        (start - scroll) / size
+    } else if start + size > scroll + view {
+        (start + size - scroll - view) / size
+    } else {
+        0.0
+    }
+}
+
+/// The deduced cell and what it holds in words.
+fn describe(deduction: &Deduction) -> String {
+    let kind = match deduction.kind {
+        DeductionKind::Safe => "safe",
+        DeductionKind::Mine => "a mine",
+    };
+    format!("[{},{}] is {}", deduction.x, deduction.y, kind)
+}
+
+/// The split timer of a new board, against the ghost's run when there is
+/// one raced on it.
+fn race_timer(game: &Game, race: Option<&GhostRace>) -> Timer {
+    match race.and_then(GhostRace::ghost) {
+        Some(ghost) => Timer::against(game, Some(ghost.run().clone())),
+        None => Timer::new(game),
+    }
+}
+
+/// The player's fastest win on boards like `config`, if any.
+fn best_time(config: GameConfig) -> Option<Duration> {
+    let level = stats::level_name(config.width, config.height, config.mines);
+    let summary = stats::Stats::load().ok()?.summary();
+    let best = summary
+        .into_iter()
+        .find(|summary| summary.level == level)?
+        .best?;
+    Some(Duration::from_secs_f64(best))
+}
+
+/// Whether a mouse message was made by a finger on a touch screen, from its
+/// extra information.
+fn is_touch(extra: LPARAM) -> bool {
+    extra.0 & PEN_SIGNATURE_MASK == PEN_SIGNATURE && extra.0 & TOUCH_BIT != 0
+}
+
+/// What releasing the left or right button does.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Release {
+    /// Whatever the button is bound to.
+    Single,
+    /// Chords, the buttons were pressed together.
+    Chord,
+    /// Nothing, the other button of a chord was released first.
+    Ignored,
+}
+
+/// The left and right button presses in progress and the last click, to
+/// tell chords, long presses and double clicks from single clicks.
+#[derive(Debug, Default)]
+struct Pointer {
+    // when the left and right buttons went down, while they are down
+    left: Option<Instant>,
+    right: Option<Instant>,
+    // whether the buttons down were pressed together, until both are up
+    chord: bool,
+    // the cell a finger is down on until it lifts or is held long enough,
+    // then set as held so lifting it does nothing more
+    touch: Option<(i16, i16)>,
+    held: bool,
+    // the cell last clicked and when
+    click: Option<((i16, i16), Instant)>,
+}
+
+impl Pointer {
+    /// Notes a button going down, chording when the other one went down at
+    /// most `window` milliseconds before and is still down.
+    fn press(&mut self, left: bool, window: u32) {
+        let now = Instant::now();
+        let (pressed, other) = match left {
+            true => (&mut self.left, self.right),
+            false => (&mut self.right, self.left),
+        };
+        *pressed = Some(now);
+        let window = Duration::from_millis(window as u64);
+        if other.is_some_and(|other| window > Duration::ZERO && now - other <= window) {
+            self.chord = true;
+            self.click = None;
+        }
+    }
+
+    /// Notes a button going up. The first of two buttons pressed together
+    /// chords and the second does nothing.
+    fn release(&mut self, left: bool) -> Release {
+        let (released, other) = match left {
+            true => (&mut self.left, self.right),
+            false => (&mut self.right, self.left),
+        };
+        *released = None;
+        match (self.chord, other.is_some()) {
+            (false, _) => Release::Single,
+            (true, true) => Release::Chord,
+            (true, false) => {
+                self.chord = false;
+                Release::Ignored
+            }
+        }
+    }
+
+    /// Notes a click on `cell`, `None` for a cell that does not count,
+    /// whether it follows a click on the same cell by at most `window`
+    /// milliseconds. The pair is not counted again.
+    fn double_click(&mut self, cell: Option<(i16, i16)>, window: u32) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_millis(window as u64);
+        let double = self
+            .click
+            .is_some_and(|(clicked, at)| Some(clicked) == cell && now - at <= window);
+        self.click = cell
+            .filter(|_| !double && window > Duration::ZERO)
+            .map(|cell| (cell, now));
+        double
+    }
+}
+
+/// The Direct2D mode drawing text smoothed as set.
+fn text_antialias_mode(text: TextAntialias) -> D2D1_TEXT_ANTIALIAS_MODE {
+    match text {
+        TextAntialias::System => D2D1_TEXT_ANTIALIAS_MODE_DEFAULT,
+        TextAntialias::ClearType => D2D1_TEXT_ANTIALIAS_MODE_CLEARTYPE,
+        TextAntialias::Grayscale => D2D1_TEXT_ANTIALIAS_MODE_GRAYSCALE,
+        TextAntialias::Aliased => D2D1_TEXT_ANTIALIAS_MODE_ALIASED,
+    }
+}
+
 fn mouse_position(lparam: LPARAM) -> (f32, f32) {
     (
         (lparam.0 & 0x0000_FFFF) as f32,
         ((lparam.0 & 0xFFFF_0000) >> 16) as f32,
     )
 }
+
+/// The bold, centered text format of the counts in `font`, or
+/// [`DEFAULT_FONT`] when not set.
+fn create_text_format(
+    write_factory: &IDWriteFactory,
+    font: Option<&str>,
+) -> Result<IDWriteTextFormat> {
+    let text_format = unsafe {
+        write_factory.CreateTextFormat(
+            &HSTRING::from(font.unwrap_or(DEFAULT_FONT)),
+            None,
+            DWRITE_FONT_WEIGHT_BOLD,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            14.0,
+            &HSTRING::from("en-US"),
+        )?
+    };
+    unsafe {
+        text_format.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_CENTER)?;
+        text_format.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER)?;
+    }
+    Ok(text_format)
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    game::{Game, GameState},
+    save, solver,
+};
+
+/// Layouts tried per board before giving up on finding a no guess board.
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// What `generate` writes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Settings {
+    pub(crate) count: usize,
+    pub(crate) width: i16,
+    pub(crate) height: i16,
+    pub(crate) mines: u16,
+    pub(crate) out: PathBuf,
+    /// Only keep layouts the solver can clear from an opening without guessing.
+    pub(crate) no_guess: bool,
+    /// Seed of the first layout, later layouts use the following seeds.
+    pub(crate) seed: u64,
+}
+
+/// Writes `count` layouts to numbered save files in the output directory and
+/// returns the number of layouts generated.
+///
+/// No guess boards are saved with their opening uncovered since the guarantee
+/// only holds when the solver starts from there.
+pub(crate) fn run(settings: &Settings) -> io::Result<usize> {
+    fs::create_dir_all(&settings.out)?;
+    let mut seed = settings.seed;
+    let mut attempts = 0;
+    for index in 0..settings.count {
+        let game = loop {
+            attempts += 1;
+            let mut game = Game::with_mines(settings.width, settings.height, settings.mines);
+            game.reset_with_seed(seed);
+            seed = seed.wrapping_add(1);
+            if !settings.no_guess {
+                break game;
+            }
+            if let Some(opening) = no_guess_opening(&game) {
+                game.uncover(opening.0, opening.1);
+                break game;
+            }
+            if attempts >= MAX_ATTEMPTS * (index + 1) {
+                return Err(io::Error::other(
+                    "no guess layouts are too rare at this density, try fewer mines",
+                ));
+            }
+        };
+        let path = settings.out.join(format!("board-{:05}.txt", index + 1));
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        save::write(&game, &mut file)?;
+        file.flush()?;
+    }
+    Ok(attempts)
+}
+
+/// Returns the opening the solver clears the board from, if it can.
+fn no_guess_opening(game: &Game) -> Option<(i16, i16)> {
+    let (x, y) = solver::opening(game)?;
+    let mut played = game.clone();
+    played.uncover(x, y);
+    match solver::play_out(&mut played) {
+        GameState::Won => Some((x, y)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_run_no_guess() {
+        let out = std::env::temp_dir().join(format!("generate-test-{}", std::process::id()));
+        let settings = Settings {
+            count: 3,
+            width: 9,
+            height: 9,
+            mines: 10,
+            out: out.clone(),
+            no_guess: true,
+            seed: 1,
+        };
+        run(&settings).unwrap();
+        for index in 1..=3 {
+            let mut game = save::load(out.join(format!("board-{:05}.txt", index))).unwrap();
+            assert_eq!(GameState::Playing, game.state());
+            assert_eq!(GameState::Won, solver::play_out(&mut game));
+        }
+        assert!(save::load(out.join("board-00004.txt")).is_err());
+        fs::remove_dir_all(out).unwrap();
+    }
+}
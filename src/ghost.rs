@@ -0,0 +1,255 @@
+//! Racing the ghost of the player's best run.
+//!
+//! The replay of the fastest win on each level is kept in the `ghosts`
+//! folder of the data directory. A board raced against it is laid out from
+//! the ghost's seed, so both play the same mines, and the ghost's moves are
+//! played back at the times it made them, measured like the
+//! [`splits`](crate::splits) from when the board was laid out.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::Result,
+    game::{Game, GameState},
+    replay::{Action, Replay},
+    splits::Run,
+    stats::{data_dir, level_name},
+};
+
+const GHOSTS_DIR: &str = "ghosts";
+
+/// A best run played back move by move alongside a live game.
+#[derive(Clone)]
+pub struct Ghost {
+    replay: Replay,
+    // the ghost's board after the steps played so far
+    game: Game,
+    played: usize,
+    run: Run,
+    finish: Option<Duration>,
+}
+
+impl Ghost {
+    /// Starts the ghost of a recorded run at the beginning of its board.
+    pub fn new(replay: Replay) -> Ghost {
+        let mut run = Run::default();
+        let mut game = replay.position(0);
+        for step in replay.steps() {
+            step.action.apply(&mut game);
+            run.update(&game, step.at);
+        }
+        let finish = replay.outcome().map(|outcome| outcome.time);
+        Ghost {
+            game: replay.position(0),
+            replay,
+            played: 0,
+            run,
+            finish,
+        }
+    }
+
+    /// Loads the ghost of the best run on a level, see
+    /// [`crate::stats::level_name`], `None` when no run was won yet.
+    pub fn load(level: &str) -> Result<Option<Ghost>> {
+        let Some(path) = ghost_path(level) else {
+            return Ok(None);
+        };
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(Ghost::new(Replay::load(path)?)))
+    }
+
+    /// The seed of the ghost's board, a race lays its board out from it.
+    pub fn seed(&self) -> Option<u64> {
+        self.replay.position(0).seed()
+    }
+
+    /// Plays the ghost's moves made up to `elapsed` after its board was laid
+    /// out, returning whether any were.
+    pub fn update(&mut self, elapsed: Duration) -> bool {
+        let due = self.replay.steps()[self.played..]
+            .iter()
+            .take_while(|step| step.at <= elapsed)
+            .count();
+        for step in &self.replay.steps()[self.played..self.played + due] {
+            step.action.apply(&mut self.game);
+        }
+        self.played += due;
+        due > 0
+    }
+
+    /// The ghost's board as far as it has played.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The cells the ghost has uncovered that `live` has not.
+    pub fn reveals(&self, live: &Game) -> Vec<(i16, i16)> {
+        let mut cells = Vec::new();
+        for y in 0..self.game.height().min(live.height()) {
+            for x in 0..self.game.width().min(live.width()) {
+                let ghost = self.game.cell_state(x, y);
+                if !ghost.is_covered() && live.cell_state(x, y).is_covered() {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    /// The splits of the ghost's whole run, to time a race against.
+    pub fn run(&self) -> &Run {
+        &self.run
+    }
+
+    /// When the ghost won its board.
+    pub fn finish(&self) -> Option<Duration> {
+        self.finish
+    }
+}
+
+/// A live game raced against the ghost of the best run on its level: the
+/// player's moves are recorded from when the board was laid out, to become
+/// the next ghost if the run is faster.
+pub struct GhostRace {
+    ghost: Option<Ghost>,
+    recording: Replay,
+    level: String,
+    started: Instant,
+}
+
+impl GhostRace {
+    /// Lays `game` out from the seed of its level's ghost, or leaves it as it
+    /// is when the level has none, and starts the race.
+    pub fn new(game: &mut Game) -> GhostRace {
+        let level = level_name(game.width(), game.height(), game.total());
+        let ghost = Ghost::load(&level).unwrap_or_else(|error| {
+            tracing::warn!(%error, level, "unable to load the ghost");
+            None
+        });
+        if let Some(seed) = ghost.as_ref().and_then(Ghost::seed) {
+            game.reset_with_seed(seed);
+        }
+        GhostRace {
+            ghost,
+            recording: Replay::new(game),
+            level,
+            started: Instant::now(),
+        }
+    }
+
+    /// Records a move the player made.
+    pub fn play(&mut self, action: Action) {
+        self.recording.push(self.started.elapsed(), action);
+    }
+
+    /// Plays the ghost's moves that are due, returning whether there were
+    /// any.
+    pub fn update(&mut self) -> bool {
+        let elapsed = self.started.elapsed();
+        self.ghost
+            .as_mut()
+            .is_some_and(|ghost| ghost.update(elapsed))
+    }
+
+    /// The ghost raced, `None` on a level not won yet.
+    pub fn ghost(&self) -> Option<&Ghost> {
+        self.ghost.as_ref()
+    }
+
+    /// Stops racing the ghost on a board that is no longer its own, such as
+    /// one pasted in.
+    pub fn leave(&mut self) {
+        self.ghost = None;
+    }
+
+    /// Keeps the run as the level's ghost if `game` was won unassisted and
+    /// faster than the ghost, returning whether it was.
+    pub fn record(&self, game: &Game) -> Result<bool> {
+        let assisted = game.practice() || game.hints() > 0 || game.assisted();
+        if game.state() != GameState::Won || assisted {
+            return Ok(false);
+        }
+        record(&self.level, &self.recording)
+    }
+}
+
+/// Saves a recorded win as the ghost of its level if it is faster than the
+/// ghost kept, returning whether it was.
+pub fn record(level: &str, replay: &Replay) -> Result<bool> {
+    let Some(outcome) = replay.outcome().filter(|o| o.state == GameState::Won) else {
+        return Ok(false);
+    };
+    let Some(path) = ghost_path(level) else {
+        return Ok(false);
+    };
+    let best = Ghost::load(level).ok().flatten().and_then(|g| g.finish());
+    if best.is_some_and(|best| best <= outcome.time) {
+        return Ok(false);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    replay.save(path)?;
+    tracing::info!(level, time = ?outcome.time, "new ghost");
+    Ok(true)
+}
+
+/// Where the ghost of a level is kept, the level name with anything but
+/// letters and digits replaced.
+fn ghost_path(level: &str) -> Option<PathBuf> {
+    let name: String = level
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    data_dir().map(|dir| dir.join(GHOSTS_DIR).join(format!("{}.txt", name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::Move;
+
+    #[test]
+    pub fn test_ghost() {
+        // 1 * 1 0 0 0 0 0 0 1 * 1
+        let mut layout = [false; 12];
+        layout[1] = true;
+        layout[10] = true;
+        let game = Game::from_layout(12, 1, &layout);
+        let mut replay = Replay::new(&game);
+        let at = Duration::from_millis;
+        replay.push(at(800), Action::Play(Move::Uncover(0, 0)));
+        replay.push(at(2000), Action::Play(Move::Uncover(5, 0)));
+        replay.push(at(2500), Action::Play(Move::Uncover(11, 0)));
+
+        let mut ghost = Ghost::new(replay);
+        assert_eq!(Some(at(2500)), ghost.finish());
+        assert_eq!(Some(at(2000)), ghost.run().time(crate::splits::Split::Half));
+        assert!(!ghost.update(at(500)));
+        assert!(ghost.reveals(&game).is_empty());
+        assert!(ghost.update(at(2100)));
+        assert!(!ghost.game().cell_state(5, 0).is_covered());
+        assert!(ghost.game().cell_state(11, 0).is_covered());
+
+        // the cells the player has opened too are left out
+        let mut live = game.clone();
+        live.uncover(0, 0);
+        let reveals = ghost.reveals(&live);
+        assert!(reveals.contains(&(5, 0)) && !reveals.contains(&(0, 0)));
+        assert!(!ghost.update(at(2100)));
+        assert!(ghost.update(at(3000)));
+        assert_eq!(GameState::Won, ghost.game().state());
+    }
+
+    #[test]
+    pub fn test_ghost_path() {
+        let path = ghost_path("16x16/40").unwrap();
+        assert_eq!("16x16-40.txt", path.file_name().unwrap().to_str().unwrap());
+    }
+}
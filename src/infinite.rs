@@ -0,0 +1,359 @@
+//! An endless board generated lazily in square chunks as the player explores.
+//!
+//! The board is stored as chunks of [`CHUNK_SIZE`] by [`CHUNK_SIZE`] cells
+//! keyed by their chunk coordinates, and a chunk is only laid out when a move
+//! or the [`Viewport`] being shown reaches it. Every chunk is seeded from the
+//! board's seed and its coordinates, so the same seed always gives the same
+//! board whatever order it is explored in. The cells around the origin never
+//! hold a mine so the first move at `[0,0]` opens an area. There is no win,
+//! the score is the number of safe cells uncovered before a mine is hit.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{CellState, Game, GameState};
+
+/// Width and height of a chunk in cells.
+pub const CHUNK_SIZE: i64 = 16;
+/// Share of the cells holding a mine when no density is given.
+pub const DEFAULT_DENSITY: f64 = 0.16;
+/// Lowest density allowed, below it the empty areas a single move opens
+/// could grow without end.
+pub const MIN_DENSITY: f64 = 0.12;
+/// Highest density allowed.
+pub const MAX_DENSITY: f64 = 0.5;
+// the most cells one move uncovers, the rest of a very large empty area stays
+// covered for the player to open
+const FLOOD_LIMIT: usize = 4096;
+
+/// The part of the board shown to the player, in board coordinates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    /// Column of the left edge.
+    pub x: i64,
+    /// Row of the top edge.
+    pub y: i64,
+    /// Number of columns shown.
+    pub width: i16,
+    /// Number of rows shown.
+    pub height: i16,
+}
+
+impl Viewport {
+    /// A viewport of the given size with the origin in its middle.
+    pub fn centered(width: i16, height: i16) -> Self {
+        Viewport {
+            x: -(width as i64 / 2),
+            y: -(height as i64 / 2),
+            width,
+            height,
+        }
+    }
+
+    /// Moves the viewport by the given number of columns and rows.
+    pub fn scroll(&mut self, dx: i64, dy: i64) {
+        self.x += dx;
+        self.y += dy;
+    }
+
+    /// The board coordinates of a cell in the viewport.
+    pub fn to_board(&self, x: i16, y: i16) -> (i64, i64) {
+        (self.x + x as i64, self.y + y as i64)
+    }
+}
+
+struct Chunk {
+    cells: Vec<CellState>,
+}
+
+/// An endless board, see the module documentation.
+pub struct InfiniteBoard {
+    seed: u64,
+    density: f64,
+    chunks: BTreeMap<(i64, i64), Chunk>,
+    state: GameState,
+    uncovered: usize,
+    flags: usize,
+}
+
+impl InfiniteBoard {
+    /// Starts a board laid out from `seed` with `density` of the cells mined,
+    /// kept between [`MIN_DENSITY`] and [`MAX_DENSITY`].
+    pub fn new(seed: u64, density: f64) -> Self {
+        InfiniteBoard {
+            seed,
+            density: density.clamp(MIN_DENSITY, MAX_DENSITY),
+            chunks: BTreeMap::new(),
+            state: GameState::Initial,
+            uncovered: 0,
+            flags: 0,
+        }
+    }
+
+    /// The seed the chunks are laid out from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Share of the cells holding a mine.
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    /// Initial until the first move, playing until a mine is uncovered and
+    /// then lost. An endless board is never won.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// The number of safe cells uncovered, the score of the game.
+    pub fn uncovered(&self) -> usize {
+        self.uncovered
+    }
+
+    /// The number of flags placed.
+    pub fn flags(&self) -> usize {
+        self.flags
+    }
+
+    /// The number of chunks laid out so far.
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether the chunk holding the cell has been laid out.
+    pub fn is_generated(&self, x: i64, y: i64) -> bool {
+        self.chunks.contains_key(&chunk_key(x, y))
+    }
+
+    /// The state of a cell, `None` if its chunk has not been laid out yet.
+    pub fn cell_state(&self, x: i64, y: i64) -> Option<CellState> {
+        self.chunks
+            .get(&chunk_key(x, y))
+            .map(|chunk| chunk.cells[chunk_index(x, y)])
+    }
+
+    /// Lays out every chunk the viewport and the cells bordering it touch,
+    /// so the numbers along its edges can be counted. Returns the number of
+    /// chunks laid out.
+    pub fn generate(&mut self, viewport: &Viewport) -> usize {
+        let before = self.chunks.len();
+        let (left, top) = chunk_key(viewport.x - 1, viewport.y - 1);
+        let (right, bottom) = chunk_key(
+            viewport.x + viewport.width as i64,
+            viewport.y + viewport.height as i64,
+        );
+        for cy in top..=bottom {
+            for cx in left..=right {
+                self.chunk((cx, cy));
+            }
+        }
+        self.chunks.len() - before
+    }
+
+    /// The cells in the viewport as a game the renderers can draw, laying
+    /// out the chunks it needs first. A lost board shows its mines.
+    pub fn view(&mut self, viewport: &Viewport) -> Game {
+        self.generate(viewport);
+        let mut cells = Vec::with_capacity(viewport.width as usize * viewport.height as usize);
+        for y in 0..viewport.height {
+            for x in 0..viewport.width {
+                let (x, y) = viewport.to_board(x, y);
+                cells.push(self.state_at(x, y));
+            }
+        }
+        Game::from_cells(
+            viewport.width,
+            viewport.height,
+            cells,
+            self.state,
+            Duration::ZERO,
+        )
+    }
+
+    /// Uncovers a cell, opening the empty area around it across chunks. Does
+    /// nothing once the game is lost or if the cell is flagged or already
+    /// uncovered.
+    pub fn uncover(&mut self, x: i64, y: i64) -> GameState {
+        if self.state == GameState::Lost {
+            return self.state;
+        }
+        self.state = GameState::Playing;
+        let mut pending = vec![(x, y)];
+        let mut opened = 0;
+        while let Some((x, y)) = pending.pop() {
+            if opened == FLOOD_LIMIT {
+                break;
+            }
+            let mined = match self.state_at(x, y) {
+                CellState::Unknown(mined) | CellState::Questioned(mined) => mined,
+                _ => continue,
+            };
+            if mined {
+                self.set(x, y, CellState::Known(true));
+                self.state = GameState::Lost;
+                tracing::debug!(x, y, uncovered = self.uncovered, "infinite board lost");
+                break;
+            }
+            let count = self.count(x, y);
+            opened += 1;
+            self.uncovered += 1;
+            if count == 0 {
+                self.set(x, y, CellState::Known(false));
+                pending.extend(neighbors(x, y));
+            } else {
+                self.set(x, y, CellState::Counted(count));
+            }
+        }
+        self.state
+    }
+
+    /// Flags a covered cell or takes the flag off it again.
+    pub fn flag(&mut self, x: i64, y: i64) {
+        if self.state == GameState::Lost {
+            return;
+        }
+        match self.state_at(x, y) {
+            CellState::Unknown(mined) | CellState::Questioned(mined) => {
+                self.set(x, y, CellState::Flagged(mined));
+                self.flags += 1;
+            }
+            CellState::Flagged(mined) => {
+                self.set(x, y, CellState::Unknown(mined));
+                self.flags -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Uncovers the covered neighbors of a number once as many flags as its
+    /// count surround it.
+    pub fn chord(&mut self, x: i64, y: i64) -> GameState {
+        let CellState::Counted(count) = self.state_at(x, y) else {
+            return self.state;
+        };
+        let flagged = neighbors(x, y)
+            .filter(|&(x, y)| matches!(self.state_at(x, y), CellState::Flagged(_)))
+            .count();
+        if flagged == count as usize {
+            for (x, y) in neighbors(x, y) {
+                self.uncover(x, y);
+            }
+        }
+        self.state
+    }
+
+    // the state of a cell, laying out its chunk if needed
+    fn state_at(&mut self, x: i64, y: i64) -> CellState {
+        self.chunk(chunk_key(x, y)).cells[chunk_index(x, y)]
+    }
+
+    fn set(&mut self, x: i64, y: i64, state: CellState) {
+        self.chunk(chunk_key(x, y)).cells[chunk_index(x, y)] = state;
+    }
+
+    fn count(&mut self, x: i64, y: i64) -> u8 {
+        neighbors(x, y)
+            .filter(|&(x, y)| self.state_at(x, y).is_mined())
+            .count() as u8
+    }
+
+    fn chunk(&mut self, key: (i64, i64)) -> &mut Chunk {
+        let (seed, density) = (self.seed, self.density);
+        self.chunks.entry(key).or_insert_with(|| {
+            let mut rng = StdRng::seed_from_u64(chunk_seed(seed, key));
+            let cells = (0..CHUNK_SIZE * CHUNK_SIZE)
+                .map(|i| {
+                    let x = key.0 * CHUNK_SIZE + i % CHUNK_SIZE;
+                    let y = key.1 * CHUNK_SIZE + i / CHUNK_SIZE;
+                    // draw for every cell so the start area does not shift
+                    // the layout of the rest of the chunk
+                    let mined = rng.gen_bool(density);
+                    CellState::Unknown(mined && (x.abs() > 1 || y.abs() > 1))
+                })
+                .collect();
+            tracing::trace!(cx = key.0, cy = key.1, "chunk laid out");
+            Chunk { cells }
+        })
+    }
+}
+
+fn chunk_key(x: i64, y: i64) -> (i64, i64) {
+    (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+}
+
+fn chunk_index(x: i64, y: i64) -> usize {
+    (y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE)) as usize
+}
+
+// mixes the chunk coordinates into the board seed so neighboring chunks get
+// unrelated layouts
+fn chunk_seed(seed: u64, (cx, cy): (i64, i64)) -> u64 {
+    let mut z = seed
+        ^ (cx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (cy as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn neighbors(x: i64, y: i64) -> impl Iterator<Item = (i64, i64)> {
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+        .filter(move |&cell| cell != (x, y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_infinite_board() {
+        let mut board = InfiniteBoard::new(7, DEFAULT_DENSITY);
+        assert_eq!(0, board.chunks());
+        assert_eq!(None, board.cell_state(0, 0));
+
+        // the viewport lays out the chunks it touches and their borders
+        let viewport = Viewport::centered(10, 6);
+        assert_eq!((-5, -3), viewport.to_board(0, 0));
+        assert_eq!(4, board.generate(&viewport));
+        assert_eq!(0, board.generate(&viewport));
+
+        // the start area is safe and opens
+        assert_eq!(GameState::Playing, board.uncover(0, 0));
+        assert_eq!(Some(CellState::Known(false)), board.cell_state(0, 0));
+        assert!(board.uncovered() >= 9);
+
+        // a flagged cell is left alone
+        let mine = (2..)
+            .map(|x| (x, 40))
+            .find(|&(x, y)| board.state_at(x, y).is_mined())
+            .unwrap();
+        board.flag(mine.0, mine.1);
+        assert_eq!(1, board.flags());
+        assert_eq!(GameState::Playing, board.uncover(mine.0, mine.1));
+        board.flag(mine.0, mine.1);
+        assert_eq!(0, board.flags());
+
+        // the view shows the cells around the viewport's corner
+        let view = board.view(&viewport);
+        assert_eq!((10, 6), (view.width(), view.height()));
+        assert_eq!(CellState::Known(false), view.cell_state(5, 3));
+
+        // a mine ends the game
+        let uncovered = board.uncovered();
+        assert_eq!(GameState::Lost, board.uncover(mine.0, mine.1));
+        assert_eq!(uncovered, board.uncovered());
+        assert_eq!(GameState::Lost, board.uncover(0, 5));
+
+        // the same seed lays out the same chunks in any order
+        let mut other = InfiniteBoard::new(7, DEFAULT_DENSITY);
+        for (x, y) in [(100, -100), mine, (0, 0)] {
+            assert_eq!(
+                board.state_at(x, y).is_mined(),
+                other.state_at(x, y).is_mined()
+            );
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! What the hover inspection tells about a cell: where it is, what the
+//! player sees there and, when practicing, the chance the solver gives it of
+//! being a mine.
+//!
+//! Only what the player can see is told, a covered cell never gives its
+//! mine away and the faded numbers of a blind game stay faded.
+
+use crate::{
+    game::{CellState, Game},
+    solver,
+};
+
+/// Describes the cell at `x`, `y` as the player sees it. A covered cell is
+/// given its chance of being a mine from `probabilities`, row by row as
+/// [`solver::probabilities`] returns them, when they are passed.
+pub fn describe(game: &Game, x: i16, y: i16, probabilities: Option<&[f32]>) -> String {
+    let cell = game.cell_state(x, y);
+    let seen = match cell {
+        _ if game.faded(x, y) => "a faded number".to_string(),
+        CellState::Unknown(_) => "covered".to_string(),
+        CellState::Flagged(_) => "flagged".to_string(),
+        CellState::Questioned(_) => "marked with a question mark".to_string(),
+        CellState::Known(true) => "a mine".to_string(),
+        CellState::Known(false) => "empty".to_string(),
+        CellState::Counted(count) => {
+            let flags = solver::neighbors(game, x, y)
+                .filter(|&(nx, ny)| matches!(game.cell_state(nx, ny), CellState::Flagged(_)))
+                .count();
+            let plural = if flags == 1 { "" } else { "s" };
+            format!("{} with {} flag{} around it", count, flags, plural)
+        }
+    };
+    let mut text = format!("[{},{}] {}", x, y, seen);
+    let index = (y * game.width() + x) as usize;
+    let chance = probabilities.and_then(|probabilities| probabilities.get(index));
+    if let (true, Some(chance)) = (cell.is_covered(), chance) {
+        text.push_str(&format!(", {:.0}% chance of a mine", chance * 100.0));
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_describe() {
+        // . * . .
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        assert_eq!("[1,0] covered", describe(&game, 1, 0, None));
+        game.uncover(3, 0);
+        assert_eq!("[3,0] empty", describe(&game, 3, 0, None));
+        assert_eq!(
+            "[2,0] 1 with 0 flags around it",
+            describe(&game, 2, 0, None)
+        );
+        let probabilities = solver::probabilities(&game);
+        assert_eq!(
+            "[1,0] covered, 100% chance of a mine",
+            describe(&game, 1, 0, Some(&probabilities))
+        );
+        // uncovered cells have no chance to tell
+        assert_eq!("[3,0] empty", describe(&game, 3, 0, Some(&probabilities)));
+        game.flag(1, 0);
+        assert_eq!("[1,0] flagged", describe(&game, 1, 0, None));
+        assert_eq!("[2,0] 1 with 1 flag around it", describe(&game, 2, 0, None));
+    }
+}
@@ -0,0 +1,176 @@
+//! JSON output of games, hints, statistics and errors for scripts driving the
+//! CLI and the overlay server.
+
+use crate::{
+    game::{CellState, Game, GameState},
+    score,
+    solver::{Deduction, DeductionKind},
+    stats::Summary,
+};
+
+/// Renders the player's view of the game as a single line JSON document.
+///
+/// Revealed cells are their neighbor count, covered cells are one of
+/// `"covered"`, `"flagged"` or `"questioned"` and exploded or exposed mines are
+/// `"mine"`. The hidden layout is never included. `score` is the arcade score
+/// so far and `clicks` counts the left clicks, right clicks and chords made
+/// so far for speedrun overlays.
+pub fn game_json(game: &Game, command: &str) -> String {
+    let mut board = String::from("[");
+    for y in 0..game.height() {
+        if y > 0 {
+            board.push(',');
+        }
+        board.push('[');
+        for x in 0..game.width() {
+            if x > 0 {
+                board.push(',');
+            }
+            match game.cell_state(x, y) {
+                CellState::Unknown(_) => board.push_str("\"covered\""),
+                CellState::Flagged(_) => board.push_str("\"flagged\""),
+                CellState::Questioned(_) => board.push_str("\"questioned\""),
+                CellState::Known(true) => board.push_str("\"mine\""),
+                CellState::Known(false) => board.push('0'),
+                CellState::Counted(count) => board.push_str(&count.to_string()),
+            }
+        }
+        board.push(']');
+    }
+    board.push(']');
+    // the seed gives the layout away so it is only reported once the game is over
+    let seed = match (game.state(), game.seed()) {
+        (GameState::Won | GameState::Lost, Some(seed)) => format!(",\"seed\":{}", seed),
+        _ => String::new(),
+    };
+    let clicks = game.clicks();
+    format!(
+        "{{\"command\":{},\"state\":\"{}\",\"width\":{},\"height\":{},\"mines\":{},\"remaining\":{},\"elapsed\":{:.3}{},\"score\":{},\"clicks\":{{\"left\":{},\"right\":{},\"chords\":{}}},\"board\":{}}}",
+        string(command),
+        game.state(),
+        game.width(),
+        game.height(),
+        game.total(),
+        game.est_remaining(),
+        game.elapsed().as_secs_f64(),
+        seed,
+        score::score(game).total(),
+        clicks.uncovers,
+        clicks.flags,
+        clicks.chords,
+        board
+    )
+}
+
+/// Renders a solver hint with its reasoning.
+pub fn hint_json(command: &str, hint: &Deduction) -> String {
+    let kind = match hint.kind {
+        DeductionKind::Safe => "safe",
+        DeductionKind::Mine => "mine",
+    };
+    format!(
+        "{{\"command\":{},\"hint\":{{\"x\":{},\"y\":{},\"kind\":\"{}\",\"reason\":{}}}}}",
+        string(command),
+        hint.x,
+        hint.y,
+        kind,
+        string(&hint.explanation())
+    )
+}
+
+/// Renders the per level statistics, a level without wins has a `null` best
+/// time and one without scored games a `null` best score.
+pub fn stats_json(summary: &[Summary]) -> String {
+    let levels: Vec<String> = summary
+        .iter()
+        .map(|level| {
+            let best = match level.best {
+                Some(best) => format!("{:.3}", best),
+                None => "null".to_string(),
+            };
+            let best_score = match level.best_score {
+                Some(score) => score.to_string(),
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"level\":{},\"played\":{},\"won\":{},\"win_rate\":{:.1},\"best\":{},\"best_score\":{}}}",
+                string(&level.level),
+                level.played,
+                level.won,
+                level.win_rate(),
+                best,
+                best_score
+            )
+        })
+        .collect();
+    format!("{{\"levels\":[{}]}}", levels.join(","))
+}
+
+/// Renders a command that could not be carried out.
+pub fn error_json(command: &str, message: &str) -> String {
+    format!(
+        "{{\"command\":{},\"error\":{}}}",
+        string(command),
+        string(message)
+    )
+}
+
+/// Quotes and escapes a string value.
+pub fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_game_json() {
+        let mut layout = vec![false; 4];
+        layout[3] = true;
+        let mut game = Game::from_layout(2, 2, &layout);
+        game.uncover(0, 0);
+        game.flag(1, 1);
+        let json = game_json(&game, "u[0,0]");
+        assert!(json.starts_with(
+            "{\"command\":\"u[0,0]\",\"state\":\"playing\",\"width\":2,\"height\":2,\"mines\":1,\"remaining\":0,\"elapsed\":"
+        ));
+        assert!(json.ends_with(
+            "\"clicks\":{\"left\":1,\"right\":1,\"chords\":0},\"board\":[[1,\"covered\"],[\"covered\",\"flagged\"]]}"
+        ));
+    }
+
+    #[test]
+    pub fn test_stats_json() {
+        let summary = [Summary {
+            level: "beginner".to_string(),
+            played: 4,
+            won: 1,
+            best: Some(12.5),
+            best_score: None,
+        }];
+        assert_eq!(
+            "{\"levels\":[{\"level\":\"beginner\",\"played\":4,\"won\":1,\"win_rate\":25.0,\"best\":12.500,\"best_score\":null}]}",
+            stats_json(&summary)
+        );
+    }
+
+    #[test]
+    pub fn test_string_escape() {
+        assert_eq!("\"a\\\"b\\\\c\\n\"", string("a\"b\\c\n"));
+    }
+}
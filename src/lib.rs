@@ -0,0 +1,130 @@
+//! Minesweeper game engine shared by the Direct2D GUI and the CLI.
+//!
+//! [`game::Game`] holds a board and applies moves to it and [`solver`]
+//! deduces safe cells and mines from the player's view. Around them:
+//!
+//! - Playing: [`versus`] keeps the score of two players sharing a board,
+//!   [`race`] lets two players race on identical boards over TCP, [`multi`]
+//!   plays several boards at once and [`infinite`] lays out an endless board
+//!   in chunks as it is explored.
+//! - Practice: [`drill`] lays out timed pattern drills, [`patterns`] names
+//!   the patterns a hint teaches, [`inspect`] describes the cell under the
+//!   pointer and [`checkpoint`] keeps checkpoints to go back to.
+//! - Speedrunning: [`splits`] times splits against the player's best run,
+//!   [`ghost`] races its replay and [`score`] counts arcade points.
+//! - Recording: [`replay`] records and plays back games and [`simulate`]
+//!   plays batches of games without a user interface.
+//! - Files: [`save`] and [`json`] serialize games and [`csv`] exports the
+//!   statistics. Loading and saving fail with an [`error::MinesweeperError`].
+//! - Sharing: [`snapshot`] draws boards as PNG images, [`print`](mod@print)
+//!   as PDF puzzles to solve on paper, [`serve`] publishes the game for
+//!   stream overlays and [`share`] sums up a finished game without spoilers.
+//!
+//! The remaining modules hold the per user statistics and settings, the mouse
+//! and key [`bindings`], the sound [`cues`], the background [`music`], the
+//! [`skin`] packs and [`theme`] files, the window [`assets`], the [`crash`]
+//! reports and the terminal renderer.
+//!
+//! Optional features add more. The `ffi` feature adds a C interface in `ffi`
+//! and the `python` feature a Python module in `python`. The `dev-tools`
+//! feature adds the debug console commands in `console` and the frame
+//! statistics of the performance overlay in `perf`. The default `logging`
+//! feature writes the traces the crate emits to a log file in `logging`.
+//!
+//! Without the default `std` feature the crate is `no_std` and needs only
+//! `alloc`: [`game`], [`solver`] and [`render`] are built without timers,
+//! entropy or the `Display` and `FromStr` text forms, for handhelds and other
+//! embedded targets.
+//!
+//! ```
+//! use minesweeper_d2d::{game::{Game, GameState}, solver};
+//!
+//! let mut game = Game::with_mines(9, 9, 10);
+//! game.reset_with_seed(42);
+//! if let Some((x, y)) = solver::opening(&game) {
+//!     game.uncover(x, y);
+//!     assert_ne!(GameState::Lost, solver::play_out(&mut game));
+//! }
+//! ```
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod assets;
+#[cfg(feature = "std")]
+pub mod bindings;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(all(feature = "std", feature = "dev-tools"))]
+pub mod console;
+#[cfg(feature = "std")]
+pub mod crash;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod cues;
+#[cfg(feature = "std")]
+pub mod drill;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod game;
+#[cfg(feature = "std")]
+pub mod ghost;
+#[cfg(feature = "std")]
+pub mod infinite;
+#[cfg(feature = "std")]
+pub mod inspect;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "logging")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod multi;
+#[cfg(feature = "std")]
+pub mod music;
+#[cfg(all(feature = "std", feature = "dev-tools"))]
+pub mod perf;
+#[cfg(feature = "std")]
+pub mod patterns;
+#[cfg(feature = "std")]
+pub mod print;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod race;
+pub mod render;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod save;
+#[cfg(feature = "std")]
+pub mod score;
+#[cfg(feature = "std")]
+pub mod serve;
+#[cfg(feature = "std")]
+pub mod share;
+#[cfg(feature = "std")]
+pub mod simulate;
+#[cfg(feature = "std")]
+pub mod skin;
+#[cfg(feature = "std")]
+pub mod snapshot;
+pub mod solver;
+#[cfg(feature = "std")]
+pub mod splits;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod theme;
+#[cfg(feature = "std")]
+pub mod versus;
+#[cfg(feature = "std")]
+mod zip;
@@ -0,0 +1,68 @@
+//! Traces written to a log file so reported crashes and rendering problems
+//! can be diagnosed.
+//!
+//! The engine, the solver and both front-ends emit [`tracing`] events, which
+//! go nowhere until [`init`] installs a subscriber. Logging is off unless the
+//! config file sets a level with `log = debug`, or the CLI is started with
+//! `--log`.
+
+use std::{fs, io, panic};
+
+use tracing::Level;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+
+use crate::{crash::RecentEvents, error::Result, stats::data_dir};
+
+/// Directory below the per user data directory holding the log files.
+const LOG_DIR: &str = "logs";
+/// Name of the log files, suffixed with the date they were written on.
+const LOG_FILE: &str = "minesweeper.log";
+
+/// Writes traces at `level` and above to a log file in the per user data
+/// directory, starting a new file every day, and logs panics before the
+/// default panic message is printed. The latest lines are also kept in
+/// memory for a [crash report](crate::crash).
+///
+/// Lines are written on a background thread, keep the returned guard alive
+/// until exit so the last ones are flushed. Returns `None` when there is no
+/// data directory to log to.
+pub fn init(level: Level) -> Result<Option<WorkerGuard>> {
+    let Some(dir) = data_dir() else {
+        return Ok(None);
+    };
+    let dir = dir.join(LOG_DIR);
+    fs::create_dir_all(&dir)?;
+    let (writer, guard) = tracing_appender::non_blocking(rolling::daily(dir, LOG_FILE));
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer.and(|| RecentEvents))
+        .with_ansi(false)
+        .try_init()
+        .map_err(io::Error::other)?;
+    log_panics();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "logging started");
+    Ok(Some(guard))
+}
+
+/// Keeps traces at `level` and above in memory for a
+/// [crash report](crate::crash) without writing a log file, used when
+/// logging is off.
+pub fn init_recent(level: Level) -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(|| RecentEvents)
+        .with_ansi(false)
+        .try_init()
+        .map_err(io::Error::other)?;
+    log_panics();
+    Ok(())
+}
+
+fn log_panics() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tracing::error!("{}", info);
+        previous(info);
+    }));
+}
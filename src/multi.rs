@@ -0,0 +1,216 @@
+//! Rounds of two to four small boards played at the same time.
+//!
+//! A [`MultiBoard`] holds the games of a round and tiles them into one
+//! composite board, two side by side and three or four in a square, with a
+//! [`GUTTER`] of empty cells between them. Moves are given in composite
+//! coordinates and passed to the board under them. The round is won once
+//! every board is cleared and lost as soon as any of them blows up, which
+//! ends play on all of them.
+
+use std::time::Duration;
+
+use crate::{
+    game::{Game, GameConfig, GameState, Move},
+    render::{self, BoardRenderer, Overlay},
+};
+
+/// Fewest boards in a round.
+pub const MIN_BOARDS: usize = 2;
+/// Most boards in a round.
+pub const MAX_BOARDS: usize = 4;
+/// Empty cells between tiled boards.
+pub const GUTTER: i16 = 1;
+
+/// The boards of a round and where they are tiled.
+#[derive(Clone)]
+pub struct MultiBoard {
+    games: Vec<Game>,
+    origins: Vec<(i16, i16)>,
+    width: i16,
+    height: i16,
+}
+
+impl MultiBoard {
+    /// Starts a round of `count` boards like `config`, kept between
+    /// [`MIN_BOARDS`] and [`MAX_BOARDS`]. The boards are laid out from
+    /// `seed`, `seed + 1` and so on.
+    pub fn new(count: usize, config: GameConfig, seed: u64) -> Self {
+        let count = count.clamp(MIN_BOARDS, MAX_BOARDS);
+        // two boards side by side, more in rows of two
+        let columns = 2;
+        let rows = count.div_ceil(columns) as i16;
+        let origins = (0..count)
+            .map(|i| {
+                let column = (i % columns) as i16;
+                let row = (i / columns) as i16;
+                (
+                    column * (config.width + GUTTER),
+                    row * (config.height + GUTTER),
+                )
+            })
+            .collect();
+        let mut board = MultiBoard {
+            games: (0..count)
+                .map(|_| Game::with_config(config.clone()))
+                .collect(),
+            origins,
+            width: columns as i16 * (config.width + GUTTER) - GUTTER,
+            height: rows * (config.height + GUTTER) - GUTTER,
+        };
+        board.reset_with_seed(seed);
+        board
+    }
+
+    /// Lays out new boards for the next round from `seed` on.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        for (i, game) in self.games.iter_mut().enumerate() {
+            game.reset_with_seed(seed.wrapping_add(i as u64));
+        }
+    }
+
+    /// The games of the round.
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    /// Width of the composite board in cells.
+    pub fn width(&self) -> i16 {
+        self.width
+    }
+
+    /// Height of the composite board in cells.
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    /// Where a board's top left cell lies on the composite board.
+    pub fn origin(&self, board: usize) -> (i16, i16) {
+        self.origins[board]
+    }
+
+    /// The board under a cell of the composite board and the cell on that
+    /// board, `None` over a gutter or past the last board.
+    pub fn locate(&self, x: i16, y: i16) -> Option<(usize, i16, i16)> {
+        self.origins
+            .iter()
+            .zip(&self.games)
+            .position(|(&(left, top), game)| {
+                x >= left && y >= top && x < left + game.width() && y < top + game.height()
+            })
+            .map(|board| {
+                let (left, top) = self.origins[board];
+                (board, x - left, y - top)
+            })
+    }
+
+    /// Plays a move at a cell of the composite board. Once a board blows up
+    /// the mines of every board are shown and no more moves are taken.
+    pub fn play(&mut self, mv: Move) -> GameState {
+        if matches!(self.state(), GameState::Won | GameState::Lost) {
+            return self.state();
+        }
+        let (x, y) = mv.cell();
+        if let Some((board, x, y)) = self.locate(x, y) {
+            let game = &mut self.games[board];
+            game.apply(mv.at(x, y));
+            if game.state() == GameState::Lost {
+                tracing::debug!(board, "round lost");
+                for game in &mut self.games {
+                    game.show_mined();
+                }
+            }
+        }
+        self.state()
+    }
+
+    /// Lost if any board is, won once all of them are cleared, initial until
+    /// the first move and playing otherwise.
+    pub fn state(&self) -> GameState {
+        let states = || self.games.iter().map(Game::state);
+        if states().any(|state| state == GameState::Lost) {
+            GameState::Lost
+        } else if states().all(|state| state == GameState::Won) {
+            GameState::Won
+        } else if states().all(|state| state == GameState::Initial) {
+            GameState::Initial
+        } else {
+            GameState::Playing
+        }
+    }
+
+    /// Time since the first move of the round, the longest of the boards'
+    /// timers.
+    pub fn elapsed(&self) -> Duration {
+        self.games
+            .iter()
+            .map(Game::elapsed)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Mines left to flag on all the boards together.
+    pub fn est_remaining(&self) -> u32 {
+        self.games
+            .iter()
+            .map(|game| game.est_remaining() as u32)
+            .sum()
+    }
+
+    /// Draws the boards tiled, with overlays given in composite coordinates.
+    pub fn draw<R: BoardRenderer>(
+        &self,
+        renderer: &mut R,
+        overlays: &[(i16, i16, Overlay)],
+    ) -> Result<(), R::Error> {
+        let tiles: Vec<_> = self
+            .origins
+            .iter()
+            .zip(&self.games)
+            .map(|(&(x, y), game)| (x, y, game))
+            .collect();
+        render::draw_tiled(renderer, self.width, self.height, &tiles, overlays)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::{CellState, Difficulty};
+
+    #[test]
+    pub fn test_multi_board() {
+        let config = Difficulty::Beginner.config();
+        let mut round = MultiBoard::new(3, config, 42);
+        assert_eq!(3, round.games().len());
+        assert_eq!((19, 19), (round.width(), round.height()));
+        assert_eq!((0, 10), round.origin(2));
+        assert_eq!(Some((1, 2, 3)), round.locate(12, 3));
+        assert_eq!(None, round.locate(9, 3));
+        assert_eq!(None, round.locate(12, 12));
+        assert_eq!(GameState::Initial, round.state());
+
+        // a safe move on one board only touches that board
+        let (x, y) = (0..81)
+            .map(|i| (i % 9, i / 9))
+            .find(|&(x, y)| round.games()[1].cell_state(x, y) == CellState::Unknown(false))
+            .unwrap();
+        assert_eq!(GameState::Playing, round.play(Move::Uncover(x + 10, y)));
+        assert_ne!(CellState::Unknown(false), round.games()[1].cell_state(x, y));
+        assert_eq!(GameState::Initial, round.games()[0].state());
+
+        // a mine on any board loses the round and shows every board's mines
+        let (x, y) = (0..81)
+            .map(|i| (i % 9, i / 9))
+            .find(|&(x, y)| round.games()[2].cell_state(x, y) == CellState::Unknown(true))
+            .unwrap();
+        assert_eq!(GameState::Lost, round.play(Move::Uncover(x, y + 10)));
+        let shown =
+            |game: &Game| (0..81).any(|i| game.cell_state(i % 9, i / 9) == CellState::Known(true));
+        assert!(round.games().iter().all(shown));
+        assert_eq!(GameState::Lost, round.play(Move::Flag(0, 0)));
+        assert!(!matches!(
+            round.games()[0].cell_state(0, 0),
+            CellState::Flagged(_)
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//! Background music streamed from WAV files.
+//!
+//! A track comes in two stems, one for a game being played and one for a
+//! game that is over. Both loop in step and [`Fader`] crossfades between
+//! them, so the music changes mood without starting over. [`WavStream`]
+//! reads a stem a little at a time so only a few buffers are ever held in
+//! memory.
+
+use std::{
+    f32::consts::FRAC_PI_2,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::error::{MinesweeperError, Result};
+
+/// Time a crossfade between the stems takes.
+pub const CROSSFADE: Duration = Duration::from_secs(2);
+
+const WAVE_FORMAT_PCM: u16 = 1;
+
+/// A 16 bit PCM WAV file read from start to end and over again.
+#[derive(Debug)]
+pub struct WavStream<R> {
+    reader: R,
+    channels: u16,
+    sample_rate: u32,
+    data_start: u64,
+    data_len: u64,
+    position: u64,
+}
+
+impl WavStream<BufReader<File>> {
+    /// Opens a WAV file for streaming.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        WavStream::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> WavStream<R> {
+    /// Reads the header of a WAV file up to its samples.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut riff = [0; 12];
+        reader.read_exact(&mut riff)?;
+        if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+            return Err(invalid("not a WAV file"));
+        }
+        let mut format = None;
+        loop {
+            let mut header = [0; 8];
+            reader.read_exact(&mut header)?;
+            let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+            match &header[0..4] {
+                b"fmt " => {
+                    let mut fmt = vec![0; size as usize];
+                    reader.read_exact(&mut fmt)?;
+                    if fmt.len() < 16 {
+                        return Err(invalid("truncated WAV format"));
+                    }
+                    let field = |at: usize| u16::from_le_bytes([fmt[at], fmt[at + 1]]);
+                    if field(0) != WAVE_FORMAT_PCM || field(14) != 16 || field(2) == 0 {
+                        return Err(invalid("only 16 bit PCM WAV files can be played"));
+                    }
+                    let rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    format = Some((field(2), rate));
+                }
+                b"data" => {
+                    let Some((channels, sample_rate)) = format else {
+                        return Err(invalid("WAV samples before their format"));
+                    };
+                    let data_start = reader.stream_position()?;
+                    // whole frames only
+                    let frame = channels as u64 * 2;
+                    return Ok(WavStream {
+                        reader,
+                        channels,
+                        sample_rate,
+                        data_start,
+                        data_len: size - size % frame,
+                        position: 0,
+                    });
+                }
+                // chunks are padded to an even size
+                _ => {
+                    reader.seek(SeekFrom::Current((size + size % 2) as i64))?;
+                }
+            }
+        }
+    }
+
+    /// Number of interleaved channels.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Frames per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of frames, a sample of every channel each, in the file.
+    pub fn frames(&self) -> u64 {
+        self.data_len / (self.channels as u64 * 2)
+    }
+
+    /// Fills `samples` with the next interleaved samples, going back to the
+    /// start at the end of the file. A file without samples gives silence.
+    pub fn fill(&mut self, samples: &mut [i16]) -> Result<()> {
+        if self.data_len == 0 {
+            samples.fill(0);
+            return Ok(());
+        }
+        let mut bytes = [0; 2];
+        for sample in samples {
+            if self.position == self.data_len {
+                self.reader.seek(SeekFrom::Start(self.data_start))?;
+                self.position = 0;
+            }
+            self.reader.read_exact(&mut bytes)?;
+            self.position += 2;
+            *sample = i16::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> MinesweeperError {
+    MinesweeperError::Parse(message.to_string())
+}
+
+/// One of the two stems of a track.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stem {
+    /// Played while a game is in progress.
+    Playing,
+    /// Played once a game is won or lost.
+    GameOver,
+}
+
+/// Crossfades between the stems, keeping the loudness even on the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fader {
+    // 0 is all Playing, 1 all GameOver
+    position: f32,
+    target: Stem,
+}
+
+impl Default for Fader {
+    fn default() -> Self {
+        Fader {
+            position: 0.0,
+            target: Stem::Playing,
+        }
+    }
+}
+
+impl Fader {
+    /// Starts fading towards `stem`, from wherever the fader is now.
+    pub fn fade_to(&mut self, stem: Stem) {
+        self.target = stem;
+    }
+
+    /// Moves the fade on by `elapsed` and returns the gains of the
+    /// [`Stem::Playing`] and [`Stem::GameOver`] stems.
+    pub fn step(&mut self, elapsed: Duration) -> [f32; 2] {
+        let target = match self.target {
+            Stem::Playing => 0.0,
+            Stem::GameOver => 1.0,
+        };
+        let step = elapsed.as_secs_f32() / CROSSFADE.as_secs_f32();
+        self.position = if self.position < target {
+            (self.position + step).min(target)
+        } else {
+            (self.position - step).max(target)
+        };
+        let angle = self.position * FRAC_PI_2;
+        [angle.cos(), angle.sin()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn wav(bits: u16, samples: &[i16]) -> Vec<u8> {
+        let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+        file.extend_from_slice(b"LIST\x03\0\0\0abc\0");
+        file.extend_from_slice(b"fmt \x10\0\0\0");
+        for field in [1u16, 1] {
+            file.extend_from_slice(&field.to_le_bytes());
+        }
+        file.extend_from_slice(&8000u32.to_le_bytes());
+        file.extend_from_slice(&16000u32.to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&bits.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in samples {
+            file.extend_from_slice(&sample.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    pub fn test_wav_loops() {
+        let mut stream = WavStream::new(Cursor::new(wav(16, &[1, -2, 3]))).unwrap();
+        assert_eq!((1, 8000), (stream.channels(), stream.sample_rate()));
+        assert_eq!(3, stream.frames());
+        let mut samples = [0; 7];
+        stream.fill(&mut samples).unwrap();
+        assert_eq!([1, -2, 3, 1, -2, 3, 1], samples);
+        stream.fill(&mut samples[..2]).unwrap();
+        assert_eq!([-2, 3], samples[..2]);
+        assert!(WavStream::new(Cursor::new(wav(8, &[1]))).is_err());
+        assert!(WavStream::new(Cursor::new(b"RIFX".to_vec())).is_err());
+    }
+
+    #[test]
+    pub fn test_fader() {
+        let mut fader = Fader::default();
+        assert_eq!([1.0, 0.0], fader.step(Duration::from_millis(100)));
+        fader.fade_to(Stem::GameOver);
+        let [playing, over] = fader.step(CROSSFADE / 2);
+        assert!((playing - over).abs() < 1e-6);
+        assert!((playing * playing + over * over - 1.0).abs() < 1e-6);
+        // turning back halfway fades from where it is
+        fader.fade_to(Stem::Playing);
+        let [playing, _] = fader.step(CROSSFADE / 4);
+        assert!(playing > over && playing < 1.0);
+        let [_, over] = fader.step(CROSSFADE);
+        assert_eq!(0.0, over);
+    }
+}
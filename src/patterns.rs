@@ -0,0 +1,234 @@
+//! Recognizes the classic patterns on the player's view of the board, for
+//! hints that teach the pattern rather than only the cell it proves.
+//!
+//! The 1-2-1 and 1-2-2-1 are found along a straight wall of covered cells
+//! and the 50/50 where two covered cells share counts that cannot tell them
+//! apart. Patterns only hold under the classic
+//! [`Neighborhood::Adjacent`] rule, other rules have none.
+
+use crate::{
+    drill::Pattern,
+    game::{CellState, Game, Neighborhood},
+    render::Overlay,
+    solver::{self, DeductionKind},
+};
+
+// the counts along the wall and where their mines are, by wall index from
+// the cell before the first count
+const LINES: [(Pattern, &[u8], &[usize]); 2] = [
+    (Pattern::OneTwoOne, &[1, 2, 1], &[1, 3]),
+    (Pattern::OneTwoTwoOne, &[1, 2, 2, 1], &[2, 3]),
+];
+
+/// A pattern found on the board and what it proves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternHint {
+    /// The pattern found.
+    pub pattern: Pattern,
+    /// The counts making up the pattern.
+    pub counts: Vec<(i16, i16)>,
+    /// Covered cells the pattern proves mines.
+    pub mines: Vec<(i16, i16)>,
+    /// Covered cells the pattern proves safe.
+    pub safe: Vec<(i16, i16)>,
+    /// Covered cells no count can tell apart, for a 50/50.
+    pub guesses: Vec<(i16, i16)>,
+}
+
+impl PatternHint {
+    /// Names the pattern and says what it proves.
+    pub fn explanation(&self) -> String {
+        match self.pattern {
+            Pattern::OneTwoOne => "this is a 1-2-1: the cells beside the 1s are mines and \
+                the one beside the 2 is safe"
+                .to_string(),
+            Pattern::OneTwoTwoOne => "this is a 1-2-2-1: the cells beside the 2s are mines \
+                and the ones beside the 1s are safe"
+                .to_string(),
+            Pattern::EdgeFiftyFifty => "this is a 50/50: nothing on the board tells the two \
+                cells apart, guess one now rather than last"
+                .to_string(),
+        }
+    }
+
+    /// Outlines the counts of the pattern and marks the cells it proves as
+    /// hints.
+    pub fn overlays(&self) -> Vec<(i16, i16, Overlay)> {
+        let counts = self.counts.iter().chain(&self.guesses);
+        counts
+            .map(|&(x, y)| (x, y, Overlay::Pattern))
+            .chain(
+                self.mines
+                    .iter()
+                    .map(|&(x, y)| (x, y, Overlay::Hint(DeductionKind::Mine))),
+            )
+            .chain(
+                self.safe
+                    .iter()
+                    .map(|&(x, y)| (x, y, Overlay::Hint(DeductionKind::Safe))),
+            )
+            .collect()
+    }
+}
+
+/// Finds a pattern that proves a covered cell safe, or an unflagged one a
+/// mine, or failing that a 50/50. Flags are ignored like the solver does.
+pub fn find(game: &Game) -> Option<PatternHint> {
+    if game.neighborhood() != Neighborhood::Adjacent {
+        return None;
+    }
+    cells(game)
+        .find_map(|(x, y)| line(game, x, y))
+        .or_else(|| fifty_fifty(game))
+}
+
+fn cells(game: &Game) -> impl Iterator<Item = (i16, i16)> {
+    let width = game.width();
+    (0..game.height()).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+fn on_board(game: &Game, (x, y): (i16, i16)) -> bool {
+    x >= 0 && y >= 0 && x < game.width() && y < game.height()
+}
+
+fn covered_neighbors(game: &Game, x: i16, y: i16) -> Vec<(i16, i16)> {
+    solver::neighbors(game, x, y)
+        .filter(|&(nx, ny)| solver::is_covered(game.cell_state(nx, ny)))
+        .collect()
+}
+
+/// A 1-2-1 or 1-2-2-1 starting at `x`, `y` along a row or column with its
+/// covered cells all on one side.
+fn line(game: &Game, x: i16, y: i16) -> Option<PatternHint> {
+    // along a row with the wall above or below, then along a column
+    for ((dx, dy), (sx, sy)) in [((1, 0), (0, 1)), ((0, 1), (1, 0))] {
+        for side in [-1, 1] {
+            for (pattern, values, mined) in LINES {
+                let count_at = |i: i16| (x + i * dx, y + i * dy);
+                let wall_at = |j: i16| (x + j * dx + side * sx, y + j * dy + side * sy);
+                let matches = (0..values.len() as i16).all(|i| {
+                    let (cx, cy) = count_at(i);
+                    on_board(game, (cx, cy))
+                        && game.cell_state(cx, cy) == CellState::Counted(values[i as usize])
+                        && covered_neighbors(game, cx, cy)
+                            .iter()
+                            .all(|&(nx, ny)| (nx - cx) * sx + (ny - cy) * sy == side)
+                });
+                if !matches {
+                    continue;
+                }
+                let mut hint = PatternHint {
+                    pattern,
+                    counts: (0..values.len() as i16).map(count_at).collect(),
+                    mines: Vec::new(),
+                    safe: Vec::new(),
+                    guesses: Vec::new(),
+                };
+                for index in 0..values.len() + 2 {
+                    let cell = wall_at(index as i16 - 1);
+                    if !on_board(game, cell) || !solver::is_covered(game.cell_state(cell.0, cell.1))
+                    {
+                        continue;
+                    }
+                    match mined.contains(&index) {
+                        true => hint.mines.push(cell),
+                        false => hint.safe.push(cell),
+                    }
+                }
+                let flagged =
+                    |&(x, y): &(i16, i16)| matches!(game.cell_state(x, y), CellState::Flagged(_));
+                if !hint.safe.is_empty() || !hint.mines.iter().all(flagged) {
+                    return Some(hint);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Two side by side covered cells whose only neighbors are counts of 1
+/// touching both of them and no other covered cell.
+fn fifty_fifty(game: &Game) -> Option<PatternHint> {
+    let proven: Vec<(i16, i16)> = solver::solve(game).iter().map(|d| (d.x, d.y)).collect();
+    for (x, y) in cells(game) {
+        for (dx, dy) in [(1, 0), (0, 1)] {
+            let pair = [(x, y), (x + dx, y + dy)];
+            let covered = |&(x, y): &(i16, i16)| {
+                on_board(game, (x, y)) && solver::is_covered(game.cell_state(x, y))
+            };
+            if !pair.iter().all(covered) || pair.iter().any(|cell| proven.contains(cell)) {
+                continue;
+            }
+            let mut counts: Vec<(i16, i16)> = Vec::new();
+            let apart = pair.iter().any(|&(px, py)| {
+                solver::neighbors(game, px, py)
+                    .filter(|cell| !pair.contains(cell))
+                    .any(|(nx, ny)| {
+                        if !counts.contains(&(nx, ny)) {
+                            counts.push((nx, ny));
+                        }
+                        game.cell_state(nx, ny) != CellState::Counted(1)
+                            || covered_neighbors(game, nx, ny) != pair
+                    })
+            });
+            if !apart && !counts.is_empty() {
+                counts.sort_by_key(|&(x, y)| (y, x));
+                return Some(PatternHint {
+                    pattern: Pattern::EdgeFiftyFifty,
+                    counts,
+                    mines: Vec::new(),
+                    safe: Vec::new(),
+                    guesses: pair.to_vec(),
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{drill, save::parse_layout};
+
+    #[test]
+    pub fn test_find_lines() {
+        for seed in 0..8 {
+            let game = drill::board(Pattern::OneTwoOne, seed);
+            let hint = find(&game).unwrap();
+            assert_eq!(Pattern::OneTwoOne, hint.pattern);
+            assert_eq!(
+                (3, 2, 3),
+                (hint.counts.len(), hint.mines.len(), hint.safe.len())
+            );
+            for &(x, y) in &hint.mines {
+                assert!(game.cell_state(x, y).is_mined());
+            }
+            let game = drill::board(Pattern::OneTwoTwoOne, seed);
+            let hint = find(&game).unwrap();
+            assert_eq!(Pattern::OneTwoTwoOne, hint.pattern);
+            assert_eq!((2, 2), (hint.mines.len(), hint.safe.len()));
+        }
+        // the counts must have no covered cells off the wall
+        let game = parse_layout(".*.*.\n11211\n0000.\n").unwrap();
+        assert_eq!(None, find(&game).map(|hint| hint.pattern));
+        // a 1-2-1 with its mines flagged and safe cell open proves nothing
+        let mut game = parse_layout("1*2*1\n11211\n00000\n").unwrap();
+        game.flag(1, 0);
+        game.flag(3, 0);
+        assert_eq!(None, find(&game));
+    }
+
+    #[test]
+    pub fn test_find_fifty_fifty() {
+        let game = drill::board(Pattern::EdgeFiftyFifty, 0);
+        let hint = find(&game).unwrap();
+        assert_eq!(Pattern::EdgeFiftyFifty, hint.pattern);
+        assert_eq!((2, 2), (hint.counts.len(), hint.guesses.len()));
+        assert_eq!(4, hint.overlays().len());
+        assert!(hint.explanation().starts_with("this is a 50/50"));
+        // a third covered cell could tell them apart
+        let game = parse_layout(".*.\n111\n000\n").unwrap();
+        assert_eq!(None, find(&game));
+    }
+}
@@ -0,0 +1,103 @@
+//! Frame statistics for the performance overlay of the GUI, shown while the
+//! rendering of large boards is worked on.
+//!
+//! The board records each paint with [`FrameStats::record`]: when it started,
+//! how long it took, the primitives drawn and the share of the window that
+//! was dirty. [`FrameStats::summary`] is the line the overlay shows.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Paints counted for the frame rate are the ones started within this long
+/// of the last.
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// The paints of the last second and the measurements of the latest one.
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    // when the paints of the last second started, oldest first
+    frames: VecDeque<Instant>,
+    paint: Duration,
+    primitives: u32,
+    coverage: f32,
+}
+
+impl FrameStats {
+    /// Statistics before anything was painted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a paint that started at `started` and took `paint`, drew
+    /// `primitives` rectangles, lines, bitmaps and texts and redrew
+    /// `coverage` of the window, from 0 to 1.
+    pub fn record(&mut self, started: Instant, paint: Duration, primitives: u32, coverage: f32) {
+        while let Some(&oldest) = self.frames.front() {
+            if started.duration_since(oldest) < FPS_WINDOW {
+                break;
+            }
+            self.frames.pop_front();
+        }
+        self.frames.push_back(started);
+        self.paint = paint;
+        self.primitives = primitives;
+        self.coverage = coverage.clamp(0.0, 1.0);
+    }
+
+    /// Paints in the second up to the latest one. The board only paints
+    /// when something changed, so an idle board shows few.
+    pub fn fps(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// How long the latest paint took.
+    pub fn paint(&self) -> Duration {
+        self.paint
+    }
+
+    /// Primitives drawn by the latest paint, cached chunks included.
+    pub fn primitives(&self) -> u32 {
+        self.primitives
+    }
+
+    /// Share of the window the latest paint redrew, from 0 to 1.
+    pub fn coverage(&self) -> f32 {
+        self.coverage
+    }
+
+    /// The overlay line, e.g. `12 fps  1.25 ms  480 prims  100% dirty`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} fps  {:.2} ms  {} prims  {:.0}% dirty",
+            self.fps(),
+            self.paint.as_secs_f64() * 1000.0,
+            self.primitives,
+            self.coverage * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_frame_stats() {
+        let mut stats = FrameStats::new();
+        assert_eq!(0, stats.fps());
+        let start = Instant::now();
+        for frame in 0..10 {
+            let at = start + Duration::from_millis(frame * 200);
+            stats.record(at, Duration::from_micros(1250), 480, 1.0);
+        }
+        // the paints at 1.0 to 1.8 seconds
+        assert_eq!(5, stats.fps());
+        assert_eq!("5 fps  1.25 ms  480 prims  100% dirty", stats.summary());
+        stats.record(start + Duration::from_secs(10), Duration::ZERO, 3, 1.5);
+        assert_eq!(1, stats.fps());
+        assert_eq!(1.0, stats.coverage());
+        assert_eq!(3, stats.primitives());
+    }
+}
@@ -0,0 +1,459 @@
+//! Printable puzzle pages as PDF documents.
+//!
+//! A board is drawn as it stands, with the numbers the player has uncovered,
+//! for solving on paper, and an optional second page shows the solution with
+//! every mine and count. The pages are vector drawings, sharp at any printer
+//! resolution, on A4 paper with the board scaled to fit its margins.
+
+use std::{convert::Infallible, fmt::Write as _, fs, path::Path};
+
+use crate::{
+    error,
+    game::{CellState, Game},
+    render::{self, BoardRenderer, Overlay},
+    snapshot::{ANNOTATION_COLORS, COUNTS},
+    solver::DeductionKind,
+};
+
+// A4 in points, 1/72 of an inch
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 48.0;
+// room for the title above the board
+const TITLE_SIZE: f32 = 16.0;
+const TITLE_SPACE: f32 = 36.0;
+// cells of small boards are kept to a size a pencil fits in
+const MAX_CELL: f32 = 28.0;
+// Helvetica digits are 556/1000 of the font size wide
+const DIGIT_WIDTH: f32 = 0.556;
+// control point distance of a quarter circle Bézier arc of radius 1
+const ARC: f32 = 0.552;
+
+type Color = (f32, f32, f32);
+
+// covered cells are light enough to write on
+const COVERED: Color = (0.85, 0.85, 0.85);
+const REVEALED: Color = (1.0, 1.0, 1.0);
+const GRID: Color = (0.4, 0.4, 0.4);
+const MINE: Color = (0.0, 0.0, 0.0);
+const FLAG: Color = (0.85, 0.0, 0.0);
+const QUESTION: Color = (0.38, 0.0, 0.63);
+const SAFE_HINT: Color = (0.0, 0.63, 0.0);
+const MINE_HINT: Color = (0.85, 0.0, 0.0);
+const CONTRADICTION: Color = (0.78, 0.0, 0.0);
+const CONTRADICTION_CELL: Color = (1.0, 0.78, 0.78);
+const PLAYERS: [Color; 2] = [(0.78, 0.16, 0.16), (0.16, 0.31, 0.78)];
+const GHOST: Color = (0.7, 0.7, 0.7);
+const PATTERN: Color = (0.0, 0.0, 1.0);
+const AUTO_FLAG: Color = (0.0, 0.55, 0.78);
+
+/// Draws boards as the pages of a PDF document, a page for each frame from
+/// [`begin`](BoardRenderer::begin) to [`present`](BoardRenderer::present).
+#[derive(Debug, Default)]
+pub struct PdfRenderer {
+    title: String,
+    // page coordinates of the board's top left corner and the cell size
+    left: f32,
+    top: f32,
+    cell: f32,
+    content: String,
+    pages: Vec<String>,
+}
+
+impl PdfRenderer {
+    /// Creates a renderer with no pages.
+    pub fn new() -> Self {
+        PdfRenderer::default()
+    }
+
+    /// Sets the title printed above the boards drawn from now on.
+    pub fn title(&mut self, title: &str) {
+        self.title = title.to_string();
+    }
+
+    /// The pages presented so far as a PDF document.
+    pub fn pdf(&self) -> Vec<u8> {
+        // the catalog, the page tree and the font come first, then each
+        // page and its content stream
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..self.pages.len())
+                    .map(|page| format!("{} 0 R", 4 + page * 2))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                self.pages.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>".to_string(),
+        ];
+        for (page, content) in self.pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                5 + page * 2
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ));
+        }
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", index + 1, object);
+        }
+        let xref = pdf.len();
+        let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        pdf.into_bytes()
+    }
+
+    /// The bottom left corner of cell `x`, `y` in page coordinates, which
+    /// run up from the bottom of the page.
+    fn corner(&self, x: i16, y: i16) -> (f32, f32) {
+        (
+            self.left + x as f32 * self.cell,
+            self.top - (y + 1) as f32 * self.cell,
+        )
+    }
+
+    fn fill(&mut self, (x, y): (f32, f32), (width, height): (f32, f32), color: Color) {
+        let _ = writeln!(
+            self.content,
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f",
+            color.0, color.1, color.2, x, y, width, height
+        );
+    }
+
+    /// Outlines a cell inside its grid line.
+    fn frame(&mut self, x: i16, y: i16, color: Color) {
+        let (left, bottom) = self.corner(x, y);
+        let width = self.cell * 0.08;
+        let _ = writeln!(
+            self.content,
+            "{:.3} {:.3} {:.3} RG {:.2} w {:.2} {:.2} {:.2} {:.2} re S",
+            color.0,
+            color.1,
+            color.2,
+            width,
+            left + width,
+            bottom + width,
+            self.cell - width * 2.0,
+            self.cell - width * 2.0
+        );
+    }
+
+    /// Writes one or two characters in the middle of a cell.
+    fn text(&mut self, x: i16, y: i16, text: &str, color: Color) {
+        let (left, bottom) = self.corner(x, y);
+        let size = self.cell * 0.7;
+        let width = size * DIGIT_WIDTH * text.len() as f32;
+        let _ = writeln!(
+            self.content,
+            "BT {:.3} {:.3} {:.3} rg /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+            color.0,
+            color.1,
+            color.2,
+            size,
+            left + (self.cell - width) / 2.0,
+            bottom + self.cell * 0.27,
+            text
+        );
+    }
+
+    /// A filled circle in the middle of a cell, four Bézier arcs.
+    fn mine(&mut self, x: i16, y: i16) {
+        let (left, bottom) = self.corner(x, y);
+        let (cx, cy, r) = (
+            left + self.cell / 2.0,
+            bottom + self.cell / 2.0,
+            self.cell * 0.3,
+        );
+        let _ = write!(
+            self.content,
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} m",
+            MINE.0,
+            MINE.1,
+            MINE.2,
+            cx + r,
+            cy
+        );
+        // each arc runs a quarter turn on from where the last one ended, its
+        // control points along the tangents at either end
+        for (dx, dy) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+            let (nx, ny) = (-dy, dx);
+            let _ = write!(
+                self.content,
+                " {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+                cx + r * (dx + ARC * nx),
+                cy + r * (dy + ARC * ny),
+                cx + r * (nx + ARC * dx),
+                cy + r * (ny + ARC * dy),
+                cx + r * nx,
+                cy + r * ny
+            );
+        }
+        let _ = writeln!(self.content, " f");
+    }
+
+    /// A pennant on a pole.
+    fn flag(&mut self, x: i16, y: i16) {
+        let (left, bottom) = self.corner(x, y);
+        let cell = self.cell;
+        let pole = left + cell * 0.4;
+        self.fill((pole, bottom + cell * 0.2), (cell * 0.06, cell * 0.6), MINE);
+        let _ = writeln!(
+            self.content,
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l h f",
+            FLAG.0,
+            FLAG.1,
+            FLAG.2,
+            pole,
+            bottom + cell * 0.8,
+            left + cell * 0.75,
+            bottom + cell * 0.62,
+            pole,
+            bottom + cell * 0.45
+        );
+    }
+}
+
+impl BoardRenderer for PdfRenderer {
+    type Error = Infallible;
+
+    fn begin(&mut self, width: i16, height: i16) -> Result<(), Infallible> {
+        let (columns, rows) = (width.max(1) as f32, height.max(1) as f32);
+        let available = (
+            PAGE_WIDTH - MARGIN * 2.0,
+            PAGE_HEIGHT - MARGIN * 2.0 - TITLE_SPACE,
+        );
+        self.cell = (available.0 / columns)
+            .min(available.1 / rows)
+            .min(MAX_CELL);
+        // centered across the page, below the title
+        self.left = (PAGE_WIDTH - self.cell * columns) / 2.0;
+        self.top = PAGE_HEIGHT - MARGIN - TITLE_SPACE;
+        self.content.clear();
+        let title = escape(&self.title);
+        let _ = writeln!(
+            self.content,
+            "BT 0 0 0 rg /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET",
+            TITLE_SIZE,
+            self.left,
+            PAGE_HEIGHT - MARGIN - TITLE_SIZE,
+            title
+        );
+        // the grid shows through the gaps between cells
+        self.fill(
+            (self.left, self.top - self.cell * rows),
+            (self.cell * columns, self.cell * rows),
+            GRID,
+        );
+        Ok(())
+    }
+
+    fn draw_cell(&mut self, x: i16, y: i16, state: CellState) -> Result<(), Infallible> {
+        let background = match state {
+            CellState::Known(_) | CellState::Counted(_) => REVEALED,
+            _ => COVERED,
+        };
+        let (left, bottom) = self.corner(x, y);
+        let line = self.cell * 0.04;
+        self.fill(
+            (left + line, bottom + line),
+            (self.cell - line * 2.0, self.cell - line * 2.0),
+            background,
+        );
+        match state {
+            CellState::Counted(count) => {
+                let [r, g, b] = COUNTS[render::count_color(count, COUNTS.len())];
+                let color = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+                self.text(x, y, &count.to_string(), color);
+            }
+            CellState::Known(true) => self.mine(x, y),
+            CellState::Flagged(_) => self.flag(x, y),
+            CellState::Questioned(_) => self.text(x, y, "?", QUESTION),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn draw_overlay(&mut self, x: i16, y: i16, overlay: Overlay) -> Result<(), Infallible> {
+        match overlay {
+            Overlay::Hint(DeductionKind::Safe) => self.frame(x, y, SAFE_HINT),
+            Overlay::Hint(DeductionKind::Mine) => self.frame(x, y, MINE_HINT),
+            Overlay::Claimed(player) => self.frame(x, y, PLAYERS[player as usize % PLAYERS.len()]),
+            Overlay::Contradiction(count) => {
+                let (left, bottom) = self.corner(x, y);
+                let line = self.cell * 0.04;
+                self.fill(
+                    (left + line, bottom + line),
+                    (self.cell - line * 2.0, self.cell - line * 2.0),
+                    CONTRADICTION_CELL,
+                );
+                self.text(x, y, &count.to_string(), CONTRADICTION);
+            }
+            Overlay::Annotation(mark) => {
+                let [r, g, b] = ANNOTATION_COLORS[mark as usize % ANNOTATION_COLORS.len()];
+                let (left, bottom) = self.corner(x, y);
+                let size = self.cell * 0.25;
+                self.fill(
+                    (
+                        left + self.cell - size * 1.5,
+                        bottom + self.cell - size * 1.5,
+                    ),
+                    (size, size),
+                    (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0),
+                );
+            }
+            Overlay::Ghost => self.frame(x, y, GHOST),
+            Overlay::Peek(count) => {
+                let [r, g, b] = COUNTS[render::count_color(count, COUNTS.len())];
+                let color = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+                self.text(x, y, &count.to_string(), color);
+            }
+            Overlay::Pattern => self.frame(x, y, PATTERN),
+            Overlay::AutoFlag => self.frame(x, y, AUTO_FLAG),
+            #[cfg(feature = "dev-tools")]
+            Overlay::Mined => {
+                let (left, bottom) = self.corner(x, y);
+                let size = self.cell * 0.25;
+                self.fill(
+                    (
+                        left + (self.cell - size) / 2.0,
+                        bottom + (self.cell - size) / 2.0,
+                    ),
+                    (size, size),
+                    MINE,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), Infallible> {
+        self.pages.push(std::mem::take(&mut self.content));
+        Ok(())
+    }
+}
+
+/// Draws the player's view of a game on a page for solving on paper,
+/// followed by a page with its solution when `solution` is set, as a PDF
+/// document.
+pub fn pdf(game: &Game, solution: bool) -> Vec<u8> {
+    let mut renderer = PdfRenderer::new();
+    let title = format!(
+        "Minesweeper {}x{}, {} mines",
+        game.width(),
+        game.height(),
+        game.total()
+    );
+    renderer.title(&title);
+    render::draw(&mut renderer, game, &[]).unwrap_or_else(|never| match never {});
+    if solution {
+        renderer.title(&format!("{}: solution", title));
+        draw_solution(&mut renderer, game).unwrap_or_else(|never| match never {});
+    }
+    renderer.pdf()
+}
+
+/// Writes the pages of [`pdf`] to `path`.
+pub fn save(game: &Game, solution: bool, path: impl AsRef<Path>) -> error::Result<()> {
+    let path = path.as_ref();
+    fs::write(path, pdf(game, solution))?;
+    tracing::info!(path = %path.display(), solution, "puzzle printed");
+    Ok(())
+}
+
+/// Draws the whole board uncovered, every mine shown and every other cell
+/// with its count.
+fn draw_solution<R: BoardRenderer>(renderer: &mut R, game: &Game) -> Result<(), R::Error> {
+    renderer.begin(game.width(), game.height())?;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let state = match game.cell_state(x, y).is_mined() {
+                true => CellState::Known(true),
+                false => match mined_neighbors(game, x, y) {
+                    0 => CellState::Known(false),
+                    count => CellState::Counted(count),
+                },
+            };
+            renderer.draw_cell(x, y, state)?;
+        }
+    }
+    renderer.present()
+}
+
+fn mined_neighbors(game: &Game, x: i16, y: i16) -> u8 {
+    game.neighborhood()
+        .cells(game.width(), game.height(), x, y)
+        .filter(|&(nx, ny)| game.cell_state(nx, ny).is_mined())
+        .count() as u8
+}
+
+/// Escapes the characters that end or break a PDF string.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // kept to ASCII, which the standard fonts all cover
+            c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_pdf() {
+        // * 1 0
+        // 1 1 0
+        let mut game = Game::from_layout(3, 2, &[true, false, false, false, false, false]);
+        game.uncover(2, 1);
+        let document = String::from_utf8(pdf(&game, true)).unwrap();
+        assert!(document.starts_with("%PDF-1.4\n"));
+        assert!(document.ends_with("%%EOF\n"));
+        assert!(document.contains("/Count 2"));
+        assert!(document.contains("(Minesweeper 3x2, 1 mines) Tj"));
+        assert!(document.contains("(Minesweeper 3x2, 1 mines: solution) Tj"));
+
+        // every offset in the cross reference table points at its object
+        let xref = document.rfind("\nxref\n").unwrap() + 1;
+        let offsets = document[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "));
+        for (index, line) in offsets.enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(document[offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+        let start: usize = document.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert_eq!(xref, start);
+
+        let puzzle = pdf(&game, false);
+        assert!(String::from_utf8(puzzle).unwrap().contains("/Count 1"));
+        assert_eq!(1, mined_neighbors(&game, 1, 1));
+        assert_eq!("a \\(b\\) ?", escape("a (b) é"));
+    }
+}
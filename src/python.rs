@@ -0,0 +1,329 @@
+//! Python module exposing the game, the bot and the solver.
+//!
+//! Built with `maturin build --release` from the repository root, which
+//! enables the `python` feature through `pyproject.toml`. Boards are exported
+//! as one signed byte per cell in row order so they load straight into numpy:
+//!
+//! ```python
+//! import numpy, minesweeper_d2d as ms
+//!
+//! game = ms.Game(16, 16, 40, seed=7)
+//! game.uncover(8, 8)
+//! board = numpy.frombuffer(game.board_bytes(), dtype=numpy.int8)
+//! board = board.reshape(game.height, game.width)
+//! ```
+//!
+//! An uncovered cell holds its number of mined neighbors and the other cells
+//! hold one of the negative codes below, also exported from the module.
+
+use pyo3::{
+    exceptions::{PyIndexError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+use crate::{
+    game::{CellState, Difficulty, Game, GameConfig, GameState, Move, MAX_CELLS},
+    simulate::{self, Bot, Strategy},
+    solver::{self, DeductionKind},
+};
+
+/// Code of a covered cell without a mark.
+pub const COVERED: i8 = -1;
+/// Code of a covered cell with a flag.
+pub const FLAGGED: i8 = -2;
+/// Code of a covered cell with a question mark.
+pub const QUESTIONED: i8 = -3;
+/// Code of an uncovered mine.
+pub const MINE: i8 = -4;
+
+fn cell_code(state: CellState) -> i8 {
+    match state {
+        CellState::Unknown(_) => COVERED,
+        CellState::Flagged(_) => FLAGGED,
+        CellState::Questioned(_) => QUESTIONED,
+        CellState::Known(true) => MINE,
+        CellState::Known(false) => 0,
+        CellState::Counted(count) => count as i8,
+    }
+}
+
+/// A minesweeper board and the player's progress on it.
+#[pyclass(name = "Game", module = "minesweeper_d2d")]
+pub struct PyGame {
+    game: Game,
+}
+
+impl PyGame {
+    fn check(&self, x: i16, y: i16) -> PyResult<()> {
+        if x < 0 || y < 0 || x >= self.game.width() || y >= self.game.height() {
+            return Err(PyIndexError::new_err(format!(
+                "[{},{}] is off the board",
+                x, y
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyGame {
+    /// Creates a game with the given size and number of mines, laid out from
+    /// `seed` when one is given.
+    #[new]
+    #[pyo3(signature = (width, height, mines, seed = None))]
+    fn new(width: i16, height: i16, mines: u16, seed: Option<u64>) -> PyResult<Self> {
+        let size = width as usize * height as usize;
+        if width <= 0 || height <= 0 || size > MAX_CELLS || mines as usize >= size {
+            return Err(PyValueError::new_err(
+                "invalid board size or number of mines",
+            ));
+        }
+        let mut game = Game::with_mines(width, height, mines);
+        if let Some(seed) = seed {
+            game.reset_with_seed(seed);
+        }
+        Ok(PyGame { game })
+    }
+
+    /// Creates a game with a classic preset: beginner, intermediate or expert.
+    #[staticmethod]
+    #[pyo3(signature = (name, seed = None))]
+    fn difficulty(name: &str, seed: Option<u64>) -> PyResult<Self> {
+        let GameConfig {
+            width,
+            height,
+            mines,
+            ..
+        } = name
+            .parse::<Difficulty>()
+            .map_err(PyValueError::new_err)?
+            .config();
+        PyGame::new(width, height, mines, seed)
+    }
+
+    /// Lays out the mines again, from `seed` when one is given.
+    #[pyo3(signature = (seed = None))]
+    fn reset(&mut self, seed: Option<u64>) {
+        match seed {
+            Some(seed) => self.game.reset_with_seed(seed),
+            None => self.game.reset(),
+        }
+    }
+
+    /// Width of the board in cells.
+    #[getter]
+    fn width(&self) -> i16 {
+        self.game.width()
+    }
+
+    /// Height of the board in cells.
+    #[getter]
+    fn height(&self) -> i16 {
+        self.game.height()
+    }
+
+    /// Number of mines on the board.
+    #[getter]
+    fn mines(&self) -> u16 {
+        self.game.total()
+    }
+
+    /// Number of mines less the number of flags placed.
+    #[getter]
+    fn mines_left(&self) -> u16 {
+        self.game.est_remaining()
+    }
+
+    /// State of the game: initial, playing, won or lost.
+    #[getter]
+    fn state(&self) -> String {
+        self.game.state().to_string()
+    }
+
+    /// Whether the game was won or lost.
+    #[getter]
+    fn finished(&self) -> bool {
+        matches!(self.game.state(), GameState::Won | GameState::Lost)
+    }
+
+    /// Seconds spent on the game.
+    #[getter]
+    fn elapsed(&self) -> f64 {
+        self.game.elapsed().as_secs_f64()
+    }
+
+    /// Code of the cell at `x`, `y`.
+    fn cell(&self, x: i16, y: i16) -> PyResult<i8> {
+        self.check(x, y)?;
+        Ok(cell_code(self.game.cell_state(x, y)))
+    }
+
+    /// Uncovers a cell and returns the resulting state.
+    fn uncover(&mut self, x: i16, y: i16) -> PyResult<String> {
+        self.check(x, y)?;
+        Ok(self.game.uncover(x, y).to_string())
+    }
+
+    /// Uncovers the neighbors of a counted cell whose flags match its count
+    /// and returns the resulting state.
+    fn chord(&mut self, x: i16, y: i16) -> PyResult<String> {
+        self.check(x, y)?;
+        Ok(self.game.chord(x, y).to_string())
+    }
+
+    /// Toggles a flag on a covered cell.
+    fn flag(&mut self, x: i16, y: i16) -> PyResult<()> {
+        self.check(x, y)?;
+        self.game.flag(x, y);
+        Ok(())
+    }
+
+    /// Clears a flag or question mark.
+    fn clear_mark(&mut self, x: i16, y: i16) -> PyResult<()> {
+        self.check(x, y)?;
+        self.game.set_unknown(x, y);
+        Ok(())
+    }
+
+    /// Plays a move written as in replays, such as "uncover [3,4]", and
+    /// returns the resulting state.
+    fn apply(&mut self, mv: &str) -> PyResult<String> {
+        let mv: Move = mv.parse().map_err(PyValueError::new_err)?;
+        let (Move::Uncover(x, y)
+        | Move::Flag(x, y)
+        | Move::Question(x, y)
+        | Move::SetUnknown(x, y)
+        | Move::Chord(x, y)) = mv;
+        self.check(x, y)?;
+        Ok(self.game.apply(mv).to_string())
+    }
+
+    /// Takes back the last move, returning it if there was one.
+    fn undo(&mut self) -> Option<String> {
+        self.game.undo().map(|mv| mv.to_string())
+    }
+
+    /// Cell codes as a list of rows.
+    fn board(&self) -> Vec<Vec<i8>> {
+        (0..self.game.height())
+            .map(|y| {
+                (0..self.game.width())
+                    .map(|x| cell_code(self.game.cell_state(x, y)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Cell codes in row order, one signed byte per cell.
+    fn board_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let mut bytes =
+            Vec::with_capacity(self.game.width() as usize * self.game.height() as usize);
+        for y in 0..self.game.height() {
+            for x in 0..self.game.width() {
+                bytes.push(cell_code(self.game.cell_state(x, y)) as u8);
+            }
+        }
+        PyBytes::new_bound(py, &bytes)
+    }
+
+    fn __str__(&self) -> String {
+        self.game.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Game({}, {}, {}, state={})",
+            self.game.width(),
+            self.game.height(),
+            self.game.total(),
+            self.game.state()
+        )
+    }
+}
+
+/// Plays what the solver can prove and guesses a random covered cell when
+/// it cannot prove anything.
+#[pyclass(name = "Bot", module = "minesweeper_d2d")]
+pub struct PyBot {
+    bot: Bot,
+}
+
+#[pymethods]
+impl PyBot {
+    /// Creates a bot whose guesses follow from `seed`.
+    #[new]
+    #[pyo3(signature = (seed = 0))]
+    fn new(seed: u64) -> Self {
+        PyBot {
+            bot: Bot::new(seed),
+        }
+    }
+
+    /// The move the bot would play next, in the form `Game.apply` takes.
+    fn next_move(&mut self, game: &PyGame) -> Option<String> {
+        self.bot.next_move(&game.game).map(|mv| mv.to_string())
+    }
+
+    /// Plays the game to the end and returns the final state.
+    fn play(&mut self, game: &mut PyGame) -> String {
+        while !game.finished() {
+            let Some(mv) = self.bot.next_move(&game.game) else {
+                break;
+            };
+            game.game.apply(mv);
+        }
+        game.state()
+    }
+}
+
+/// Cells the solver can prove from the uncovered counts, as tuples of x, y,
+/// whether the cell is a mine and an explanation.
+#[pyfunction]
+fn solve(game: &PyGame) -> Vec<(i16, i16, bool, String)> {
+    solver::solve(&game.game)
+        .into_iter()
+        .map(|d| (d.x, d.y, d.kind == DeductionKind::Mine, d.explanation()))
+        .collect()
+}
+
+/// Plays `games` boards with the built-in bot and returns the totals.
+#[pyfunction]
+#[pyo3(name = "simulate", signature = (width, height, mines, games, seed = 0))]
+fn simulate_bot(
+    py: Python<'_>,
+    width: i16,
+    height: i16,
+    mines: u16,
+    games: usize,
+    seed: u64,
+) -> PyResult<Bound<'_, PyDict>> {
+    PyGame::new(width, height, mines, None)?;
+    let config = GameConfig {
+        width,
+        height,
+        mines,
+        zones: Vec::new(),
+    };
+    let report = py.allow_threads(|| simulate::simulate(config, games, seed, &mut Bot::new(seed)));
+    let totals = PyDict::new_bound(py);
+    totals.set_item("games", report.games)?;
+    totals.set_item("won", report.won)?;
+    totals.set_item("lost", report.lost)?;
+    totals.set_item("guesses", report.guesses)?;
+    totals.set_item("seconds", report.time.as_secs_f64())?;
+    Ok(totals)
+}
+
+#[pymodule]
+fn minesweeper_d2d(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyBot>()?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    m.add_function(wrap_pyfunction!(simulate_bot, m)?)?;
+    m.add("COVERED", COVERED)?;
+    m.add("FLAGGED", FLAGGED)?;
+    m.add("QUESTIONED", QUESTIONED)?;
+    m.add("MINE", MINE)?;
+    Ok(())
+}
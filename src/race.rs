@@ -0,0 +1,411 @@
+//! Races between two players on identical boards over TCP.
+//!
+//! One player hosts a race and waits in the lobby for an opponent to join,
+//! then sends the seed and size of the board so both lay it out the same.
+//! While they play, each side sends its progress after every move and its
+//! result once the board is cleared or a mine goes off.
+//!
+//! Messages are lines of text:
+//!
+//! ```text
+//! hello <protocol version> <player name>
+//! start <seed> <width> <height> <mines>
+//! progress <percent revealed> <elapsed ms>
+//! finish won|lost <elapsed ms>
+//! bye
+//! ```
+
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    game::{CellState, Game, GameConfig, GameState},
+};
+
+/// Version of the protocol, both players must speak the same.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Port a race is hosted on unless another one is given.
+pub const DEFAULT_PORT: u16 = 4807;
+
+/// A line sent between the two players.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Opens the lobby handshake from either side.
+    Hello {
+        /// Protocol version of the sender.
+        version: u32,
+        /// Name the sender plays under.
+        name: String,
+    },
+    /// Sent by the host with the board both players race on.
+    Start {
+        /// Seed the board is laid out from.
+        seed: u64,
+        /// Size and number of mines of the board.
+        config: GameConfig,
+    },
+    /// The sender's progress after a move.
+    Progress {
+        /// Share of the safe cells uncovered, from 0 to 100.
+        percent: u8,
+        /// Time played so far.
+        elapsed: Duration,
+    },
+    /// The sender cleared the board or hit a mine.
+    Finish {
+        /// Whether the board was cleared.
+        won: bool,
+        /// Time the game took.
+        elapsed: Duration,
+    },
+    /// The sender left the race.
+    Bye,
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello { version, name } => write!(f, "hello {} {}", version, name),
+            Message::Start { seed, config } => {
+                write!(
+                    f,
+                    "start {} {} {} {}",
+                    seed, config.width, config.height, config.mines
+                )?;
+                // zones last, where older versions stop reading
+                config
+                    .zones
+                    .iter()
+                    .try_for_each(|zone| write!(f, " {}", zone))
+            }
+            Message::Progress { percent, elapsed } => {
+                write!(f, "progress {} {}", percent, elapsed.as_millis())
+            }
+            Message::Finish { won, elapsed } => write!(
+                f,
+                "finish {} {}",
+                if *won { "won" } else { "lost" },
+                elapsed.as_millis()
+            ),
+            Message::Bye => write!(f, "bye"),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = MinesweeperError;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let invalid = || MinesweeperError::Parse(format!("invalid race message {:?}", line));
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut fields = rest.split_whitespace();
+        let mut next = || fields.next().ok_or_else(invalid);
+        let message = match kind {
+            "hello" => {
+                let (version, name) = rest.split_once(' ').ok_or_else(invalid)?;
+                Message::Hello {
+                    version: version.parse().map_err(|_| invalid())?,
+                    name: name.trim().to_string(),
+                }
+            }
+            "start" => Message::Start {
+                seed: next()?.parse().map_err(|_| invalid())?,
+                config: GameConfig {
+                    width: next()?.parse().map_err(|_| invalid())?,
+                    height: next()?.parse().map_err(|_| invalid())?,
+                    mines: next()?.parse().map_err(|_| invalid())?,
+                    zones: fields
+                        .by_ref()
+                        .map(|zone| zone.parse().map_err(|_| invalid()))
+                        .collect::<Result<_>>()?,
+                },
+            },
+            "progress" => Message::Progress {
+                percent: next()?.parse().map_err(|_| invalid())?,
+                elapsed: Duration::from_millis(next()?.parse().map_err(|_| invalid())?),
+            },
+            "finish" => Message::Finish {
+                won: match next()? {
+                    "won" => true,
+                    "lost" => false,
+                    _ => return Err(invalid()),
+                },
+                elapsed: Duration::from_millis(next()?.parse().map_err(|_| invalid())?),
+            },
+            "bye" => Message::Bye,
+            _ => return Err(invalid()),
+        };
+        Ok(message)
+    }
+}
+
+/// What is known about the opponent's game.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Share of the safe cells uncovered, from 0 to 100.
+    pub percent: u8,
+    /// Time played when the last update was sent.
+    pub elapsed: Duration,
+    /// Whether the board was cleared, once the game is over.
+    pub finished: Option<bool>,
+    /// Whether the opponent left or the connection dropped.
+    pub left: bool,
+}
+
+/// A race in progress with an opponent on the other end of a connection.
+#[derive(Debug)]
+pub struct Race {
+    /// Name the opponent plays under.
+    pub opponent: String,
+    /// Seed both boards are laid out from.
+    pub seed: u64,
+    /// Size and number of mines of both boards.
+    pub config: GameConfig,
+    stream: TcpStream,
+    incoming: Receiver<Message>,
+    progress: Progress,
+}
+
+impl Race {
+    /// Waits for an opponent to connect to `listener` and starts a race on a
+    /// board of `config` laid out from `seed`.
+    pub fn host(listener: &TcpListener, name: &str, config: GameConfig, seed: u64) -> Result<Self> {
+        let (stream, address) = listener.accept()?;
+        tracing::info!(%address, "opponent connected");
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let opponent = hello(&mut reader)?;
+        let mut race = Race::start(stream, reader, opponent, config.clone(), seed);
+        race.send(&greeting(name))?;
+        race.send(&Message::Start { seed, config })?;
+        Ok(race)
+    }
+
+    /// Joins the race hosted at `address` and waits for the host to start it.
+    pub fn join(address: impl ToSocketAddrs, name: &str) -> Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+        writeln!(stream, "{}", greeting(name))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let opponent = hello(&mut reader)?;
+        match receive(&mut reader)? {
+            Message::Start { seed, config } => {
+                Ok(Race::start(stream, reader, opponent, config, seed))
+            }
+            message => Err(MinesweeperError::Parse(format!(
+                "expected the race to start, got {}",
+                message
+            ))),
+        }
+    }
+
+    /// Hands the rest of the connection to a thread receiving the opponent's
+    /// updates.
+    fn start(
+        stream: TcpStream,
+        mut reader: BufReader<TcpStream>,
+        opponent: String,
+        config: GameConfig,
+        seed: u64,
+    ) -> Self {
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(message) = receive(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        tracing::info!(%opponent, seed, "race started");
+        Race {
+            opponent,
+            seed,
+            config,
+            stream,
+            incoming,
+            progress: Progress::default(),
+        }
+    }
+
+    /// A new game on the board both players race on.
+    pub fn game(&self) -> Game {
+        let mut game = Game::with_config(self.config.clone());
+        game.reset_with_seed(self.seed);
+        game
+    }
+
+    /// Tells the opponent how far `game` has come, or how it ended.
+    pub fn update(&mut self, game: &Game) -> Result<()> {
+        let elapsed = game.elapsed();
+        let message = match game.state() {
+            GameState::Won | GameState::Lost => Message::Finish {
+                won: game.state() == GameState::Won,
+                elapsed,
+            },
+            _ => Message::Progress {
+                percent: revealed(game),
+                elapsed,
+            },
+        };
+        self.send(&message)
+    }
+
+    /// The opponent's progress, taking in any updates received since the
+    /// last call.
+    pub fn opponent_progress(&mut self) -> Progress {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Message::Progress { percent, elapsed }) => {
+                    self.progress.percent = percent;
+                    self.progress.elapsed = elapsed;
+                }
+                Ok(Message::Finish { won, elapsed }) => {
+                    self.progress.finished = Some(won);
+                    self.progress.elapsed = elapsed;
+                }
+                Ok(Message::Bye) | Err(TryRecvError::Disconnected) => {
+                    self.progress.left = true;
+                    break;
+                }
+                Ok(message) => tracing::warn!(%message, "unexpected race message"),
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        self.progress
+    }
+
+    fn send(&mut self, message: &Message) -> Result<()> {
+        writeln!(self.stream, "{}", message)?;
+        Ok(())
+    }
+}
+
+impl Drop for Race {
+    fn drop(&mut self) {
+        // the opponent also notices a dropped connection, saying bye is a courtesy
+        let _ = self.send(&Message::Bye);
+    }
+}
+
+/// Share of the safe cells uncovered in `game`, from 0 to 100.
+pub fn revealed(game: &Game) -> u8 {
+    let cells = game.width() as usize * game.height() as usize;
+    let safe = cells.saturating_sub(game.total() as usize);
+    if safe == 0 {
+        return 100;
+    }
+    let mut uncovered = 0;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if matches!(
+                game.cell_state(x, y),
+                CellState::Known(false) | CellState::Counted(_)
+            ) {
+                uncovered += 1;
+            }
+        }
+    }
+    (uncovered * 100 / safe) as u8
+}
+
+fn greeting(name: &str) -> Message {
+    Message::Hello {
+        version: PROTOCOL_VERSION,
+        // a name must not end the line it is sent on
+        name: name.replace(['\r', '\n'], " "),
+    }
+}
+
+/// Reads the opponent's hello, returning their name.
+fn hello(reader: &mut impl BufRead) -> Result<String> {
+    match receive(reader)? {
+        Message::Hello { version, name } if version == PROTOCOL_VERSION => Ok(name),
+        Message::Hello { version, .. } => Err(MinesweeperError::Parse(format!(
+            "the opponent speaks race protocol {}, this is {}",
+            version, PROTOCOL_VERSION
+        ))),
+        message => Err(MinesweeperError::Parse(format!(
+            "expected a hello, got {}",
+            message
+        ))),
+    }
+}
+
+fn receive(reader: &mut impl BufRead) -> Result<Message> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    line.trim_end().parse()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_message_text() {
+        let messages = [
+            greeting("Ada L"),
+            Message::Start {
+                seed: 42,
+                config: GameConfig {
+                    width: 9,
+                    height: 9,
+                    mines: 10,
+                    zones: vec!["0,0,4,9,150%".parse().unwrap()],
+                },
+            },
+            Message::Progress {
+                percent: 37,
+                elapsed: Duration::from_millis(12_345),
+            },
+            Message::Finish {
+                won: true,
+                elapsed: Duration::from_millis(60_000),
+            },
+            Message::Bye,
+        ];
+        for message in messages {
+            assert_eq!(message, message.to_string().parse().unwrap());
+        }
+        assert!("progress 37".parse::<Message>().is_err());
+        assert!("finish maybe 10".parse::<Message>().is_err());
+        assert!("shout".parse::<Message>().is_err());
+    }
+
+    #[test]
+    pub fn test_race_loopback() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let config = GameConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            zones: Vec::new(),
+        };
+        let joining = thread::spawn(move || Race::join(address, "guest").unwrap());
+        let mut host = Race::host(&listener, "host", config.clone(), 7).unwrap();
+        let mut guest = joining.join().unwrap();
+        assert_eq!(("guest", "host"), (&*host.opponent, &*guest.opponent));
+        assert_eq!((7, &config), (guest.seed, &guest.config));
+        let mut game = guest.game();
+        assert_eq!(host.game().snapshot(), game.snapshot());
+        game.uncover(0, 0);
+        guest.update(&game).unwrap();
+        drop(guest);
+        // wait for the thread to see the connection drop
+        let mut progress = host.opponent_progress();
+        while !progress.left {
+            thread::sleep(Duration::from_millis(10));
+            progress = host.opponent_progress();
+        }
+        assert_eq!(revealed(&game), progress.percent);
+    }
+}
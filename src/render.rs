@@ -0,0 +1,357 @@
+//! Drawing a board independently of the graphics backend.
+//!
+//! Very large boards are drawn a [`Region`] at a time, only the cells a frame
+//! shows, and backends that cache what they draw split the board into
+//! [`CHUNK_CELLS`] square chunks redrawn only when a move changes them.
+//!
+//! Counts past the classic eight, from the wider
+//! [`Neighborhood`](crate::game::Neighborhood) rules, take the colors of
+//! [`count_color`] and the single character of [`count_glyph`].
+//!
+//! The faded numbers of a [blind](Game::set_blind) game are drawn as
+//! uncovered cells without a number, a [`Overlay::Peek`] shows one again.
+
+use alloc::vec::Vec;
+
+use crate::{
+    game::{CellState, Game},
+    solver::DeductionKind,
+};
+
+/// A marker drawn over a cell after the cells themselves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overlay {
+    /// A hinted cell, marked as safe or as a mine.
+    Hint(DeductionKind),
+    /// A cell claimed by a player of a versus match, counting from 0, shown
+    /// in that player's color.
+    Claimed(u8),
+    /// A number with more flags around it than its count, drawn again in red.
+    Contradiction(u8),
+    /// A candidate mark on a covered cell counting from 0, see
+    /// [`Game::annotate`], shown as a badge in the mark's color.
+    Annotation(u8),
+    /// A cell the ghost of the best run has uncovered and the player has
+    /// not, see [`Ghost`](crate::ghost::Ghost), drawn as a faint outline.
+    Ghost,
+    /// A flag the solver placed rather than the player, see
+    /// [`Game::auto_flagged`], drawn in a color of its own.
+    AutoFlag,
+    /// A faded number of a blind game the player is looking at, see
+    /// [`Game::faded`], drawn again.
+    Peek(u8),
+    /// A cell of a pattern a hint names, see
+    /// [`patterns`](crate::patterns), outlined for the player to study.
+    Pattern,
+    /// A mine the player has not found, shown by the debug view.
+    #[cfg(feature = "dev-tools")]
+    Mined,
+}
+
+/// A backend the board can be drawn with, such as Direct2D or a terminal.
+///
+/// [`draw`] calls [`begin`](BoardRenderer::begin) once, then
+/// [`draw_cell`](BoardRenderer::draw_cell) for every cell, then
+/// [`draw_overlay`](BoardRenderer::draw_overlay) for every overlay and
+/// finally [`present`](BoardRenderer::present).
+pub trait BoardRenderer {
+    /// Error returned when drawing fails.
+    type Error;
+
+    /// Starts a frame for a board of the given size.
+    fn begin(&mut self, width: i16, height: i16) -> Result<(), Self::Error> {
+        let _ = (width, height);
+        Ok(())
+    }
+
+    /// Draws the cell at `x`, `y` as the player sees it.
+    fn draw_cell(&mut self, x: i16, y: i16, state: CellState) -> Result<(), Self::Error>;
+
+    /// Draws a marker over the cell at `x`, `y`.
+    fn draw_overlay(&mut self, x: i16, y: i16, overlay: Overlay) -> Result<(), Self::Error>;
+
+    /// Finishes the frame and shows it.
+    fn present(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Width and height in cells of the chunks a cached board is split into.
+pub const CHUNK_CELLS: i16 = 32;
+
+/// A rectangle of cells on a board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Column of the left edge.
+    pub x: i16,
+    /// Row of the top edge.
+    pub y: i16,
+    /// Number of columns.
+    pub width: i16,
+    /// Number of rows.
+    pub height: i16,
+}
+
+impl Region {
+    /// Every cell of the board.
+    pub fn whole(game: &Game) -> Self {
+        Region {
+            x: 0,
+            y: 0,
+            width: game.width(),
+            height: game.height(),
+        }
+    }
+
+    /// The cells a view `width` by `height` pixels shows once it is scrolled
+    /// `left` and `top` pixels into the board, clipped to the board.
+    pub fn visible(
+        game: &Game,
+        (left, top): (f32, f32),
+        (width, height): (f32, f32),
+        (cell_width, cell_height): (f32, f32),
+    ) -> Self {
+        let clip = |start: f32, size: f32, cell: f32, cells: i16| {
+            let first = ((start / cell) as i16).clamp(0, cells);
+            // rounded up by hand, f32::ceil needs std
+            let end = (start + size) / cell;
+            let last = end as i16 + ((end as i16 as f32) < end) as i16;
+            (first, last.clamp(first, cells) - first)
+        };
+        let (x, width) = clip(left, width, cell_width, game.width());
+        let (y, height) = clip(top, height, cell_height, game.height());
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The cells of a chunk, by chunk column and row, clipped to the board.
+    pub fn chunk(game: &Game, (column, row): (i16, i16)) -> Self {
+        let x = column * CHUNK_CELLS;
+        let y = row * CHUNK_CELLS;
+        Region {
+            x,
+            y,
+            width: CHUNK_CELLS.min(game.width() - x),
+            height: CHUNK_CELLS.min(game.height() - y),
+        }
+    }
+
+    /// Whether the cell lies in the region.
+    pub fn contains(&self, x: i16, y: i16) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    /// The chunks the region touches, by chunk column and row.
+    pub fn chunks(&self) -> impl Iterator<Item = (i16, i16)> {
+        let columns = chunk_range(self.x, self.width);
+        chunk_range(self.y, self.height)
+            .flat_map(move |row| columns.clone().map(move |column| (column, row)))
+    }
+}
+
+/// The chunks holding the cells a move changed, each once, which are stale
+/// in any cache of the board.
+pub fn changed_chunks(changed: &[(i16, i16)]) -> Vec<(i16, i16)> {
+    let mut chunks: Vec<_> = changed
+        .iter()
+        .map(|&(x, y)| (x / CHUNK_CELLS, y / CHUNK_CELLS))
+        .collect();
+    chunks.sort_unstable();
+    chunks.dedup();
+    chunks
+}
+
+fn chunk_range(start: i16, size: i16) -> core::ops::Range<i16> {
+    if size <= 0 {
+        return 0..0;
+    }
+    start / CHUNK_CELLS..(start + size - 1) / CHUNK_CELLS + 1
+}
+
+/// The flags the solver placed in a game, see [`Game::auto_flag`].
+pub fn auto_flag_overlays(game: &Game) -> Vec<(i16, i16, Overlay)> {
+    let mut overlays = Vec::new();
+    if !game.auto_flag() {
+        return overlays;
+    }
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if game.auto_flagged(x, y) {
+                overlays.push((x, y, Overlay::AutoFlag));
+            }
+        }
+    }
+    overlays
+}
+
+/// The debug view of a game: its mines under covered cells followed by
+/// every deduction the solver can make, as hints.
+#[cfg(feature = "dev-tools")]
+pub fn debug_overlays(game: &Game) -> Vec<(i16, i16, Overlay)> {
+    let mut overlays = Vec::new();
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if matches!(
+                game.cell_state(x, y),
+                CellState::Unknown(true) | CellState::Questioned(true)
+            ) {
+                overlays.push((x, y, Overlay::Mined));
+            }
+        }
+    }
+    for deduction in crate::solver::solve(game) {
+        overlays.push((deduction.x, deduction.y, Overlay::Hint(deduction.kind)));
+    }
+    overlays
+}
+
+/// Counts the classic palette has a color each for, see [`count_color`].
+pub const PALETTE_COUNTS: u8 = 8;
+
+/// The entry of a palette of `colors` number colors a count is drawn in.
+/// The first [`PALETTE_COUNTS`] counts take an entry each, or the last one
+/// of a shorter palette, and larger counts start over from the first.
+pub fn count_color(count: u8, colors: usize) -> usize {
+    (((count.max(1) - 1) % PALETTE_COUNTS) as usize).min(colors - 1)
+}
+
+/// The character a count is written as where a cell takes one: its digit up
+/// to 9, then a letter from `a` for 10 as in base 36.
+pub fn count_glyph(count: u8) -> char {
+    char::from_digit(count as u32, 36).unwrap_or('?')
+}
+
+/// Draws a whole game followed by any overlays.
+pub fn draw<R: BoardRenderer>(
+    renderer: &mut R,
+    game: &Game,
+    overlays: &[(i16, i16, Overlay)],
+) -> Result<(), R::Error> {
+    draw_region(renderer, game, overlays, Region::whole(game))
+}
+
+/// Draws the cells of a game in `region` followed by the overlays in it,
+/// leaving the cells a frame does not show alone.
+pub fn draw_region<R: BoardRenderer>(
+    renderer: &mut R,
+    game: &Game,
+    overlays: &[(i16, i16, Overlay)],
+    region: Region,
+) -> Result<(), R::Error> {
+    renderer.begin(game.width(), game.height())?;
+    for y in region.y..region.y + region.height {
+        for x in region.x..region.x + region.width {
+            renderer.draw_cell(x, y, shown(game, x, y))?;
+        }
+    }
+    for &(x, y, overlay) in overlays {
+        if region.contains(x, y) {
+            renderer.draw_overlay(x, y, overlay)?;
+        }
+    }
+    renderer.present()
+}
+
+/// Draws several games on one frame `width` by `height` cells, each with its
+/// top left cell at the given column and row, followed by any overlays in
+/// frame coordinates. Cells no game covers are left alone.
+pub fn draw_tiled<R: BoardRenderer>(
+    renderer: &mut R,
+    width: i16,
+    height: i16,
+    tiles: &[(i16, i16, &Game)],
+    overlays: &[(i16, i16, Overlay)],
+) -> Result<(), R::Error> {
+    renderer.begin(width, height)?;
+    for &(left, top, game) in tiles {
+        for y in 0..game.height() {
+            for x in 0..game.width() {
+                renderer.draw_cell(left + x, top + y, shown(game, x, y))?;
+            }
+        }
+    }
+    for &(x, y, overlay) in overlays {
+        renderer.draw_overlay(x, y, overlay)?;
+    }
+    renderer.present()
+}
+
+// the cell as the player sees it, faded numbers left blank
+fn shown(game: &Game, x: i16, y: i16) -> CellState {
+    match game.faded(x, y) {
+        true => CellState::Known(false),
+        false => game.cell_state(x, y),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    pub fn test_counts() {
+        assert_eq!(0, count_color(1, 8));
+        assert_eq!(7, count_color(8, 8));
+        assert_eq!(6, count_color(8, 7));
+        assert_eq!(0, count_color(9, 8));
+        assert_eq!(7, count_color(24, 8));
+        assert_eq!('8', count_glyph(8));
+        assert_eq!('a', count_glyph(10));
+        assert_eq!('o', count_glyph(24));
+    }
+
+    #[test]
+    pub fn test_regions() {
+        // as large as the cell indices allow
+        let mut game = Game::with_mines(300, 100, 0);
+        let view = Region::visible(&game, (95.0, 0.0), (200.0, 100.0), (10.0, 10.0));
+        assert_eq!(
+            Region {
+                x: 9,
+                y: 0,
+                width: 21,
+                height: 10
+            },
+            view
+        );
+        assert!(view.contains(29, 9) && !view.contains(30, 9) && !view.contains(8, 0));
+        assert_eq!(vec![(0, 0)], view.chunks().collect::<Vec<_>>());
+
+        // scrolled past the end the view is clipped to the board
+        let view = Region::visible(&game, (2900.0, 900.0), (200.0, 200.0), (10.0, 10.0));
+        assert_eq!((290, 90, 10, 10), (view.x, view.y, view.width, view.height));
+        assert_eq!(vec![(9, 2), (9, 3)], view.chunks().collect::<Vec<_>>());
+        let chunk = Region::chunk(&game, (9, 3));
+        assert_eq!(
+            (288, 96, 12, 4),
+            (chunk.x, chunk.y, chunk.width, chunk.height)
+        );
+        // the last cells of the board can be played
+        assert_eq!(GameState::Won, game.uncover(299, 99));
+
+        let changed = [(0, 0), (31, 31), (32, 0), (33, 1)];
+        assert_eq!(vec![(0, 0), (1, 0)], changed_chunks(&changed));
+    }
+
+    #[cfg(feature = "dev-tools")]
+    #[test]
+    pub fn test_debug_overlays() {
+        //   . * . .
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        assert_eq!(vec![(1, 0, Overlay::Mined)], debug_overlays(&game));
+        game.uncover(3, 0);
+        assert_eq!(
+            vec![
+                (1, 0, Overlay::Mined),
+                (1, 0, Overlay::Hint(DeductionKind::Mine))
+            ],
+            debug_overlays(&game)
+        );
+        // a flagged mine is neither hidden nor left to deduce
+        game.flag(1, 0);
+        assert!(debug_overlays(&game).is_empty());
+    }
+}
@@ -0,0 +1,482 @@
+//! Recording games move by move and playing them back.
+//!
+//! A finished replay ends with the result it claims: how the game ended, when
+//! and a hash of the final board. [`Replay::verify`] plays the moves again
+//! from the recorded layout and seed and checks the claim, so a time can be
+//! trusted by a leaderboard without watching the game.
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    game::{CellState, Game, GameState, Move},
+    save,
+};
+
+/// First line of every replay file, followed by the format version.
+const REPLAY_HEADER: &str = "minesweeper-replay";
+/// Version of the replay file format written by [`Replay::write`].
+pub const REPLAY_VERSION: u32 = 3;
+
+// A replay is the starting position in the save file format followed by the
+// player's actions and the milliseconds since the start of the recording:
+//
+//   minesweeper-replay 3
+//   minesweeper-save 2
+//   ...
+//   cells
+//   .....
+//   moves
+//   1520 uncover [3,4]
+//   2210 flag [0,1]
+//   2900 undo
+//   3400 uncover [0,0]
+//   end won 3400 5c1f0e4a9b3d2e17
+//
+// The end line is written once the game is won or lost: the final state, the
+// milliseconds of the move that ended the game and the hash of the final
+// board in hex. Version 1 had no version after the header and version 2 no
+// end line. The moves have not changed since, the starting position is
+// migrated by the save file reader.
+
+/// Something the player did during a recorded game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    /// A move on the board.
+    Play(Move),
+    /// An undo of the last move.
+    Undo,
+    /// A redo of the last undone move.
+    Redo,
+}
+
+/// An action and when it happened.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// Time since the start of the game.
+    pub at: Duration,
+    /// What the player did.
+    pub action: Action,
+}
+
+/// How a recorded game ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// Won or lost.
+    pub state: GameState,
+    /// Time of the move that ended the game.
+    pub time: Duration,
+    /// Hash of the final board, see [`board_hash`].
+    pub hash: u64,
+}
+
+/// A recorded game that can be stepped through move by move.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    start: Game,
+    steps: Vec<Step>,
+    // the end line of a replay that was read
+    claim: Option<Outcome>,
+}
+
+impl Replay {
+    /// Starts recording from the current position of a game.
+    pub fn new(game: &Game) -> Replay {
+        Replay {
+            start: game.clone(),
+            steps: Vec::new(),
+            claim: None,
+        }
+    }
+
+    /// Adds an action that happened `at` into the game.
+    pub fn push(&mut self, at: Duration, action: Action) {
+        self.steps.push(Step { at, action });
+    }
+
+    /// The recorded actions in order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the game as it was after the first `count` steps.
+    pub fn position(&self, count: usize) -> Game {
+        let mut game = self.start.clone();
+        for step in self.steps.iter().take(count) {
+            step.action.apply(&mut game);
+        }
+        game
+    }
+
+    /// Plays the whole replay and returns how the game ended, if it did.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let mut game = self.start.clone();
+        let mut ended = None;
+        for step in &self.steps {
+            let over = is_over(game.state());
+            step.action.apply(&mut game);
+            if !over && is_over(game.state()) {
+                ended = Some(step.at);
+            }
+        }
+        // an undo can take back the move that ended the game
+        if !is_over(game.state()) {
+            return None;
+        }
+        Some(Outcome {
+            state: game.state(),
+            time: ended?,
+            hash: board_hash(&game),
+        })
+    }
+
+    /// The result the replay file claims, if it was read from a file of a
+    /// finished game.
+    pub fn claim(&self) -> Option<&Outcome> {
+        self.claim.as_ref()
+    }
+
+    /// Plays the replay again and checks that it ends the way the file
+    /// claims: the same result at the same time on the same board, with the
+    /// moves in time order. Returns the verified result.
+    pub fn verify(&self) -> Result<Outcome> {
+        let claim = self
+            .claim
+            .ok_or_else(|| unverified("the replay claims no result"))?;
+        if self.steps.windows(2).any(|pair| pair[1].at < pair[0].at) {
+            return Err(unverified("the move times go backwards"));
+        }
+        let outcome = self
+            .outcome()
+            .ok_or_else(|| unverified("the moves do not finish the game"))?;
+        if outcome.state != claim.state {
+            return Err(unverified(&format!(
+                "the game is {}, not {}",
+                outcome.state, claim.state
+            )));
+        }
+        if outcome.time != claim.time {
+            return Err(unverified(&format!(
+                "the game ended at {:.3} s, not {:.3} s",
+                outcome.time.as_secs_f64(),
+                claim.time.as_secs_f64()
+            )));
+        }
+        if outcome.hash != claim.hash {
+            return Err(unverified("the final board does not match"));
+        }
+        Ok(outcome)
+    }
+
+    /// Writes the replay in the replay file format.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "{} {}", REPLAY_HEADER, REPLAY_VERSION)?;
+        save::write(&self.start, &mut writer)?;
+        writeln!(writer, "moves")?;
+        for step in &self.steps {
+            writeln!(writer, "{} {}", step.at.as_millis(), step.action)?;
+        }
+        if let Some(outcome) = self.outcome() {
+            writeln!(
+                writer,
+                "end {} {} {:016x}",
+                outcome.state,
+                outcome.time.as_millis(),
+                outcome.hash
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a replay written by [`Replay::write`] in this or any earlier
+    /// version of the format.
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Replay> {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        save::format_version(&header, REPLAY_HEADER, REPLAY_VERSION)?;
+        let start = save::read(&mut reader)?;
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line.trim() == "moves" => {}
+            _ => return Err(invalid("missing moves")),
+        }
+        let mut steps = Vec::new();
+        let mut claim = None;
+        for line in lines {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if claim.is_some() {
+                return Err(invalid("moves after the end"));
+            }
+            if let Some(end) = line.strip_prefix("end ") {
+                claim = Some(parse_end(end).ok_or_else(|| invalid("invalid end line"))?);
+                continue;
+            }
+            let (at, action) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid("invalid move line"))?;
+            let at = at.parse().map_err(|_| invalid("invalid move time"))?;
+            let action = action.parse().map_err(|e: String| invalid(&e))?;
+            if let Action::Play(mv) = action {
+                check_move(&start, mv)?;
+            }
+            steps.push(Step {
+                at: Duration::from_millis(at),
+                action,
+            });
+        }
+        Ok(Replay {
+            start,
+            steps,
+            claim,
+        })
+    }
+
+    /// Saves the replay to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads a replay from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay> {
+        Replay::read(BufReader::new(File::open(path)?))
+    }
+}
+
+impl Action {
+    pub(crate) fn apply(self, game: &mut Game) {
+        match self {
+            Action::Play(mv) => {
+                game.apply(mv);
+            }
+            Action::Undo => {
+                game.undo();
+            }
+            Action::Redo => {
+                game.redo();
+            }
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Play(mv) => mv.fmt(f),
+            Action::Undo => f.write_str("undo"),
+            Action::Redo => f.write_str("redo"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "undo" => Ok(Action::Undo),
+            "redo" => Ok(Action::Redo),
+            _ => Ok(Action::Play(s.parse()?)),
+        }
+    }
+}
+
+/// Hashes the size, seed, state and every cell of a board with 64 bit
+/// FNV-1a, the same on every platform and build.
+pub fn board_hash(game: &Game) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    feed(&game.width().to_le_bytes());
+    feed(&game.height().to_le_bytes());
+    match game.seed() {
+        Some(seed) => {
+            feed(&[1]);
+            feed(&seed.to_le_bytes());
+        }
+        None => feed(&[0]),
+    }
+    feed(&[game.state() as u8]);
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            feed(&match game.cell_state(x, y) {
+                CellState::Unknown(mined) => [0, mined as u8],
+                CellState::Known(mined) => [1, mined as u8],
+                CellState::Flagged(mined) => [2, mined as u8],
+                CellState::Questioned(mined) => [3, mined as u8],
+                CellState::Counted(count) => [4, count],
+            });
+        }
+    }
+    hash
+}
+
+fn is_over(state: GameState) -> bool {
+    matches!(state, GameState::Won | GameState::Lost)
+}
+
+// `won 3400 5c1f0e4a9b3d2e17`
+fn parse_end(end: &str) -> Option<Outcome> {
+    let mut fields = end.split_whitespace();
+    let state: GameState = fields.next()?.parse().ok()?;
+    let time = Duration::from_millis(fields.next()?.parse().ok()?);
+    let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+    if !is_over(state) || fields.next().is_some() {
+        return None;
+    }
+    Some(Outcome { state, time, hash })
+}
+
+fn unverified(message: &str) -> MinesweeperError {
+    MinesweeperError::Unverified(message.to_string())
+}
+
+// a move off the board would panic when the replay is played back
+fn check_move(game: &Game, mv: Move) -> Result<()> {
+    let (Move::Uncover(x, y)
+    | Move::Flag(x, y)
+    | Move::Question(x, y)
+    | Move::SetUnknown(x, y)
+    | Move::Chord(x, y)) = mv;
+    if x < 0 || y < 0 || x >= game.width() || y >= game.height() {
+        return Err(MinesweeperError::InvalidMove {
+            mv,
+            width: game.width(),
+            height: game.height(),
+        });
+    }
+    Ok(())
+}
+
+fn invalid(message: &str) -> MinesweeperError {
+    MinesweeperError::Parse(message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::{CellState, GameState};
+
+    #[test]
+    pub fn test_round_trip() {
+        let mut layout = vec![false; 10];
+        layout[2] = true;
+        layout[9] = true;
+        let game = Game::from_layout(5, 2, &layout);
+        let mut replay = Replay::new(&game);
+        replay.push(Duration::from_millis(100), Action::Play(Move::Flag(2, 0)));
+        replay.push(Duration::from_millis(250), Action::Undo);
+        replay.push(
+            Duration::from_millis(900),
+            Action::Play(Move::Uncover(0, 0)),
+        );
+        replay.push(
+            Duration::from_millis(1200),
+            Action::Play(Move::SetUnknown(4, 1)),
+        );
+        let mut buf = Vec::new();
+        replay.write(&mut buf).unwrap();
+        let loaded = Replay::read(buf.as_slice()).unwrap();
+        assert_eq!(replay.steps(), loaded.steps());
+        assert_eq!(
+            CellState::Flagged(true),
+            loaded.position(1).cell_state(2, 0)
+        );
+        assert_eq!(
+            CellState::Unknown(true),
+            loaded.position(2).cell_state(2, 0)
+        );
+        assert_eq!(GameState::Playing, loaded.position(3).state());
+    }
+
+    #[test]
+    pub fn test_read_v1() {
+        let text = "minesweeper-replay\nminesweeper-save\nwidth 3\nheight 1\nelapsed 0.25\ncells\n.*.\nmoves\n100 uncover [0,0]\n";
+        let replay = Replay::read(text.as_bytes()).unwrap();
+        assert_eq!(CellState::Counted(1), replay.position(1).cell_state(0, 0));
+        let mut buf = Vec::new();
+        replay.write(&mut buf).unwrap();
+        assert!(buf.starts_with(b"minesweeper-replay 3\nminesweeper-save 2\n"));
+        let loaded = Replay::read(buf.as_slice()).unwrap();
+        assert_eq!(replay.steps(), loaded.steps());
+    }
+
+    #[test]
+    pub fn test_verify() {
+        let mut game = Game::from_layout(3, 1, &[false, false, true]);
+        game.set_seed(Some(42));
+        let mut replay = Replay::new(&game);
+        replay.push(Duration::from_millis(400), Action::Play(Move::Flag(2, 0)));
+        replay.push(
+            Duration::from_millis(1250),
+            Action::Play(Move::Uncover(0, 0)),
+        );
+        // not read from a file, nothing is claimed
+        assert!(replay.verify().is_err());
+        let outcome = replay.outcome().unwrap();
+        assert_eq!(GameState::Won, outcome.state);
+        assert_eq!(Duration::from_millis(1250), outcome.time);
+        let mut buf = Vec::new();
+        replay.write(&mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let end = format!("end won 1250 {:016x}\n", outcome.hash);
+        assert!(text.ends_with(&end));
+        let loaded = Replay::read(text.as_bytes()).unwrap();
+        assert_eq!(Some(&outcome), loaded.claim());
+        assert_eq!(outcome, loaded.verify().unwrap());
+
+        // a faster claimed time, another result or another board fail
+        for forged in [
+            text.replace("end won 1250", "end won 900"),
+            text.replace("end won", "end lost"),
+            text.replace(&format!("{:016x}", outcome.hash), "0"),
+            text.replace("1250 uncover", "300 uncover")
+                .replace("end won 1250", "end won 300"),
+        ] {
+            let forged = Replay::read(forged.as_bytes()).unwrap();
+            assert!(matches!(
+                forged.verify(),
+                Err(MinesweeperError::Unverified(_))
+            ));
+        }
+        assert!(Replay::read(format!("{}5 undo\n", text).as_bytes()).is_err());
+        // an unfinished game writes no end line
+        replay.push(Duration::from_millis(1300), Action::Undo);
+        assert_eq!(None, replay.outcome());
+    }
+
+    #[test]
+    pub fn test_read_invalid() {
+        assert!(Replay::read("minesweeper-save\n".as_bytes()).is_err());
+        let text = "minesweeper-replay\nminesweeper-save\nwidth 1\nheight 2\ncells\n.\n*\nmoves\n5 dig [0,0]\n";
+        assert!(matches!(
+            Replay::read(text.as_bytes()),
+            Err(MinesweeperError::Parse(_))
+        ));
+        let text = text.replace("dig [0,0]", "uncover [3,0]");
+        assert!(matches!(
+            Replay::read(text.as_bytes()),
+            Err(MinesweeperError::InvalidMove { width: 1, .. })
+        ));
+    }
+}
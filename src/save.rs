@@ -0,0 +1,663 @@
+//! The plain text save file format.
+
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    game::{CellState, Game, GameState, Neighborhood, MAX_CELLS},
+    stats::{self, data_dir},
+};
+
+/// Folder of the data directory holding the saved games and authored boards
+/// listed by [`browse`].
+pub const SAVES_DIR: &str = "saves";
+
+/// First line of every saved game, followed by the format version.
+const SAVE_HEADER: &str = "minesweeper-save";
+/// Version of the save file format written by [`fn@write`].
+pub const SAVE_VERSION: u32 = 2;
+
+// The save file is plain text so it can be inspected and edited by hand:
+//
+//   minesweeper-save 2
+//   width 5
+//   height 2
+//   state playing
+//   elapsed_ms 12500
+//   seed 1234
+//   mode nf
+//   assist auto
+//   hints 1/3
+//   penalty 2
+//   practice checkpoint
+//   rule distance2
+//   cells
+//   01*..
+//   02F.q
+//
+// Mode nf is written for games played without flags, assist auto for
+// games opening the cells proven safe on their own and assist flag for games
+// flagging the proven mines, both as `assist auto flag`. Hints are the number
+// taken, then the number allowed when they are limited. Penalty is written
+// for games refusing flags on safe cells, with the number refused so far.
+// Practice checkpoint
+// marks a game that went back to a checkpoint. Rule distance2 is written
+// for games whose numbers count a 5x5 square, their counts past 9 are the
+// letters of LARGE_COUNTS. Each cell is one character, the lower case forms
+// are safe and the upper case forms are mined.
+//
+// A board can also be given as just its cell rows, see [`parse_layout`],
+// to paste or type in a layout without the settings.
+//
+// Older files are read by taking their fields through the migrations below,
+// one version at a time, before they are parsed:
+//
+//   1  no version after the header, elapsed time in decimal seconds
+//   2  elapsed time in whole milliseconds so it round trips exactly
+
+/// The characters of the counts from 10 on, lower case letters skipping the
+/// `f` and `q` of marked cells.
+const LARGE_COUNTS: &[u8] = b"abcdeghijklmnop";
+
+/// Takes the fields of each version to the next, the first entry upgrades
+/// version 1.
+const MIGRATIONS: [fn(&mut Fields) -> Result<()>; SAVE_VERSION as usize - 1] = [elapsed_ms];
+
+/// The settings and cell rows of a save file before they are parsed.
+struct Fields {
+    settings: Vec<(String, String)>,
+    rows: Vec<String>,
+}
+
+impl Fields {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.settings.iter().rposition(|(k, _)| k == key)?;
+        Some(self.settings.remove(index).1)
+    }
+}
+
+/// Writes a game, including its hidden layout, in the save file format.
+pub fn write<W: Write>(game: &Game, mut writer: W) -> Result<()> {
+    writeln!(writer, "{} {}", SAVE_HEADER, SAVE_VERSION)?;
+    writeln!(writer, "width {}", game.width())?;
+    writeln!(writer, "height {}", game.height())?;
+    writeln!(writer, "state {}", game.state())?;
+    writeln!(writer, "elapsed_ms {}", game.elapsed().as_millis())?;
+    if let Some(seed) = game.seed() {
+        writeln!(writer, "seed {}", seed)?;
+    }
+    if game.no_flags() {
+        writeln!(writer, "mode nf")?;
+    }
+    let assists = [(game.auto_open(), "auto"), (game.auto_flag(), "flag")];
+    let assists: Vec<&str> = assists
+        .into_iter()
+        .filter_map(|(on, assist)| on.then_some(assist))
+        .collect();
+    if !assists.is_empty() {
+        writeln!(writer, "assist {}", assists.join(" "))?;
+    }
+    match game.hints_left() {
+        Some(left) => writeln!(writer, "hints {}/{}", game.hints(), game.hints() + left)?,
+        None if game.hints() > 0 => writeln!(writer, "hints {}", game.hints())?,
+        None => {}
+    }
+    if game.flag_penalty() {
+        writeln!(writer, "penalty {}", game.wrong_flags())?;
+    }
+    if game.practice() {
+        writeln!(writer, "practice checkpoint")?;
+    }
+    if game.neighborhood() != Neighborhood::Adjacent {
+        writeln!(writer, "rule {}", game.neighborhood())?;
+    }
+    writeln!(writer, "cells")?;
+    for y in 0..game.height() {
+        let row: String = (0..game.width())
+            .map(|x| cell_char(game.cell_state(x, y)))
+            .collect();
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(())
+}
+
+/// Reads a game written by [`fn@write`] in this or any earlier version of the
+/// format.
+pub fn read<R: BufRead>(reader: R) -> Result<Game> {
+    let mut lines = reader.lines();
+    let mut next_line = || -> Result<String> {
+        match lines.next() {
+            Some(line) => Ok(line?),
+            None => Err(invalid("unexpected end of save file")),
+        }
+    };
+    let version = format_version(&next_line()?, SAVE_HEADER, SAVE_VERSION)?;
+    let mut fields = Fields {
+        settings: Vec::new(),
+        rows: Vec::new(),
+    };
+    loop {
+        let line = next_line()?;
+        let line = line.trim();
+        if line == "cells" {
+            break;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        fields.settings.push((key.to_string(), value.to_string()));
+    }
+    let height = parse_dimension(fields.get("height").unwrap_or_default())?;
+    for _ in 0..height {
+        fields.rows.push(next_line()?);
+    }
+    for migrate in &MIGRATIONS[version as usize - 1..] {
+        migrate(&mut fields)?;
+    }
+    parse_fields(&fields)
+}
+
+/// Parses a board layout: either a whole save file or only its cell rows,
+/// one line per row in the characters of the save file, `.` for a safe
+/// covered cell and `*` for a mine. Spaces between cells are ignored, so
+/// grids laid out for reading parse too. A layout with uncovered cells is
+/// in play, lost when one of them is a mine, and one without is ready for
+/// its first click.
+pub fn parse_layout(text: &str) -> Result<Game> {
+    if text.trim_start().starts_with(SAVE_HEADER) {
+        return read(text.as_bytes());
+    }
+    let rows: Vec<Vec<char>> = text
+        .lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+        .filter(|row: &Vec<char>| !row.is_empty())
+        .collect();
+    let width = rows.first().map_or(0, Vec::len);
+    if width == 0 {
+        return Err(invalid("no cells in the layout"));
+    }
+    if width * rows.len() > MAX_CELLS {
+        return Err(invalid("board is too large"));
+    }
+    let mut cells = Vec::with_capacity(width * rows.len());
+    for row in &rows {
+        if row.len() != width {
+            return Err(invalid("row does not match the board width"));
+        }
+        for &c in row {
+            cells.push(parse_cell(c)?);
+        }
+    }
+    let state = if cells.contains(&CellState::Known(true)) {
+        GameState::Lost
+    } else if cells
+        .iter()
+        .any(|cell| matches!(cell, CellState::Known(_) | CellState::Counted(_)))
+    {
+        GameState::Playing
+    } else {
+        GameState::Initial
+    };
+    Ok(Game::from_cells(
+        width as i16,
+        rows.len() as i16,
+        cells,
+        state,
+        Duration::ZERO,
+    ))
+}
+
+/// Checks the first line of a file in a versioned format and returns its
+/// version, 1 when the header has no version.
+pub(crate) fn format_version(line: &str, header: &str, current: u32) -> Result<u32> {
+    let not_ours = || invalid(&format!("not a {} file", header));
+    let version = match line.trim().strip_prefix(header).ok_or_else(not_ours)? {
+        "" => 1,
+        rest => rest
+            .strip_prefix(' ')
+            .and_then(|version| version.parse::<u32>().ok())
+            .filter(|&version| version >= 1)
+            .ok_or_else(not_ours)?,
+    };
+    if version > current {
+        return Err(invalid(&format!(
+            "{} version {} is newer than the supported version {}",
+            header, version, current
+        )));
+    }
+    Ok(version)
+}
+
+fn parse_fields(fields: &Fields) -> Result<Game> {
+    let (Some(width), Some(height)) = (fields.get("width"), fields.get("height")) else {
+        return Err(invalid("missing board size"));
+    };
+    let width = parse_dimension(width)?;
+    let height = parse_dimension(height)?;
+    if (width as usize * height as usize) > MAX_CELLS {
+        return Err(invalid("board is too large"));
+    }
+    let state = match fields.get("state") {
+        Some(state) => state.parse().map_err(|_| invalid("invalid game state"))?,
+        None => GameState::Playing,
+    };
+    let elapsed = match fields.get("elapsed_ms") {
+        Some(ms) => Duration::from_millis(ms.parse().map_err(|_| invalid("invalid elapsed time"))?),
+        None => Duration::ZERO,
+    };
+    let seed = match fields.get("seed") {
+        Some(seed) => Some(seed.parse().map_err(|_| invalid("invalid seed"))?),
+        None => None,
+    };
+    let no_flags = match fields.get("mode") {
+        Some("nf") => true,
+        None => false,
+        Some(mode) => return Err(invalid(&format!("unknown mode {}", mode))),
+    };
+    let (mut auto_open, mut auto_flag) = (false, false);
+    for assist in fields.get("assist").unwrap_or_default().split_whitespace() {
+        match assist {
+            "auto" => auto_open = true,
+            "flag" => auto_flag = true,
+            _ => return Err(invalid(&format!("unknown assist {}", assist))),
+        }
+    }
+    let (hints, hint_budget) = match fields.get("hints") {
+        Some(hints) => parse_hints(hints).ok_or_else(|| invalid("invalid hints"))?,
+        None => (0, None),
+    };
+    let wrong_flags = match fields.get("penalty") {
+        Some(wrong) => Some(wrong.parse().map_err(|_| invalid("invalid penalty"))?),
+        None => None,
+    };
+    let practice = match fields.get("practice") {
+        Some("checkpoint") => true,
+        None => false,
+        Some(practice) => return Err(invalid(&format!("unknown practice {}", practice))),
+    };
+    let neighborhood = match fields.get("rule") {
+        Some(rule) => rule.parse().map_err(|e: String| invalid(&e))?,
+        None => Neighborhood::Adjacent,
+    };
+    // unknown settings are skipped
+    let mut cells = Vec::with_capacity(width as usize * height as usize);
+    for line in &fields.rows {
+        let row: Vec<char> = line.trim_end().chars().collect();
+        if row.len() != width as usize {
+            return Err(invalid("row does not match the board width"));
+        }
+        for c in row {
+            cells.push(parse_cell(c)?);
+        }
+    }
+    let mut game = Game::from_cells(width, height, cells, state, elapsed);
+    game.set_seed(seed);
+    game.set_no_flags(no_flags);
+    game.set_auto_open(auto_open);
+    game.set_auto_flag(auto_flag);
+    game.set_hint_budget(hint_budget);
+    game.set_hints(hints);
+    game.set_flag_penalty(wrong_flags.is_some());
+    game.set_wrong_flags(wrong_flags.unwrap_or_default());
+    game.set_practice(practice);
+    game.set_neighborhood(neighborhood);
+    Ok(game)
+}
+
+/// Parses the hints taken and, after a slash, the hints allowed.
+fn parse_hints(hints: &str) -> Option<(u16, Option<u16>)> {
+    match hints.split_once('/') {
+        Some((taken, budget)) => {
+            let (taken, budget) = (taken.parse().ok()?, budget.parse().ok()?);
+            (taken <= budget).then_some((taken, Some(budget)))
+        }
+        None => Some((hints.parse().ok()?, None)),
+    }
+}
+
+/// Version 1 to 2: the elapsed time in milliseconds rather than seconds.
+fn elapsed_ms(fields: &mut Fields) -> Result<()> {
+    if let Some(seconds) = fields.remove("elapsed") {
+        let seconds = seconds
+            .parse::<f64>()
+            .map_err(|_| invalid("invalid elapsed time"))?;
+        let elapsed =
+            Duration::try_from_secs_f64(seconds).map_err(|_| invalid("invalid elapsed time"))?;
+        fields
+            .settings
+            .push(("elapsed_ms".to_string(), elapsed.as_millis().to_string()));
+    }
+    Ok(())
+}
+
+/// Saves a game to a file.
+pub fn save<P: AsRef<Path>>(game: &Game, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(game, &mut writer)?;
+    Ok(writer.flush()?)
+}
+
+/// Loads a game from a file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Game> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// A saved game or an authored board found by [`browse`].
+#[derive(Clone)]
+pub struct SavedBoard {
+    /// File the board was read from.
+    pub path: PathBuf,
+    /// The game as it was saved.
+    pub game: Game,
+    /// Whether the file holds only the cell rows of a board laid out by
+    /// hand, see [`parse_layout`].
+    pub authored: bool,
+    /// When the file was last written.
+    pub modified: Option<SystemTime>,
+}
+
+impl SavedBoard {
+    /// Reads a saved game or a layout, `None` when the file is neither.
+    pub fn read(path: &Path) -> Option<SavedBoard> {
+        let text = fs::read_to_string(path).ok()?;
+        let game = parse_layout(&text).ok()?;
+        Some(SavedBoard {
+            path: path.to_path_buf(),
+            game,
+            authored: !text.trim_start().starts_with(SAVE_HEADER),
+            modified: fs::metadata(path).and_then(|meta| meta.modified()).ok(),
+        })
+    }
+
+    /// The file name without its extension.
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// The preset the board was laid out as, or its size and mines.
+    pub fn level(&self) -> String {
+        stats::level_name(self.game.width(), self.game.height(), self.game.total())
+    }
+
+    /// How far the game got: new, the share of its safe cells uncovered, won
+    /// or lost.
+    pub fn progress(&self) -> String {
+        match self.game.state() {
+            GameState::Won => "won".to_string(),
+            GameState::Lost => "lost".to_string(),
+            GameState::Initial => "new".to_string(),
+            GameState::Playing => {
+                let cells = self.game.width() as usize * self.game.height() as usize;
+                let safe = cells.saturating_sub(self.game.total() as usize).max(1);
+                let uncovered = safe.saturating_sub(self.game.safe_cells_remaining());
+                format!("{}% cleared", uncovered * 100 / safe)
+            }
+        }
+    }
+
+    /// One line telling the board apart from the others: its name, level,
+    /// progress and how long ago it was saved as of `now`.
+    pub fn summary(&self, now: SystemTime) -> String {
+        let kind = if self.authored { "board" } else { "game" };
+        let mut line = format!(
+            "{} - {} {}, {}",
+            self.name(),
+            self.level(),
+            kind,
+            self.progress()
+        );
+        if let Some(age) = self.modified.and_then(|at| now.duration_since(at).ok()) {
+            line.push_str(&format!(", {}", ago(age)));
+        }
+        line
+    }
+}
+
+/// The saves folder of the data directory, see [`SAVES_DIR`].
+pub fn saves_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SAVES_DIR))
+}
+
+/// The saved games and authored boards in `dir`, the latest written first.
+/// Files holding neither are skipped and a missing folder has none.
+pub fn browse(dir: &Path) -> Vec<SavedBoard> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut boards: Vec<SavedBoard> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| SavedBoard::read(&path))
+        .collect();
+    boards.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)));
+    boards
+}
+
+// a time ago in its largest whole unit
+fn ago(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    match (minutes, minutes / 60, minutes / (60 * 24)) {
+        (0, _, _) => "just now".to_string(),
+        (minutes, 0, _) => format!("{} min ago", minutes),
+        (_, hours, 0) => format!("{} h ago", hours),
+        (_, _, 1) => "yesterday".to_string(),
+        (_, _, days) => format!("{} days ago", days),
+    }
+}
+
+fn cell_char(state: CellState) -> char {
+    match state {
+        CellState::Unknown(false) => '.',
+        CellState::Unknown(true) => '*',
+        CellState::Flagged(false) => 'f',
+        CellState::Flagged(true) => 'F',
+        CellState::Questioned(false) => 'q',
+        CellState::Questioned(true) => 'Q',
+        CellState::Known(false) => '0',
+        CellState::Known(true) => 'X',
+        CellState::Counted(count @ 1..=9) => (b'0' + count) as char,
+        CellState::Counted(count) => LARGE_COUNTS
+            .get(count as usize - 10)
+            .map_or('?', |&c| c as char),
+    }
+}
+
+fn parse_cell(c: char) -> Result<CellState> {
+    let state = match c {
+        '.' => CellState::Unknown(false),
+        '*' => CellState::Unknown(true),
+        'f' => CellState::Flagged(false),
+        'F' => CellState::Flagged(true),
+        'q' => CellState::Questioned(false),
+        'Q' => CellState::Questioned(true),
+        '0' => CellState::Known(false),
+        'X' => CellState::Known(true),
+        '1'..='9' => CellState::Counted(c as u8 - b'0'),
+        _ => match LARGE_COUNTS.iter().position(|&large| large as char == c) {
+            Some(index) => CellState::Counted(index as u8 + 10),
+            None => return Err(invalid("invalid cell")),
+        },
+    };
+    Ok(state)
+}
+
+fn parse_dimension(value: &str) -> Result<i16> {
+    match value.parse::<i16>() {
+        Ok(value) if value > 0 => Ok(value),
+        _ => Err(invalid("invalid board size")),
+    }
+}
+
+fn invalid(message: &str) -> MinesweeperError {
+    MinesweeperError::Parse(message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_round_trip() {
+        let mut layout = vec![false; 10];
+        layout[2] = true;
+        layout[9] = true;
+        let mut game = Game::from_layout(5, 2, &layout);
+        game.uncover(0, 0);
+        game.flag(2, 0);
+        game.question(4, 0);
+        game.set_seed(Some(7));
+        let mut buf = Vec::new();
+        write(&game, &mut buf).unwrap();
+        let loaded = read(buf.as_slice()).unwrap();
+        assert_eq!(game.width(), loaded.width());
+        assert_eq!(game.height(), loaded.height());
+        assert_eq!(game.state(), loaded.state());
+        assert_eq!(Some(7), loaded.seed());
+        assert_eq!(game.est_remaining(), loaded.est_remaining());
+        assert_eq!(game.act_remaining(), loaded.act_remaining());
+        for y in 0..game.height() {
+            for x in 0..game.width() {
+                assert_eq!(game.cell_state(x, y), loaded.cell_state(x, y));
+            }
+        }
+        assert!(!loaded.no_flags() && !loaded.auto_open() && !loaded.practice());
+        game.set_no_flags(true);
+        game.set_auto_open(true);
+        game.set_hint_budget(Some(3));
+        game.set_hints(1);
+        game.set_flag_penalty(true);
+        game.set_wrong_flags(2);
+        game.set_practice(true);
+        let mut buf = Vec::new();
+        write(&game, &mut buf).unwrap();
+        let loaded = read(buf.as_slice()).unwrap();
+        assert!(loaded.no_flags() && loaded.auto_open() && loaded.practice());
+        assert!(!loaded.auto_flag());
+        assert_eq!((true, 2), (loaded.flag_penalty(), loaded.wrong_flags()));
+        game.set_auto_flag(true);
+        let mut buf = Vec::new();
+        write(&game, &mut buf).unwrap();
+        let loaded = read(buf.as_slice()).unwrap();
+        assert!(loaded.auto_open() && loaded.auto_flag());
+        assert_eq!((1, Some(2)), (loaded.hints(), loaded.hints_left()));
+    }
+
+    #[test]
+    pub fn test_round_trip_distance2() {
+        let mut layout = vec![true; 25];
+        layout[12] = false;
+        layout[0] = false;
+        let mut game = Game::from_layout(5, 5, &layout);
+        game.set_neighborhood(Neighborhood::Distance2);
+        game.uncover(2, 2);
+        let mut buf = Vec::new();
+        write(&game, &mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.contains("rule distance2\n"));
+        let mut loaded = read(text.as_bytes()).unwrap();
+        assert_eq!(Neighborhood::Distance2, loaded.neighborhood());
+        assert_eq!(CellState::Counted(23), loaded.cell_state(2, 2));
+        assert_eq!(GameState::Won, loaded.uncover(0, 0));
+    }
+
+    #[test]
+    pub fn test_read() {
+        let text =
+            "minesweeper-save\nwidth 3\nheight 2\nstate playing\nelapsed 1.5\ncells\n01F\n01.\n";
+        let mut game = read(text.as_bytes()).unwrap();
+        assert_eq!(CellState::Flagged(true), game.cell_state(2, 0));
+        assert_eq!(0, game.est_remaining());
+        assert_eq!(GameState::Won, game.uncover(2, 1));
+    }
+
+    #[test]
+    pub fn test_read_versions() {
+        let v1 = "minesweeper-save\nwidth 3\nheight 2\nstate won\nelapsed 1.5\ncells\n01F\n011\n";
+        let v2 =
+            "minesweeper-save 2\nwidth 3\nheight 2\nstate won\nelapsed_ms 1500\ncells\n01F\n011\n";
+        for text in [v1, v2] {
+            let game = read(text.as_bytes()).unwrap();
+            assert_eq!(Duration::from_millis(1500), game.elapsed());
+            assert_eq!(CellState::Flagged(true), game.cell_state(2, 0));
+            // an older file is written back in the current version
+            let mut buf = Vec::new();
+            write(&game, &mut buf).unwrap();
+            assert!(buf.starts_with(b"minesweeper-save 2\n"));
+            let loaded = read(buf.as_slice()).unwrap();
+            assert_eq!(game.elapsed(), loaded.elapsed());
+            assert_eq!(GameState::Won, loaded.state());
+        }
+        assert!(read("minesweeper-save 3\nwidth 1\nheight 1\ncells\n.\n".as_bytes()).is_err());
+        assert!(read("minesweeper-save v2\nwidth 1\nheight 1\ncells\n.\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    pub fn test_read_invalid() {
+        assert!(read("minesweeper-save\nwidth 3\nheight 1\ncells\n0Z1\n".as_bytes()).is_err());
+        assert!(read("something else\n".as_bytes()).is_err());
+        assert!(read("minesweeper-save\nwidth 3\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    pub fn test_browse() {
+        let dir = std::env::temp_dir().join(format!("browse-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut game = Game::from_layout(3, 2, &[true, false, false, false, false, false]);
+        game.uncover(2, 1);
+        save(&game, dir.join("resumed.txt")).unwrap();
+        fs::write(dir.join("authored.txt"), "*..\n..*\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a board").unwrap();
+        let boards = browse(&dir);
+        assert_eq!(2, boards.len());
+        let resumed = boards.iter().find(|board| !board.authored).unwrap();
+        assert_eq!("resumed", resumed.name());
+        assert_eq!("3x2/1", resumed.level());
+        assert_eq!("80% cleared", resumed.progress());
+        let authored = boards.iter().find(|board| board.authored).unwrap();
+        assert_eq!("new", authored.progress());
+        let now = authored.modified.unwrap() + Duration::from_secs(3 * 3600);
+        assert_eq!(
+            "authored - 3x2/2 board, new, 3 h ago",
+            authored.summary(now)
+        );
+        assert!(browse(&dir.join("missing")).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_parse_layout() {
+        let game = parse_layout("\n* . .\n. . *\n").unwrap();
+        assert_eq!((3, 2, 2), (game.width(), game.height(), game.total()));
+        assert_eq!(GameState::Initial, game.state());
+        assert_eq!(CellState::Unknown(true), game.cell_state(2, 1));
+
+        let game = parse_layout("*1.\n110\n").unwrap();
+        assert_eq!(GameState::Playing, game.state());
+        assert_eq!(CellState::Counted(1), game.cell_state(1, 0));
+        assert_eq!(GameState::Lost, parse_layout("X1\n").unwrap().state());
+
+        let mut saved = Vec::new();
+        write(&game, &mut saved).unwrap();
+        let loaded = parse_layout(&String::from_utf8(saved).unwrap()).unwrap();
+        assert_eq!(game.cell_state(0, 0), loaded.cell_state(0, 0));
+
+        assert!(parse_layout(" \n").is_err());
+        assert!(parse_layout("..\n.\n").is_err());
+        assert!(parse_layout(".#\n").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Points for arcade style play, computed from the moves of a game.
+//!
+//! Every safe cell uncovered scores [`CELL_POINTS`]. A chord opening several
+//! cells at once adds a chain bonus that grows with every cell after the
+//! first, and the sum is multiplied by how fast the board is being cleared:
+//! one plus the 3BV cleared per second, at most [`MAX_MULTIPLIER`]. Flags on
+//! safe cells cost [`WRONG_FLAG_POINTS`] each once the game is over, so the
+//! score never gives away a wrong flag during play.
+
+use std::fmt::Display;
+
+use crate::{
+    game::{CellState, Game, GameState, Move},
+    solver,
+};
+
+/// Points for every safe cell uncovered.
+pub const CELL_POINTS: u32 = 10;
+/// Bonus for the second cell a chord opens, the third adds twice as much and
+/// so on.
+pub const CHAIN_POINTS: u32 = 5;
+/// Points lost for every flag on a safe cell when the game ends.
+pub const WRONG_FLAG_POINTS: u32 = 50;
+/// Highest speed multiplier.
+pub const MAX_MULTIPLIER: f64 = 3.0;
+
+/// The parts of a game's score.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Score {
+    /// Points for the safe cells uncovered.
+    pub cells: u32,
+    /// Chain bonus of the chords.
+    pub chains: u32,
+    /// Points lost for wrong flags, counted once the game is over.
+    pub penalty: u32,
+    /// Speed multiplier applied to the cell and chain points.
+    pub multiplier: f64,
+}
+
+impl Score {
+    /// The points scored.
+    pub fn total(&self) -> u32 {
+        let points = (self.cells + self.chains) as f64 * self.multiplier;
+        (points as u32).saturating_sub(self.penalty)
+    }
+}
+
+/// Scores the game as it is now, see the module documentation.
+pub fn score(game: &Game) -> Score {
+    let mut cells = 0;
+    let mut wrong_flags = 0;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            match game.cell_state(x, y) {
+                CellState::Known(false) | CellState::Counted(_) => cells += 1,
+                CellState::Flagged(false) => wrong_flags += 1,
+                _ => {}
+            }
+        }
+    }
+    let chains = game
+        .moves()
+        .into_iter()
+        .filter(|(mv, _)| matches!(mv, Move::Chord(..)))
+        .map(|(_, opened)| chain_bonus(opened as u32))
+        .sum();
+    let over = matches!(game.state(), GameState::Won | GameState::Lost);
+    let seconds = game.elapsed().as_secs_f64();
+    let multiplier = if seconds > 0.0 {
+        let cleared = solver::three_bv(game).cleared as f64;
+        (1.0 + cleared / seconds).min(MAX_MULTIPLIER)
+    } else {
+        1.0
+    };
+    Score {
+        cells: cells * CELL_POINTS,
+        chains,
+        penalty: over as u32 * wrong_flags * WRONG_FLAG_POINTS,
+        multiplier,
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} pts (x{:.1})", self.total(), self.multiplier)
+    }
+}
+
+// 0, 5, 15, 30, ... for a chord opening 1, 2, 3, 4, ... cells
+fn chain_bonus(opened: u32) -> u32 {
+    CHAIN_POINTS * opened * opened.saturating_sub(1) / 2
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_score() {
+        //   * . .
+        //   . 2 .
+        //   . . *
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        layout[8] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(1, 1);
+        let first = score(&game);
+        assert_eq!((10, 0, 0), (first.cells, first.chains, first.penalty));
+        assert!(first.multiplier >= 1.0 && first.multiplier <= MAX_MULTIPLIER);
+
+        // the chord opens the six other safe cells at once
+        game.flag(0, 0);
+        game.flag(2, 2);
+        game.chord(1, 1);
+        let opened: Vec<usize> = game.moves().iter().map(|&(_, opened)| opened).collect();
+        assert_eq!(vec![1, 0, 0, 6], opened);
+        let won = score(&game);
+        assert_eq!((70, 75, 0), (won.cells, won.chains, won.penalty));
+        assert!(won.total() >= 145);
+        assert!(won
+            .to_string()
+            .ends_with(&format!("(x{:.1})", won.multiplier)));
+
+        // a wrong flag only costs once the game is over
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(1, 1);
+        game.flag(0, 1);
+        assert_eq!(0, score(&game).penalty);
+        game.uncover(0, 0);
+        let lost = score(&game);
+        assert_eq!(WRONG_FLAG_POINTS, lost.penalty);
+        assert_eq!(0, lost.total());
+    }
+}
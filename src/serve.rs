@@ -0,0 +1,187 @@
+//! A local HTTP server publishing the game being played, for stream overlays.
+//!
+//! Streaming software such as OBS can show a browser source pointed at the
+//! server: `/` is a page with the board and the timer, `/state` the game as
+//! JSON (see [`json::game_json`]), `/stats` the per level statistics and
+//! `/board.png` a snapshot of the board. The server answers GET and HEAD
+//! requests one at a time and never accepts moves.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, PoisonError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{game::Game, json, render::Overlay, snapshot, stats::Stats};
+
+/// Port the server listens on unless another one is given.
+pub const DEFAULT_PORT: u16 = 4808;
+/// Time a client gets to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A browser source refreshing the board and timer every second, on a
+/// transparent background.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Minesweeper</title>
+<style>
+body { margin: 0; background: transparent; color: #fff; font: bold 24px sans-serif; text-shadow: 0 0 4px #000; }
+img { display: block; image-rendering: pixelated; }
+</style>
+</head>
+<body>
+<div id="status"></div>
+<img id="board" src="board.png" alt="">
+<script>
+async function refresh() {
+  try {
+    const state = await (await fetch("state")).json();
+    document.getElementById("status").textContent =
+      `${state.state}  ${state.remaining} mines left  ${state.elapsed.toFixed(1)} s  ` +
+      `L ${state.clicks.left}  R ${state.clicks.right}  C ${state.clicks.chords}`;
+    document.getElementById("board").src = "board.png?" + Date.now();
+  } catch (e) {}
+}
+setInterval(refresh, 1000);
+refresh();
+</script>
+</body>
+</html>
+"#;
+
+type Board = (Game, Vec<(i16, i16, Overlay)>);
+
+/// The game the server publishes, updated by the front-end after every move.
+#[derive(Clone, Default)]
+pub struct Shared {
+    board: Arc<Mutex<Option<Board>>>,
+}
+
+impl Shared {
+    /// Creates a handle with no game published yet.
+    pub fn new() -> Self {
+        Shared::default()
+    }
+
+    /// Publishes the game as it is now along with the overlays drawn on it.
+    pub fn publish(&self, game: &Game, overlays: &[(i16, i16, Overlay)]) {
+        *self.lock() = Some((game.clone(), overlays.to_vec()));
+    }
+
+    fn board(&self) -> Option<Board> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Board>> {
+        // a panic while publishing leaves a whole board behind, keep serving it
+        self.board.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Answers requests on `listener` from a background thread for as long as
+/// the process runs.
+pub fn serve(listener: TcpListener, shared: Shared) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| respond(stream, &shared));
+            if let Err(e) = result {
+                tracing::debug!(error = %e, "overlay request failed");
+            }
+        }
+    })
+}
+
+fn respond(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // the headers are read and ignored
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts
+        .next()
+        .and_then(|target| target.split('?').next())
+        .unwrap_or_default();
+    tracing::trace!(method, path, "overlay request");
+    let (status, content_type, body) = match method {
+        "GET" | "HEAD" => route(path, shared),
+        _ => text("405 Method Not Allowed", "only GET and HEAD are supported"),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+/// Returns the status line, content type and body answering `path`.
+fn route(path: &str, shared: &Shared) -> (&'static str, &'static str, Vec<u8>) {
+    const JSON: &str = "application/json";
+    match (path, shared.board()) {
+        ("/" | "/index.html", _) => ("200 OK", "text/html; charset=utf-8", PAGE.into()),
+        ("/stats", _) => match Stats::load() {
+            Ok(stats) => ("200 OK", JSON, json::stats_json(&stats.summary()).into()),
+            Err(e) => text("500 Internal Server Error", &e.to_string()),
+        },
+        ("/state", Some((game, _))) => ("200 OK", JSON, json::game_json(&game, "").into()),
+        ("/board.png", Some((game, overlays))) => {
+            ("200 OK", "image/png", snapshot::png(&game, &overlays))
+        }
+        ("/state" | "/board.png", None) => text("503 Service Unavailable", "no game yet"),
+        _ => text("404 Not Found", "not found"),
+    }
+}
+
+fn text(status: &'static str, message: &str) -> (&'static str, &'static str, Vec<u8>) {
+    (status, "text/plain; charset=utf-8", message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use super::*;
+
+    fn get(address: std::net::SocketAddr, path: &str) -> Vec<u8> {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    pub fn test_serve_game() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let shared = Shared::new();
+        serve(listener, shared.clone());
+        assert!(get(address, "/state").starts_with(b"HTTP/1.1 503"));
+        let mut game = Game::from_layout(2, 2, &[false, false, false, true]);
+        game.uncover(0, 0);
+        shared.publish(&game, &[]);
+        let state = String::from_utf8(get(address, "/state?t=1")).unwrap();
+        assert!(state.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(state.contains("\"board\":[[1,\"covered\"],[\"covered\",\"covered\"]]"));
+        let png = get(address, "/board.png");
+        let start = png.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        assert_eq!(b"\x89PNG", &png[start..start + 4]);
+        assert!(get(address, "/moves").starts_with(b"HTTP/1.1 404"));
+    }
+}
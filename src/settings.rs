@@ -0,0 +1,544 @@
+use std::{path::PathBuf, sync::Once};
+
+use windows::{
+    core::{w, Result, HSTRING, PCWSTR, PWSTR},
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::Gdi::{COLOR_WINDOW, HBRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::{
+            Controls::{
+                Dialogs::{
+                    GetOpenFileNameW, GetSaveFileNameW, OFN_FILEMUSTEXIST, OFN_OVERWRITEPROMPT,
+                    OFN_PATHMUSTEXIST, OPENFILENAMEW,
+                },
+                InitCommonControlsEx, ICC_BAR_CLASSES, INITCOMMONCONTROLSEX, TBM_GETPOS,
+                TBM_SETPOS, TBM_SETRANGE, TBS_HORZ, TRACKBAR_CLASSW,
+            },
+            WindowsAndMessaging::{
+                AdjustWindowRect, CreateWindowExW, DefWindowProcW, GetWindowLongPtrA,
+                GetWindowTextW, LoadCursorW, MessageBoxW, RegisterClassW, SendMessageW,
+                SetWindowLongPtrA, BM_GETCHECK, BM_SETCHECK, BN_CLICKED, BS_AUTOCHECKBOX,
+                CBN_SELCHANGE, CBS_DROPDOWNLIST, CB_ADDSTRING, CB_GETCURSEL, CB_RESETCONTENT,
+                CB_SETCURSEL, CREATESTRUCTA, CW_USEDEFAULT, ES_NUMBER, GWLP_USERDATA, HMENU,
+                IDC_ARROW, MB_ICONWARNING, MB_OK, WINDOW_EX_STYLE, WINDOW_STYLE, WM_COMMAND,
+                WM_CREATE, WM_DESTROY, WM_HSCROLL, WNDCLASSW, WS_BORDER, WS_CAPTION, WS_CHILD,
+                WS_OVERLAPPED, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+            },
+        },
+    },
+};
+
+use crate::{
+    audio::Category,
+    config::{AudioSettings, Config, TimingSettings, CLASSIC_SKIN, NO_THEME},
+    skin,
+    theme::{self, ThemeFile},
+};
+
+static REGISTER_SETTINGS_WINDOW_CLASS: Once = Once::new();
+
+const STYLE: WINDOW_STYLE = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0);
+// client area and control layout in pixels, a row per category, the rows of
+// the skins, the themes and the theme buttons below and a row per pointer
+// timing at the bottom
+const WIDTH: i32 = 300;
+const ROW_HEIGHT: i32 = 40;
+const MARGIN: i32 = 15;
+const LOOK_ROWS: i32 = 3;
+const TIMING_ROWS: i32 = 3;
+const BUTTON_WIDTH: i32 = 90;
+const TIMING_LABEL_WIDTH: i32 = 150;
+const TIMING_EDIT_WIDTH: i32 = 80;
+// control ids are the category index plus these
+const SLIDER_ID: usize = 100;
+const MUTE_ID: usize = 200;
+const SKIN_ID: usize = 300;
+const THEME_ID: usize = 301;
+const IMPORT_ID: usize = 302;
+const EXPORT_ID: usize = 303;
+const TIMING_ID: usize = 400;
+// the skin and theme lists drop down this many rows
+const LIST_ROWS: i32 = 6;
+// trackbars run from silent to full volume in percent
+const STEPS: f32 = 100.0;
+const CHECKED: isize = 1;
+
+/// Called with the new settings whenever a control changes.
+pub(crate) type OnChange<'a> = Box<dyn FnMut(&AudioSettings) + 'a>;
+
+/// A small window with a volume slider and a mute box per sound category,
+/// lists of the installed skins and themes and buttons to import a theme
+/// file and export the look of the board as one, and the pointer timing in
+/// milliseconds. Volume changes apply right away and all are written to the
+/// config file when the window closes, which puts the new skin, theme and
+/// timing on the board.
+pub(crate) struct SettingsWindow<'a> {
+    handle: HWND,
+    settings: AudioSettings,
+    sliders: [HWND; Category::ALL.len()],
+    mutes: [HWND; Category::ALL.len()],
+    // the chosen skin and the ones to choose from, the built in theme first
+    skin: Option<String>,
+    skins: Vec<String>,
+    skin_list: HWND,
+    // the chosen theme and the installed ones, no theme first
+    theme: Option<String>,
+    themes: Vec<String>,
+    theme_list: HWND,
+    // the look of the board when the window opened, what is exported
+    look: ThemeFile,
+    // the timing when the window opened, kept for times left blank, and the
+    // boxes of the chord, long press and double click times
+    timing: TimingSettings,
+    timing_edits: [HWND; TIMING_ROWS as usize],
+    on_change: OnChange<'a>,
+}
+
+impl<'a> SettingsWindow<'a> {
+    /// Opens the window over its owner showing `settings`, `skin`, `theme`
+    /// and `timing`, exporting `look`.
+    pub(crate) fn new(
+        owner: HWND,
+        settings: AudioSettings,
+        skin: Option<String>,
+        theme: Option<String>,
+        look: ThemeFile,
+        timing: TimingSettings,
+        on_change: impl FnMut(&AudioSettings) + 'a,
+    ) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_SETTINGS_WINDOW_CLASS.call_once(|| {
+            let controls = INITCOMMONCONTROLSEX {
+                dwSize: std::mem::size_of::<INITCOMMONCONTROLSEX>() as u32,
+                dwICC: ICC_BAR_CLASSES,
+            };
+            let _ = unsafe { InitCommonControlsEx(&controls) };
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hbrBackground: HBRUSH(COLOR_WINDOW.0 as isize),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-settings"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        let mut window = Box::new(SettingsWindow {
+            handle: HWND(0),
+            settings,
+            sliders: [HWND(0); Category::ALL.len()],
+            mutes: [HWND(0); Category::ALL.len()],
+            skin,
+            skins: skin::installed(),
+            skin_list: HWND(0),
+            theme,
+            themes: theme::installed(),
+            theme_list: HWND(0),
+            look,
+            timing,
+            timing_edits: [HWND(0); TIMING_ROWS as usize],
+            on_change: Box::new(on_change),
+        });
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: WIDTH,
+            bottom: MARGIN * 2
+                + ROW_HEIGHT * (Category::ALL.len() as i32 + LOOK_ROWS + TIMING_ROWS),
+        };
+        unsafe { AdjustWindowRect(&mut rect, STYLE, false)? };
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-settings"),
+                w!("Options"),
+                STYLE | WS_VISIBLE,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                owner,
+                HMENU(0),
+                instance,
+                Some(window.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(window)
+    }
+
+    pub(crate) fn hwnd(&self) -> HWND {
+        self.handle
+    }
+
+    /// Creates the label, slider and mute box of every category.
+    fn create_controls(&mut self) -> Result<()> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        for (index, category) in Category::ALL.into_iter().enumerate() {
+            let volume = *category.volume(&mut self.settings);
+            let top = MARGIN + ROW_HEIGHT * index as i32;
+            let control = |class: PCWSTR, text: &str, style: WINDOW_STYLE, x, width, id: usize| unsafe {
+                CreateWindowExW(
+                    WINDOW_EX_STYLE::default(),
+                    class,
+                    &HSTRING::from(text),
+                    WS_CHILD | WS_VISIBLE | style,
+                    x,
+                    top,
+                    width,
+                    ROW_HEIGHT - 10,
+                    self.handle,
+                    HMENU(id as isize),
+                    instance,
+                    None,
+                )
+            };
+            control(
+                w!("STATIC"),
+                category.name(),
+                WINDOW_STYLE(0),
+                MARGIN,
+                60,
+                0,
+            );
+            let slider = control(
+                TRACKBAR_CLASSW,
+                "",
+                WS_TABSTOP | WINDOW_STYLE(TBS_HORZ),
+                MARGIN + 60,
+                140,
+                SLIDER_ID + index,
+            );
+            let mute = control(
+                w!("BUTTON"),
+                "Mute",
+                WS_TABSTOP | WINDOW_STYLE(BS_AUTOCHECKBOX as u32),
+                MARGIN + 210,
+                WIDTH - MARGIN * 2 - 210,
+                MUTE_ID + index,
+            );
+            unsafe {
+                SendMessageW(
+                    slider,
+                    TBM_SETRANGE,
+                    WPARAM(1),
+                    LPARAM((STEPS as isize) << 16),
+                );
+                SendMessageW(
+                    slider,
+                    TBM_SETPOS,
+                    WPARAM(1),
+                    LPARAM((volume.level * STEPS).round() as isize),
+                );
+                SendMessageW(mute, BM_SETCHECK, WPARAM(volume.muted as usize), LPARAM(0));
+            }
+            self.sliders[index] = slider;
+            self.mutes[index] = mute;
+        }
+        self.create_look_controls()
+    }
+
+    /// Creates the drop down lists of skins and themes below the sounds and
+    /// the buttons importing and exporting themes.
+    fn create_look_controls(&mut self) -> Result<()> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        let top = MARGIN + ROW_HEIGHT * Category::ALL.len() as i32;
+        let control = |class: PCWSTR, text: &str, style: WINDOW_STYLE, x, y, width, height, id: usize| unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class,
+                &HSTRING::from(text),
+                WS_CHILD | WS_VISIBLE | style,
+                x,
+                y,
+                width,
+                height,
+                self.handle,
+                HMENU(id as isize),
+                instance,
+                None,
+            )
+        };
+        let list = |label: &str, row: i32, id: usize| {
+            let y = top + ROW_HEIGHT * row;
+            control(w!("STATIC"), label, WINDOW_STYLE(0), MARGIN, y, 60, ROW_HEIGHT - 10, 0);
+            control(
+                w!("COMBOBOX"),
+                "",
+                WS_TABSTOP | WS_VSCROLL | WINDOW_STYLE(CBS_DROPDOWNLIST as u32),
+                MARGIN + 60,
+                y,
+                WIDTH - MARGIN * 2 - 60,
+                ROW_HEIGHT * LIST_ROWS,
+                id,
+            )
+        };
+        self.skin_list = list("Skin", 0, SKIN_ID);
+        self.theme_list = list("Theme", 1, THEME_ID);
+        let buttons = top + ROW_HEIGHT * 2;
+        for (index, (text, id)) in [("Import...", IMPORT_ID), ("Export...", EXPORT_ID)]
+            .into_iter()
+            .enumerate()
+        {
+            control(
+                w!("BUTTON"),
+                text,
+                WS_TABSTOP,
+                MARGIN + 60 + (BUTTON_WIDTH + 10) * index as i32,
+                buttons,
+                BUTTON_WIDTH,
+                ROW_HEIGHT - 10,
+                id,
+            );
+        }
+        fill_list(self.skin_list, "Classic", &self.skins, self.skin.as_deref());
+        fill_list(self.theme_list, "None", &self.themes, self.theme.as_deref());
+        self.create_timing_controls()
+    }
+
+    /// Creates a labelled number box per pointer timing below the look.
+    fn create_timing_controls(&mut self) -> Result<()> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        let top = MARGIN + ROW_HEIGHT * (Category::ALL.len() as i32 + LOOK_ROWS);
+        let rows = [
+            ("Chord window (ms)", self.timing.chord),
+            ("Long press (ms)", self.timing.long_press),
+            ("Double click (ms)", self.timing.double_click),
+        ];
+        for (index, (label, millis)) in rows.into_iter().enumerate() {
+            let y = top + ROW_HEIGHT * index as i32;
+            let control = |class: PCWSTR, text: &str, style: WINDOW_STYLE, x, width, id: usize| unsafe {
+                CreateWindowExW(
+                    WINDOW_EX_STYLE::default(),
+                    class,
+                    &HSTRING::from(text),
+                    WS_CHILD | WS_VISIBLE | style,
+                    x,
+                    y,
+                    width,
+                    ROW_HEIGHT - 15,
+                    self.handle,
+                    HMENU(id as isize),
+                    instance,
+                    None,
+                )
+            };
+            control(
+                w!("STATIC"),
+                label,
+                WINDOW_STYLE(0),
+                MARGIN,
+                TIMING_LABEL_WIDTH,
+                0,
+            );
+            self.timing_edits[index] = control(
+                w!("EDIT"),
+                &millis.to_string(),
+                WS_TABSTOP | WS_BORDER | WINDOW_STYLE(ES_NUMBER as u32),
+                MARGIN + TIMING_LABEL_WIDTH,
+                TIMING_EDIT_WIDTH,
+                TIMING_ID + index,
+            );
+        }
+        Ok(())
+    }
+
+    /// The times in the number boxes, brought into range, the time the
+    /// window opened with for a box left blank.
+    fn entered_timing(&self) -> TimingSettings {
+        let millis = |index: usize, opened: u32| {
+            let mut text = [0u16; 16];
+            let length = unsafe { GetWindowTextW(self.timing_edits[index], &mut text) };
+            String::from_utf16_lossy(&text[..length.max(0) as usize])
+                .parse::<u32>()
+                .map_or(opened, |millis| millis.min(TimingSettings::MAX))
+        };
+        TimingSettings {
+            chord: millis(0, self.timing.chord),
+            long_press: millis(1, self.timing.long_press).max(TimingSettings::MIN_LONG_PRESS),
+            double_click: millis(2, self.timing.double_click),
+            act_on_press: self.timing.act_on_press,
+        }
+    }
+
+    /// Asks for a theme file, checks it and installs it as the chosen theme.
+    /// A broken file is refused with the reason.
+    fn import_theme(&mut self) {
+        let mut file = [0u16; 260];
+        let mut dialog = OPENFILENAMEW {
+            lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+            hwndOwner: self.handle,
+            lpstrFilter: w!("Theme files (*.theme)\0*.theme\0"),
+            lpstrFile: PWSTR(file.as_mut_ptr()),
+            nMaxFile: file.len() as u32,
+            Flags: OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST,
+            ..Default::default()
+        };
+        // cancelled
+        if !unsafe { GetOpenFileNameW(&mut dialog) }.as_bool() {
+            return;
+        }
+        let path = path_from(&file);
+        match theme::import(&path) {
+            Ok(name) => {
+                self.themes = theme::installed();
+                self.theme = Some(name);
+                fill_list(self.theme_list, "None", &self.themes, self.theme.as_deref());
+            }
+            Err(error) => {
+                tracing::warn!(%error, path = %path.display(), "unable to import the theme");
+                let text = format!("{} is not a theme file:\n{}", path.display(), error);
+                unsafe {
+                    MessageBoxW(
+                        self.handle,
+                        &HSTRING::from(text),
+                        w!("Import theme"),
+                        MB_OK | MB_ICONWARNING,
+                    )
+                };
+            }
+        }
+    }
+
+    /// Asks where to save the look of the board as a theme file.
+    fn export_theme(&self) {
+        let mut file = [0u16; 260];
+        let name = format!("{}.{}", self.look.name.to_lowercase(), theme::EXTENSION);
+        for (to, from) in file.iter_mut().zip(name.encode_utf16()) {
+            *to = from;
+        }
+        let mut dialog = OPENFILENAMEW {
+            lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+            hwndOwner: self.handle,
+            lpstrFilter: w!("Theme files (*.theme)\0*.theme\0"),
+            lpstrFile: PWSTR(file.as_mut_ptr()),
+            nMaxFile: file.len() as u32,
+            lpstrDefExt: w!("theme"),
+            Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+            ..Default::default()
+        };
+        // cancelled
+        if !unsafe { GetSaveFileNameW(&mut dialog) }.as_bool() {
+            return;
+        }
+        let path = path_from(&file);
+        if let Err(error) = self.look.save(&path) {
+            tracing::warn!(%error, path = %path.display(), "unable to export the theme");
+        }
+    }
+
+    fn changed(&mut self) {
+        (self.on_change)(&self.settings);
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_CREATE => {
+                if let Err(error) = self.create_controls() {
+                    tracing::error!(%error, "unable to create the settings controls");
+                    return LRESULT(-1);
+                }
+                LRESULT(0)
+            }
+            WM_HSCROLL => {
+                let slider = HWND(lparam.0);
+                if let Some(index) = self.sliders.iter().position(|&s| s == slider) {
+                    let position =
+                        unsafe { SendMessageW(slider, TBM_GETPOS, WPARAM(0), LPARAM(0)) };
+                    Category::ALL[index].volume(&mut self.settings).level =
+                        position.0 as f32 / STEPS;
+                    self.changed();
+                }
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                let id = wparam.0 & 0xffff;
+                let notification = (wparam.0 >> 16) & 0xffff;
+                let index = id.wrapping_sub(MUTE_ID);
+                if id == SKIN_ID && notification == CBN_SELCHANGE as usize {
+                    self.skin = selected(self.skin_list, &self.skins);
+                } else if id == THEME_ID && notification == CBN_SELCHANGE as usize {
+                    self.theme = selected(self.theme_list, &self.themes);
+                } else if id == IMPORT_ID && notification == BN_CLICKED as usize {
+                    self.import_theme();
+                } else if id == EXPORT_ID && notification == BN_CLICKED as usize {
+                    self.export_theme();
+                } else if notification == BN_CLICKED as usize && index < self.mutes.len() {
+                    let checked = unsafe {
+                        SendMessageW(self.mutes[index], BM_GETCHECK, WPARAM(0), LPARAM(0))
+                    };
+                    Category::ALL[index].volume(&mut self.settings).muted = checked.0 == CHECKED;
+                    self.changed();
+                }
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                let mut entries = self.settings.entries();
+                let skin = self.skin.as_deref().unwrap_or(CLASSIC_SKIN);
+                entries.push(("board.skin".to_string(), skin.to_string()));
+                let theme = self.theme.as_deref().unwrap_or(NO_THEME);
+                entries.push(("theme".to_string(), theme.to_string()));
+                entries.extend(self.entered_timing().entries());
+                if let Err(error) = Config::store(&entries) {
+                    tracing::warn!(%error, "unable to save the settings");
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+/// Lists `names` after the built in entry `first`, selecting `chosen` or the
+/// built in entry when it is not one of them.
+fn fill_list(list: HWND, first: &str, names: &[String], chosen: Option<&str>) {
+    unsafe { SendMessageW(list, CB_RESETCONTENT, WPARAM(0), LPARAM(0)) };
+    let mut selected = 0;
+    for (index, name) in std::iter::once(first)
+        .chain(names.iter().map(String::as_str))
+        .enumerate()
+    {
+        let text = HSTRING::from(name);
+        unsafe { SendMessageW(list, CB_ADDSTRING, WPARAM(0), LPARAM(text.as_ptr() as isize)) };
+        if index > 0 && chosen == Some(name) {
+            selected = index;
+        }
+    }
+    unsafe { SendMessageW(list, CB_SETCURSEL, WPARAM(selected), LPARAM(0)) };
+}
+
+/// The name chosen in a list [`fill_list`] filled, `None` for the built in
+/// entry.
+fn selected(list: HWND, names: &[String]) -> Option<String> {
+    let selected = unsafe { SendMessageW(list, CB_GETCURSEL, WPARAM(0), LPARAM(0)) };
+    (selected.0 as usize)
+        .checked_sub(1)
+        .and_then(|index| names.get(index).cloned())
+}
+
+/// The path a file dialog wrote to its buffer.
+fn path_from(file: &[u16]) -> PathBuf {
+    let length = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+    PathBuf::from(String::from_utf16_lossy(&file[..length]))
+}
@@ -0,0 +1,139 @@
+//! Spoiler-free summaries of a finished game to share, in the style of the
+//! daily puzzle share strings.
+//!
+//! A summary names the level, or the day of a daily board, the result with
+//! its time and 3BV/s and draws a mini-map of the opening in emoji squares.
+//! The map only tells the cells the first opening uncovered from the ones it
+//! left covered, never where the mines are, and large boards are drawn a
+//! block of cells to a square so the map stays at most [`MAP_COLUMNS`]
+//! across.
+
+use std::time::Duration;
+
+use crate::{
+    game::{Game, GameState},
+    solver, stats,
+};
+
+/// Most squares across the mini-map of the opening.
+pub const MAP_COLUMNS: usize = 10;
+
+const UNCOVERED: char = '🟩';
+const COVERED: char = '⬛';
+
+/// The summary of a game once it is won or lost, `daily` being the day of
+/// the daily board played. `None` while the game is being played.
+pub fn summary(game: &Game, daily: Option<u64>) -> Option<String> {
+    summary_at(game, daily, game.elapsed())
+}
+
+fn summary_at(game: &Game, daily: Option<u64>, elapsed: Duration) -> Option<String> {
+    let result = match game.state() {
+        GameState::Won => "Won",
+        GameState::Lost => "Lost",
+        _ => return None,
+    };
+    let level = stats::level_name(game.width(), game.height(), game.total());
+    let mut text = match daily {
+        Some(day) => format!("Minesweeper daily {}, {}", stats::date(day), level),
+        None => format!("Minesweeper {}", level),
+    };
+    if game.practice() {
+        text.push_str(", practice");
+    }
+    let three_bv = solver::three_bv(game);
+    let seconds = elapsed.as_secs_f64();
+    let speed = match seconds > 0.0 {
+        true => three_bv.cleared as f64 / seconds,
+        false => 0.0,
+    };
+    text.push_str(&format!(
+        "\n{} in {:.1} s, 3BV/s {:.2}",
+        result, seconds, speed
+    ));
+    if game.state() == GameState::Lost && three_bv.total > 0 {
+        let cleared = three_bv.cleared as u32 * 100 / three_bv.total as u32;
+        text.push_str(&format!(", {}% cleared", cleared));
+    }
+    if let Some(map) = opening(game).map(|opening| map(&opening)) {
+        text.push('\n');
+        text.push_str(&map);
+    }
+    Some(text)
+}
+
+/// The board as the first move that uncovered cells left it, `None` when
+/// the game has no such move, like a game loaded from its cells.
+fn opening(game: &Game) -> Option<Game> {
+    let moves = game.moves();
+    let first = moves.iter().position(|&(_, uncovered)| uncovered > 0)?;
+    let mut opening = game.clone();
+    for _ in first + 1..moves.len() {
+        opening.undo();
+    }
+    Some(opening)
+}
+
+/// Draws which cells are uncovered, a square for each block of cells, the
+/// block uncovered when most of its cells are.
+fn map(game: &Game) -> String {
+    let (width, height) = (game.width() as usize, game.height() as usize);
+    let block = width.div_ceil(MAP_COLUMNS).max(1);
+    let mut rows = Vec::with_capacity(height.div_ceil(block));
+    for top in (0..height).step_by(block) {
+        let mut row = String::new();
+        for left in (0..width).step_by(block) {
+            let (mut cells, mut uncovered) = (0, 0);
+            for y in top..(top + block).min(height) {
+                for x in left..(left + block).min(width) {
+                    cells += 1;
+                    if !solver::is_covered(game.cell_state(x as i16, y as i16)) {
+                        uncovered += 1;
+                    }
+                }
+            }
+            row.push(if uncovered * 2 > cells {
+                UNCOVERED
+            } else {
+                COVERED
+            });
+        }
+        rows.push(row);
+    }
+    rows.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::save::parse_layout;
+
+    #[test]
+    pub fn test_summary() {
+        let mut game = parse_layout("...*.\n...*.\n...*.\n").unwrap();
+        assert_eq!(None, summary(&game, None));
+        game.uncover(0, 0);
+        game.flag(3, 0);
+        for y in 0..3 {
+            game.uncover(4, y);
+        }
+        let text = summary_at(&game, None, Duration::from_secs(4)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("Minesweeper 5x3/3", lines[0]);
+        assert_eq!("Won in 4.0 s, 3BV/s 1.00", lines[1]);
+        // the map is of the opening and keeps the mines to itself
+        assert_eq!(vec!["🟩🟩🟩⬛⬛"; 3], lines[2..]);
+        let text = summary_at(&game, Some(20000), Duration::from_secs(4)).unwrap();
+        assert!(text.starts_with("Minesweeper daily 2024-10-04, 5x3/3\n"));
+    }
+
+    #[test]
+    pub fn test_map_blocks() {
+        let mut game = Game::from_layout(30, 16, &[false; 480]);
+        game.uncover(0, 0);
+        let map = map(&game);
+        assert_eq!(6, map.lines().count());
+        assert!(map.lines().all(|row| row.chars().count() == MAP_COLUMNS));
+        assert!(!map.contains(COVERED));
+    }
+}
@@ -0,0 +1,275 @@
+//! Plays many games without a user interface to measure a strategy.
+//!
+//! [`simulate`] lays out boards from a [`GameConfig`] and a seed, while
+//! [`simulate_boards`] plays boards the caller prepared, such as the output of
+//! the no-guess generator. Either way each board is played to the end by a
+//! [`Strategy`], which is the built-in [`Bot`] or any closure from a game to
+//! its next move, and the results are summed up in a [`Report`]. With the
+//! `parallel` feature `simulate_parallel` spreads a batch over all cores.
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    game::{CellState, Game, GameConfig, GameState, Move},
+    solver::{self, DeductionKind},
+};
+
+/// Games in each batch of [`simulate_parallel`].
+#[cfg(feature = "parallel")]
+const BATCH_GAMES: usize = 64;
+
+/// Picks the moves for a simulated game.
+pub trait Strategy {
+    /// The next move to play, or `None` to give up on the game.
+    fn next_move(&mut self, game: &Game) -> Option<Move>;
+}
+
+impl<F: FnMut(&Game) -> Option<Move>> Strategy for F {
+    fn next_move(&mut self, game: &Game) -> Option<Move> {
+        self(game)
+    }
+}
+
+/// Plays whatever the solver can prove and guesses a random covered cell
+/// when it cannot prove anything.
+pub struct Bot {
+    rng: StdRng,
+}
+
+impl Bot {
+    /// Creates a bot whose guesses follow from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Bot {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for Bot {
+    fn next_move(&mut self, game: &Game) -> Option<Move> {
+        if let Some(hint) = solver::hint(game) {
+            return Some(match hint.kind {
+                DeductionKind::Safe => Move::Uncover(hint.x, hint.y),
+                DeductionKind::Mine => Move::Flag(hint.x, hint.y),
+            });
+        }
+        let mut covered = Vec::new();
+        for y in 0..game.height() {
+            for x in 0..game.width() {
+                if matches!(game.cell_state(x, y), CellState::Unknown(_)) {
+                    covered.push((x, y));
+                }
+            }
+        }
+        if covered.is_empty() {
+            return None;
+        }
+        let (x, y) = covered[self.rng.gen_range(0..covered.len())];
+        Some(Move::Uncover(x, y))
+    }
+}
+
+/// Totals over a batch of simulated games.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Number of games played.
+    pub games: usize,
+    /// Games that ended in a win.
+    pub won: usize,
+    /// Games that ended on a mine.
+    pub lost: usize,
+    /// Cells uncovered without the solver proving them safe first, including
+    /// the opening click.
+    pub guesses: usize,
+    /// Time spent playing all games.
+    pub time: Duration,
+}
+
+impl Report {
+    /// Games the strategy gave up on or that ran out of moves.
+    pub fn unfinished(&self) -> usize {
+        self.games - self.won - self.lost
+    }
+
+    /// Fraction of games won, 0 when no game was played.
+    pub fn win_rate(&self) -> f64 {
+        self.won as f64 / self.games.max(1) as f64
+    }
+
+    /// Mean time spent on a game.
+    pub fn average_time(&self) -> Duration {
+        self.time / self.games.max(1) as u32
+    }
+
+    /// Mean number of guesses per game.
+    pub fn average_guesses(&self) -> f64 {
+        self.guesses as f64 / self.games.max(1) as f64
+    }
+
+    /// Adds the totals of another batch to this one.
+    pub fn merge(&mut self, other: &Report) {
+        self.games += other.games;
+        self.won += other.won;
+        self.lost += other.lost;
+        self.guesses += other.guesses;
+        self.time += other.time;
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} games, {} won ({:.1}%), {} lost, {} unfinished, {:.2} guesses and {:.3} ms per game",
+            self.games,
+            self.won,
+            self.win_rate() * 100.0,
+            self.lost,
+            self.unfinished(),
+            self.average_guesses(),
+            self.average_time().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Plays `games` boards of the given size, laid out from consecutive seeds
+/// starting at `seed` so a run can be repeated.
+pub fn simulate<S: Strategy>(
+    config: GameConfig,
+    games: usize,
+    seed: u64,
+    strategy: &mut S,
+) -> Report {
+    let boards = (0..games as u64).map(|i| {
+        let mut game = Game::with_config(config.clone());
+        game.reset_with_seed(seed.wrapping_add(i));
+        game
+    });
+    simulate_boards(boards, strategy)
+}
+
+/// Plays `games` boards like [`simulate`], spread over all cores.
+///
+/// The games are split into fixed batches and each batch plays with its own
+/// strategy, made by calling `strategy` with the batch's first seed, so the
+/// results do not depend on the number of threads. The report's time is the
+/// sum over all threads rather than the wall clock time.
+#[cfg(feature = "parallel")]
+pub fn simulate_parallel<S, F>(config: GameConfig, games: usize, seed: u64, strategy: F) -> Report
+where
+    S: Strategy,
+    F: Fn(u64) -> S + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    (0..games.div_ceil(BATCH_GAMES))
+        .into_par_iter()
+        .map(|batch| {
+            let first = batch * BATCH_GAMES;
+            let seed = seed.wrapping_add(first as u64);
+            let count = BATCH_GAMES.min(games - first);
+            simulate(config.clone(), count, seed, &mut strategy(seed))
+        })
+        .reduce(Report::default, |mut total, report| {
+            total.merge(&report);
+            total
+        })
+}
+
+/// Plays every board to the end, or until the strategy gives up.
+pub fn simulate_boards<S: Strategy>(
+    boards: impl IntoIterator<Item = Game>,
+    strategy: &mut S,
+) -> Report {
+    let mut report = Report::default();
+    for mut game in boards {
+        let start = Instant::now();
+        report.guesses += play(&mut game, strategy);
+        report.time += start.elapsed();
+        report.games += 1;
+        match game.state() {
+            GameState::Won => report.won += 1,
+            GameState::Lost => report.lost += 1,
+            _ => {}
+        }
+    }
+    report
+}
+
+/// Plays one game and returns the number of guesses made.
+fn play<S: Strategy>(game: &mut Game, strategy: &mut S) -> usize {
+    // a strategy that keeps repeating moves without progress is cut off
+    let limit = game.width() as usize * game.height() as usize * 4;
+    let mut guesses = 0;
+    for _ in 0..limit {
+        if matches!(game.state(), GameState::Won | GameState::Lost) {
+            break;
+        }
+        let Some(mv) = strategy.next_move(game) else {
+            break;
+        };
+        if let Move::Uncover(x, y) = mv {
+            let proven = solver::solve(game)
+                .iter()
+                .any(|d| (d.x, d.y) == (x, y) && d.kind == DeductionKind::Safe);
+            if !proven {
+                guesses += 1;
+            }
+        }
+        game.apply(mv);
+    }
+    guesses
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_simulate_bot() {
+        let config = GameConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            zones: Vec::new(),
+        };
+        let report = simulate(config.clone(), 20, 7, &mut Bot::new(7));
+        assert_eq!(20, report.games);
+        assert_eq!(0, report.unfinished());
+        assert!(report.guesses >= report.games);
+        // the same seeds play the same games
+        let again = simulate(config, 20, 7, &mut Bot::new(7));
+        assert_eq!((report.won, report.guesses), (again.won, again.guesses));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    pub fn test_simulate_parallel() {
+        let config = GameConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            zones: Vec::new(),
+        };
+        let report = simulate_parallel(config.clone(), 70, 3, Bot::new);
+        assert_eq!(70, report.games);
+        // the last batch of 6 games matches a sequential run of it
+        let last = simulate(config.clone(), 6, 3 + 64, &mut Bot::new(3 + 64));
+        let first = simulate_parallel(config, 64, 3, Bot::new);
+        assert_eq!(report.won, first.won + last.won);
+        assert_eq!(report.guesses, first.guesses + last.guesses);
+    }
+
+    #[test]
+    pub fn test_simulate_callback() {
+        // a board without mines is won by the first click
+        let boards = vec![Game::from_layout(3, 3, &[false; 9])];
+        let report = simulate_boards(boards, &mut |_: &Game| Some(Move::Uncover(1, 1)));
+        assert_eq!((1, 1, 1), (report.games, report.won, report.guesses));
+    }
+}
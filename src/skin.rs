@@ -0,0 +1,357 @@
+//! Skin packs that change the sprites, colors and sounds of the GUI without
+//! rebuilding it.
+//!
+//! A skin is a folder or a zip archive in the `skins` folder of the data
+//! directory, with a `skin.txt` manifest naming its files:
+//!
+//! ```text
+//! name = Midnight
+//! author = A. Player
+//! image.mine = mine.png
+//! image.flag = flag.png
+//! color.board = #1a1a1a
+//! color.number1 = #7fa7ff
+//! sound.tick = tick.wav
+//! ```
+//!
+//! The colors are `board`, `cell`, `highlight`, `text`, `number1` to
+//! `number7`, `player1`, `player2`, `contradiction` and `annotation1` to
+//! `annotation4`, the images `mine` and `flag` and the sounds the practice
+//! cues `tick` and `heartbeat`. Whatever a skin leaves out keeps the look of
+//! the theme it is applied over. Sounds are 16 bit PCM WAV files, mixed down
+//! to mono.
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    music::WavStream,
+    stats::data_dir,
+    zip,
+};
+
+/// Name of the manifest at the top of every skin.
+pub const MANIFEST: &str = "skin.txt";
+/// The colors a skin can set.
+pub const COLORS: [&str; 18] = [
+    "board",
+    "cell",
+    "highlight",
+    "text",
+    "number1",
+    "number2",
+    "number3",
+    "number4",
+    "number5",
+    "number6",
+    "number7",
+    "player1",
+    "player2",
+    "contradiction",
+    "annotation1",
+    "annotation2",
+    "annotation3",
+    "annotation4",
+];
+/// The images a skin can replace.
+pub const IMAGES: [&str; 2] = ["mine", "flag"];
+/// The sounds a skin can replace.
+pub const SOUNDS: [&str; 2] = ["tick", "heartbeat"];
+const SKINS_DIR: &str = "skins";
+
+/// A color as red, green and blue from 0 to 1.
+pub type Color = (f32, f32, f32);
+
+/// A sound held in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Samples {
+    /// 16 bit mono samples.
+    pub samples: Vec<i16>,
+    /// Samples per second.
+    pub sample_rate: u32,
+}
+
+/// A loaded skin with its files read into memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skin {
+    /// Name the skin gives itself.
+    pub name: String,
+    /// Who made the skin, if it says.
+    pub author: Option<String>,
+    colors: Vec<(String, Color)>,
+    images: Vec<(String, Vec<u8>)>,
+    sounds: Vec<(String, Samples)>,
+}
+
+impl Skin {
+    /// Loads the skin in a folder or zip archive. The manifest of an archive
+    /// may also sit in a folder inside it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Skin> {
+        let path = path.as_ref();
+        let _span = tracing::debug_span!("load skin", path = %path.display()).entered();
+        if path.is_dir() {
+            return Skin::read(|name| Ok(fs::read(path.join(name))?));
+        }
+        let files = zip::read(&fs::read(path)?)?;
+        let prefix = files
+            .iter()
+            .filter_map(|(name, _)| name.strip_suffix(MANIFEST))
+            .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+            .min_by_key(|prefix| prefix.len())
+            .unwrap_or("")
+            .to_string();
+        Skin::read(|name| {
+            let name = format!("{}{}", prefix, name);
+            match files.iter().find(|(file, _)| *file == name) {
+                Some((_, contents)) => Ok(contents.clone()),
+                None => Err(invalid(&format!("{} is missing from the archive", name))),
+            }
+        })
+    }
+
+    /// Loads an installed skin by the name [`installed`] lists it under. An
+    /// absolute path loads a skin from anywhere.
+    pub fn find(name: &str) -> Result<Skin> {
+        let dir = skins_dir().ok_or_else(|| invalid("no data directory for skins"))?;
+        Skin::load(dir.join(name))
+    }
+
+    /// Reads a skin from its manifest and the files it names, given by
+    /// `file` from their paths inside the skin.
+    fn read(mut file: impl FnMut(&str) -> Result<Vec<u8>>) -> Result<Skin> {
+        let manifest = String::from_utf8(file(MANIFEST)?)
+            .map_err(|_| invalid(&format!("{} is not UTF-8 text", MANIFEST)))?;
+        let mut skin = Skin {
+            name: String::new(),
+            author: None,
+            colors: Vec::new(),
+            images: Vec::new(),
+            sounds: Vec::new(),
+        };
+        for (number, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| {
+                MinesweeperError::Parse(format!("{} line {}: {}", MANIFEST, number + 1, message))
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let mut contents = |kind: &str, name: &str, known: &[&str]| {
+                if !known.contains(&name) {
+                    return Err(error(format!(
+                        "unknown {} {}, expected one of {}",
+                        kind,
+                        name,
+                        known.join(", ")
+                    )));
+                }
+                let inside = Path::new(value)
+                    .components()
+                    .all(|part| matches!(part, Component::Normal(_)));
+                if !inside {
+                    return Err(error(format!("{} is outside the skin", value)));
+                }
+                file(value)
+            };
+            match key.split_once('.') {
+                Some(("color", name)) => {
+                    if !COLORS.contains(&name) {
+                        return Err(error(format!("unknown color {}", name)));
+                    }
+                    let color = parse_color(value).map_err(error)?;
+                    set(&mut skin.colors, name, color);
+                }
+                Some(("image", name)) => {
+                    let image = contents("image", name, &IMAGES)?;
+                    set(&mut skin.images, name, image);
+                }
+                Some(("sound", name)) => {
+                    let wav = contents("sound", name, &SOUNDS)?;
+                    let samples = decode(&wav).map_err(|e| error(format!("{}: {}", value, e)))?;
+                    set(&mut skin.sounds, name, samples);
+                }
+                _ => match key {
+                    "name" => skin.name = value.to_string(),
+                    "author" => skin.author = Some(value.to_string()),
+                    // unknown keys are skipped
+                    _ => {}
+                },
+            }
+        }
+        if skin.name.is_empty() {
+            return Err(invalid(&format!("{} does not name the skin", MANIFEST)));
+        }
+        tracing::info!(name = skin.name, "skin loaded");
+        Ok(skin)
+    }
+
+    /// The color the skin gives `name`, one of [`COLORS`].
+    pub fn color(&self, name: &str) -> Option<Color> {
+        get(&self.colors, name).copied()
+    }
+
+    /// The encoded image the skin has for `name`, one of [`IMAGES`].
+    pub fn image(&self, name: &str) -> Option<&[u8]> {
+        get(&self.images, name).map(Vec::as_slice)
+    }
+
+    /// The sound the skin has for `name`, one of [`SOUNDS`].
+    pub fn sound(&self, name: &str) -> Option<&Samples> {
+        get(&self.sounds, name)
+    }
+}
+
+fn set<T>(entries: &mut Vec<(String, T)>, name: &str, value: T) {
+    entries.retain(|(entry, _)| entry != name);
+    entries.push((name.to_string(), value));
+}
+
+fn get<'a, T>(entries: &'a [(String, T)], name: &str) -> Option<&'a T> {
+    entries
+        .iter()
+        .find(|(entry, _)| entry == name)
+        .map(|(_, value)| value)
+}
+
+/// Where skins are installed.
+pub fn skins_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SKINS_DIR))
+}
+
+/// The names of the installed skins in order, the folders holding a
+/// manifest and the zip archives in [`skins_dir`].
+pub fn installed() -> Vec<String> {
+    let Some(entries) = skins_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.join(MANIFEST).is_file()
+                || path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        })
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Parses a color written as `#rrggbb`.
+pub(crate) fn parse_color(value: &str) -> std::result::Result<Color, String> {
+    let invalid = || format!("invalid color {}, expected #rrggbb", value);
+    let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |at: usize| {
+        u8::from_str_radix(&hex[at..at + 2], 16)
+            .map(|c| c as f32 / 255.0)
+            .map_err(|_| invalid())
+    };
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Reads a whole WAV file, averaging its channels.
+fn decode(wav: &[u8]) -> Result<Samples> {
+    let mut stream = WavStream::new(Cursor::new(wav))?;
+    let channels = stream.channels() as usize;
+    let mut interleaved = vec![0; stream.frames() as usize * channels];
+    stream.fill(&mut interleaved)?;
+    let samples = interleaved
+        .chunks(channels)
+        .map(|frame| {
+            let sum: i32 = frame.iter().map(|&s| s as i32).sum();
+            (sum / channels as i32) as i16
+        })
+        .collect();
+    Ok(Samples {
+        samples,
+        sample_rate: stream.sample_rate(),
+    })
+}
+
+fn invalid(message: &str) -> MinesweeperError {
+    MinesweeperError::Parse(message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A 16 bit stereo WAV file at 8kHz.
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let mut file = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        for field in [1u16, 2] {
+            file.extend_from_slice(&field.to_le_bytes());
+        }
+        file.extend_from_slice(&8000u32.to_le_bytes());
+        file.extend_from_slice(&32000u32.to_le_bytes());
+        file.extend_from_slice(&[4, 0, 16, 0]);
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in samples {
+            file.extend_from_slice(&sample.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    pub fn test_skin() {
+        let manifest = "# a test skin\nname = Midnight\nimage.flag = art/flag.png\n\
+            color.board = #ff8000\ncolor.contradiction = #000000\nsound.tick = tick.wav\n\
+            license = free\n";
+        let files = [
+            (MANIFEST, manifest.as_bytes().to_vec()),
+            ("art/flag.png", b"PNG".to_vec()),
+            ("tick.wav", wav(&[10, 20, -4, -8])),
+        ];
+        let read = |manifest: &str| {
+            Skin::read(|name| match name {
+                MANIFEST => Ok(manifest.as_bytes().to_vec()),
+                _ => match files.iter().find(|(file, _)| *file == name) {
+                    Some((_, contents)) => Ok(contents.clone()),
+                    None => Err(invalid(name)),
+                },
+            })
+        };
+        let skin = read(manifest).unwrap();
+        assert_eq!("Midnight", skin.name);
+        assert_eq!(None, skin.author);
+        assert_eq!(Some((1.0, 128.0 / 255.0, 0.0)), skin.color("board"));
+        assert_eq!(Some((0.0, 0.0, 0.0)), skin.color("contradiction"));
+        assert_eq!(None, skin.color("cell"));
+        assert_eq!(Some(&b"PNG"[..]), skin.image("flag"));
+        assert_eq!(None, skin.image("mine"));
+        let tick = skin.sound("tick").unwrap();
+        assert_eq!((vec![15, -6], 8000), (tick.samples.clone(), tick.sample_rate));
+
+        assert!(read("author = nobody\n").is_err());
+        assert!(read("name = x\ncolor.board = #12345\n").is_err());
+        assert!(read("name = x\ncolor.sky = #123456\n").is_err());
+        assert!(read("name = x\nimage.smiley = art/flag.png\n").is_err());
+        assert!(read("name = x\nimage.mine = missing.png\n").is_err());
+        assert!(read("name = x\nimage.mine = ../flag.png\n").is_err());
+        assert!(read("name = x\nsound.tick = art/flag.png\n").is_err());
+
+        // from a folder
+        let dir = std::env::temp_dir().join(format!("skin-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("art")).unwrap();
+        for (name, contents) in &files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        let loaded = Skin::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(skin, loaded.unwrap());
+    }
+}
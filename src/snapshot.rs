@@ -0,0 +1,523 @@
+//! PNG snapshots of the board, drawn without a graphics backend.
+//!
+//! [`PngRenderer`] draws cells as flat squares with the classic number
+//! colors and encodes the frame as an uncompressed PNG, small boards stay
+//! small and no image library is needed. [`apng`] animates a replay the
+//! same way, only the part of the board a move changed is stored after the
+//! first frame.
+
+use std::{convert::Infallible, time::Duration};
+
+use crate::{
+    game::{CellState, Game, ANNOTATIONS},
+    render::{self, BoardRenderer, Overlay},
+    replay::Replay,
+    solver::DeductionKind,
+};
+
+/// Width and height of a cell in pixels.
+pub const CELL_PIXELS: usize = 16;
+/// Shortest a position of an animated replay is shown, quick chords stay
+/// visible.
+pub const MIN_FRAME_DELAY: Duration = Duration::from_millis(60);
+/// Longest a position of an animated replay is shown, long pauses to think
+/// do not stall the animation.
+pub const MAX_FRAME_DELAY: Duration = Duration::from_secs(2);
+/// How long the final board of an animated replay is shown.
+pub const FINAL_FRAME_DELAY: Duration = Duration::from_secs(3);
+
+type Rgb = [u8; 3];
+
+const COVERED: Rgb = [160, 160, 160];
+const REVEALED: Rgb = [224, 224, 224];
+const GRID: Rgb = [128, 128, 128];
+const MINE: Rgb = [0, 0, 0];
+const FLAG: Rgb = [220, 0, 0];
+const QUESTION: Rgb = [96, 0, 160];
+const SAFE_HINT: Rgb = [0, 160, 0];
+const MINE_HINT: Rgb = [220, 0, 0];
+// a contradicted number is drawn in red on a pale red cell
+const CONTRADICTION: Rgb = [200, 0, 0];
+const CONTRADICTION_CELL: Rgb = [255, 200, 200];
+// the same red and blue the terminal marks claimed cells with
+const PLAYERS: [Rgb; 2] = [[200, 40, 40], [40, 80, 200]];
+// a shade lighter than the covered cells
+const GHOST: Rgb = [225, 225, 225];
+// the blue of the 1s around the cells of a pattern
+const PATTERN: Rgb = [0, 0, 255];
+// the flags the solver placed
+const AUTO_FLAG: Rgb = [0, 140, 200];
+// the candidate marks, badges in the corner of the cell
+pub(crate) const ANNOTATION_COLORS: [Rgb; ANNOTATIONS as usize] =
+    [[230, 190, 0], [0, 170, 200], [200, 0, 200], [0, 170, 60]];
+pub(crate) const COUNTS: [Rgb; 8] = [
+    [0, 0, 255],
+    [0, 128, 0],
+    [255, 0, 0],
+    [0, 0, 128],
+    [128, 0, 0],
+    [0, 128, 128],
+    [0, 0, 0],
+    [128, 128, 128],
+];
+
+// 3x5 pixel digits 0 to 9 followed by a question mark, a row per byte
+const FONT: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b111, 0b001, 0b011, 0b000, 0b010],
+];
+const QUESTION_GLYPH: usize = 10;
+
+/// Draws a board into an RGB image and encodes it as a PNG.
+#[derive(Debug, Default)]
+pub struct PngRenderer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    png: Vec<u8>,
+}
+
+impl PngRenderer {
+    /// Creates a renderer with an empty frame.
+    pub fn new() -> Self {
+        PngRenderer::default()
+    }
+
+    /// The PNG encoded by the last [`present`](BoardRenderer::present).
+    pub fn png(&self) -> &[u8] {
+        &self.png
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for row in y..y + height {
+            for column in x..x + width {
+                let offset = (row * self.width + column) * 3;
+                self.pixels[offset..offset + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Rgb) {
+        for row in y..y + CELL_PIXELS {
+            for column in x..x + CELL_PIXELS {
+                let offset = (row * self.width + column) * 3;
+                for (pixel, tint) in self.pixels[offset..offset + 3].iter_mut().zip(color) {
+                    *pixel = ((*pixel as u16 * 2 + tint as u16) / 3) as u8;
+                }
+            }
+        }
+    }
+
+    /// Draws a glyph of [`FONT`] twice its size in the middle of a cell.
+    fn glyph(&mut self, x: usize, y: usize, glyph: usize, color: Rgb) {
+        self.glyph_at(x + 5, y + 3, glyph, color);
+    }
+
+    /// Draws a count in the middle of a cell, counts past 9 as two digits
+    /// side by side.
+    fn count(&mut self, x: usize, y: usize, count: u8, color: Rgb) {
+        if count < 10 {
+            self.glyph(x, y, count as usize, color);
+        } else {
+            self.glyph_at(x + 1, y + 3, count as usize / 10, color);
+            self.glyph_at(x + 9, y + 3, count as usize % 10, color);
+        }
+    }
+
+    /// Draws a glyph of [`FONT`] twice its size from `left`, `top`.
+    fn glyph_at(&mut self, left: usize, top: usize, glyph: usize, color: Rgb) {
+        for (row, bits) in FONT[glyph].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    self.fill(left + column * 2, top + row * 2, 2, 2, color);
+                }
+            }
+        }
+    }
+
+    /// Outlines a cell inside its grid line.
+    fn frame(&mut self, x: usize, y: usize, color: Rgb) {
+        let inner = CELL_PIXELS - 2;
+        self.fill(x + 1, y + 1, inner, 2, color);
+        self.fill(x + 1, y + inner - 1, inner, 2, color);
+        self.fill(x + 1, y + 1, 2, inner, color);
+        self.fill(x + inner - 1, y + 1, 2, inner, color);
+    }
+}
+
+impl BoardRenderer for PngRenderer {
+    type Error = Infallible;
+
+    fn begin(&mut self, width: i16, height: i16) -> Result<(), Infallible> {
+        self.width = width.max(1) as usize * CELL_PIXELS;
+        self.height = height.max(1) as usize * CELL_PIXELS;
+        self.pixels.clear();
+        self.pixels.resize(self.width * self.height * 3, 0);
+        Ok(())
+    }
+
+    fn draw_cell(&mut self, x: i16, y: i16, state: CellState) -> Result<(), Infallible> {
+        let (x, y) = (x as usize * CELL_PIXELS, y as usize * CELL_PIXELS);
+        let background = match state {
+            CellState::Known(_) | CellState::Counted(_) => REVEALED,
+            _ => COVERED,
+        };
+        self.fill(x, y, CELL_PIXELS, CELL_PIXELS, GRID);
+        self.fill(x + 1, y + 1, CELL_PIXELS - 1, CELL_PIXELS - 1, background);
+        match state {
+            CellState::Counted(count) => {
+                let color = COUNTS[render::count_color(count, COUNTS.len())];
+                self.count(x, y, count, color)
+            }
+            CellState::Known(true) => self.fill(x + 4, y + 4, 8, 8, MINE),
+            CellState::Flagged(_) => self.fill(x + 5, y + 4, 6, 8, FLAG),
+            CellState::Questioned(_) => self.glyph(x, y, QUESTION_GLYPH, QUESTION),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn draw_overlay(&mut self, x: i16, y: i16, overlay: Overlay) -> Result<(), Infallible> {
+        let (x, y) = (x as usize * CELL_PIXELS, y as usize * CELL_PIXELS);
+        match overlay {
+            Overlay::Hint(DeductionKind::Safe) => self.frame(x, y, SAFE_HINT),
+            Overlay::Hint(DeductionKind::Mine) => self.frame(x, y, MINE_HINT),
+            Overlay::Claimed(player) => self.blend(x, y, PLAYERS[player as usize % PLAYERS.len()]),
+            Overlay::Contradiction(count) => {
+                self.fill(
+                    x + 1,
+                    y + 1,
+                    CELL_PIXELS - 1,
+                    CELL_PIXELS - 1,
+                    CONTRADICTION_CELL,
+                );
+                self.count(x, y, count, CONTRADICTION);
+            }
+            Overlay::Annotation(mark) => {
+                let color = ANNOTATION_COLORS[mark as usize % ANNOTATION_COLORS.len()];
+                self.fill(x + CELL_PIXELS - 6, y + 2, 4, 4, color);
+            }
+            Overlay::Ghost => self.frame(x, y, GHOST),
+            Overlay::Peek(count) => {
+                let color = COUNTS[render::count_color(count, COUNTS.len())];
+                self.count(x, y, count, color);
+            }
+            Overlay::Pattern => self.frame(x, y, PATTERN),
+            Overlay::AutoFlag => self.fill(x + 5, y + 4, 6, 8, AUTO_FLAG),
+            #[cfg(feature = "dev-tools")]
+            Overlay::Mined => self.fill(x + 6, y + 6, 4, 4, MINE),
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), Infallible> {
+        self.png = encode(self.width, self.height, &self.pixels);
+        Ok(())
+    }
+}
+
+/// Draws the player's view of a game and any overlays as a PNG.
+pub fn png(game: &Game, overlays: &[(i16, i16, Overlay)]) -> Vec<u8> {
+    let mut renderer = PngRenderer::new();
+    render::draw(&mut renderer, game, overlays).unwrap_or_else(|never| match never {});
+    renderer.png
+}
+
+/// The positions of a replay from its start to after its last step and how
+/// long each is shown: as long as the player took for the next step, kept
+/// between [`MIN_FRAME_DELAY`] and [`MAX_FRAME_DELAY`], and the final board
+/// for [`FINAL_FRAME_DELAY`].
+pub fn frames(replay: &Replay) -> Vec<(Game, Duration)> {
+    let mut game = replay.position(0);
+    let mut frames = Vec::with_capacity(replay.steps().len() + 1);
+    let mut shown = Duration::ZERO;
+    for step in replay.steps() {
+        let delay = step.at.saturating_sub(shown);
+        frames.push((game.clone(), delay.clamp(MIN_FRAME_DELAY, MAX_FRAME_DELAY)));
+        step.action.apply(&mut game);
+        shown = step.at;
+    }
+    frames.push((game, FINAL_FRAME_DELAY));
+    frames
+}
+
+/// Draws a replay as an animated PNG that plays once, a frame per position
+/// of [`frames`]. Viewers without APNG support show the starting board.
+pub fn apng(replay: &Replay) -> Vec<u8> {
+    let mut renderer = PngRenderer::new();
+    // the changed area of each position, its pixels and delay, positions a
+    // step did not change add their delay to the one before
+    let mut shown: Vec<u8> = Vec::new();
+    let mut changes: Vec<(Area, Vec<u8>, Duration)> = Vec::new();
+    for (game, delay) in frames(replay) {
+        render::draw(&mut renderer, &game, &[]).unwrap_or_else(|never| match never {});
+        let (width, height) = (renderer.width, renderer.height);
+        let area = match shown.is_empty() {
+            true => Some(Area {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }),
+            false => Area::changed(width, &shown, &renderer.pixels),
+        };
+        match (area, changes.last_mut()) {
+            (Some(area), _) => changes.push((area, area.crop(width, &renderer.pixels), delay)),
+            (None, Some((_, _, last))) => *last += delay,
+            (None, None) => {}
+        }
+        shown.clone_from(&renderer.pixels);
+    }
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header(renderer.width, renderer.height));
+    let mut control = Vec::with_capacity(8);
+    control.extend_from_slice(&(changes.len() as u32).to_be_bytes());
+    // played once
+    control.extend_from_slice(&1u32.to_be_bytes());
+    chunk(&mut png, b"acTL", &control);
+    // frame controls and frame data share one sequence
+    let mut sequence = 0u32;
+    for (index, (area, pixels, delay)) in changes.iter().enumerate() {
+        let millis = delay.as_millis().min(u16::MAX as u128) as u16;
+        let mut frame = Vec::with_capacity(26);
+        for value in [sequence, area.width as u32, area.height as u32] {
+            frame.extend_from_slice(&value.to_be_bytes());
+        }
+        frame.extend_from_slice(&(area.x as u32).to_be_bytes());
+        frame.extend_from_slice(&(area.y as u32).to_be_bytes());
+        frame.extend_from_slice(&millis.to_be_bytes());
+        frame.extend_from_slice(&1000u16.to_be_bytes());
+        // the area stays drawn and replaces what was under it
+        frame.extend_from_slice(&[0, 0]);
+        chunk(&mut png, b"fcTL", &frame);
+        sequence += 1;
+        let data = zlib(area.width, pixels);
+        if index == 0 {
+            chunk(&mut png, b"IDAT", &data);
+        } else {
+            let mut fdat = sequence.to_be_bytes().to_vec();
+            fdat.extend_from_slice(&data);
+            chunk(&mut png, b"fdAT", &fdat);
+            sequence += 1;
+        }
+    }
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// A rectangle of pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Area {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Area {
+    /// The smallest area holding every pixel that differs between two
+    /// frames `width` pixels wide, `None` when they are the same.
+    fn changed(width: usize, before: &[u8], after: &[u8]) -> Option<Area> {
+        let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+        let pixels = before.chunks(3).zip(after.chunks(3));
+        for (index, _) in pixels.enumerate().filter(|(_, (a, b))| a != b) {
+            let (x, y) = (index % width, index / width);
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+        (left != usize::MAX).then(|| Area {
+            x: left,
+            y: top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        })
+    }
+
+    /// The pixels of the area out of a frame `width` pixels wide.
+    fn crop(&self, width: usize, pixels: &[u8]) -> Vec<u8> {
+        let mut cropped = Vec::with_capacity(self.width * self.height * 3);
+        for row in self.y..self.y + self.height {
+            let start = (row * width + self.x) * 3;
+            cropped.extend_from_slice(&pixels[start..start + self.width * 3]);
+        }
+        cropped
+    }
+}
+
+/// Encodes 8 bit RGB pixels as a PNG with stored, uncompressed deflate
+/// blocks.
+fn encode(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header(width, height));
+    chunk(&mut png, b"IDAT", &zlib(width, pixels));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn header(width: usize, height: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, color type 2 (RGB), default compression, filter and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    header
+}
+
+/// The image data of rows of pixels `width` wide as a zlib stream of
+/// stored blocks.
+fn zlib(width: usize, pixels: &[u8]) -> Vec<u8> {
+    // every row starts with filter type 0, none
+    let mut raw = Vec::with_capacity(pixels.len() + pixels.len() / (width * 3).max(1));
+    for row in pixels.chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(u16::MAX as usize);
+    let last = blocks.len().saturating_sub(1);
+    for (index, block) in blocks.enumerate() {
+        let len = block.len() as u16;
+        zlib.push((index == last) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    zlib
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{game::Move, replay::Action};
+
+    #[test]
+    pub fn test_png_layout() {
+        let mut game = Game::from_layout(3, 2, &[true, false, false, false, false, false]);
+        game.uncover(2, 1);
+        let png = png(&game, &[(0, 0, Overlay::Hint(DeductionKind::Mine))]);
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+        assert_eq!(b"IHDR", &png[12..16]);
+        assert_eq!(
+            3 * CELL_PIXELS as u32,
+            u32::from_be_bytes(png[16..20].try_into().unwrap())
+        );
+        assert_eq!(
+            2 * CELL_PIXELS as u32,
+            u32::from_be_bytes(png[20..24].try_into().unwrap())
+        );
+        // IEND with its well known checksum closes the file
+        assert_eq!(b"IEND\xae\x42\x60\x82", &png[png.len() - 8..]);
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    pub fn test_apng() {
+        let game = Game::from_layout(3, 2, &[true, false, false, false, false, false]);
+        let mut replay = Replay::new(&game);
+        let steps = [
+            (10, Move::Flag(0, 0)),
+            (20, Move::Question(1, 0)),
+            (9000, Move::SetUnknown(1, 0)),
+            (10000, Move::Uncover(2, 1)),
+            // an uncovered cell cannot be flagged, the board does not change
+            (10500, Move::Flag(2, 1)),
+        ];
+        for (millis, mv) in steps {
+            replay.push(Duration::from_millis(millis), Action::Play(mv));
+        }
+        let frames = frames(&replay);
+        let delays: Vec<Duration> = frames.iter().map(|(_, delay)| *delay).collect();
+        assert_eq!(
+            vec![
+                MIN_FRAME_DELAY,
+                MIN_FRAME_DELAY,
+                MAX_FRAME_DELAY,
+                Duration::from_secs(1),
+                Duration::from_millis(500),
+                FINAL_FRAME_DELAY
+            ],
+            delays
+        );
+        assert_eq!(CellState::Flagged(true), frames[1].0.cell_state(0, 0));
+
+        let apng = apng(&replay);
+        let mut chunks = Vec::new();
+        let mut offset = 8;
+        while offset < apng.len() {
+            let length = u32::from_be_bytes(apng[offset..offset + 4].try_into().unwrap()) as usize;
+            let end = offset + 8 + length;
+            let crc = u32::from_be_bytes(apng[end..end + 4].try_into().unwrap());
+            assert_eq!(crc32(&apng[offset + 4..end]), crc);
+            chunks.push((&apng[offset + 4..offset + 8], &apng[offset + 8..end]));
+            offset = end + 4;
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| *kind).collect();
+        let mut expected: Vec<&[u8]> = vec![b"IHDR", b"acTL", b"fcTL", b"IDAT"];
+        for _ in 0..4 {
+            expected.extend([&b"fcTL"[..], b"fdAT"]);
+        }
+        expected.push(b"IEND");
+        assert_eq!(expected, kinds);
+        let number = |bytes: &[u8]| u32::from_be_bytes(bytes[..4].try_into().unwrap());
+        // the unchanged last position adds its delay to the one before
+        assert_eq!(5, number(chunks[1].1));
+        let last = chunks[chunks.len() - 3].1;
+        assert_eq!(3500u16.to_be_bytes(), last[20..22]);
+        // the flag frame holds no more than the flagged cell at the top left
+        let flag = chunks[4].1;
+        assert_eq!(1, number(flag));
+        for (size, offset) in [(4, 12), (8, 16)] {
+            assert!(number(&flag[size..]) + number(&flag[offset..]) <= CELL_PIXELS as u32);
+        }
+        // the sequence runs through the frame controls and data
+        assert_eq!(2, number(chunks[5].1));
+        assert_eq!(8, number(chunks[chunks.len() - 2].1));
+    }
+}
@@ -0,0 +1,727 @@
+//! Deduces safe cells and mines from what the player can see.
+
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::fmt::Display;
+
+use crate::game::{CellState, Game, GameState};
+
+/// Whether a deduced cell is safe or a mine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeductionKind {
+    /// The cell can be uncovered.
+    Safe,
+    /// The cell holds a mine.
+    Mine,
+}
+
+/// The counted cell(s) a deduction was derived from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// A single counted cell whose count is fully explained by its neighbors.
+    Single {
+        /// Column of the counted cell.
+        x: i16,
+        /// Row of the counted cell.
+        y: i16,
+        /// The cell's count.
+        count: u8,
+    },
+    /// The covered neighbors of `inner` are a subset of the covered neighbors
+    /// of `outer`, so the difference holds `outer` minus `inner` mines.
+    Subset {
+        /// The counted cell whose covered neighbors are the subset.
+        inner: (i16, i16),
+        /// The counted cell whose covered neighbors are the superset.
+        outer: (i16, i16),
+    },
+}
+
+/// A cell whose contents follow from the uncovered counts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Deduction {
+    /// Column of the deduced cell.
+    pub x: i16,
+    /// Row of the deduced cell.
+    pub y: i16,
+    /// Whether the cell is safe or a mine.
+    pub kind: DeductionKind,
+    /// The counts the deduction follows from.
+    pub reason: Reason,
+}
+
+/// What the player could have known before the move that lost a game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Loss {
+    /// Nothing could be proven, the fatal click was a forced guess.
+    Forced,
+    /// The cell that blew up could be proven a mine.
+    Mine(Deduction),
+    /// Another cell could be proven safe, there was no need to guess.
+    Safe(Deduction),
+}
+
+/// The 3BV of a board, the fewest clicks that clear it without flags, and
+/// how many of those clicks the player's uncovered cells account for.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ThreeBv {
+    /// Openings plus the safe cells not bordering any opening.
+    pub total: u16,
+    /// Openings and lone cells already uncovered.
+    pub cleared: u16,
+}
+
+struct Constraint {
+    source: (i16, i16),
+    cells: Vec<usize>,
+    mines: u8,
+}
+
+/// Deduces safe cells and mines from the player's view of the board.
+///
+/// Only uncovered counts are used; the hidden layout and the player's flags
+/// are ignored. Deductions are returned in the order they were found.
+pub fn solve(game: &Game) -> Vec<Deduction> {
+    let width = game.width();
+    let height = game.height();
+    let size = width as usize * height as usize;
+    // per cell deduction made so far
+    let mut known: Vec<Option<DeductionKind>> = vec![None; size];
+    let mut deductions = Vec::new();
+    loop {
+        let constraints = constraints(game, &known);
+        let found = deductions.len();
+        for constraint in &constraints {
+            let (x, y) = constraint.source;
+            let count = match game.cell_state(x, y) {
+                CellState::Counted(count) => count,
+                _ => 0,
+            };
+            let reason = Reason::Single { x, y, count };
+            if constraint.mines == 0 {
+                mark(
+                    &mut known,
+                    &mut deductions,
+                    width,
+                    &constraint.cells,
+                    DeductionKind::Safe,
+                    reason,
+                );
+            } else if constraint.mines as usize == constraint.cells.len() {
+                mark(
+                    &mut known,
+                    &mut deductions,
+                    width,
+                    &constraint.cells,
+                    DeductionKind::Mine,
+                    reason,
+                );
+            }
+        }
+        if deductions.len() == found {
+            // constraint index by source cell for the neighborhood lookup
+            let mut by_source: Vec<Option<usize>> = vec![None; size];
+            for (i, constraint) in constraints.iter().enumerate() {
+                let (x, y) = constraint.source;
+                by_source[(y * width + x) as usize] = Some(i);
+            }
+            // numbers sharing a covered cell are at most twice the reach
+            // of the counting rule apart
+            let reach = 2 * game.neighborhood().radius();
+            for inner in &constraints {
+                let (x, y) = inner.source;
+                for oy in (y - reach).max(0)..=(y + reach).min(height - 1) {
+                    for ox in (x - reach).max(0)..=(x + reach).min(width - 1) {
+                        let Some(i) = by_source[(oy * width + ox) as usize] else {
+                            continue;
+                        };
+                        let outer = &constraints[i];
+                        if inner.cells.len() >= outer.cells.len()
+                            || !inner.cells.iter().all(|c| outer.cells.contains(c))
+                        {
+                            continue;
+                        }
+                        let rest: Vec<usize> = outer
+                            .cells
+                            .iter()
+                            .filter(|c| !inner.cells.contains(c))
+                            .copied()
+                            .collect();
+                        let reason = Reason::Subset {
+                            inner: inner.source,
+                            outer: outer.source,
+                        };
+                        if outer.mines == inner.mines {
+                            mark(
+                                &mut known,
+                                &mut deductions,
+                                width,
+                                &rest,
+                                DeductionKind::Safe,
+                                reason,
+                            );
+                        } else if outer.mines.saturating_sub(inner.mines) as usize == rest.len() {
+                            mark(
+                                &mut known,
+                                &mut deductions,
+                                width,
+                                &rest,
+                                DeductionKind::Mine,
+                                reason,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        if deductions.len() == found {
+            break;
+        }
+    }
+    // mines the player has already flagged are not worth reporting
+    deductions.retain(|d| {
+        d.kind == DeductionKind::Safe || !matches!(game.cell_state(d.x, d.y), CellState::Flagged(_))
+    });
+    tracing::trace!(deductions = deductions.len(), "solved");
+    deductions
+}
+
+/// Proves a chord on the number at `x`, `y` careless: one of its flags is
+/// on a safe cell, or one of the cells it would uncover is a mine. Returns
+/// the deduction showing it, `None` when no mistake can be proven.
+pub fn chord_risk(game: &Game, x: i16, y: i16) -> Option<Deduction> {
+    if !matches!(game.cell_state(x, y), CellState::Counted(_)) {
+        return None;
+    }
+    let deductions = solve(game);
+    neighbors(game, x, y).find_map(|(nx, ny)| {
+        let deduction = deductions.iter().find(|d| (d.x, d.y) == (nx, ny))?;
+        let wrong = matches!(
+            (game.cell_state(nx, ny), deduction.kind),
+            (CellState::Flagged(_), DeductionKind::Safe)
+                | (
+                    CellState::Unknown(_) | CellState::Questioned(_),
+                    DeductionKind::Mine
+                )
+        );
+        wrong.then_some(*deduction)
+    })
+}
+
+/// The covered cells a single number proves safe, counting only the mines
+/// other single numbers prove: a number with as many covered neighbors as its
+/// count proves them mines, and a number touching as many of those as its
+/// count proves its other covered neighbors safe. Deductions are not chained
+/// any further and flags are ignored, flagged cells are never returned.
+pub fn obvious_safe(game: &Game) -> Vec<(i16, i16)> {
+    let width = game.width();
+    let mut counted = Vec::new();
+    for y in 0..game.height() {
+        for x in 0..width {
+            if let CellState::Counted(count) = game.cell_state(x, y) {
+                counted.push((x, y, count as usize));
+            }
+        }
+    }
+    let covered =
+        |x, y| neighbors(game, x, y).filter(|&(nx, ny)| is_covered(game.cell_state(nx, ny)));
+    let mut mined = vec![false; width as usize * game.height() as usize];
+    for &(x, y, count) in &counted {
+        if covered(x, y).count() == count {
+            for (nx, ny) in covered(x, y) {
+                mined[(ny * width + nx) as usize] = true;
+            }
+        }
+    }
+    let mut safe = Vec::new();
+    for &(x, y, count) in &counted {
+        let is_mined = |&(nx, ny): &(i16, i16)| mined[(ny * width + nx) as usize];
+        if covered(x, y).filter(is_mined).count() != count {
+            continue;
+        }
+        for cell in covered(x, y) {
+            let open = matches!(
+                game.cell_state(cell.0, cell.1),
+                CellState::Unknown(_) | CellState::Questioned(_)
+            );
+            if open && !is_mined(&cell) && !safe.contains(&cell) {
+                safe.push(cell);
+            }
+        }
+    }
+    safe
+}
+
+/// Returns the first safe deduction, falling back to the first mine.
+pub fn hint(game: &Game) -> Option<Deduction> {
+    let deductions = solve(game);
+    deductions
+        .iter()
+        .find(|d| d.kind == DeductionKind::Safe)
+        .or_else(|| deductions.first())
+        .copied()
+}
+
+/// Looks back at the move that lost `game` and tells whether it was a forced
+/// guess or what the solver could prove before it. `None` unless the game
+/// is lost with the fatal move still in its undo history.
+pub fn analyze_loss(game: &Game) -> Option<Loss> {
+    if game.state() != GameState::Lost {
+        return None;
+    }
+    let (x, y) = game.exploded()?;
+    let mut before = game.clone();
+    before.undo()?;
+    let deductions = solve(&before);
+    let mine = deductions
+        .iter()
+        .find(|d| (d.x, d.y, d.kind) == (x, y, DeductionKind::Mine));
+    let safe = deductions.iter().find(|d| d.kind == DeductionKind::Safe);
+    let loss = match (mine, safe) {
+        (Some(&mine), _) => Loss::Mine(mine),
+        (None, Some(&safe)) => Loss::Safe(safe),
+        (None, None) => Loss::Forced,
+    };
+    tracing::debug!(?loss, "analyzed loss");
+    Some(loss)
+}
+
+/// Finds a cell with no mined neighbors so the solver starts from an opening.
+pub fn opening(game: &Game) -> Option<(i16, i16)> {
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let mined = |(x, y)| matches!(game.cell_state(x, y), CellState::Unknown(true));
+            if !mined((x, y)) && !neighbors(game, x, y).any(mined) {
+                return Some((x, y));
+            }
+        }
+    }
+    None
+}
+
+/// Uncovers safe cells and flags mines until the solver can prove nothing
+/// more, returning the final state. A won game needed no guesses.
+pub fn play_out(game: &mut Game) -> GameState {
+    loop {
+        let deductions = solve(game);
+        if deductions.is_empty() || matches!(game.state(), GameState::Won | GameState::Lost) {
+            return game.state();
+        }
+        for deduction in deductions {
+            match deduction.kind {
+                DeductionKind::Safe => {
+                    game.uncover(deduction.x, deduction.y);
+                }
+                DeductionKind::Mine => game.flag(deduction.x, deduction.y),
+            }
+        }
+    }
+}
+
+/// Estimates the chance of a mine under every cell, row by row.
+///
+/// Uncovered cells are 0 and deduced cells 0 or 1. A covered cell next to a
+/// count gets the largest share of mines any of its counts leaves for the
+/// undeduced cells around it, and the mines left over are spread evenly over
+/// the covered cells away from the counts. This is an estimate for cues and
+/// overlays, not an exact count over every layout.
+pub fn probabilities(game: &Game) -> Vec<f32> {
+    let width = game.width();
+    let size = width as usize * game.height() as usize;
+    let mut known: Vec<Option<DeductionKind>> = vec![None; size];
+    for deduction in solve(game) {
+        known[(deduction.y * width + deduction.x) as usize] = Some(deduction.kind);
+    }
+    let mut probabilities = vec![0.0f32; size];
+    let mut fringe = vec![false; size];
+    for constraint in constraints(game, &known) {
+        let share = constraint.mines as f32 / constraint.cells.len() as f32;
+        for cell in constraint.cells {
+            fringe[cell] = true;
+            probabilities[cell] = probabilities[cell].max(share);
+        }
+    }
+    let mut placed = 0.0;
+    let mut open = 0;
+    for (index, probability) in probabilities.iter_mut().enumerate() {
+        let (x, y) = (
+            (index % width as usize) as i16,
+            (index / width as usize) as i16,
+        );
+        if !is_covered(game.cell_state(x, y)) {
+            continue;
+        }
+        match known[index] {
+            Some(DeductionKind::Mine) => {
+                *probability = 1.0;
+                placed += 1.0;
+            }
+            Some(DeductionKind::Safe) => {}
+            None if fringe[index] => placed += *probability,
+            None => open += 1,
+        }
+    }
+    if open > 0 {
+        let rest = ((game.total() as f32 - placed) / open as f32).clamp(0.0, 1.0);
+        for (index, probability) in probabilities.iter_mut().enumerate() {
+            let (x, y) = (
+                (index % width as usize) as i16,
+                (index / width as usize) as i16,
+            );
+            if known[index].is_none() && !fringe[index] && is_covered(game.cell_state(x, y)) {
+                *probability = rest;
+            }
+        }
+    }
+    probabilities
+}
+
+/// Counts the 3BV of the board from its hidden layout and how much of it is
+/// cleared. Every opening, a region of cells without mined neighbors, takes
+/// one click along with the counts around it; every other safe cell takes a
+/// click of its own.
+pub fn three_bv(game: &Game) -> ThreeBv {
+    let width = game.width();
+    let size = width as usize * game.height() as usize;
+    let at = |index: usize| {
+        (
+            (index % width as usize) as i16,
+            (index / width as usize) as i16,
+        )
+    };
+    let mined = |(x, y)| game.cell_state(x, y).is_mined();
+    let empty: Vec<bool> = (0..size)
+        .map(|index| {
+            let (x, y) = at(index);
+            !mined((x, y)) && !neighbors(game, x, y).any(mined)
+        })
+        .collect();
+    let mut bordered = vec![false; size];
+    let mut bv = ThreeBv::default();
+    let mut stack = Vec::new();
+    for start in 0..size {
+        if !empty[start] || bordered[start] {
+            continue;
+        }
+        // an uncovered empty cell has uncovered its whole opening
+        let mut cleared = false;
+        bordered[start] = true;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            let (x, y) = at(index);
+            cleared |= !is_covered(game.cell_state(x, y));
+            for (nx, ny) in neighbors(game, x, y) {
+                let neighbor = (ny * width + nx) as usize;
+                if !bordered[neighbor] {
+                    bordered[neighbor] = true;
+                    if empty[neighbor] {
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+        bv.total += 1;
+        bv.cleared += cleared as u16;
+    }
+    for (index, &bordered) in bordered.iter().enumerate() {
+        let (x, y) = at(index);
+        if !bordered && !mined((x, y)) {
+            bv.total += 1;
+            bv.cleared += !is_covered(game.cell_state(x, y)) as u16;
+        }
+    }
+    bv
+}
+
+fn constraints(game: &Game, known: &[Option<DeductionKind>]) -> Vec<Constraint> {
+    let width = game.width();
+    let mut constraints = Vec::new();
+    for y in 0..game.height() {
+        for x in 0..width {
+            let CellState::Counted(count) = game.cell_state(x, y) else {
+                continue;
+            };
+            let mut cells = Vec::new();
+            let mut mines = count;
+            for (nx, ny) in neighbors(game, x, y) {
+                let index = (ny * width + nx) as usize;
+                if !is_covered(game.cell_state(nx, ny)) {
+                    continue;
+                }
+                match known[index] {
+                    Some(DeductionKind::Mine) => mines = mines.saturating_sub(1),
+                    Some(DeductionKind::Safe) => {}
+                    None => cells.push(index),
+                }
+            }
+            if !cells.is_empty() {
+                constraints.push(Constraint {
+                    source: (x, y),
+                    cells,
+                    mines,
+                });
+            }
+        }
+    }
+    constraints
+}
+
+fn mark(
+    known: &mut [Option<DeductionKind>],
+    deductions: &mut Vec<Deduction>,
+    width: i16,
+    cells: &[usize],
+    kind: DeductionKind,
+    reason: Reason,
+) {
+    for &index in cells {
+        if known[index].is_none() {
+            known[index] = Some(kind);
+            deductions.push(Deduction {
+                x: (index % width as usize) as i16,
+                y: (index / width as usize) as i16,
+                kind,
+                reason,
+            });
+        }
+    }
+}
+
+/// Whether the player has not uncovered a cell, marked or not.
+pub fn is_covered(state: CellState) -> bool {
+    matches!(
+        state,
+        CellState::Unknown(_) | CellState::Flagged(_) | CellState::Questioned(_)
+    )
+}
+
+/// Iterates over the coordinates of the cells a number at `x`, `y` counts,
+/// the up to eight around it under the classic rule, see
+/// [`Game::neighborhood`].
+pub fn neighbors(game: &Game, x: i16, y: i16) -> impl Iterator<Item = (i16, i16)> {
+    game.neighborhood().cells(game.width(), game.height(), x, y)
+}
+
+impl Deduction {
+    /// Explains in words why the deduction holds.
+    #[cfg(feature = "std")]
+    pub fn explanation(&self) -> String {
+        match (self.reason, self.kind) {
+            (Reason::Single { x, y, count }, DeductionKind::Safe) => format!(
+                "the {} at [{},{}] already touches all of its mines",
+                count, x, y
+            ),
+            (Reason::Single { x, y, count }, DeductionKind::Mine) => format!(
+                "the {} at [{},{}] has no other covered cells left for its mines",
+                count, x, y
+            ),
+            (Reason::Subset { inner, outer }, DeductionKind::Safe) => format!(
+                "[{},{}] surrounds every covered cell of [{},{}] and needs no more mines than it",
+                outer.0, outer.1, inner.0, inner.1
+            ),
+            (Reason::Subset { inner, outer }, DeductionKind::Mine) => format!(
+                "[{},{}] surrounds every covered cell of [{},{}] and its extra mines can only go in its other cells",
+                outer.0, outer.1, inner.0, inner.1
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Deduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            DeductionKind::Safe => "safe",
+            DeductionKind::Mine => "a mine",
+        };
+        write!(
+            f,
+            "[{},{}] is {}: {}",
+            self.x,
+            self.y,
+            kind,
+            self.explanation()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Loss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Loss::Forced => write!(f, "a forced guess, nothing could be proven"),
+            Loss::Mine(mine) => write!(f, "avoidable, {}", mine),
+            Loss::Safe(safe) => write!(f, "avoidable, {}", safe),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::Neighborhood;
+
+    #[test]
+    pub fn test_solve_single() {
+        //   * 1 0
+        //   1 1 0
+        //   0 0 0
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(2, 2);
+        let deductions = solve(&game);
+        assert_eq!(1, deductions.len());
+        assert_eq!((0, 0), (deductions[0].x, deductions[0].y));
+        assert_eq!(DeductionKind::Mine, deductions[0].kind);
+    }
+
+    #[test]
+    pub fn test_solve_subset() {
+        // a 1-1 pair: everything past the shared cells is safe
+        //   * ? ? ?
+        //   1 1 ? ?
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.uncover(0, 1);
+        game.uncover(1, 1);
+        let deductions = solve(&game);
+        assert_eq!(2, deductions.len());
+        for deduction in deductions {
+            assert_eq!(DeductionKind::Safe, deduction.kind);
+            assert_eq!(2, deduction.x);
+            assert_eq!(
+                Reason::Subset {
+                    inner: (0, 1),
+                    outer: (1, 1)
+                },
+                deduction.reason
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_solve_distance2() {
+        //   ? 1 1 0 0
+        let mut layout = vec![false; 5];
+        layout[0] = true;
+        let mut game = Game::from_layout(5, 1, &layout);
+        game.set_neighborhood(Neighborhood::Distance2);
+        game.uncover(4, 0);
+        assert_eq!(CellState::Counted(1), game.cell_state(1, 0));
+        let deductions = solve(&game);
+        assert!(deductions
+            .iter()
+            .all(|d| (d.x, d.y, d.kind) == (0, 0, DeductionKind::Mine)));
+        assert!(!deductions.is_empty());
+    }
+
+    #[test]
+    pub fn test_probabilities() {
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.uncover(0, 1);
+        game.uncover(1, 1);
+        // the mine is one of the two cells above the counts
+        assert_eq!(
+            vec![0.5, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            probabilities(&game)
+        );
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        assert!(probabilities(&game).iter().all(|&p| p == 1.0 / 9.0));
+        game.uncover(2, 2);
+        assert_eq!(1.0, probabilities(&game)[0]);
+    }
+
+    #[test]
+    pub fn test_three_bv() {
+        // 1 * 1 0 0: a lone count and an opening with the count next to it
+        let layout = [false, true, false, false, false];
+        let mut game = Game::from_layout(5, 1, &layout);
+        assert_eq!(
+            ThreeBv {
+                total: 2,
+                cleared: 0
+            },
+            three_bv(&game)
+        );
+        game.uncover(0, 0);
+        assert_eq!(1, three_bv(&game).cleared);
+        game.uncover(4, 0);
+        assert_eq!(2, three_bv(&game).cleared);
+        assert_eq!(GameState::Won, game.state());
+    }
+
+    #[test]
+    pub fn test_chord_risk() {
+        //   * . . *
+        //   1 1 1 1
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        layout[3] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        for x in 0..4 {
+            game.uncover(x, 1);
+        }
+        // the flag is on a safe cell and the chord would hit the mine
+        game.flag(1, 0);
+        let risk = chord_risk(&game, 1, 1).unwrap();
+        assert_eq!((0, 0, DeductionKind::Mine), (risk.x, risk.y, risk.kind));
+        game.set_unknown(1, 0);
+        game.flag(0, 0);
+        assert_eq!(None, chord_risk(&game, 1, 1));
+        game.flag(2, 0);
+        let risk = chord_risk(&game, 1, 1).unwrap();
+        assert_eq!((2, 0, DeductionKind::Safe), (risk.x, risk.y, risk.kind));
+        assert_eq!(None, chord_risk(&game, 0, 0));
+    }
+
+    #[test]
+    pub fn test_analyze_loss() {
+        //   * . . *
+        //   . . . .
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        layout[3] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.uncover(0, 1);
+        assert_eq!(None, analyze_loss(&game));
+        // only [0,1] is counted, any of its covered cells could be the mine
+        game.uncover(0, 0);
+        assert_eq!(Some(Loss::Forced), analyze_loss(&game));
+
+        // the 1 at [1,1] leaves [2,0] safe
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.uncover(0, 1);
+        game.uncover(1, 1);
+        game.uncover(3, 0);
+        let Some(Loss::Safe(safe)) = analyze_loss(&game) else {
+            panic!("expected a safe cell");
+        };
+        assert_eq!((2, 0), (safe.x, safe.y));
+
+        // the 1 at [2,0] has only [1,0] left for its mine, [0,0] is still
+        // covered when it is uncovered
+        let mut game = Game::from_layout(4, 1, &[false, true, false, false]);
+        assert_eq!(GameState::Playing, game.uncover(3, 0));
+        game.uncover(1, 0);
+        let Some(Loss::Mine(mine)) = analyze_loss(&game) else {
+            panic!("expected the mine to be proven");
+        };
+        assert_eq!((1, 0), (mine.x, mine.y));
+    }
+
+    #[test]
+    pub fn test_hint_prefers_safe() {
+        let mut layout = vec![false; 8];
+        layout[0] = true;
+        let mut game = Game::from_layout(4, 2, &layout);
+        game.uncover(0, 1);
+        game.uncover(1, 1);
+        assert_eq!(DeductionKind::Safe, hint(&game).unwrap().kind);
+    }
+}
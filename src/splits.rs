@@ -0,0 +1,382 @@
+//! Speedrun splits: when a run made its first click, cleared a quarter, half
+//! and three quarters of the board's 3BV and finished, compared with the
+//! player's best run on the level.
+//!
+//! Split times are measured from when the board was laid out, so the first
+//! click split is the time taken to start. The best run of each level is kept
+//! in `splits.txt` in the data directory, one run per line as space separated
+//! `key=value` pairs like the statistics store.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::Result,
+    game::{Game, GameState},
+    solver,
+    stats::{data_dir, level_name},
+};
+
+const SPLITS_FILE: &str = "splits.txt";
+
+/// A point in a run whose time is kept.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Split {
+    /// The first cell was uncovered.
+    FirstClick,
+    /// A quarter of the 3BV was cleared.
+    Quarter,
+    /// Half of the 3BV was cleared.
+    Half,
+    /// Three quarters of the 3BV were cleared.
+    ThreeQuarters,
+    /// The game was won.
+    Finish,
+}
+
+impl Split {
+    /// Every split in the order a run reaches them.
+    pub const ALL: [Split; 5] = [
+        Split::FirstClick,
+        Split::Quarter,
+        Split::Half,
+        Split::ThreeQuarters,
+        Split::Finish,
+    ];
+
+    /// Short name shown next to the split time.
+    pub fn label(self) -> &'static str {
+        match self {
+            Split::FirstClick => "start",
+            Split::Quarter => "25%",
+            Split::Half => "50%",
+            Split::ThreeQuarters => "75%",
+            Split::Finish => "finish",
+        }
+    }
+
+    // key in the splits file
+    fn key(self) -> &'static str {
+        match self {
+            Split::FirstClick => "first",
+            Split::Quarter => "25",
+            Split::Half => "50",
+            Split::ThreeQuarters => "75",
+            Split::Finish => "finish",
+        }
+    }
+
+    /// Whether the game has reached the split.
+    fn reached(self, game: &Game) -> bool {
+        let quarters = match self {
+            Split::FirstClick => return game.state() != GameState::Initial,
+            Split::Finish => return game.state() == GameState::Won,
+            Split::Quarter => 1,
+            Split::Half => 2,
+            Split::ThreeQuarters => 3,
+        };
+        let bv = solver::three_bv(game);
+        bv.total > 0 && bv.cleared as u32 * 4 >= bv.total as u32 * quarters
+    }
+}
+
+/// The split times of one run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Run {
+    times: [Option<Duration>; Split::ALL.len()],
+}
+
+impl Run {
+    /// Records the splits the game has reached, `elapsed` after the board was
+    /// laid out. Returns whether a new split was reached. A lost game reaches
+    /// no more splits.
+    pub fn update(&mut self, game: &Game, elapsed: Duration) -> bool {
+        if game.state() == GameState::Lost {
+            return false;
+        }
+        let mut reached = false;
+        for (split, time) in Split::ALL.into_iter().zip(&mut self.times) {
+            if time.is_none() && split.reached(game) {
+                *time = Some(elapsed);
+                reached = true;
+            }
+        }
+        reached
+    }
+
+    /// Time of a split, if the run has reached it.
+    pub fn time(&self, split: Split) -> Option<Duration> {
+        self.times[split as usize]
+    }
+
+    /// Whether the run has reached no split yet.
+    pub fn is_empty(&self) -> bool {
+        self.times.iter().all(Option::is_none)
+    }
+
+    /// Seconds the run is ahead, negative, or behind, positive, of `best` at
+    /// a split both reached.
+    pub fn delta(&self, best: &Run, split: Split) -> Option<f64> {
+        Some(self.time(split)?.as_secs_f64() - best.time(split)?.as_secs_f64())
+    }
+
+    /// The reached splits with their deltas against `best`, e.g.
+    /// `start 0.8 (-0.1)  25% 3.4 (+0.5)`.
+    pub fn summary(&self, best: Option<&Run>) -> String {
+        let mut parts = Vec::new();
+        for split in Split::ALL {
+            let Some(time) = self.time(split) else {
+                continue;
+            };
+            let mut part = format!("{} {:.1}", split.label(), time.as_secs_f64());
+            if let Some(delta) = best.and_then(|best| self.delta(best, split)) {
+                part.push_str(&format!(" ({:+.1})", delta));
+            }
+            parts.push(part);
+        }
+        parts.join("  ")
+    }
+
+    fn parse(line: &str) -> Option<(String, Run)> {
+        let mut level = None;
+        let mut run = Run::default();
+        for pair in line.split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            if key == "level" {
+                level = Some(value.to_string());
+            } else if let Some(split) = Split::ALL.into_iter().find(|s| s.key() == key) {
+                let seconds: f64 = value.parse().ok()?;
+                run.times[split as usize] = Some(Duration::try_from_secs_f64(seconds).ok()?);
+            }
+        }
+        Some((level?, run))
+    }
+
+    fn line(&self, level: &str) -> String {
+        let mut line = format!("level={}", level);
+        for split in Split::ALL {
+            if let Some(time) = self.time(split) {
+                line.push_str(&format!(" {}={:.3}", split.key(), time.as_secs_f64()));
+            }
+        }
+        line
+    }
+}
+
+/// The best run of every level played.
+#[derive(Debug, Default)]
+pub struct Bests {
+    runs: Vec<(String, Run)>,
+}
+
+impl Bests {
+    /// Loads the best runs. A missing file has none.
+    pub fn load() -> Result<Bests> {
+        let Some(path) = store_path() else {
+            return Ok(Bests::default());
+        };
+        match File::open(path) {
+            Ok(file) => Bests::read(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Bests::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads runs, skipping lines that cannot be parsed.
+    pub fn read<R: BufRead>(reader: R) -> Result<Bests> {
+        let mut runs = Vec::new();
+        for line in reader.lines() {
+            if let Some(run) = Run::parse(&line?) {
+                runs.push(run);
+            }
+        }
+        Ok(Bests { runs })
+    }
+
+    /// The best run on a level, see [`crate::stats::level_name`].
+    pub fn get(&self, level: &str) -> Option<&Run> {
+        self.runs
+            .iter()
+            .find(|(name, _)| name == level)
+            .map(|(_, run)| run)
+    }
+
+    /// Keeps a finished run if it beats the best on its level, returning
+    /// whether it did.
+    pub fn update(&mut self, level: &str, run: &Run) -> bool {
+        let Some(finish) = run.time(Split::Finish) else {
+            return false;
+        };
+        match self.runs.iter_mut().find(|(name, _)| name == level) {
+            Some((_, best)) if best.time(Split::Finish).is_some_and(|best| best <= finish) => false,
+            Some((_, best)) => {
+                *best = run.clone();
+                true
+            }
+            None => {
+                self.runs.push((level.to_string(), run.clone()));
+                true
+            }
+        }
+    }
+
+    /// Writes the runs to the shared splits file.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = store_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = File::create(path)?;
+        for (level, run) in &self.runs {
+            writeln!(file, "{}", run.line(level))?;
+        }
+        Ok(())
+    }
+}
+
+/// Times the run on a board from when it was laid out, against the best
+/// run on its level.
+#[derive(Debug)]
+pub struct Timer {
+    run: Run,
+    started: Instant,
+    level: String,
+    best: Option<Run>,
+}
+
+impl Timer {
+    /// Starts timing a new board, loading the best run on its level.
+    pub fn new(game: &Game) -> Self {
+        let level = level_name(game.width(), game.height(), game.total());
+        let best = Bests::load()
+            .ok()
+            .and_then(|bests| bests.get(&level).cloned());
+        Timer {
+            run: Run::default(),
+            started: Instant::now(),
+            level,
+            best,
+        }
+    }
+
+    /// Starts timing a new board against `best`, such as the run of a
+    /// [`Ghost`](crate::ghost::Ghost) raced on it, rather than the best run
+    /// kept for its level.
+    pub fn against(game: &Game, best: Option<Run>) -> Self {
+        Timer {
+            best,
+            ..Timer::new(game)
+        }
+    }
+
+    /// Records the splits reached after a move, returning whether there
+    /// were any new ones.
+    pub fn update(&mut self, game: &Game) -> bool {
+        self.run.update(game, self.started.elapsed())
+    }
+
+    /// The run so far.
+    pub fn run(&self) -> &Run {
+        &self.run
+    }
+
+    /// The best run on the level when the board was laid out.
+    pub fn best(&self) -> Option<&Run> {
+        self.best.as_ref()
+    }
+
+    /// Level name of the board.
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// The splits so far with their deltas against the best run.
+    pub fn summary(&self) -> String {
+        self.run.summary(self.best.as_ref())
+    }
+
+    /// Saves the run if it finished faster than the best, see [`record`].
+    pub fn record(&self) -> Result<bool> {
+        record(&self.level, &self.run)
+    }
+}
+
+/// Saves a finished run as the best on its level if it is, returning whether
+/// it was.
+pub fn record(level: &str, run: &Run) -> Result<bool> {
+    let mut bests = Bests::load()?;
+    if !bests.update(level, run) {
+        return Ok(false);
+    }
+    bests.save()?;
+    Ok(true)
+}
+
+fn store_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SPLITS_FILE))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_run_splits() {
+        // 1 * 1 0 0 0 0 0 0 1 * 1: two lone counts and an opening, 3BV 3
+        let mut layout = [false; 12];
+        layout[1] = true;
+        layout[10] = true;
+        let mut game = Game::from_layout(12, 1, &layout);
+        let mut run = Run::default();
+        let at = Duration::from_millis;
+        assert!(!run.update(&game, at(100)) && run.is_empty());
+        game.uncover(0, 0);
+        assert!(run.update(&game, at(800)));
+        assert_eq!(Some(at(800)), run.time(Split::FirstClick));
+        assert_eq!(Some(at(800)), run.time(Split::Quarter));
+        game.uncover(5, 0);
+        run.update(&game, at(2000));
+        assert_eq!(Some(at(2000)), run.time(Split::Half));
+        assert_eq!(None, run.time(Split::ThreeQuarters));
+        game.uncover(11, 0);
+        run.update(&game, at(2500));
+        assert_eq!(Some(at(2500)), run.time(Split::Finish));
+
+        let mut bests = Bests::default();
+        assert!(bests.update("beginner", &run));
+        let best = bests.get("beginner").unwrap();
+        assert_eq!(Some(0.0), run.delta(best, Split::Finish));
+        assert_eq!(
+            "start 0.8 (+0.0)  25% 0.8 (+0.0)  50% 2.0 (+0.0)  75% 2.5 (+0.0)  finish 2.5 (+0.0)",
+            run.summary(Some(best))
+        );
+        assert!(!bests.update("beginner", &run));
+    }
+
+    #[test]
+    pub fn test_bests_round_trip() {
+        let text = "level=expert first=1.000 25=20.500 finish=90.000\ngarbage\n";
+        let mut bests = Bests::read(text.as_bytes()).unwrap();
+        let best = bests.get("expert").unwrap().clone();
+        assert_eq!(
+            Some(Duration::from_millis(20500)),
+            best.time(Split::Quarter)
+        );
+        assert_eq!(None, best.time(Split::Half));
+        assert_eq!(
+            Some(best.clone()),
+            Run::parse(&best.line("expert")).map(|(_, run)| run)
+        );
+        let mut faster = Run::default();
+        faster.times[Split::Finish as usize] = Some(Duration::from_secs(80));
+        assert_eq!(Some(10.0), best.delta(&faster, Split::Finish));
+        assert!(bests.update("expert", &faster));
+        assert!(bests.get("beginner").is_none());
+    }
+}
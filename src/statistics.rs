@@ -0,0 +1,413 @@
+use std::{path::PathBuf, sync::Once};
+
+use windows::{
+    core::{w, Result, HSTRING, PCWSTR, PWSTR},
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::{
+            Direct2D::ID2D1Factory1,
+            Gdi::{COLOR_WINDOW, HBRUSH},
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::Controls::Dialogs::{
+            GetSaveFileNameW, OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST, OPENFILENAMEW,
+        },
+        UI::WindowsAndMessaging::{
+            AdjustWindowRect, CreateWindowExW, DefWindowProcW, GetWindowLongPtrA, LoadCursorW,
+            RegisterClassW, SendMessageW, SetWindowLongPtrA, BN_CLICKED, CREATESTRUCTA,
+            CW_USEDEFAULT, GWLP_USERDATA, HMENU, IDC_ARROW, LBS_NOINTEGRALHEIGHT, LB_ADDSTRING,
+            WINDOW_EX_STYLE, WINDOW_STYLE, WM_COMMAND, WM_CREATE, WNDCLASSW, WS_BORDER, WS_CAPTION,
+            WS_CHILD, WS_OVERLAPPED, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+        },
+    },
+};
+
+use crate::{
+    chart::{HeatmapChart, Plot, PlotChart},
+    clipboard, csv,
+    stats::{Heatmap, Histogram, Stats},
+};
+
+static REGISTER_STATISTICS_WINDOW_CLASS: Once = Once::new();
+
+const STYLE: WINDOW_STYLE = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0);
+// client area and control layout in pixels
+const WIDTH: i32 = 420;
+const MARGIN: i32 = 15;
+const LINE_HEIGHT: i32 = 24;
+const LIST_HEIGHT: i32 = 200;
+const BUTTON_WIDTH: i32 = 90;
+const CHART_SIZE: i32 = 200;
+// the trend plots are stacked in a second column
+const PLOT_WIDTH: i32 = 300;
+const PLOT_HEIGHT: i32 = 130;
+// games the win rate and wins the efficiency are averaged over
+const TREND_WINDOW: usize = 10;
+const TIME_BINS: usize = 12;
+// areas across and down the loss heatmap
+const HEATMAP_AREAS: usize = 8;
+const COPY_ID: usize = 100;
+const EXPORT_ID: usize = 101;
+
+/// A window with the win streaks and the games played since the app started,
+/// which can be copied to the clipboard as text, a heatmap of where games
+/// were lost and charts of the win rate, winning times and efficiency over
+/// all games. All games played can be exported to CSV.
+pub(crate) struct StatisticsWindow<'a> {
+    handle: HWND,
+    streaks: String,
+    history: Vec<String>,
+    heatmap: Heatmap,
+    factory: &'a ID2D1Factory1,
+    chart: Option<Box<HeatmapChart<'a>>>,
+    // captions and what they describe, top to bottom
+    trends: Vec<(String, Plot)>,
+    plots: Vec<Box<PlotChart<'a>>>,
+}
+
+impl<'a> StatisticsWindow<'a> {
+    /// Opens the window over its owner with the statistics as they are now.
+    pub(crate) fn new(owner: HWND, factory: &'a ID2D1Factory1) -> Result<Box<Self>> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        REGISTER_STATISTICS_WINDOW_CLASS.call_once(|| {
+            // use defaults for all other fields
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(Self::wnd_proc),
+                hbrBackground: HBRUSH(COLOR_WINDOW.0 as isize),
+                hInstance: instance.into(),
+                hCursor: unsafe { LoadCursorW(HINSTANCE(0), IDC_ARROW).ok().unwrap() },
+                lpszClassName: w!("bytetrail.window.minesweeper-statistics"),
+                ..Default::default()
+            };
+            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+        });
+        // an unreadable store shows as empty rather than failing the window
+        let stats = Stats::load().unwrap_or_default();
+        let streaks = stats.streaks();
+        let history = stats
+            .history(crate::stats::session())
+            .into_iter()
+            .map(|record| record.history_line())
+            .collect();
+        let mut window = Box::new(StatisticsWindow {
+            handle: HWND(0),
+            streaks: format!(
+                "Win streak: {}    Best streak: {}",
+                streaks.current, streaks.best
+            ),
+            history,
+            heatmap: stats.heatmap(HEATMAP_AREAS),
+            factory,
+            chart: None,
+            trends: trends(&stats),
+            plots: Vec::new(),
+        });
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: WIDTH + PLOT_WIDTH + MARGIN,
+            bottom: MARGIN * 5 + LINE_HEIGHT * 3 + LIST_HEIGHT + CHART_SIZE,
+        };
+        unsafe { AdjustWindowRect(&mut rect, STYLE, false)? };
+        unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("bytetrail.window.minesweeper-statistics"),
+                w!("Statistics"),
+                STYLE | WS_VISIBLE,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                owner,
+                HMENU(0),
+                instance,
+                Some(window.as_mut() as *mut _ as _),
+            )
+        };
+        Ok(window)
+    }
+
+    pub(crate) fn hwnd(&self) -> HWND {
+        self.handle
+    }
+
+    /// Creates the streak label, the session list, the heatmap, the copy and
+    /// export buttons and the column of trend plots.
+    fn create_controls(&mut self) -> Result<()> {
+        let instance = unsafe { GetModuleHandleW(None)? };
+        let control = |class: PCWSTR, text: &str, style: WINDOW_STYLE, rect: RECT, id: usize| unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class,
+                &HSTRING::from(text),
+                WS_CHILD | WS_VISIBLE | style,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                self.handle,
+                HMENU(id as isize),
+                instance,
+                None,
+            )
+        };
+        let mut top = MARGIN;
+        control(
+            w!("STATIC"),
+            &self.streaks,
+            WINDOW_STYLE(0),
+            RECT {
+                left: MARGIN,
+                top,
+                right: WIDTH - MARGIN,
+                bottom: top + LINE_HEIGHT,
+            },
+            0,
+        );
+        top += LINE_HEIGHT + MARGIN;
+        let list = control(
+            w!("LISTBOX"),
+            "",
+            WS_BORDER | WS_VSCROLL | WINDOW_STYLE(LBS_NOINTEGRALHEIGHT as u32),
+            RECT {
+                left: MARGIN,
+                top,
+                right: WIDTH - MARGIN,
+                bottom: top + LIST_HEIGHT,
+            },
+            0,
+        );
+        let lines = match self.history.is_empty() {
+            true => vec!["No games finished yet".to_string()],
+            false => self.history.clone(),
+        };
+        for line in lines {
+            let line = HSTRING::from(line);
+            unsafe {
+                SendMessageW(
+                    list,
+                    LB_ADDSTRING,
+                    WPARAM(0),
+                    LPARAM(line.as_ptr() as isize),
+                )
+            };
+        }
+        top += LIST_HEIGHT + MARGIN;
+        control(
+            w!("STATIC"),
+            "Where games were lost",
+            WINDOW_STYLE(0),
+            RECT {
+                left: MARGIN,
+                top,
+                right: WIDTH - MARGIN,
+                bottom: top + LINE_HEIGHT,
+            },
+            0,
+        );
+        top += LINE_HEIGHT;
+        let left = (WIDTH - CHART_SIZE) / 2;
+        self.chart = Some(HeatmapChart::new(
+            self.handle,
+            self.factory,
+            self.heatmap.clone(),
+            RECT {
+                left,
+                top,
+                right: left + CHART_SIZE,
+                bottom: top + CHART_SIZE,
+            },
+        )?);
+        top += CHART_SIZE + MARGIN;
+        control(
+            w!("BUTTON"),
+            "Copy",
+            WS_TABSTOP,
+            RECT {
+                left: WIDTH - MARGIN - BUTTON_WIDTH,
+                top,
+                right: WIDTH - MARGIN,
+                bottom: top + LINE_HEIGHT,
+            },
+            COPY_ID,
+        );
+        control(
+            w!("BUTTON"),
+            "Export...",
+            WS_TABSTOP,
+            RECT {
+                left: WIDTH - (MARGIN + BUTTON_WIDTH) * 2,
+                top,
+                right: WIDTH - MARGIN * 2 - BUTTON_WIDTH,
+                bottom: top + LINE_HEIGHT,
+            },
+            EXPORT_ID,
+        );
+        let mut top = MARGIN;
+        for (caption, plot) in &self.trends {
+            control(
+                w!("STATIC"),
+                caption,
+                WINDOW_STYLE(0),
+                RECT {
+                    left: WIDTH,
+                    top,
+                    right: WIDTH + PLOT_WIDTH,
+                    bottom: top + LINE_HEIGHT,
+                },
+                0,
+            );
+            top += LINE_HEIGHT;
+            self.plots.push(PlotChart::new(
+                self.handle,
+                self.factory,
+                plot.clone(),
+                RECT {
+                    left: WIDTH,
+                    top,
+                    right: WIDTH + PLOT_WIDTH,
+                    bottom: top + PLOT_HEIGHT,
+                },
+            )?);
+            top += PLOT_HEIGHT + MARGIN;
+        }
+        Ok(())
+    }
+
+    /// The streaks and the session's games as text, a game per line.
+    fn text(&self) -> String {
+        let mut text = self.streaks.clone();
+        for line in &self.history {
+            text.push_str("\r\n");
+            text.push_str(line);
+        }
+        text
+    }
+
+    /// Asks where to save the history, the summary is written next to it.
+    fn export(&self) {
+        let mut file = [0u16; 260];
+        for (to, from) in file.iter_mut().zip("statistics.csv".encode_utf16()) {
+            *to = from;
+        }
+        let mut dialog = OPENFILENAMEW {
+            lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+            hwndOwner: self.handle,
+            lpstrFilter: w!("CSV files (*.csv)\0*.csv\0"),
+            lpstrFile: PWSTR(file.as_mut_ptr()),
+            nMaxFile: file.len() as u32,
+            lpstrDefExt: w!("csv"),
+            Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+            ..Default::default()
+        };
+        // cancelled
+        if !unsafe { GetSaveFileNameW(&mut dialog) }.as_bool() {
+            return;
+        }
+        let length = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+        let path = PathBuf::from(String::from_utf16_lossy(&file[..length]));
+        let exported = Stats::load().and_then(|stats| csv::export(&stats, &path));
+        if let Err(error) = exported {
+            tracing::warn!(%error, path = %path.display(), "unable to export the statistics");
+        }
+    }
+
+    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_CREATE => {
+                if let Err(error) = self.create_controls() {
+                    tracing::error!(%error, "unable to create the statistics controls");
+                    return LRESULT(-1);
+                }
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                let id = wparam.0 & 0xffff;
+                let notification = (wparam.0 >> 16) & 0xffff;
+                if notification == BN_CLICKED as usize {
+                    match id {
+                        COPY_ID => {
+                            if let Err(error) = clipboard::copy_text(self.handle, &self.text()) {
+                                tracing::warn!(%error, "unable to copy the statistics");
+                            }
+                        }
+                        EXPORT_ID => self.export(),
+                        _ => {}
+                    }
+                }
+                LRESULT(0)
+            }
+            _ => unsafe { DefWindowProcW(self.handle, message, wparam, lparam) },
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_CREATE {
+            let create_struct = lparam.0 as *const CREATESTRUCTA;
+            let this = (*create_struct).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrA(window, GWLP_USERDATA, this as _);
+        }
+        let this = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut Self;
+
+        if !this.is_null() {
+            return (*this).message_handler(message, wparam, lparam);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+/// The win rate, the winning times on the level won most and the efficiency
+/// of wins over all games, with captions giving their ranges.
+fn trends(stats: &Stats) -> Vec<(String, Plot)> {
+    let mut trends = vec![(
+        format!("Win rate over the last {} games, 0 to 100%", TREND_WINDOW),
+        Plot::Line {
+            values: stats.win_rates(TREND_WINDOW),
+            max: 100.0,
+        },
+    )];
+    let level = stats
+        .summary()
+        .into_iter()
+        .max_by_key(|summary| summary.won)
+        .filter(|summary| summary.won > 0);
+    trends.push(match level {
+        Some(level) => {
+            let times = stats.times(&level.level, TIME_BINS);
+            let slowest = times.min + times.width * times.counts.len() as f64;
+            (
+                format!(
+                    "Winning times on {}, {:.0} to {:.0} s",
+                    level.level, times.min, slowest
+                ),
+                Plot::Bars(times),
+            )
+        }
+        None => (
+            "Winning times, no games won yet".to_string(),
+            Plot::Bars(Histogram::new(&[], TIME_BINS)),
+        ),
+    });
+    let efficiencies = stats.efficiencies(TREND_WINDOW);
+    // rounded up to the next 50% so chords above 100% stay on the chart
+    let max = efficiencies.iter().copied().fold(100.0, f64::max);
+    let max = (max / 50.0).ceil() * 50.0;
+    trends.push((
+        format!(
+            "Efficiency over the last {} wins, 0 to {:.0}%",
+            TREND_WINDOW, max
+        ),
+        Plot::Line {
+            values: efficiencies,
+            max,
+        },
+    ));
+    trends
+}
@@ -0,0 +1,834 @@
+//! Per user statistics of finished games, win and daily challenge streaks,
+//! the history of each session, where on the board games were lost and how
+//! the win rate, times and efficiency trend over the games played.
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::Result,
+    game::{Clicks, Difficulty, Game, GameConfig, GameState, Neighborhood},
+    score,
+    solver::{self, ThreeBv},
+};
+
+const STATS_FILE: &str = "stats.txt";
+
+/// One finished game in the statistics store.
+///
+/// The store keeps one record per line as space separated `key=value` pairs so
+/// new fields can be added without breaking older files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Seconds since the Unix epoch when the game ended.
+    pub timestamp: u64,
+    /// Level name, see [`level_name`].
+    pub level: String,
+    /// Whether the game was won.
+    pub won: bool,
+    /// Seconds taken to win or lose.
+    pub elapsed: f64,
+    /// Day of the daily challenge the game was, in days since the Unix epoch.
+    pub daily: Option<u64>,
+    /// 3BV of the board and how much of it was cleared, not kept by older
+    /// builds.
+    pub three_bv: Option<ThreeBv>,
+    /// Clicks made in the game, not kept by older builds.
+    pub clicks: Option<Clicks>,
+    /// The session the game was played in, see [`session`], not kept by
+    /// older builds.
+    pub session: Option<u64>,
+    /// Whether the game was played without flags, see [`Game::no_flags`].
+    pub no_flags: bool,
+    /// Whether cells proven safe opened or proven mines were flagged on
+    /// their own, see [`Game::assisted`].
+    pub assisted: bool,
+    /// Hints taken, see [`Game::hint`], their penalty is in the time.
+    pub hints: u16,
+    /// Arcade points, see [`score`], not kept by older builds.
+    pub score: Option<u32>,
+    /// Where the mine that lost the game was, as fractions of the board's
+    /// width and height to the middle of the cell.
+    pub fatal: Option<(f32, f32)>,
+}
+
+/// Consecutive wins over all levels.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Streaks {
+    /// Wins since the last loss.
+    pub current: usize,
+    /// Longest run of wins ever.
+    pub best: usize,
+}
+
+/// Aggregated results for one level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// Level name, see [`level_name`], followed by `NF` for games played
+    /// without flags, `assisted` for assisted games and `hinted` for games
+    /// with hints, see [`Record::category`].
+    pub level: String,
+    /// Games played.
+    pub played: usize,
+    /// Games won.
+    pub won: usize,
+    /// Fastest win in seconds.
+    pub best: Option<f64>,
+    /// Highest score of any game, won or lost.
+    pub best_score: Option<u32>,
+}
+
+impl Summary {
+    /// Percentage of games won.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.won as f64 / self.played as f64 * 100.0
+        }
+    }
+}
+
+/// Fatal clicks counted over a grid laid over the board, so boards of any
+/// size share the same map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    size: usize,
+    counts: Vec<u32>,
+}
+
+impl Heatmap {
+    /// An empty map of `size` by `size` areas.
+    pub fn new(size: usize) -> Self {
+        Heatmap {
+            size,
+            counts: vec![0; size * size],
+        }
+    }
+
+    /// Counts a fatal click at fractions of the board's width and height.
+    pub fn add(&mut self, (x, y): (f32, f32)) {
+        if self.size == 0 {
+            return;
+        }
+        let area = |at: f32| ((at * self.size as f32) as usize).min(self.size - 1);
+        self.counts[area(y) * self.size + area(x)] += 1;
+    }
+
+    /// Number of areas across and down.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Fatal clicks in the area at `column`, `row`.
+    pub fn count(&self, column: usize, row: usize) -> u32 {
+        self.counts[row * self.size + column]
+    }
+
+    /// Fatal clicks in the area with the most.
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Fatal clicks in the area at `column`, `row` relative to the area with
+    /// the most, from 0 to 1.
+    pub fn intensity(&self, column: usize, row: usize) -> f32 {
+        match self.max() {
+            0 => 0.0,
+            max => self.count(column, row) as f32 / max as f32,
+        }
+    }
+}
+
+/// Values counted in bins of equal width from the smallest to the largest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Lower bound of the first bin.
+    pub min: f64,
+    /// Width of each bin.
+    pub width: f64,
+    /// Values in each bin.
+    pub counts: Vec<u32>,
+}
+
+impl Histogram {
+    /// Counts `values` in `bins` bins, the largest value in the last one.
+    pub fn new(values: &[f64], bins: usize) -> Histogram {
+        let mut histogram = Histogram {
+            min: 0.0,
+            width: 0.0,
+            counts: vec![0; bins],
+        };
+        if bins == 0 || values.is_empty() {
+            return histogram;
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        histogram.min = min;
+        histogram.width = (max - min) / bins as f64;
+        for value in values {
+            let bin = match histogram.width {
+                width if width > 0.0 => ((value - min) / width) as usize,
+                _ => 0,
+            };
+            histogram.counts[bin.min(bins - 1)] += 1;
+        }
+        histogram
+    }
+
+    /// Values in the fullest bin.
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Averages of each value with up to `window - 1` values before it, a
+/// trend smoothed of game to game swings.
+pub fn rolling(values: &[f64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    (0..values.len())
+        .map(|end| {
+            let recent = &values[(end + 1).saturating_sub(window)..=end];
+            recent.iter().sum::<f64>() / recent.len() as f64
+        })
+        .collect()
+}
+
+/// All recorded games.
+#[derive(Debug, Default)]
+pub struct Stats {
+    records: Vec<Record>,
+}
+
+impl Record {
+    /// Creates a record for a game that has just been won or lost.
+    pub fn from_game(game: &Game) -> Option<Record> {
+        let won = match game.state() {
+            GameState::Won => true,
+            GameState::Lost => false,
+            _ => return None,
+        };
+        Some(Record {
+            timestamp: now(),
+            level: level_name(game.width(), game.height(), game.total()),
+            won,
+            elapsed: game.elapsed().as_secs_f64(),
+            daily: None,
+            three_bv: Some(solver::three_bv(game)),
+            clicks: Some(game.clicks()),
+            session: Some(session()),
+            no_flags: game.no_flags(),
+            assisted: game.assisted(),
+            hints: game.hints(),
+            score: Some(score::score(game).total()),
+            fatal: game.exploded().map(|(x, y)| {
+                (
+                    (x as f32 + 0.5) / game.width() as f32,
+                    (y as f32 + 0.5) / game.height() as f32,
+                )
+            }),
+        })
+    }
+
+    /// The level the game is ranked in, no flag games, assisted games and
+    /// games with hints are their own categories: `expert NF`,
+    /// `expert assisted`, `expert hinted`.
+    pub fn category(&self) -> String {
+        let mut category = self.level.clone();
+        if self.no_flags {
+            category.push_str(" NF");
+        }
+        if self.assisted {
+            category.push_str(" assisted");
+        }
+        if self.hints > 0 {
+            category.push_str(" hinted");
+        }
+        category
+    }
+
+    /// One line for the session history: level, result, time, 3BV, hints and
+    /// score.
+    pub fn history_line(&self) -> String {
+        let mut line = format!(
+            "{:<16} {:<4} {:>7.1} s",
+            self.category(),
+            if self.won { "won" } else { "lost" },
+            self.elapsed
+        );
+        if let Some(three_bv) = self.three_bv {
+            line.push_str(&format!("  3BV {}/{}", three_bv.cleared, three_bv.total));
+        }
+        if self.hints > 0 {
+            line.push_str(&format!("  hints {}", self.hints));
+        }
+        if let Some(score) = self.score {
+            line.push_str(&format!("  {} pts", score));
+        }
+        line
+    }
+
+    /// 3BV cleared per second.
+    pub fn three_bv_per_second(&self) -> Option<f64> {
+        let three_bv = self.three_bv?;
+        (self.elapsed > 0.0).then(|| three_bv.cleared as f64 / self.elapsed)
+    }
+
+    /// 3BV cleared per click in percent, above 100 when chords saved clicks.
+    pub fn efficiency(&self) -> Option<f64> {
+        let (three_bv, total) = (self.three_bv?, self.clicks?.total());
+        (total > 0).then(|| three_bv.cleared as f64 / total as f64 * 100.0)
+    }
+
+    fn parse(line: &str) -> Option<Record> {
+        let mut record = Record {
+            timestamp: 0,
+            level: String::new(),
+            won: false,
+            elapsed: 0.0,
+            daily: None,
+            three_bv: None,
+            clicks: None,
+            session: None,
+            no_flags: false,
+            assisted: false,
+            hints: 0,
+            score: None,
+            fatal: None,
+        };
+        for pair in line.split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            match key {
+                "time" => record.timestamp = value.parse().ok()?,
+                "level" => record.level = value.to_string(),
+                "result" => record.won = value == "won",
+                "elapsed" => record.elapsed = value.parse().ok()?,
+                "daily" => record.daily = Some(parse_date(value)?),
+                "session" => record.session = Some(value.parse().ok()?),
+                "mode" => record.no_flags = value == "nf",
+                "assist" => record.assisted = value == "auto",
+                "hints" => record.hints = value.parse().ok()?,
+                "score" => record.score = Some(value.parse().ok()?),
+                "fatal" => {
+                    let (x, y) = value.split_once(',')?;
+                    record.fatal = Some((x.parse().ok()?, y.parse().ok()?));
+                }
+                "3bv" => {
+                    let (cleared, total) = value.split_once('/')?;
+                    record.three_bv = Some(ThreeBv {
+                        total: total.parse().ok()?,
+                        cleared: cleared.parse().ok()?,
+                    });
+                }
+                "uncovers" | "chords" | "flags" | "wasted" => {
+                    let clicks = record.clicks.get_or_insert_with(Clicks::default);
+                    let count = match key {
+                        "uncovers" => &mut clicks.uncovers,
+                        "chords" => &mut clicks.chords,
+                        "flags" => &mut clicks.flags,
+                        _ => &mut clicks.wasted,
+                    };
+                    *count = value.parse().ok()?;
+                }
+                _ => {}
+            }
+        }
+        if record.level.is_empty() {
+            None
+        } else {
+            Some(record)
+        }
+    }
+
+    fn line(&self) -> String {
+        let mut line = format!(
+            "time={} level={} result={} elapsed={:.3}",
+            self.timestamp,
+            self.level,
+            if self.won { "won" } else { "lost" },
+            self.elapsed
+        );
+        if let Some(day) = self.daily {
+            line.push_str(&format!(" daily={}", date(day)));
+        }
+        if let Some(three_bv) = self.three_bv {
+            line.push_str(&format!(" 3bv={}/{}", three_bv.cleared, three_bv.total));
+        }
+        if let Some(clicks) = self.clicks {
+            line.push_str(&format!(
+                " uncovers={} chords={} flags={} wasted={}",
+                clicks.uncovers, clicks.chords, clicks.flags, clicks.wasted
+            ));
+        }
+        if let Some(session) = self.session {
+            line.push_str(&format!(" session={}", session));
+        }
+        if self.no_flags {
+            line.push_str(" mode=nf");
+        }
+        if self.assisted {
+            line.push_str(" assist=auto");
+        }
+        if self.hints > 0 {
+            line.push_str(&format!(" hints={}", self.hints));
+        }
+        if let Some(score) = self.score {
+            line.push_str(&format!(" score={}", score));
+        }
+        if let Some((x, y)) = self.fatal {
+            line.push_str(&format!(" fatal={:.3},{:.3}", x, y));
+        }
+        line
+    }
+}
+
+impl Stats {
+    /// Loads the shared statistics store. A missing store is empty.
+    pub fn load() -> Result<Stats> {
+        let Some(path) = store_path() else {
+            return Ok(Stats::default());
+        };
+        match File::open(path) {
+            Ok(file) => Stats::read(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Stats::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads records, skipping lines that cannot be parsed.
+    pub fn read<R: BufRead>(reader: R) -> Result<Stats> {
+        let mut records = Vec::new();
+        for line in reader.lines() {
+            if let Some(record) = Record::parse(&line?) {
+                records.push(record);
+            }
+        }
+        Ok(Stats { records })
+    }
+
+    /// The records in the order they were played.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Summarizes the records per category in the order categories were
+    /// first played.
+    pub fn summary(&self) -> Vec<Summary> {
+        let mut summary: Vec<Summary> = Vec::new();
+        for record in &self.records {
+            let category = record.category();
+            let index = match summary.iter().position(|s| s.level == category) {
+                Some(index) => index,
+                None => {
+                    summary.push(Summary {
+                        level: category,
+                        played: 0,
+                        won: 0,
+                        best: None,
+                        best_score: None,
+                    });
+                    summary.len() - 1
+                }
+            };
+            let level = &mut summary[index];
+            level.played += 1;
+            if record.won {
+                level.won += 1;
+                if level.best.is_none_or(|best| record.elapsed < best) {
+                    level.best = Some(record.elapsed);
+                }
+            }
+            if let Some(score) = record.score {
+                level.best_score = Some(level.best_score.unwrap_or(0).max(score));
+            }
+        }
+        summary
+    }
+
+    /// The current and longest runs of wins, in the order games were played.
+    pub fn streaks(&self) -> Streaks {
+        let mut streaks = Streaks::default();
+        for record in &self.records {
+            if record.won {
+                streaks.current += 1;
+                streaks.best = streaks.best.max(streaks.current);
+            } else {
+                streaks.current = 0;
+            }
+        }
+        streaks
+    }
+
+    /// The sessions games were recorded in, oldest first.
+    pub fn sessions(&self) -> Vec<u64> {
+        let mut sessions: Vec<u64> = Vec::new();
+        for session in self.records.iter().filter_map(|record| record.session) {
+            if !sessions.contains(&session) {
+                sessions.push(session);
+            }
+        }
+        sessions
+    }
+
+    /// The games of a session in the order they were played.
+    pub fn history(&self, session: u64) -> Vec<&Record> {
+        self.records
+            .iter()
+            .filter(|record| record.session == Some(session))
+            .collect()
+    }
+
+    /// Where games were lost over all levels, on a `size` by `size` grid.
+    pub fn heatmap(&self, size: usize) -> Heatmap {
+        let mut heatmap = Heatmap::new(size);
+        for fatal in self.records.iter().filter_map(|record| record.fatal) {
+            heatmap.add(fatal);
+        }
+        heatmap
+    }
+
+    /// Percentage of games won over the last `window` games, after each game
+    /// in the order they were played.
+    pub fn win_rates(&self, window: usize) -> Vec<f64> {
+        let results: Vec<f64> = self
+            .records
+            .iter()
+            .map(|record| if record.won { 100.0 } else { 0.0 })
+            .collect();
+        rolling(&results, window)
+    }
+
+    /// Times of the games won in `category`, see [`Record::category`],
+    /// counted in `bins` bins.
+    pub fn times(&self, category: &str, bins: usize) -> Histogram {
+        let times: Vec<f64> = self
+            .records
+            .iter()
+            .filter(|record| record.won && record.category() == category)
+            .map(|record| record.elapsed)
+            .collect();
+        Histogram::new(&times, bins)
+    }
+
+    /// Efficiency of the games won, see [`Record::efficiency`], averaged over
+    /// the last `window` of them in the order they were played.
+    pub fn efficiencies(&self, window: usize) -> Vec<f64> {
+        let efficiencies: Vec<f64> = self
+            .records
+            .iter()
+            .filter(|record| record.won)
+            .filter_map(Record::efficiency)
+            .collect();
+        rolling(&efficiencies, window)
+    }
+
+    /// Counts the consecutive days of won daily challenges ending today, or
+    /// yesterday if today's has not been won yet.
+    pub fn daily_streak(&self, today: u64) -> usize {
+        let won = |day: u64| {
+            self.records
+                .iter()
+                .any(|record| record.won && record.daily == Some(day))
+        };
+        let mut day = if won(today) {
+            today
+        } else {
+            today.saturating_sub(1)
+        };
+        let mut streak = 0;
+        while won(day) {
+            streak += 1;
+            let Some(previous) = day.checked_sub(1) else {
+                break;
+            };
+            day = previous;
+        }
+        streak
+    }
+}
+
+/// Identifies the games of this process, the Unix time of the first call.
+pub fn session() -> u64 {
+    static SESSION: OnceLock<u64> = OnceLock::new();
+    *SESSION.get_or_init(now)
+}
+
+/// Appends a finished game, the daily challenge of `daily` if set, to the
+/// shared statistics store. Practice games, games counted by a variant
+/// [`Neighborhood`], blind games, boards weighed by zones, handicapped games
+/// and games refusing wrong flags, which tell safe cells apart, are skipped.
+pub fn record(game: &Game, daily: Option<u64>) -> Result<()> {
+    let variant = game.neighborhood() != Neighborhood::Adjacent
+        || game.blind().is_some()
+        || !game.zones().is_empty()
+        || game.handicap().is_some()
+        || game.flag_penalty();
+    if game.practice() || variant {
+        return Ok(());
+    }
+    let Some(mut record) = Record::from_game(game) else {
+        return Ok(());
+    };
+    record.daily = daily;
+    let Some(path) = store_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.line())?;
+    Ok(())
+}
+
+/// Names the classic board sizes, anything else is described by its size.
+pub fn level_name(width: i16, height: i16, mines: u16) -> String {
+    let config = GameConfig {
+        width,
+        height,
+        mines,
+        zones: Vec::new(),
+    };
+    match Difficulty::from_config(config) {
+        Some(difficulty) => difficulty.to_string(),
+        None => format!("{}x{}/{}", width, height, mines),
+    }
+}
+
+/// Directory shared by the GUI and CLI for per user data such as statistics
+/// and the config file.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(appdata) = env::var_os("APPDATA") {
+        return Some(PathBuf::from(appdata).join("minesweeper-d2d"));
+    }
+    if let Some(data) = env::var_os("XDG_DATA_HOME") {
+        return Some(PathBuf::from(data).join("minesweeper-d2d"));
+    }
+    env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("minesweeper-d2d")
+    })
+}
+
+fn store_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(STATS_FILE))
+}
+
+/// Returns the current day in days since the Unix epoch, in UTC.
+pub fn today() -> u64 {
+    now() / 86400
+}
+
+/// Formats days since the Unix epoch as a YYYY-MM-DD date.
+pub fn date(day: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Formats seconds since the Unix epoch as a YYYY-MM-DD HH:MM:SS time in UTC.
+pub fn date_time(timestamp: u64) -> String {
+    let seconds = timestamp % 86400;
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        date(timestamp / 86400),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Parses a YYYY-MM-DD date into days since the Unix epoch.
+fn parse_date(s: &str) -> Option<u64> {
+    let mut parts = s.split('-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    u64::try_from(era * 146097 + doe - 719468).ok()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_record_round_trip() {
+        let record = Record {
+            timestamp: 1666000000,
+            level: "expert".to_string(),
+            won: true,
+            elapsed: 98.25,
+            daily: Some(20742),
+            three_bv: Some(ThreeBv {
+                total: 150,
+                cleared: 150,
+            }),
+            clicks: Some(Clicks {
+                uncovers: 120,
+                chords: 15,
+                flags: 40,
+                wasted: 5,
+            }),
+            session: Some(1666000000),
+            no_flags: true,
+            assisted: true,
+            hints: 2,
+            score: Some(1234),
+            fatal: None,
+        };
+        assert_eq!(Some(record.clone()), Record::parse(&record.line()));
+        assert_eq!(None, Record::parse("garbage"));
+        assert!((record.three_bv_per_second().unwrap() - 150.0 / 98.25).abs() < 1e-9);
+        assert_eq!(Some(150.0 / 175.0 * 100.0), record.efficiency());
+        let old = Record::parse("time=1 level=beginner result=won elapsed=20.0").unwrap();
+        assert_eq!((None, None), (old.three_bv_per_second(), old.efficiency()));
+    }
+
+    #[test]
+    pub fn test_summary() {
+        let text = "time=1 level=beginner result=won elapsed=20.0\n\
+                    time=2 level=expert result=lost elapsed=5.0\n\
+                    time=3 level=beginner result=lost elapsed=3.0\n\
+                    time=4 level=beginner result=won elapsed=12.5 extra=ignored\n";
+        let stats = Stats::read(text.as_bytes()).unwrap();
+        let summary = stats.summary();
+        assert_eq!(2, summary.len());
+        let text = format!(
+            "{}time=5 level=expert result=won elapsed=90.0 mode=nf\n",
+            text
+        );
+        let no_flags = Stats::read(text.as_bytes()).unwrap();
+        let no_flags = no_flags.summary();
+        assert_eq!("expert NF", no_flags[2].level);
+        assert_eq!((1, Some(90.0)), (no_flags[2].won, no_flags[2].best));
+        assert_eq!(None, no_flags[1].best);
+        let text = format!(
+            "{}time=6 level=expert result=won elapsed=80.0 assist=auto\n",
+            text
+        );
+        let assisted = Stats::read(text.as_bytes()).unwrap().summary();
+        assert_eq!("expert assisted", assisted[3].level);
+        assert_eq!(Some(90.0), assisted[2].best);
+        assert_eq!("beginner", summary[0].level);
+        assert_eq!(3, summary[0].played);
+        assert_eq!(2, summary[0].won);
+        assert_eq!(Some(12.5), summary[0].best);
+        assert_eq!(None, summary[1].best);
+        assert_eq!(0.0, summary[1].win_rate());
+    }
+
+    #[test]
+    pub fn test_streaks_and_history() {
+        let text = "time=1 level=beginner result=won elapsed=20.0 session=1\n\
+                    time=2 level=beginner result=won elapsed=18.0 session=1\n\
+                    time=3 level=expert result=lost elapsed=5.0 session=1\n\
+                    time=4 level=beginner result=won elapsed=12.5 3bv=30/30\n\
+                    time=5 level=beginner result=won elapsed=15.0 session=5 3bv=28/28\n";
+        let stats = Stats::read(text.as_bytes()).unwrap();
+        assert_eq!(
+            Streaks {
+                current: 2,
+                best: 2
+            },
+            stats.streaks()
+        );
+        assert_eq!(vec![1, 5], stats.sessions());
+        assert_eq!(3, stats.history(1).len());
+        let history = stats.history(5);
+        assert_eq!(
+            "beginner         won     15.0 s  3BV 28/28",
+            history[0].history_line()
+        );
+        assert_eq!(session(), session());
+    }
+
+    #[test]
+    pub fn test_heatmap() {
+        let mut game = Game::from_layout(
+            4,
+            2,
+            &[true, false, false, false, false, false, false, false],
+        );
+        game.uncover(0, 0);
+        let record = Record::from_game(&game).unwrap();
+        assert_eq!(Some((0.125, 0.25)), record.fatal);
+        assert_eq!(record.fatal, Record::parse(&record.line()).unwrap().fatal);
+        let text = format!(
+            "{}\ntime=2 level=expert result=lost elapsed=5.0 fatal=0.990,0.010\n\
+             time=3 level=expert result=lost elapsed=9.0 fatal=0.950,0.100\n\
+             time=4 level=expert result=won elapsed=90.0\n",
+            record.line()
+        );
+        let heatmap = Stats::read(text.as_bytes()).unwrap().heatmap(4);
+        assert_eq!((1, 2), (heatmap.count(0, 1), heatmap.count(3, 0)));
+        assert_eq!(0.5, heatmap.intensity(0, 1));
+        assert_eq!(0.0, heatmap.intensity(1, 1));
+        assert_eq!(0.0, Heatmap::new(3).intensity(2, 2));
+    }
+
+    #[test]
+    pub fn test_trends() {
+        let text = "time=1 level=expert result=won elapsed=80.0 3bv=10/10 uncovers=10 chords=0 flags=0 wasted=0\n\
+             time=2 level=expert result=lost elapsed=9.0\n\
+             time=3 level=expert result=won elapsed=120.0 3bv=10/10 uncovers=5 chords=0 flags=0 wasted=0\n\
+             time=4 level=beginner result=won elapsed=7.0\n\
+             time=5 level=expert result=won elapsed=100.0\n";
+        let stats = Stats::read(text.as_bytes()).unwrap();
+        assert_eq!(vec![100.0, 50.0, 50.0, 100.0, 100.0], stats.win_rates(2));
+        assert_eq!(vec![100.0, 150.0], stats.efficiencies(5));
+
+        let times = stats.times("expert", 4);
+        assert_eq!((80.0, 10.0), (times.min, times.width));
+        assert_eq!(vec![1, 0, 1, 1], times.counts);
+        assert_eq!(1, times.max());
+        assert_eq!(vec![0, 0], stats.times("intermediate", 2).counts);
+        assert_eq!(vec![1], Histogram::new(&[3.0], 1).counts);
+        assert!(rolling(&[], 3).is_empty());
+    }
+
+    #[test]
+    pub fn test_daily_streak() {
+        assert_eq!("2026-10-16", date(20742));
+        assert_eq!(Some(20742), parse_date("2026-10-16"));
+        assert_eq!("1970-01-01", date(0));
+        let text = "time=1 level=intermediate result=won elapsed=50.0 daily=2026-10-13\n\
+                    time=2 level=intermediate result=won elapsed=40.0 daily=2026-10-14\n\
+                    time=3 level=intermediate result=lost elapsed=9.0 daily=2026-10-15\n\
+                    time=4 level=intermediate result=won elapsed=30.0 daily=2026-10-15\n";
+        let stats = Stats::read(text.as_bytes()).unwrap();
+        // today not played yet, the streak continues from yesterday
+        assert_eq!(3, stats.daily_streak(20742));
+        assert_eq!(0, stats.daily_streak(20744));
+    }
+}
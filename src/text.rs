@@ -0,0 +1,507 @@
+//! Terminal rendering of the board in several glyph styles and in words.
+
+use std::{convert::Infallible, str::FromStr};
+
+use crate::{
+    game::{CellState, Game, GameState, ANNOTATIONS},
+    render::{self, BoardRenderer, Overlay},
+    solver::{self, Deduction, DeductionKind},
+};
+
+// background colors of claimed cells, red and blue
+const PLAYER_COLORS: [u8; 2] = [41, 44];
+// foreground of contradicted numbers, red
+const CONTRADICTION_COLOR: u8 = 31;
+// background colors of the candidate marks, yellow, cyan, magenta and green
+const ANNOTATION_COLORS: [u8; ANNOTATIONS as usize] = [43, 46, 45, 42];
+// faint cells the ghost of the best run has uncovered
+const GHOST_STYLE: u8 = 2;
+// underlined cells of a pattern a hint names
+const PATTERN_STYLE: u8 = 4;
+// foreground of the flags the solver placed, cyan
+const AUTO_FLAG_COLOR: u8 = 36;
+// up to the largest count of the distance-2 rule
+const NUMBER_NAMES: [&str; 25] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+    "twenty-one",
+    "twenty-two",
+    "twenty-three",
+    "twenty-four",
+];
+
+/// Glyph set used to print the board in a terminal.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Style {
+    /// Emoji tiles for terminals with color emoji fonts.
+    Emoji,
+    /// Unicode squares, matching the `Display` implementation of `Game`.
+    #[default]
+    Unicode,
+    /// Plain ASCII for dumb terminals and CI logs.
+    Ascii,
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "emoji" => Ok(Style::Emoji),
+            "unicode" => Ok(Style::Unicode),
+            "ascii" => Ok(Style::Ascii),
+            _ => Err(format!(
+                "unknown style {}, expected emoji, unicode or ascii",
+                s
+            )),
+        }
+    }
+}
+
+/// Glyph overrides for individual cell states, set in the config file or with
+/// `--glyphs flag=F,covered=#`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Cells that have not been uncovered.
+    pub covered: Option<String>,
+    /// Uncovered cells without mined neighbors.
+    pub blank: Option<String>,
+    /// Uncovered mines.
+    pub mine: Option<String>,
+    /// Flagged cells.
+    pub flag: Option<String>,
+    /// Cells marked with a question mark.
+    pub question: Option<String>,
+}
+
+impl Glyphs {
+    /// Overrides the glyph for one of covered, blank, mine, flag or question.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return Err(format!("empty glyph for {}", key));
+        }
+        let glyph = match key {
+            "covered" => &mut self.covered,
+            "blank" => &mut self.blank,
+            "mine" => &mut self.mine,
+            "flag" => &mut self.flag,
+            "question" => &mut self.question,
+            _ => {
+                return Err(format!(
+                    "unknown glyph {}, expected covered, blank, mine, flag or question",
+                    key
+                ))
+            }
+        };
+        *glyph = Some(value.to_string());
+        Ok(())
+    }
+
+    /// Replaces glyphs with those set in `other`.
+    pub fn overlay(&mut self, other: &Glyphs) {
+        let pairs = [
+            (&mut self.covered, &other.covered),
+            (&mut self.blank, &other.blank),
+            (&mut self.mine, &other.mine),
+            (&mut self.flag, &other.flag),
+            (&mut self.question, &other.question),
+        ];
+        for (glyph, other) in pairs {
+            if other.is_some() {
+                glyph.clone_from(other);
+            }
+        }
+    }
+
+    /// The glyphs set, by the names [`Glyphs::set`] takes.
+    pub fn entries(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("covered", &self.covered),
+            ("blank", &self.blank),
+            ("mine", &self.mine),
+            ("flag", &self.flag),
+            ("question", &self.question),
+        ]
+        .into_iter()
+        .filter_map(|(name, glyph)| Some((name, glyph.as_deref()?)))
+        .collect()
+    }
+
+    fn get(&self, state: CellState) -> Option<&str> {
+        let glyph = match state {
+            CellState::Unknown(_) => &self.covered,
+            CellState::Known(false) => &self.blank,
+            CellState::Known(true) => &self.mine,
+            CellState::Flagged(_) => &self.flag,
+            CellState::Questioned(_) => &self.question,
+            CellState::Counted(_) => &None,
+        };
+        glyph.as_deref()
+    }
+}
+
+impl FromStr for Glyphs {
+    type Err = String;
+
+    /// Parses a comma separated list of `state=glyph` overrides.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut glyphs = Glyphs::default();
+        for pair in s.split(',') {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected state=glyph, found {}", pair))?;
+            glyphs.set(key.trim(), value.trim())?;
+        }
+        Ok(glyphs)
+    }
+}
+
+/// Returns the glyph for a single cell in the given style.
+pub fn glyph(state: CellState, style: Style) -> String {
+    match style {
+        Style::Emoji => match state {
+            CellState::Unknown(_) => "\u{1F7E6}".to_string(),
+            CellState::Known(false) => "\u{2B1C}".to_string(),
+            CellState::Known(true) => "\u{1F4A3}".to_string(),
+            // keycaps stop at 9, two digits are as wide as an emoji
+            CellState::Counted(count @ 0..=9) => format!("{}\u{FE0F}\u{20E3}", count),
+            CellState::Counted(count) => count.to_string(),
+            CellState::Flagged(_) => "\u{1F6A9}".to_string(),
+            CellState::Questioned(_) => "\u{2753}".to_string(),
+        },
+        Style::Unicode => match state {
+            CellState::Unknown(_) => "\u{25A0}".to_string(),
+            CellState::Known(false) => "\u{25A1}".to_string(),
+            CellState::Known(true) => "*".to_string(),
+            CellState::Counted(count) => render::count_glyph(count).to_string(),
+            CellState::Flagged(_) => "\u{1F3F3}".to_string(),
+            CellState::Questioned(_) => "?".to_string(),
+        },
+        Style::Ascii => match state {
+            CellState::Unknown(_) => "#".to_string(),
+            CellState::Known(false) => ".".to_string(),
+            CellState::Known(true) => "*".to_string(),
+            CellState::Counted(count) => render::count_glyph(count).to_string(),
+            CellState::Flagged(_) => "F".to_string(),
+            CellState::Questioned(_) => "?".to_string(),
+        },
+    }
+}
+
+/// Returns the marker drawn in place of a hinted cell.
+fn hint_glyph(kind: DeductionKind, style: Style) -> &'static str {
+    match (style, kind) {
+        (Style::Emoji, DeductionKind::Safe) => "\u{2705}",
+        (Style::Emoji, DeductionKind::Mine) => "\u{2757}",
+        (Style::Unicode, DeductionKind::Safe) => "\u{25CB}",
+        (Style::Unicode, DeductionKind::Mine) => "\u{25C6}",
+        (Style::Ascii, DeductionKind::Safe) => "S",
+        (Style::Ascii, DeductionKind::Mine) => "M",
+    }
+}
+
+/// Renders the player's view of the board, one row per line.
+pub fn render(game: &Game, style: Style, glyphs: &Glyphs) -> String {
+    render_hint(game, style, glyphs, None)
+}
+
+/// Renders the board using any glyph overrides, with the hinted cell replaced
+/// by a safe or mine marker.
+pub fn render_hint(game: &Game, style: Style, glyphs: &Glyphs, hint: Option<&Deduction>) -> String {
+    let overlays: Vec<_> = hint
+        .map(|hint| (hint.x, hint.y, Overlay::Hint(hint.kind)))
+        .into_iter()
+        .collect();
+    render_overlays(game, style, glyphs, &overlays)
+}
+
+/// Renders the board using any glyph overrides with overlays drawn on top.
+pub fn render_overlays(
+    game: &Game,
+    style: Style,
+    glyphs: &Glyphs,
+    overlays: &[(i16, i16, Overlay)],
+) -> String {
+    let mut renderer = TextRenderer::new(style, glyphs.clone());
+    render::draw(&mut renderer, game, overlays).unwrap_or_else(|never| match never {});
+    renderer.frame
+}
+
+/// Draws the board as lines of glyphs for the CLI and TUI.
+#[derive(Debug, Default, Clone)]
+pub struct TextRenderer {
+    style: Style,
+    glyphs: Glyphs,
+    width: usize,
+    cells: Vec<String>,
+    frame: String,
+}
+
+impl TextRenderer {
+    /// Creates a renderer drawing in a style with glyph overrides.
+    pub fn new(style: Style, glyphs: Glyphs) -> Self {
+        TextRenderer {
+            style,
+            glyphs,
+            ..Default::default()
+        }
+    }
+
+    /// The last presented frame, one row per line.
+    pub fn frame(&self) -> &str {
+        &self.frame
+    }
+}
+
+impl BoardRenderer for TextRenderer {
+    type Error = Infallible;
+
+    fn begin(&mut self, width: i16, height: i16) -> Result<(), Infallible> {
+        self.width = width as usize;
+        self.cells.clear();
+        self.cells
+            .resize(width as usize * height as usize, String::new());
+        Ok(())
+    }
+
+    fn draw_cell(&mut self, x: i16, y: i16, state: CellState) -> Result<(), Infallible> {
+        self.cells[y as usize * self.width + x as usize] = match self.glyphs.get(state) {
+            Some(glyph) => glyph.to_string(),
+            None => glyph(state, self.style),
+        };
+        Ok(())
+    }
+
+    fn draw_overlay(&mut self, x: i16, y: i16, overlay: Overlay) -> Result<(), Infallible> {
+        let cell = &mut self.cells[y as usize * self.width + x as usize];
+        match overlay {
+            Overlay::Hint(kind) => *cell = hint_glyph(kind, self.style).to_string(),
+            // ascii boards are for terminals that may not understand colors
+            Overlay::Claimed(player) if self.style != Style::Ascii => {
+                let color = PLAYER_COLORS[player as usize % PLAYER_COLORS.len()];
+                *cell = format!("\x1b[{}m{}\x1b[0m", color, cell);
+            }
+            Overlay::Contradiction(_) if self.style != Style::Ascii => {
+                *cell = format!("\x1b[{}m{}\x1b[0m", CONTRADICTION_COLOR, cell);
+            }
+            Overlay::Annotation(mark) if self.style != Style::Ascii => {
+                let color = ANNOTATION_COLORS[mark as usize % ANNOTATION_COLORS.len()];
+                *cell = format!("\x1b[{}m{}\x1b[0m", color, cell);
+            }
+            Overlay::Ghost if self.style != Style::Ascii => {
+                *cell = format!("\x1b[{}m{}\x1b[0m", GHOST_STYLE, cell);
+            }
+            Overlay::Pattern if self.style != Style::Ascii => {
+                *cell = format!("\x1b[{}m{}\x1b[0m", PATTERN_STYLE, cell);
+            }
+            Overlay::AutoFlag if self.style != Style::Ascii => {
+                *cell = format!("\x1b[{}m{}\x1b[0m", AUTO_FLAG_COLOR, cell);
+            }
+            Overlay::Peek(count) => {
+                let counted = CellState::Counted(count);
+                *cell = match self.glyphs.get(counted) {
+                    Some(glyph) => glyph.to_string(),
+                    None => glyph(counted, self.style),
+                };
+            }
+            Overlay::Claimed(_)
+            | Overlay::Contradiction(_)
+            | Overlay::Annotation(_)
+            | Overlay::Ghost
+            | Overlay::Pattern
+            | Overlay::AutoFlag => {}
+            #[cfg(feature = "dev-tools")]
+            Overlay::Mined => {
+                let mined = CellState::Known(true);
+                *cell = match self.glyphs.get(mined) {
+                    Some(glyph) => glyph.to_string(),
+                    None => glyph(mined, self.style),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), Infallible> {
+        // emoji are already double width so they are not spaced out
+        let separator = match self.style {
+            Style::Emoji => "",
+            Style::Unicode | Style::Ascii => " ",
+        };
+        // cells nothing was drawn on, such as the gaps between tiled boards
+        let gap = match self.style {
+            Style::Emoji => "  ",
+            Style::Unicode | Style::Ascii => " ",
+        };
+        self.frame.clear();
+        for row in self.cells.chunks(self.width.max(1)) {
+            for cell in row {
+                let cell = if cell.is_empty() { gap } else { cell };
+                self.frame.push_str(cell);
+                self.frame.push_str(separator);
+            }
+            self.frame.push('\n');
+        }
+        Ok(())
+    }
+}
+
+/// Names a cell in words for screen readers.
+pub fn cell_name(state: CellState) -> &'static str {
+    match state {
+        CellState::Unknown(_) => "covered",
+        CellState::Known(false) => "blank",
+        CellState::Known(true) => "mine",
+        CellState::Counted(count) => NUMBER_NAMES.get(count as usize).unwrap_or(&"many"),
+        CellState::Flagged(_) => "flag",
+        CellState::Questioned(_) => "question",
+    }
+}
+
+/// Describes the board row by row in words, for example
+/// `Row 2: covered, flag, two, blank`.
+pub fn describe(game: &Game) -> String {
+    let mut text = format!(
+        "Game {}, {} mines left, {} by {} board\n",
+        game.state(),
+        game.est_remaining(),
+        game.width(),
+        game.height()
+    );
+    for y in 0..game.height() {
+        let cells: Vec<&str> = (0..game.width())
+            .map(|x| cell_name(game.cell_state(x, y)))
+            .collect();
+        text.push_str(&format!("Row {}: {}\n", y, cells.join(", ")));
+    }
+    text
+}
+
+/// Counts the cells the player has not uncovered.
+pub fn covered(game: &Game) -> usize {
+    let mut covered = 0;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if solver::is_covered(game.cell_state(x, y)) {
+                covered += 1;
+            }
+        }
+    }
+    covered
+}
+
+/// Announces the effect of a move on the cell at `x`, `y` given the number of
+/// covered cells before the move.
+pub fn announce(game: &Game, x: i16, y: i16, covered_before: usize) -> String {
+    if game.state() == GameState::Lost {
+        return "You uncovered a mine".to_string();
+    }
+    let name = cell_name(game.cell_state(x, y));
+    match covered_before.saturating_sub(covered(game)) {
+        0 | 1 => format!("[{},{}] is {}", x, y, name),
+        uncovered => format!("Uncovered {} cells, [{},{}] is {}", uncovered, x, y, name),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_render_ascii() {
+        let mut layout = vec![false; 6];
+        layout[2] = true;
+        let mut game = Game::from_layout(3, 2, &layout);
+        game.uncover(0, 0);
+        game.flag(2, 0);
+        game.question(2, 1);
+        assert_eq!(
+            ". 1 F \n. 1 ? \n",
+            render(&game, Style::Ascii, &Glyphs::default())
+        );
+    }
+
+    #[test]
+    pub fn test_render_unicode_matches_display() {
+        let mut game = Game::new(5, 5);
+        game.uncover(2, 2);
+        assert_eq!(
+            game.to_string(),
+            render(&game, Style::Unicode, &Glyphs::default())
+        );
+    }
+
+    #[test]
+    pub fn test_render_hint() {
+        let mut layout = vec![false; 9];
+        layout[0] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        game.uncover(2, 2);
+        let hint = crate::solver::hint(&game).unwrap();
+        assert_eq!(
+            "M 1 . \n1 1 . \n. . . \n",
+            render_hint(&game, Style::Ascii, &Glyphs::default(), Some(&hint))
+        );
+    }
+
+    #[test]
+    pub fn test_render_glyphs() {
+        let mut layout = vec![false; 6];
+        layout[2] = true;
+        let mut game = Game::from_layout(3, 2, &layout);
+        game.uncover(0, 0);
+        game.flag(2, 0);
+        let glyphs: Glyphs = "flag=P, blank=_".parse().unwrap();
+        assert_eq!(
+            "_ 1 P \n_ 1 \u{25A0} \n",
+            render_hint(&game, Style::Unicode, &glyphs, None)
+        );
+        assert!("tile=x".parse::<Glyphs>().is_err());
+        assert!("flag".parse::<Glyphs>().is_err());
+    }
+
+    #[test]
+    pub fn test_describe() {
+        let mut layout = vec![false; 6];
+        layout[2] = true;
+        let mut game = Game::from_layout(3, 2, &layout);
+        let covered_before = covered(&game);
+        game.uncover(0, 0);
+        assert_eq!(
+            "Uncovered 4 cells, [0,0] is blank",
+            announce(&game, 0, 0, covered_before)
+        );
+        game.flag(2, 0);
+        assert_eq!(
+            "Game playing, 0 mines left, 3 by 2 board\n\
+             Row 0: blank, one, flag\n\
+             Row 1: blank, one, covered\n",
+            describe(&game)
+        );
+    }
+
+    #[test]
+    pub fn test_style_from_str() {
+        assert_eq!(Ok(Style::Emoji), "emoji".parse::<Style>());
+        assert!("fancy".parse::<Style>().is_err());
+    }
+}
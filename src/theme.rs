@@ -0,0 +1,268 @@
+//! Theme files that trade the colors, font and glyphs of the boards without
+//! the sprites and sounds of a full [`skin`](crate::skin) pack.
+//!
+//! A theme is a single text file in the `themes` folder of the data
+//! directory, one `key = value` setting per line:
+//!
+//! ```text
+//! name = Sunset
+//! author = A. Player
+//! color.board = #402030
+//! color.number1 = #ffb070
+//! font = Segoe UI
+//! glyph.flag = F
+//! ```
+//!
+//! The colors are the ones a skin can set, see [`COLORS`], the font is the
+//! family the window board writes its counts in and the glyphs are the
+//! terminal board's, see [`Glyphs::set`]. Whatever a theme leaves out keeps
+//! the built in look, and a skin chosen along with it is applied over it.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{MinesweeperError, Result},
+    skin::{self, Color, COLORS},
+    stats::data_dir,
+    text::Glyphs,
+};
+
+/// Extension of theme files.
+pub const EXTENSION: &str = "theme";
+const THEMES_DIR: &str = "themes";
+
+/// A theme read from its file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ThemeFile {
+    /// Name the theme gives itself.
+    pub name: String,
+    /// Who made the theme, if it says.
+    pub author: Option<String>,
+    /// Font family of the window board, the built in one when not set.
+    pub font: Option<String>,
+    /// Glyphs of the terminal board.
+    pub glyphs: Glyphs,
+    colors: Vec<(String, Color)>,
+}
+
+impl ThemeFile {
+    /// A theme setting nothing yet.
+    pub fn new(name: &str) -> ThemeFile {
+        ThemeFile {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses a theme, failing on malformed lines, unknown colors and
+    /// glyphs and invalid values so a broken file is caught when it is
+    /// imported rather than when it is drawn. Unknown keys are skipped.
+    pub fn parse(text: &str) -> Result<ThemeFile> {
+        let mut theme = ThemeFile::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| {
+                MinesweeperError::Parse(format!("line {}: {}", number + 1, message))
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            match key.split_once('.') {
+                Some(("color", name)) => {
+                    if !COLORS.contains(&name) {
+                        return Err(error(format!(
+                            "unknown color {}, expected one of {}",
+                            name,
+                            COLORS.join(", ")
+                        )));
+                    }
+                    theme.set_color(name, skin::parse_color(value).map_err(error)?);
+                }
+                Some(("glyph", name)) => theme.glyphs.set(name, value).map_err(error)?,
+                _ => match key {
+                    "name" => theme.name = value.to_string(),
+                    "author" => theme.author = Some(value.to_string()),
+                    "font" if value.is_empty() => return Err(error("empty font".to_string())),
+                    "font" => theme.font = Some(value.to_string()),
+                    // unknown keys are skipped
+                    _ => {}
+                },
+            }
+        }
+        if theme.name.is_empty() {
+            return Err(MinesweeperError::Parse("the theme has no name".to_string()));
+        }
+        Ok(theme)
+    }
+
+    /// Loads the theme file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<ThemeFile> {
+        let path = path.as_ref();
+        let _span = tracing::debug_span!("load theme", path = %path.display()).entered();
+        let theme = ThemeFile::parse(&fs::read_to_string(path)?)?;
+        tracing::info!(name = theme.name, "theme loaded");
+        Ok(theme)
+    }
+
+    /// Loads an installed theme by the name [`installed`] lists it under. An
+    /// absolute path loads a theme from anywhere.
+    pub fn find(name: &str) -> Result<ThemeFile> {
+        let dir = themes_dir()
+            .ok_or_else(|| MinesweeperError::Parse("no data directory for themes".to_string()))?;
+        ThemeFile::load(dir.join(name))
+    }
+
+    /// Writes the theme to `path`, in the form [`ThemeFile::parse`] reads.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// The color the theme gives `name`, one of [`COLORS`].
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.colors
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, color)| *color)
+    }
+
+    /// Gives `name`, one of [`COLORS`], a color.
+    pub fn set_color(&mut self, name: &str, color: Color) {
+        self.colors.retain(|(entry, _)| entry != name);
+        self.colors.push((name.to_string(), color));
+        // in the order of COLORS so themes setting the same colors are equal
+        self.colors
+            .sort_by_key(|(entry, _)| COLORS.iter().position(|known| known == entry));
+    }
+}
+
+impl fmt::Display for ThemeFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name = {}", self.name)?;
+        if let Some(author) = &self.author {
+            writeln!(f, "author = {}", author)?;
+        }
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        for (name, (r, g, b)) in &self.colors {
+            writeln!(
+                f,
+                "color.{} = #{:02x}{:02x}{:02x}",
+                name,
+                channel(*r),
+                channel(*g),
+                channel(*b)
+            )?;
+        }
+        if let Some(font) = &self.font {
+            writeln!(f, "font = {}", font)?;
+        }
+        for (name, glyph) in self.glyphs.entries() {
+            writeln!(f, "glyph.{} = {}", name, glyph)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where themes are installed.
+pub fn themes_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(THEMES_DIR))
+}
+
+/// The names of the installed themes in order, the theme files in
+/// [`themes_dir`].
+pub fn installed() -> Vec<String> {
+    let Some(entries) = themes_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case(EXTENSION))
+        })
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Checks the theme file at `path` and copies it into [`themes_dir`],
+/// returning the name it is installed under. An installed theme of the same
+/// name is replaced.
+pub fn import(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let theme = ThemeFile::load(path)?;
+    let dir = themes_dir()
+        .ok_or_else(|| MinesweeperError::Parse("no data directory for themes".to_string()))?;
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| theme.name.clone());
+    let name = format!("{}.{}", stem, EXTENSION);
+    fs::create_dir_all(&dir)?;
+    theme.save(dir.join(&name))?;
+    tracing::info!(name, theme = theme.name, "theme imported");
+    Ok(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_theme() {
+        let text = "# a test theme\nname = Sunset\nauthor = A. Player\ncolor.number1 = #ff8000\n\
+            color.board = #000000\nfont = Segoe UI\nglyph.flag = F\nlicense = free\n";
+        let theme = ThemeFile::parse(text).unwrap();
+        assert_eq!("Sunset", theme.name);
+        assert_eq!(Some("A. Player".to_string()), theme.author);
+        assert_eq!(Some((0.0, 0.0, 0.0)), theme.color("board"));
+        assert_eq!(Some((1.0, 128.0 / 255.0, 0.0)), theme.color("number1"));
+        assert_eq!(None, theme.color("cell"));
+        assert_eq!(Some("Segoe UI".to_string()), theme.font);
+        assert_eq!(Some("F".to_string()), theme.glyphs.flag);
+
+        // exported with the colors in order and read back the same
+        let exported = theme.to_string();
+        assert_eq!(
+            "name = Sunset\nauthor = A. Player\ncolor.board = #000000\n\
+                color.number1 = #ff8000\nfont = Segoe UI\nglyph.flag = F\n",
+            exported
+        );
+        assert_eq!(theme, ThemeFile::parse(&exported).unwrap());
+
+        assert!(ThemeFile::parse("author = nobody\n").is_err());
+        assert!(ThemeFile::parse("name = x\ncolor.board = #12345\n").is_err());
+        assert!(ThemeFile::parse("name = x\ncolor.sky = #123456\n").is_err());
+        assert!(ThemeFile::parse("name = x\nglyph.tile = x\n").is_err());
+        assert!(ThemeFile::parse("name = x\nfont =\n").is_err());
+        assert!(ThemeFile::parse("name = x\nfont\n").is_err());
+    }
+
+    #[test]
+    pub fn test_save_and_load() {
+        let mut theme = ThemeFile::new("Night");
+        theme.set_color("cell", (0.0, 0.2, 1.0));
+        theme.set_color("cell", (0.2, 0.2, 0.2));
+        let path = std::env::temp_dir().join(format!("theme-test-{}.theme", std::process::id()));
+        theme.save(&path).unwrap();
+        let loaded = ThemeFile::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!("Night", loaded.name);
+        assert_eq!(
+            Some((51.0 / 255.0, 51.0 / 255.0, 51.0 / 255.0)),
+            loaded.color("cell")
+        );
+    }
+}
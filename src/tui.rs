@@ -0,0 +1,348 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_TO_END: &str = "\x1b[J";
+const CLEAR_LINE: &str = "\r\x1b[K";
+// report button presses and releases in the SGR encoding
+const ENABLE_MOUSE: &str = "\x1b[?1000h\x1b[?1006h";
+const DISABLE_MOUSE: &str = "\x1b[?1006l\x1b[?1000l";
+const PROMPT: &str = "> ";
+/// Terminal columns taken by each board cell, glyph and separator.
+const CELL_COLUMNS: u16 = 2;
+
+/// Mouse buttons reported by the terminal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Button {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Input read from the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// A line of text completed with enter.
+    Line(String),
+    /// A mouse button pressed at a 1 based terminal column and row.
+    Click(Button, u16, u16),
+    /// End of input, ctrl+d or a closed stdin.
+    Eof,
+}
+
+/// The terminal alternate screen, redrawn in place for every frame.
+///
+/// The original screen and its scroll back are restored when dropped.
+pub(crate) struct Screen {
+    out: io::Stdout,
+    input: Input,
+    mode: InputMode,
+    // first terminal row of the board in the last frame
+    board_top: u16,
+}
+
+impl Screen {
+    pub(crate) fn enter() -> io::Result<Screen> {
+        enable_virtual_terminal()?;
+        let mode = InputMode::raw()?;
+        let mut out = io::stdout();
+        write!(out, "{}{}", ENTER_ALTERNATE_SCREEN, ENABLE_MOUSE)?;
+        out.flush()?;
+        Ok(Screen {
+            out,
+            input: Input::default(),
+            mode,
+            board_top: 1,
+        })
+    }
+
+    /// Replaces the screen contents with the board followed by any messages
+    /// and leaves the cursor on a prompt line.
+    pub(crate) fn draw(
+        &mut self,
+        header: &str,
+        board: &str,
+        messages: &[String],
+    ) -> io::Result<()> {
+        let mut frame = String::new();
+        frame.push_str(CURSOR_HOME);
+        frame.push_str(CLEAR_TO_END);
+        frame.push_str(header);
+        frame.push_str("\n\n");
+        self.board_top = header.lines().count() as u16 + 2;
+        frame.push_str(board);
+        frame.push('\n');
+        for message in messages {
+            frame.push_str(message);
+            frame.push('\n');
+        }
+        frame.push_str(PROMPT);
+        frame.push_str(self.input.line());
+        write!(self.out, "{}", frame)?;
+        self.out.flush()
+    }
+
+    /// Maps a terminal column and row to board coordinates, the caller checks
+    /// them against the board size.
+    pub(crate) fn cell_at(&self, column: u16, row: u16) -> Option<(i16, i16)> {
+        if column == 0 || row < self.board_top {
+            return None;
+        }
+        let x = (column - 1) / CELL_COLUMNS;
+        let y = row - self.board_top;
+        Some((i16::try_from(x).ok()?, i16::try_from(y).ok()?))
+    }
+
+    /// Waits for the next line or mouse click, echoing the line as it is typed.
+    pub(crate) fn read_event(&mut self) -> io::Result<Event> {
+        let mut buf = [0u8; 64];
+        loop {
+            if let Some(event) = self.input.next() {
+                return Ok(event);
+            }
+            let n = io::stdin().read(&mut buf)?;
+            if n == 0 {
+                return Ok(Event::Eof);
+            }
+            self.input.feed(&buf[..n]);
+            // echo is off in raw mode so the prompt line is redrawn instead
+            write!(self.out, "{}{}{}", CLEAR_LINE, PROMPT, self.input.line())?;
+            self.out.flush()?;
+        }
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = write!(self.out, "{}{}", DISABLE_MOUSE, LEAVE_ALTERNATE_SCREEN);
+        let _ = self.out.flush();
+        self.mode.restore();
+    }
+}
+
+/// Decodes raw terminal input into lines and mouse clicks.
+#[derive(Debug, Default)]
+struct Input {
+    // bytes of an incomplete escape sequence or UTF-8 character
+    pending: Vec<u8>,
+    line: String,
+    events: VecDeque<Event>,
+    // a line feed right after a carriage return ends the same line
+    after_cr: bool,
+}
+
+impl Input {
+    fn line(&self) -> &str {
+        &self.line
+    }
+
+    fn next(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let mut start = 0;
+        while start < self.pending.len() {
+            match self.decode(start) {
+                Some(used) => start += used,
+                None => break,
+            }
+        }
+        self.pending.drain(..start);
+    }
+
+    /// Decodes one key or sequence at `start`, returning the bytes used or
+    /// `None` if more input is needed.
+    fn decode(&mut self, start: usize) -> Option<usize> {
+        let after_cr = std::mem::take(&mut self.after_cr);
+        match self.pending[start] {
+            0x1b => return self.decode_escape(start),
+            b'\r' => {
+                self.after_cr = true;
+                self.end_line();
+            }
+            b'\n' if !after_cr => self.end_line(),
+            b'\n' => {}
+            0x04 => self.events.push_back(Event::Eof),
+            0x08 | 0x7f => {
+                self.line.pop();
+            }
+            b if b < 0x20 => {}
+            b => {
+                let len = match b {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let bytes = self.pending.get(start..start + len)?;
+                let text = String::from_utf8_lossy(bytes).to_string();
+                self.line.push_str(&text);
+                return Some(len);
+            }
+        }
+        Some(1)
+    }
+
+    fn decode_escape(&mut self, start: usize) -> Option<usize> {
+        let bytes = &self.pending[start..];
+        if bytes.len() < 2 {
+            return None;
+        }
+        if bytes[1] != b'[' {
+            // alt+key, drop both
+            return Some(2);
+        }
+        // a control sequence ends with a byte in 0x40..=0x7e
+        let end = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b))? + 2;
+        if bytes.get(2) == Some(&b'<') && bytes[end] == b'M' {
+            let params = String::from_utf8_lossy(&bytes[3..end]).to_string();
+            if let Some(event) = parse_mouse(&params) {
+                self.events.push_back(event);
+            }
+        }
+        // releases, keys such as arrows and anything else are ignored
+        Some(end + 1)
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.events.push_back(Event::Line(line));
+    }
+}
+
+/// Parses the `button;column;row` parameters of an SGR mouse press.
+fn parse_mouse(params: &str) -> Option<Event> {
+    let mut parts = params.split(';').map(|p| p.parse::<u16>().ok());
+    let (code, column, row) = (parts.next()??, parts.next()??, parts.next()??);
+    // motion and wheel events set higher bits
+    let button = match code {
+        0 => Button::Left,
+        1 => Button::Middle,
+        2 => Button::Right,
+        _ => return None,
+    };
+    Some(Event::Click(button, column, row))
+}
+
+/// Windows consoles only interpret escape sequences once asked to.
+#[cfg(windows)]
+fn enable_virtual_terminal() -> io::Result<()> {
+    use windows::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, CONSOLE_MODE,
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_OUTPUT_HANDLE,
+    };
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE)?;
+        let mut mode = CONSOLE_MODE::default();
+        GetConsoleMode(handle, &mut mode)?;
+        SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn enable_virtual_terminal() -> io::Result<()> {
+    Ok(())
+}
+
+/// Console input mode saved while keys are read one at a time.
+#[cfg(windows)]
+struct InputMode(windows::Win32::System::Console::CONSOLE_MODE);
+
+#[cfg(windows)]
+impl InputMode {
+    /// Turns off line buffering and echo and asks for escape sequences, quick
+    /// edit is turned off so that clicks reach the game.
+    fn raw() -> io::Result<InputMode> {
+        use windows::Win32::System::Console::{
+            GetConsoleMode, GetStdHandle, SetConsoleMode, CONSOLE_MODE, ENABLE_ECHO_INPUT,
+            ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_QUICK_EDIT_MODE,
+            ENABLE_VIRTUAL_TERMINAL_INPUT, STD_INPUT_HANDLE,
+        };
+        unsafe {
+            let handle = GetStdHandle(STD_INPUT_HANDLE)?;
+            let mut mode = CONSOLE_MODE::default();
+            GetConsoleMode(handle, &mut mode)?;
+            let raw = (mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_QUICK_EDIT_MODE))
+                | ENABLE_VIRTUAL_TERMINAL_INPUT
+                | ENABLE_EXTENDED_FLAGS;
+            SetConsoleMode(handle, raw)?;
+            Ok(InputMode(mode))
+        }
+    }
+
+    fn restore(&self) {
+        use windows::Win32::System::Console::{GetStdHandle, SetConsoleMode, STD_INPUT_HANDLE};
+        unsafe {
+            if let Ok(handle) = GetStdHandle(STD_INPUT_HANDLE) {
+                let _ = SetConsoleMode(handle, self.0);
+            }
+        }
+    }
+}
+
+/// Terminal settings saved by `stty` while keys are read one at a time.
+#[cfg(not(windows))]
+struct InputMode(String);
+
+#[cfg(not(windows))]
+impl InputMode {
+    fn raw() -> io::Result<InputMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "1"])?;
+        Ok(InputMode(saved.trim().to_string()))
+    }
+
+    fn restore(&self) {
+        let _ = stty(&[&self.0]);
+    }
+}
+
+#[cfg(not(windows))]
+fn stty(args: &[&str]) -> io::Result<String> {
+    use std::process::{Command, Stdio};
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_input_lines() {
+        let mut input = Input::default();
+        input.feed(b"u[1,");
+        assert_eq!(None, input.next());
+        assert_eq!("u[1,", input.line());
+        input.feed(b"3x\x7f]\r\nh\n");
+        assert_eq!(Some(Event::Line("u[1,3]".to_string())), input.next());
+        assert_eq!(Some(Event::Line("h".to_string())), input.next());
+        assert_eq!(None, input.next());
+        assert_eq!("", input.line());
+    }
+
+    #[test]
+    pub fn test_input_mouse() {
+        let mut input = Input::default();
+        // a press split across reads, its release and an arrow key
+        input.feed(b"\x1b[<2;5");
+        assert_eq!(None, input.next());
+        input.feed(b";4M\x1b[<2;5;4m\x1b[Az\x1b[<64;1;1M");
+        assert_eq!(Some(Event::Click(Button::Right, 5, 4)), input.next());
+        assert_eq!(None, input.next());
+        assert_eq!("z", input.line());
+    }
+}
@@ -0,0 +1,244 @@
+//! Hot-seat matches where two players take turns on the same board.
+//!
+//! A [`Match`] keeps the turn, the scores and who claimed which cell, while
+//! the front-end keeps its own [`Game`] and passes it to [`Match::play`] for
+//! every move. Uncovering scores a point for every cell revealed and claims
+//! them for the player, flags are claimed too and settled once the board is
+//! cleared, and whoever uncovers a mine loses the match.
+
+use crate::{
+    game::{CellState, Game, GameState, Move},
+    render::Overlay,
+};
+
+/// Number of players taking turns.
+pub const PLAYERS: usize = 2;
+/// Points for a flag on a mine when the match ends, a flag on a safe cell
+/// costs as many.
+pub const FLAG_POINTS: i32 = 3;
+
+/// Who a cell belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Claim {
+    /// Still covered and up for grabs.
+    Free,
+    /// Uncovered or flagged before the match started.
+    Nobody,
+    /// Uncovered or flagged by a player.
+    Player(usize),
+}
+
+/// Turns, scores and claimed cells of a hot-seat match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    width: i16,
+    claims: Vec<Claim>,
+    scores: [i32; PLAYERS],
+    turn: usize,
+    over: bool,
+    loser: Option<usize>,
+}
+
+impl Match {
+    /// Starts a match on a game with the first player to move. Cells already
+    /// uncovered or flagged belong to nobody.
+    pub fn new(game: &Game) -> Self {
+        let mut claims = Vec::with_capacity(game.width() as usize * game.height() as usize);
+        for y in 0..game.height() {
+            for x in 0..game.width() {
+                claims.push(match game.cell_state(x, y) {
+                    CellState::Unknown(_) | CellState::Questioned(_) => Claim::Free,
+                    _ => Claim::Nobody,
+                });
+            }
+        }
+        Match {
+            width: game.width(),
+            claims,
+            scores: [0; PLAYERS],
+            turn: 0,
+            over: false,
+            loser: None,
+        }
+    }
+
+    /// The player to move, counting from 0.
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// Points a player has scored so far.
+    pub fn score(&self, player: usize) -> i32 {
+        self.scores[player]
+    }
+
+    /// The player who uncovered or flagged the cell at `x`, `y`.
+    pub fn claimed_by(&self, x: i16, y: i16) -> Option<usize> {
+        match self.claims[(y * self.width + x) as usize] {
+            Claim::Player(player) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Whether the board was cleared or a player hit a mine.
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// The winner of a finished match, `None` while it is played or when the
+    /// scores are tied.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.over {
+            return None;
+        }
+        if let Some(loser) = self.loser {
+            return Some((loser + 1) % PLAYERS);
+        }
+        let best = *self.scores.iter().max()?;
+        let mut leaders = (0..PLAYERS).filter(|&player| self.scores[player] == best);
+        match (leaders.next(), leaders.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Every claimed cell as an overlay in its player's color.
+    pub fn overlays(&self) -> Vec<(i16, i16, Overlay)> {
+        let width = self.width.max(1) as usize;
+        self.claims
+            .iter()
+            .enumerate()
+            .filter_map(|(index, claim)| match *claim {
+                Claim::Player(player) => Some((
+                    (index % width) as i16,
+                    (index / width) as i16,
+                    Overlay::Claimed(player as u8),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Plays a move for the player whose turn it is and passes the turn on.
+    ///
+    /// Players may uncover or flag a covered cell and chord a counted one.
+    /// Returns false, leaving the turn where it is, for any other move or one
+    /// that does not change the board.
+    pub fn play(&mut self, game: &mut Game, mv: Move) -> bool {
+        if self.over || !matches!(game.state(), GameState::Initial | GameState::Playing) {
+            return false;
+        }
+        let (Move::Uncover(x, y)
+        | Move::Flag(x, y)
+        | Move::Question(x, y)
+        | Move::SetUnknown(x, y)
+        | Move::Chord(x, y)) = mv;
+        if x < 0 || y < 0 || x >= game.width() || y >= game.height() {
+            return false;
+        }
+        let allowed = matches!(
+            (mv, game.cell_state(x, y)),
+            (Move::Uncover(..) | Move::Flag(..), CellState::Unknown(_))
+                | (Move::Chord(..), CellState::Counted(_))
+        );
+        if !allowed {
+            return false;
+        }
+        let player = self.turn;
+        let state = game.apply(mv);
+        let mut changed = false;
+        for (index, claim) in self.claims.iter_mut().enumerate() {
+            if *claim != Claim::Free {
+                continue;
+            }
+            let x = (index % self.width as usize) as i16;
+            let y = (index / self.width as usize) as i16;
+            match game.cell_state(x, y) {
+                CellState::Known(false) | CellState::Counted(_) => {
+                    *claim = Claim::Player(player);
+                    self.scores[player] += 1;
+                    changed = true;
+                }
+                CellState::Flagged(_) => {
+                    *claim = Claim::Player(player);
+                    changed = true;
+                }
+                CellState::Known(true) => changed = true,
+                _ => {}
+            }
+        }
+        if !changed {
+            return false;
+        }
+        match state {
+            GameState::Won | GameState::Lost => {
+                if state == GameState::Lost {
+                    self.loser = Some(player);
+                }
+                self.settle_flags(game);
+            }
+            _ => self.turn = (self.turn + 1) % PLAYERS,
+        }
+        true
+    }
+
+    fn settle_flags(&mut self, game: &Game) {
+        for (index, claim) in self.claims.iter().enumerate() {
+            let Claim::Player(player) = *claim else {
+                continue;
+            };
+            let x = (index % self.width as usize) as i16;
+            let y = (index / self.width as usize) as i16;
+            match game.cell_state(x, y) {
+                CellState::Flagged(true) => self.scores[player] += FLAG_POINTS,
+                CellState::Flagged(false) => self.scores[player] -= FLAG_POINTS,
+                _ => {}
+            }
+        }
+        self.over = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_match_turns() {
+        // a mine in each corner of the top row
+        let mut layout = vec![false; 12];
+        layout[0] = true;
+        layout[3] = true;
+        let mut game = Game::from_layout(4, 3, &layout);
+        let mut versus = Match::new(&game);
+        // the opening reveals the two bottom rows for the first player
+        assert!(versus.play(&mut game, Move::Uncover(1, 2)));
+        assert_eq!((8, 1), (versus.score(0), versus.turn()));
+        assert_eq!(Some(0), versus.claimed_by(0, 1));
+        // revealed cells cannot be played again and the turn stays
+        assert!(!versus.play(&mut game, Move::Uncover(1, 2)));
+        assert!(!versus.play(&mut game, Move::Question(0, 0)));
+        assert!(versus.play(&mut game, Move::Flag(0, 0)));
+        assert!(versus.play(&mut game, Move::Uncover(1, 0)));
+        assert!(versus.play(&mut game, Move::Uncover(2, 0)));
+        assert_eq!(GameState::Won, game.state());
+        assert!(versus.is_over());
+        // player 0 uncovered 9 cells, player 1 one cell and a correct flag
+        assert_eq!((9, 1 + FLAG_POINTS), (versus.score(0), versus.score(1)));
+        assert_eq!(Some(0), versus.winner());
+        assert_eq!(11, versus.overlays().len());
+    }
+
+    #[test]
+    pub fn test_match_mine_loses() {
+        let mut layout = vec![false; 9];
+        layout[4] = true;
+        let mut game = Game::from_layout(3, 3, &layout);
+        let mut versus = Match::new(&game);
+        assert!(versus.play(&mut game, Move::Uncover(0, 0)));
+        assert!(versus.play(&mut game, Move::Uncover(1, 1)));
+        assert!(versus.is_over());
+        assert_eq!(Some(0), versus.winner());
+        assert!(!versus.play(&mut game, Move::Uncover(2, 2)));
+    }
+}
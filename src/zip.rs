@@ -0,0 +1,228 @@
+//! Reads the files of a zip archive, stored or deflated, for skin packs.
+//!
+//! Only what a skin needs is supported: no encryption, no zip64 and no
+//! compression methods other than deflate. Packs come from anyone, so a file
+//! may not inflate past the size the archive gives for it and all of them
+//! together past [`MAX_PACK_SIZE`].
+
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::{
+    error::{MinesweeperError, Result},
+    snapshot::crc32,
+};
+
+/// Most bytes the files of an archive may take once inflated.
+pub(crate) const MAX_PACK_SIZE: usize = 64 << 20;
+
+const END_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const END_LEN: usize = 22;
+const CENTRAL_LEN: usize = 46;
+const LOCAL_LEN: usize = 30;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const ENCRYPTED: u16 = 1;
+
+/// The files of an archive as names and contents, directories left out.
+pub(crate) fn read(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    // the end record sits before a comment of up to 64k at the very end
+    let end = (0..=archive.len().saturating_sub(END_LEN))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|&at| u32_at(archive, at) == Some(END_SIGNATURE))
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let count = u16_at(archive, end + 10).unwrap_or(0) as usize;
+    let mut at = u32_at(archive, end + 16).unwrap_or(0) as usize;
+    let mut files = Vec::with_capacity(count);
+    let mut total = 0;
+    for _ in 0..count {
+        if u32_at(archive, at) != Some(CENTRAL_SIGNATURE) {
+            return Err(invalid("broken zip directory"));
+        }
+        let field = |offset| u16_at(archive, at + offset).unwrap_or(0);
+        let flags = field(8);
+        let method = field(10);
+        let crc = u32_at(archive, at + 16).unwrap_or(0);
+        let size = u32_at(archive, at + 20).unwrap_or(0) as usize;
+        let inflated = u32_at(archive, at + 24).unwrap_or(0) as usize;
+        let (name_len, extra_len, comment_len) = (field(28), field(30), field(32));
+        let local = u32_at(archive, at + 42).unwrap_or(0) as usize;
+        let name = archive
+            .get(at + CENTRAL_LEN..at + CENTRAL_LEN + name_len as usize)
+            .ok_or_else(|| invalid("broken zip directory"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        at += CENTRAL_LEN + name_len as usize + extra_len as usize + comment_len as usize;
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags & ENCRYPTED != 0 {
+            return Err(invalid(&format!("{} is encrypted", name)));
+        }
+        total += inflated;
+        if total > MAX_PACK_SIZE {
+            return Err(invalid(&format!(
+                "the files are larger than {} MB",
+                MAX_PACK_SIZE >> 20
+            )));
+        }
+        if u32_at(archive, local) != Some(LOCAL_SIGNATURE) {
+            return Err(invalid(&format!("{} is missing from the archive", name)));
+        }
+        let start = local
+            + LOCAL_LEN
+            + u16_at(archive, local + 26).unwrap_or(0) as usize
+            + u16_at(archive, local + 28).unwrap_or(0) as usize;
+        let data = archive
+            .get(start..start + size)
+            .ok_or_else(|| invalid(&format!("{} is truncated", name)))?;
+        let data = match method {
+            STORED => data.to_vec(),
+            DEFLATED => inflate(data, inflated)?,
+            _ => {
+                return Err(invalid(&format!(
+                    "{} is compressed with method {}, only deflate is supported",
+                    name, method
+                )))
+            }
+        };
+        if data.len() != inflated || crc32(&data) != crc {
+            return Err(invalid(&format!("{} is corrupt", name)));
+        }
+        files.push((name, data));
+    }
+    Ok(files)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn invalid(message: &str) -> MinesweeperError {
+    MinesweeperError::Parse(message.to_string())
+}
+
+/// Decompresses a raw deflate stream, failing once it would inflate to more
+/// than `limit` bytes.
+pub(crate) fn inflate(bytes: &[u8], limit: usize) -> Result<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec_with_limit(bytes, limit).map_err(|error| {
+        match error.status {
+            TINFLStatus::HasMoreOutput => invalid("deflate stream inflates past its size"),
+            _ => invalid("broken deflate stream"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // "name = Midnight\n" twice, deflated with the fixed codes
+    const FIXED: [u8; 21] = [
+        203, 75, 204, 77, 85, 176, 85, 240, 205, 76, 201, 203, 76, 207, 40, 225, 202, 67, 227, 3, 0,
+    ];
+    // the number colors twice and a sound, deflated with dynamic codes
+    const DYNAMIC: [u8; 99] = [
+        205, 206, 57, 14, 128, 32, 16, 64, 209, 222, 83, 144, 216, 19, 217, 161, 240, 48, 48, 80,
+        24, 23, 18, 21, 189, 190, 78, 103, 230, 4, 86, 63, 121, 213, 135, 186, 212, 157, 111, 109,
+        77, 101, 23, 108, 100, 189, 40, 69, 248, 161, 131, 143, 75, 116, 5, 160, 136, 43, 116, 19,
+        163, 33, 174, 209, 157, 247, 142, 184, 65, 15, 214, 6, 226, 22, 61, 105, 157, 136, 59, 244,
+        44, 101, 38, 254, 231, 207, 163, 182, 45, 243, 115, 130, 249, 69, 12, 191, 227, 213, 61,
+    ];
+
+    /// An archive of `(name, method, data, contents)` entries.
+    fn archive(entries: &[(&str, u16, &[u8], &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, method, data, contents) in entries {
+            let header = |signature: u32, out: &mut Vec<u8>| {
+                out.extend_from_slice(&signature.to_le_bytes());
+                if signature == CENTRAL_SIGNATURE {
+                    out.extend_from_slice(&20u16.to_le_bytes());
+                }
+                out.extend_from_slice(&[20, 0, 0, 0]);
+                out.extend_from_slice(&method.to_le_bytes());
+                out.extend_from_slice(&[0; 4]);
+                out.extend_from_slice(&crc32(contents).to_le_bytes());
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+                out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                out.extend_from_slice(&[0; 2]);
+            };
+            header(CENTRAL_SIGNATURE, &mut directory);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&(out.len() as u32).to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+            header(LOCAL_SIGNATURE, &mut out);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+        }
+        let offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&END_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        let count = (entries.len() as u16).to_le_bytes();
+        out.extend_from_slice(&[count[0], count[1], count[0], count[1]]);
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&[0; 2]);
+        out
+    }
+
+    #[test]
+    pub fn test_inflate() {
+        let name = b"name = Midnight\n".repeat(2);
+        assert_eq!(name, inflate(&FIXED, 32).unwrap());
+        let numbers: String = (1..8)
+            .map(|i| {
+                let (a, b) = (i * 30, 255 - i * 30);
+                format!("color.number{} = #{:02x}{:02x}80\n", i, a, b)
+            })
+            .collect();
+        let expected = format!("{}{}sound.tick = tick.wav\n", numbers, numbers);
+        assert_eq!(expected.as_bytes(), inflate(&DYNAMIC, 4096).unwrap());
+
+        // a stored block, then the fixed one
+        let mut stored = vec![0, 3, 0, 0xfc, 0xff];
+        stored.extend_from_slice(b"abc");
+        stored.extend_from_slice(&FIXED);
+        let mut expected = b"abc".to_vec();
+        expected.extend_from_slice(&name);
+        assert_eq!(expected, inflate(&stored, 35).unwrap());
+        assert!(inflate(&FIXED[..10], 32).is_err());
+        // never past the size given for it
+        assert!(inflate(&FIXED, 31).is_err());
+    }
+
+    #[test]
+    pub fn test_read() {
+        let name = b"name = Midnight\n".repeat(2);
+        let files = read(&archive(&[
+            ("midnight/", STORED, b"", b""),
+            ("midnight/skin.txt", DEFLATED, &FIXED, &name),
+            ("midnight/tick.wav", STORED, b"RIFF", b"RIFF"),
+        ]))
+        .unwrap();
+        assert_eq!(2, files.len());
+        assert_eq!(("midnight/skin.txt".to_string(), name.clone()), files[0]);
+        assert_eq!(b"RIFF".to_vec(), files[1].1);
+
+        let corrupt = archive(&[("skin.txt", STORED, b"name", b"game")]);
+        assert!(read(&corrupt).is_err());
+        // a file inflating past its size, and files too large together
+        let bomb = archive(&[("skin.txt", DEFLATED, &FIXED, b"name")]);
+        let error = read(&bomb).unwrap_err().to_string();
+        assert!(error.contains("inflates past its size"), "{}", error);
+        let mut pack = archive(&[("tick.wav", STORED, b"RIFF", b"RIFF")]);
+        let size = LOCAL_LEN + 8 + 4 + 24;
+        pack[size..size + 4].copy_from_slice(&(MAX_PACK_SIZE as u32 + 1).to_le_bytes());
+        let error = read(&pack).unwrap_err().to_string();
+        assert!(error.contains("larger than 64 MB"), "{}", error);
+        assert!(read(b"PK not really").is_err());
+    }
+}